@@ -0,0 +1,136 @@
+//! Alert rules, each a keyword and optionally an author, checked against the tweets that background
+//! refreshes and `watch` bring in.  Configured in [UserConfig::alerts]; matching tweets also run
+//! the [hooks](crate::hooks).
+
+use crate::twitter_client::api;
+use crate::user_config::UserConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Matched case-insensitively anywhere in the text
+    pub keyword: String,
+    /// Only tweets by this username, without the leading '@'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+impl AlertRule {
+    pub fn matches(&self, tweet: &api::Tweet) -> bool {
+        let is_by_author = match &self.author {
+            Some(author) => tweet.author_username.as_deref().is_some_and(|username| {
+                username.eq_ignore_ascii_case(author.trim_start_matches('@'))
+            }),
+            None => true,
+        };
+        is_by_author
+            && tweet
+                .text
+                .to_lowercase()
+                .contains(&self.keyword.to_lowercase())
+    }
+
+    /// e.g. "rust" from @alice
+    pub fn describe(&self) -> String {
+        match &self.author {
+            Some(author) => format!(
+                "\"{}\" from @{}",
+                self.keyword,
+                author.trim_start_matches('@')
+            ),
+            None => format!("\"{}\"", self.keyword),
+        }
+    }
+}
+
+/// A tweet that matched a rule; if it matched several, the first.
+#[derive(Clone, Debug)]
+pub struct AlertHit {
+    pub rule: AlertRule,
+    pub tweet: api::Tweet,
+}
+
+impl AlertHit {
+    /// On one line, e.g. for a notification.
+    pub fn describe(&self) -> String {
+        let author = self.tweet.author_username.as_deref().unwrap_or("[unknown]");
+        let text = self.tweet.text.split_whitespace().collect::<Vec<&str>>();
+        format!("{}: @{author}: {}", self.rule.describe(), text.join(" "))
+    }
+}
+
+/// Each of [tweets] that matches one of [user_config]'s alert rules, in order.
+pub fn find_hits(user_config: &UserConfig, tweets: &[api::Tweet]) -> Vec<AlertHit> {
+    tweets
+        .iter()
+        .filter_map(|tweet| {
+            let rule = user_config.alerts.iter().find(|rule| rule.matches(tweet))?;
+            Some(AlertHit {
+                rule: rule.clone(),
+                tweet: tweet.clone(),
+            })
+        })
+        .collect()
+}
+
+/// What to notify of [hits], if any.
+pub fn summary(hits: &[AlertHit]) -> Option<String> {
+    match hits {
+        [] => None,
+        [hit] => Some(format!("Alert {}", hit.describe())),
+        _ => Some(format!("{} alerts; :alerts for them", hits.len())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+
+    fn rule(keyword: &str, author: Option<&str>) -> AlertRule {
+        AlertRule {
+            keyword: keyword.to_string(),
+            author: author.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let tweet = fixtures::tweet("1", "alice", "Rust 2.0 is out", 0);
+        assert!(rule("rust", None).matches(&tweet));
+        assert!(rule("RUST", Some("@Alice")).matches(&tweet));
+        assert!(!rule("rust", Some("bob")).matches(&tweet));
+        assert!(!rule("go", None).matches(&tweet));
+    }
+
+    #[test]
+    fn test_find_hits() {
+        let mut user_config = UserConfig::default();
+        user_config.alerts = vec![rule("release", Some("bob")), rule("rust", None)];
+        let tweets = [
+            fixtures::tweet("1", "alice", "rust release", 0),
+            fixtures::tweet("2", "bob", "rust release", 1),
+            fixtures::tweet("3", "carol", "nothing to see", 2),
+        ];
+        let hits = find_hits(&user_config, &tweets);
+        let summary: Vec<(&str, String)> = hits
+            .iter()
+            .map(|hit| (hit.tweet.id.as_str(), hit.rule.describe()))
+            .collect();
+        // NB: the first rule to match wins
+        assert_eq!(
+            summary,
+            vec![
+                ("1", "\"rust\"".to_string()),
+                ("2", "\"release\" from @bob".to_string())
+            ]
+        );
+        assert_eq!(
+            super::summary(&hits[..1]).unwrap(),
+            "Alert \"rust\": @alice: rust release"
+        );
+        assert_eq!(super::summary(&hits).unwrap(), "2 alerts; :alerts for them");
+        assert_eq!(super::summary(&[]), None);
+    }
+}
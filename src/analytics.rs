@@ -0,0 +1,215 @@
+//! Engagement with my recent tweets: totals and averages of their public metrics, the best
+//! performers, and engagement per day as a bar chart.  Shown with `:analytics`.
+
+use crate::twitter_client::api;
+use crate::ui_framework::text_width;
+use chrono::{Duration, NaiveDate};
+use std::fmt::Write;
+use std::ops::AddAssign;
+
+/// How many days the chart goes back, today included.
+const CHART_DAYS: i64 = 14;
+const CHART_WIDTH: usize = 40;
+const NUM_BEST: usize = 5;
+
+/// Eighths of a block, for the fractional end of a bar.
+const PARTIAL_BLOCKS: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Engagement {
+    pub likes: i64,
+    pub retweets: i64,
+    pub replies: i64,
+    pub quotes: i64,
+}
+
+impl Engagement {
+    fn of(tweet: &api::Tweet) -> Self {
+        match &tweet.public_metrics {
+            Some(metrics) => Self {
+                likes: metrics.like_count.into(),
+                retweets: metrics.retweet_count.into(),
+                replies: metrics.reply_count.into(),
+                quotes: metrics.quote_count.into(),
+            },
+            None => Self::default(),
+        }
+    }
+
+    pub fn total(&self) -> i64 {
+        self.likes + self.retweets + self.replies + self.quotes
+    }
+
+    fn describe(&self) -> String {
+        format!(
+            "♥ {}  ↻ {}  ↩ {}  ❝ {}",
+            self.likes, self.retweets, self.replies, self.quotes
+        )
+    }
+}
+
+impl AddAssign for Engagement {
+    fn add_assign(&mut self, other: Self) {
+        self.likes += other.likes;
+        self.retweets += other.retweets;
+        self.replies += other.replies;
+        self.quotes += other.quotes;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Analytics {
+    pub num_tweets: usize,
+    pub totals: Engagement,
+    /// Most engagement first, at most [NUM_BEST]
+    pub best: Vec<api::Tweet>,
+    /// Engagement with the tweets posted each day, oldest first, ending today
+    pub days: Vec<(NaiveDate, Engagement)>,
+}
+
+impl Analytics {
+    /// NB: retweets are left out, their metrics are the original tweet's
+    pub fn new(tweets: &[api::Tweet], today: NaiveDate) -> Self {
+        let tweets: Vec<&api::Tweet> = tweets
+            .iter()
+            .filter(|tweet| tweet.retweeted_id().is_none())
+            .collect();
+
+        let mut totals = Engagement::default();
+        let mut days: Vec<(NaiveDate, Engagement)> = (0..CHART_DAYS)
+            .rev()
+            .map(|days_ago| (today - Duration::days(days_ago), Engagement::default()))
+            .collect();
+        for tweet in tweets.iter() {
+            let engagement = Engagement::of(tweet);
+            totals += engagement;
+            let date = tweet.local_created_at().date_naive();
+            if let Some((_, day)) = days.iter_mut().find(|(day_date, _)| *day_date == date) {
+                *day += engagement;
+            }
+        }
+
+        let mut best = tweets.clone();
+        best.sort_by_key(|tweet| std::cmp::Reverse(Engagement::of(tweet).total()));
+        Self {
+            num_tweets: tweets.len(),
+            totals,
+            best: best.into_iter().take(NUM_BEST).cloned().collect(),
+            days,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        if self.num_tweets == 0 {
+            return "No recent tweets of yours to go on".to_string();
+        }
+
+        let average = |total: i64| total as f64 / self.num_tweets as f64;
+        let mut out = format!("{} recent tweets\n\n", self.num_tweets);
+        // NB: writing to a String can't fail
+        let _ = writeln!(out, "Total     {}", self.totals.describe());
+        let _ = writeln!(
+            out,
+            "Average   ♥ {:.1}  ↻ {:.1}  ↩ {:.1}  ❝ {:.1}",
+            average(self.totals.likes),
+            average(self.totals.retweets),
+            average(self.totals.replies),
+            average(self.totals.quotes)
+        );
+
+        out.push_str("\nBest performers\n");
+        for tweet in self.best.iter() {
+            let text = tweet
+                .text
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ");
+            let _ = writeln!(
+                out,
+                "  {:>6}  {}  {}",
+                Engagement::of(tweet).total(),
+                tweet.local_created_at().format("%m-%d"),
+                text_width::truncate(&text, 60)
+            );
+        }
+
+        out.push_str("\nEngagement by day posted\n");
+        let max_total = self
+            .days
+            .iter()
+            .map(|(_, engagement)| engagement.total())
+            .max()
+            .unwrap_or(0);
+        for (date, engagement) in self.days.iter() {
+            let _ = writeln!(
+                out,
+                "  {}  {} {}",
+                date.format("%m-%d"),
+                bar(engagement.total(), max_total),
+                engagement.total()
+            );
+        }
+        out
+    }
+}
+
+/// [value] as a bar of block characters, [CHART_WIDTH] long at [max].
+fn bar(value: i64, max: i64) -> String {
+    if max <= 0 || value <= 0 {
+        return String::new();
+    }
+    let eighths = (value * CHART_WIDTH as i64 * 8 / max) as usize;
+    format!("{}{}", "█".repeat(eighths / 8), PARTIAL_BLOCKS[eighths % 8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+
+    fn tweet(id: &str, minute: u32, likes: i32, retweets: i32) -> api::Tweet {
+        api::Tweet {
+            public_metrics: Some(api::PublicMetrics {
+                retweet_count: retweets,
+                reply_count: 1,
+                like_count: likes,
+                quote_count: 0,
+            }),
+            ..fixtures::tweet(id, "me", &format!("tweet {id}"), minute)
+        }
+    }
+
+    #[test]
+    fn test_analytics() {
+        let mut retweet = tweet("4", 4, 1000, 1000);
+        retweet.referenced_tweets = Some(vec![api::TweetReference {
+            r#type: "retweeted".to_string(),
+            id: "900".to_string(),
+        }]);
+        let tweets = vec![tweet("1", 1, 10, 2), tweet("2", 2, 30, 5), retweet];
+        let today = tweets[0].local_created_at().date_naive();
+
+        let analytics = Analytics::new(&tweets, today);
+        assert_eq!(analytics.num_tweets, 2);
+        assert_eq!(analytics.totals.total(), 49);
+        let best: Vec<&str> = analytics.best.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(best, vec!["2", "1"]);
+        assert_eq!(analytics.days.len(), CHART_DAYS as usize);
+        assert_eq!(analytics.days.last().unwrap(), &(today, analytics.totals));
+
+        let report = analytics.describe();
+        assert!(report.contains("Average   ♥ 20.0  ↻ 3.5  ↩ 1.0  ❝ 0.0\n"));
+        assert!(report.ends_with(&format!(
+            "  {}  {} 49\n",
+            today.format("%m-%d"),
+            "█".repeat(CHART_WIDTH)
+        )));
+    }
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(0, 10), "");
+        assert_eq!(bar(10, 10), "█".repeat(CHART_WIDTH));
+        assert_eq!(bar(1, 16), "██▌");
+    }
+}
@@ -0,0 +1,255 @@
+//! Where read-only timelines come from.  Besides the official API, a [NitterBackend] reads them
+//! from a Nitter instance's RSS feeds, for when API access is too restricted (or too expensive) to
+//! be worth it.  Writing (posting, bookmarks, etc.) still needs the API.
+
+use crate::twitter_client::{api, TwitterApi};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use futures_util::future;
+use hyper::{Body, Client};
+use hyper_tls::HttpsConnector;
+use regex::Regex;
+use std::collections::HashMap;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BackendKind {
+    /// The official API
+    Api,
+    /// A Nitter instance's RSS feeds; no login needed
+    Nitter,
+}
+
+/// A read-only source of tweets, newest first.
+#[async_trait]
+pub trait Backend: Send + Sync {
+    async fn home_timeline(&self) -> Result<Vec<api::Tweet>>;
+
+    async fn user_tweets(&self, username: &str) -> Result<Vec<api::Tweet>>;
+
+    async fn search(&self, query: &str) -> Result<Vec<api::Tweet>>;
+}
+
+pub struct ApiBackend<A: TwitterApi> {
+    twitter_client: A,
+    me: api::User,
+}
+
+impl<A: TwitterApi> ApiBackend<A> {
+    pub fn new(twitter_client: A, me: &api::User) -> Self {
+        Self {
+            twitter_client,
+            me: me.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<A: TwitterApi> Backend for ApiBackend<A> {
+    async fn home_timeline(&self) -> Result<Vec<api::Tweet>> {
+        let (page, _) = self
+            .twitter_client
+            .timeline_reverse_chronological(&self.me.id, None)
+            .await?;
+        Ok(page.tweets)
+    }
+
+    async fn user_tweets(&self, username: &str) -> Result<Vec<api::Tweet>> {
+        let user = self.twitter_client.user_by_username(username).await?;
+        let (page, _) = self.twitter_client.user_tweets(&user.id, None).await?;
+        Ok(page.tweets)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<api::Tweet>> {
+        let (page, _) = self.twitter_client.search_tweets(query).await?;
+        Ok(page.tweets)
+    }
+}
+
+pub struct NitterBackend {
+    /// e.g. "https://nitter.net"
+    instance_url: String,
+    /// Nitter has no home timeline (there's no one logged in), so it's made up of these accounts'
+    /// tweets instead, i.e. the starred accounts
+    follows: Vec<api::User>,
+}
+
+impl NitterBackend {
+    pub fn new(instance_url: &str, follows: Vec<api::User>) -> Self {
+        Self {
+            instance_url: instance_url.trim_end_matches('/').to_string(),
+            follows,
+        }
+    }
+
+    async fn get_feed(&self, uri: &Url) -> Result<Vec<api::Tweet>> {
+        let client = Client::builder().build::<_, Body>(HttpsConnector::new());
+        let resp = client.get(uri.to_string().parse()?).await?;
+        let status = resp.status();
+        let body = hyper::body::to_bytes(resp.into_body()).await?;
+        if !status.is_success() {
+            return Err(anyhow!("{status}: {uri}"));
+        }
+        let rss = String::from_utf8_lossy(&body);
+        parse_rss(&rss, &self.follows).with_context(|| format!("Parsing {uri}"))
+    }
+}
+
+#[async_trait]
+impl Backend for NitterBackend {
+    async fn home_timeline(&self) -> Result<Vec<api::Tweet>> {
+        if self.follows.is_empty() {
+            return Err(anyhow!(
+                "The Nitter home timeline is made of starred accounts; star some first"
+            ));
+        }
+        let feeds = future::try_join_all(
+            self.follows
+                .iter()
+                .map(|user| self.user_tweets(&user.username)),
+        )
+        .await?;
+        let mut tweets: Vec<api::Tweet> = feeds.into_iter().flatten().collect();
+        tweets.sort_by_key(|tweet| std::cmp::Reverse(tweet.created_at));
+        Ok(tweets)
+    }
+
+    async fn user_tweets(&self, username: &str) -> Result<Vec<api::Tweet>> {
+        let uri = Url::parse(&format!("{}/{username}/rss", self.instance_url))?;
+        self.get_feed(&uri).await
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<api::Tweet>> {
+        let mut uri = Url::parse(&format!("{}/search/rss", self.instance_url))?;
+        uri.query_pairs_mut()
+            .append_pair("f", "tweets")
+            .append_pair("q", query);
+        self.get_feed(&uri).await
+    }
+}
+
+/// Tweets in a Nitter RSS feed.  Authors are only known by username, so their ids are filled in
+/// from [known_users] where possible, e.g. so starred accounts are still recognized.
+fn parse_rss(rss: &str, known_users: &[api::User]) -> Result<Vec<api::Tweet>> {
+    let re_status_id = Regex::new(r"/status/(\d+)").unwrap();
+    let user_ids: HashMap<&str, &str> = known_users
+        .iter()
+        .map(|user| (user.username.as_str(), user.id.as_str()))
+        .collect();
+    let document = roxmltree::Document::parse(rss)?;
+    let text_of = |item: roxmltree::Node, name: &str| -> Result<String> {
+        item.children()
+            .find(|child| child.tag_name().name() == name)
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+            .ok_or(anyhow!("Expected <{name}> in every <item>"))
+    };
+
+    let mut tweets = Vec::new();
+    for item in document
+        .descendants()
+        .filter(|node| node.has_tag_name("item"))
+    {
+        let link = text_of(item, "link")?;
+        let id = re_status_id
+            .captures(&link)
+            .map(|captures| captures[1].to_string())
+            .ok_or(anyhow!("Not a link to a tweet: {link}"))?;
+        // NB: "creator" is dc:creator, e.g. "@alice"
+        let username = text_of(item, "creator")?
+            .trim_start_matches('@')
+            .to_string();
+        let created_at = DateTime::parse_from_rfc2822(&text_of(item, "pubDate")?)?;
+        tweets.push(api::Tweet {
+            id,
+            text: text_of(item, "title")?,
+            created_at: created_at.with_timezone(&Utc),
+            author_id: user_ids
+                .get(username.as_str())
+                .map(|id| id.to_string())
+                .unwrap_or(format!("@{username}")),
+            author_username: Some(username),
+            author_name: None,
+            author_protected: None,
+            author_verified_type: None,
+            author_affiliation: None,
+            author_profile_image_url: None,
+            author_created_at: None,
+            author_public_metrics: None,
+            conversation_id: None,
+            referenced_tweets: None,
+            attachments: None,
+            public_metrics: None,
+            entities: None,
+            lang: None,
+            possibly_sensitive: None,
+            media: None,
+            edit_history_tweet_ids: None,
+            edit_controls: None,
+        });
+    }
+    Ok(tweets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss xmlns:dc="http://purl.org/dc/elements/1.1/" version="2.0">
+  <channel>
+    <title>alice / @alice</title>
+    <item>
+      <title>Fish &amp; chips</title>
+      <dc:creator>@alice</dc:creator>
+      <description><![CDATA[<p>Fish &amp; chips</p>]]></description>
+      <pubDate>Wed, 01 Feb 2023 12:30:00 GMT</pubDate>
+      <guid>https://nitter.net/alice/status/1003#m</guid>
+      <link>https://nitter.net/alice/status/1003#m</link>
+    </item>
+    <item>
+      <title>RT by @alice: hello</title>
+      <dc:creator>@bob</dc:creator>
+      <pubDate>Wed, 01 Feb 2023 12:00:00 GMT</pubDate>
+      <link>https://nitter.net/bob/status/1001#m</link>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn test_parse_rss() {
+        let alice = api::User {
+            id: "2".to_string(),
+            name: "Alice".to_string(),
+            username: "alice".to_string(),
+            protected: None,
+            verified_type: None,
+            affiliation: None,
+            profile_image_url: None,
+            created_at: None,
+            public_metrics: None,
+        };
+        let tweets = parse_rss(RSS, &[alice]).unwrap();
+        let summary: Vec<(&str, &str, &str)> = tweets
+            .iter()
+            .map(|tweet| {
+                (
+                    tweet.id.as_str(),
+                    tweet.author_id.as_str(),
+                    tweet.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("1003", "2", "Fish & chips"),
+                ("1001", "@bob", "RT by @alice: hello")
+            ]
+        );
+        assert_eq!(tweets[0].created_at.timestamp(), 1675254600);
+        assert!(parse_rss("<rss><item><title>x</title></item></rss>", &[]).is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Output of the non-interactive subcommands, for reading or for piping into jq and friends.
+
+use crate::alerts;
+use crate::compose;
+use crate::hooks;
+use crate::store;
+use crate::twitter_client::{api, TwitterApi};
+use crate::ui_framework::text_width;
+use crate::user_config::UserConfig;
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// One JSON array of every result
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Aligned columns, for people
+    Table,
+}
+
+/// A result that can be printed as a row of a table.
+pub trait Tabular: Serialize {
+    fn header() -> Vec<&'static str>;
+
+    fn row(&self) -> Vec<String>;
+}
+
+impl Tabular for api::Tweet {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "TIME", "AUTHOR", "TEXT"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        let author = self.author_username.as_deref().unwrap_or("[unknown]");
+        vec![
+            self.id.clone(),
+            self.local_created_at()
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            format!("@{author}"),
+            self.text
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" "),
+        ]
+    }
+}
+
+impl Tabular for api::User {
+    fn header() -> Vec<&'static str> {
+        vec!["ID", "USERNAME", "NAME"]
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.id.clone(),
+            format!("@{}", self.username),
+            self.name.clone(),
+        ]
+    }
+}
+
+pub fn write_results<T: Tabular>(
+    out: &mut impl Write,
+    format: OutputFormat,
+    results: &[T],
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, results)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Ndjson => {
+            for result in results {
+                serde_json::to_writer(&mut *out, result)?;
+                writeln!(out)?;
+            }
+        }
+        OutputFormat::Table => {
+            let header = T::header().into_iter().map(String::from).collect();
+            let rows: Vec<Vec<String>> = std::iter::once(header)
+                .chain(results.iter().map(T::row))
+                .collect();
+            let num_columns = T::header().len();
+            let column_widths: Vec<usize> = (0..num_columns)
+                .map(|i| {
+                    rows.iter()
+                        .map(|row| text_width::width(&row[i]))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            for row in rows {
+                let mut line = String::new();
+                for (i, cell) in row.iter().enumerate() {
+                    line.push_str(cell);
+                    // NB: the last column isn't padded, so lines don't end in spaces
+                    if i + 1 < num_columns {
+                        let padding = column_widths[i] - text_width::width(cell);
+                        line.push_str(&" ".repeat(padding + 2));
+                    }
+                }
+                writeln!(out, "{line}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes one result as a line of its own, for output that keeps coming (e.g. [watch]).  Json is
+/// the same as ndjson here, since an array can't be written until it's complete; table rows aren't
+/// aligned, since later rows aren't known yet.
+pub fn write_result_line<T: Tabular>(
+    out: &mut impl Write,
+    format: OutputFormat,
+    result: &T,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json | OutputFormat::Ndjson => serde_json::to_writer(&mut *out, result)?,
+        OutputFormat::Table => write!(out, "{}", result.row().join("  "))?,
+    }
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Tweets matching [query] that are newer than [since_id], oldest first; moves [since_id] up to
+/// the newest of them.
+pub async fn poll_search(
+    twitter_client: &impl TwitterApi,
+    query: &str,
+    since_id: &mut Option<String>,
+) -> Result<Vec<api::Tweet>> {
+    let (page, _) = twitter_client
+        .search_tweets_since(query, since_id.as_deref(), None)
+        .await?;
+    let mut tweets = page.tweets;
+    // NB: the API already leaves out older tweets, but a repeat would be confusing if it didn't
+    if let Some(since_id) = since_id.as_deref() {
+        tweets.retain(|tweet| store::is_newer_tweet_id(&tweet.id, since_id));
+    }
+    tweets.sort_by_key(|tweet| (tweet.id.len(), tweet.id.clone()));
+    if let Some(newest) = tweets.last() {
+        *since_id = Some(newest.id.clone());
+    }
+    Ok(tweets)
+}
+
+/// Polls recent search for [query] every [interval], printing matches as they appear and running
+/// [user_config]'s hooks on them, until killed.  Errors (e.g. rate limits) are reported on stderr
+/// and the next poll tries again.
+pub async fn watch(
+    twitter_client: &impl TwitterApi,
+    query: &str,
+    interval: Duration,
+    format: OutputFormat,
+    user_config: &UserConfig,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut since_id = None;
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        match poll_search(twitter_client, query, &mut since_id).await {
+            Ok(tweets) => {
+                for tweet in tweets.iter() {
+                    write_result_line(out, format, tweet)?;
+                }
+                // NB: to stderr, so they stay out of piped output
+                for hit in alerts::find_hits(user_config, &tweets) {
+                    eprintln!("Alert {}", hit.describe());
+                }
+                for error in hooks::run_hooks(user_config, &tweets).await {
+                    eprintln!("{error:#}");
+                }
+            }
+            Err(error) => eprintln!("{error:#}"),
+        }
+    }
+}
+
+/// The thread to post: [text], or [stdin] if [text] is "-", or the contents of [file].  Split on
+/// [compose::THREAD_DELIMITER] lines and validated, so nothing reaches the API if it can't be
+/// posted.
+pub fn read_thread(
+    text: Option<&str>,
+    file: Option<&Path>,
+    mut stdin: impl Read,
+) -> Result<Vec<String>> {
+    let buffer = match (text, file) {
+        (Some("-"), None) => {
+            let mut buffer = String::new();
+            stdin.read_to_string(&mut buffer).context("Reading stdin")?;
+            buffer
+        }
+        (Some(text), None) => text.to_string(),
+        (None, Some(file)) => {
+            fs::read_to_string(file).with_context(|| format!("Reading {}", file.display()))?
+        }
+        _ => return Err(anyhow!("Expected either text or a file")),
+    };
+    let tweets = compose::split_thread(&buffer);
+    compose::validate_thread(&tweets)?;
+    Ok(tweets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitter_client::mock::{self, MockTwitterClient};
+
+    fn users() -> Vec<api::User> {
+        vec![
+            api::User {
+                id: "1".to_string(),
+                name: "Alice".to_string(),
+                username: "alice".to_string(),
+                protected: None,
+                verified_type: None,
+                affiliation: None,
+                profile_image_url: None,
+                created_at: None,
+                public_metrics: None,
+            },
+            api::User {
+                id: "22".to_string(),
+                name: "Bob 日本".to_string(),
+                username: "bob".to_string(),
+                protected: None,
+                verified_type: None,
+                affiliation: None,
+                profile_image_url: None,
+                created_at: None,
+                public_metrics: None,
+            },
+        ]
+    }
+
+    fn write(format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        write_results(&mut out, format, &users()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_results() {
+        assert_eq!(
+            write(OutputFormat::Table),
+            "ID  USERNAME  NAME\n1   @alice    Alice\n22  @bob      Bob 日本\n"
+        );
+        assert_eq!(
+            write(OutputFormat::Ndjson),
+            "{\"id\":\"1\",\"name\":\"Alice\",\"username\":\"alice\"}\n\
+             {\"id\":\"22\",\"name\":\"Bob 日本\",\"username\":\"bob\"}\n"
+        );
+        let json: Vec<api::User> = serde_json::from_str(&write(OutputFormat::Json)).unwrap();
+        assert_eq!(json, users());
+    }
+
+    #[test]
+    fn test_read_thread() {
+        let stdin = "hello world\n---\nsecond\n".as_bytes();
+        assert_eq!(
+            read_thread(Some("-"), None, stdin).unwrap(),
+            vec!["hello world", "second"]
+        );
+        assert_eq!(
+            read_thread(Some("just this"), None, "ignored".as_bytes()).unwrap(),
+            vec!["just this"]
+        );
+        let error = read_thread(Some("-"), None, "a".repeat(281).as_bytes());
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "Tweet 1/1 is too long (281/280)"
+        );
+        assert!(read_thread(None, Some(Path::new("/nonexistent")), "".as_bytes()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_poll_search() {
+        let twitter_client = MockTwitterClient::new();
+        let search = "/2/tweets/search/recent";
+        twitter_client.respond(search, mock::fixtures::MENTIONS);
+        twitter_client.respond(search, mock::fixtures::QUOTES);
+
+        let mut since_id = None;
+        let tweets = poll_search(&twitter_client, "@me", &mut since_id)
+            .await
+            .unwrap();
+        let tweet_ids: Vec<&str> = tweets.iter().map(|tweet| tweet.id.as_str()).collect();
+        assert_eq!(tweet_ids, vec!["1004", "1008", "1010"]);
+        assert_eq!(since_id.as_deref(), Some("1010"));
+
+        // NB: nothing newer, so since_id stays put
+        let tweets = poll_search(&twitter_client, "@me", &mut since_id)
+            .await
+            .unwrap();
+        assert!(tweets.is_empty());
+        assert_eq!(since_id.as_deref(), Some("1010"));
+        assert_eq!(
+            twitter_client.requests(),
+            vec![
+                format!("{search}?query=@me"),
+                format!("{search}?query=@me&since_id=1010")
+            ]
+        );
+    }
+}
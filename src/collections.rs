@@ -0,0 +1,174 @@
+//! Collections: named folders of saved tweets, kept locally under [COLLECTIONS_DIR] as one JSON
+//! file each.  Browsed as a feed (`:collection <name>`) and exported like any feed with `:export`.
+
+use crate::twitter_client::api;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const COLLECTIONS_DIR: &str = "./var/collections";
+
+#[derive(Debug, Clone)]
+pub struct Collection {
+    pub name: String,
+    path: PathBuf,
+    /// Most recently added first; the referenced tweets are kept so retweets and quotes still show
+    /// the original offline
+    pub page: api::TweetPage,
+}
+
+impl Collection {
+    /// Reads the collection [name] from [dir]; one that hasn't been saved yet is empty.
+    pub fn open(dir: &Path, name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || matches!(ch, '-' | '_' | ' '))
+        {
+            return Err(anyhow!(
+                "Collection names are letters, digits, spaces, - and _, not `{name}`"
+            ));
+        }
+        let path = dir.join(format!("{name}.json"));
+        let page = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => api::TweetPage::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            page,
+        })
+    }
+
+    pub fn contains(&self, tweet_id: &str) -> bool {
+        self.page.tweets.iter().any(|tweet| tweet.id == tweet_id)
+    }
+
+    /// Adds [tweet] at the top, along with whichever of [referenced_tweets] aren't kept yet.
+    /// Returns false if it was already in the collection.
+    pub fn add(&mut self, tweet: &api::Tweet, referenced_tweets: Vec<api::Tweet>) -> bool {
+        if self.contains(&tweet.id) {
+            return false;
+        }
+        self.page.tweets.insert(0, tweet.clone());
+        for referenced_tweet in referenced_tweets {
+            if !self
+                .page
+                .referenced_tweets
+                .iter()
+                .any(|kept| kept.id == referenced_tweet.id)
+            {
+                self.page.referenced_tweets.push(referenced_tweet);
+            }
+        }
+        true
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.page)?)?;
+        Ok(())
+    }
+}
+
+/// Names of the saved collections in [dir], sorted.
+pub fn list_collections(dir: &Path) -> Result<Vec<String>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// [tweets] as a Markdown document headed [title], with any of [notes] (by tweet id) quoted under
+/// their tweets.
+pub fn to_markdown(
+    title: &str,
+    tweets: &[&api::Tweet],
+    notes: &BTreeMap<String, String>,
+) -> String {
+    let mut out = format!("# {title}\n");
+    for tweet in tweets {
+        let author = tweet.author("[unknown]");
+        // NB: writing to a String can't fail
+        let _ = write!(
+            out,
+            "\n## @{} ({}) · {}\n\n{}\n\nhttps://twitter.com/{}/status/{}\n",
+            author.username,
+            author.name,
+            tweet.local_created_at().format("%Y-%m-%d %H:%M"),
+            tweet.text.trim(),
+            author.username,
+            tweet.id
+        );
+        if let Some(note) = notes.get(&tweet.id) {
+            let _ = writeln!(out, "\n> {}", note.replace('\n', "\n> "));
+        }
+    }
+    out
+}
+
+// NB: the fixtures are in the store
+#[cfg(all(test, feature = "tui"))]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+    use std::{env, process};
+
+    #[test]
+    fn test_collections() {
+        let dir = env::temp_dir().join(format!("twitter-tool-collections-{}", process::id()));
+        assert!(Collection::open(&dir, "../etc").is_err());
+        assert_eq!(list_collections(&dir).unwrap(), Vec::<String>::new());
+
+        let mut collection = Collection::open(&dir, "rust papers").unwrap();
+        let alice = fixtures::tweet("1", "alice", "hello", 0);
+        let bob = fixtures::tweet("2", "bob", "world", 1);
+        assert!(collection.add(&alice, Vec::new()));
+        assert!(collection.add(&bob, vec![alice.clone()]));
+        assert!(!collection.add(&alice, Vec::new()));
+        collection.save().unwrap();
+
+        let collection = Collection::open(&dir, "rust papers").unwrap();
+        let ids: Vec<&str> = collection
+            .page
+            .tweets
+            .iter()
+            .map(|t| t.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["2", "1"]);
+        assert_eq!(collection.page.referenced_tweets.len(), 1);
+        assert_eq!(list_collections(&dir).unwrap(), vec!["rust papers"]);
+
+        let notes = BTreeMap::from([("1".to_string(), "read\nlater".to_string())]);
+        let markdown = to_markdown("rust papers", &[&alice], &notes);
+        assert!(markdown.starts_with("# rust papers\n\n## @alice (ALICE) · "));
+        assert!(markdown
+            .ends_with("\n\nhello\n\nhttps://twitter.com/alice/status/1\n\n> read\n> later\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,211 @@
+use crate::text_measure;
+use crate::twitter_client::TwitterApi;
+use anyhow::{anyhow, Error, Result};
+
+/// A line consisting solely of this string separates consecutive tweets in a thread buffer.
+pub const THREAD_DELIMITER: &str = "---";
+
+#[derive(Debug)]
+pub enum ThreadProgress {
+    Posted {
+        index: usize,
+        total: usize,
+        tweet_id: String,
+    },
+    Failed {
+        index: usize,
+        total: usize,
+        error: Error,
+    },
+    RolledBack {
+        tweet_id: String,
+    },
+    RollbackFailed {
+        tweet_id: String,
+        error: Error,
+    },
+}
+
+impl ThreadProgress {
+    pub fn describe(&self) -> String {
+        match self {
+            ThreadProgress::Posted {
+                index,
+                total,
+                tweet_id,
+            } => format!("Posted {}/{total} (id {tweet_id})", index + 1),
+            ThreadProgress::Failed {
+                index,
+                total,
+                error,
+            } => format!("Failed to post {}/{total}: {error}", index + 1),
+            ThreadProgress::RolledBack { tweet_id } => format!("Rolled back id {tweet_id}"),
+            ThreadProgress::RollbackFailed { tweet_id, error } => {
+                format!("Failed to roll back id {tweet_id}: {error}")
+            }
+        }
+    }
+}
+
+/// Splits a compose buffer into the individual tweets of a thread, dropping empty parts.
+pub fn split_thread(buffer: &str) -> Vec<String> {
+    let mut tweets = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for line in buffer.lines() {
+        if line.trim() == THREAD_DELIMITER {
+            tweets.push(current.join("\n"));
+            current.clear();
+        } else {
+            current.push(line);
+        }
+    }
+    tweets.push(current.join("\n"));
+
+    tweets
+        .into_iter()
+        .map(|tweet| tweet.trim().to_string())
+        .filter(|tweet| !tweet.is_empty())
+        .collect()
+}
+
+/// Checks that there's something to post and that every tweet is within the length limit.
+pub fn validate_thread(tweets: &[String]) -> Result<()> {
+    let total = tweets.len();
+    if total == 0 {
+        return Err(anyhow!("Nothing to post"));
+    }
+    if let Some(index) = tweets
+        .iter()
+        .position(|text| !text_measure::is_within_limit(text))
+    {
+        return Err(anyhow!(
+            "Tweet {}/{total} is too long ({}/{})",
+            index + 1,
+            text_measure::weighted_length(&tweets[index]),
+            text_measure::MAX_WEIGHTED_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// Posts [tweets] in order, each one replying to the previous (or to [in_reply_to_tweet_id] for the
+/// first).  If a tweet fails to post, the tweets already posted are deleted in reverse order so that
+/// a half-finished thread isn't left behind.  Nothing is posted if any tweet is over the length
+/// limit.  Returns the ids of the posted tweets.
+pub async fn post_thread<F: Fn(ThreadProgress)>(
+    twitter_client: &impl TwitterApi,
+    tweets: &[String],
+    in_reply_to_tweet_id: Option<String>,
+    on_progress: F,
+) -> Result<Vec<String>> {
+    let total = tweets.len();
+    validate_thread(tweets)?;
+
+    let mut posted: Vec<String> = Vec::new();
+    let mut reply_to = in_reply_to_tweet_id;
+
+    for (index, text) in tweets.iter().enumerate() {
+        match twitter_client.post_tweet(text, reply_to.as_deref()).await {
+            Ok(tweet) => {
+                on_progress(ThreadProgress::Posted {
+                    index,
+                    total,
+                    tweet_id: tweet.id.clone(),
+                });
+                reply_to = Some(tweet.id.clone());
+                posted.push(tweet.id);
+            }
+            Err(error) => {
+                let message = error.to_string();
+                on_progress(ThreadProgress::Failed {
+                    index,
+                    total,
+                    error,
+                });
+
+                let mut num_rolled_back = 0;
+                for tweet_id in posted.iter().rev() {
+                    match twitter_client.delete_tweet(tweet_id).await {
+                        Ok(()) => {
+                            num_rolled_back += 1;
+                            on_progress(ThreadProgress::RolledBack {
+                                tweet_id: tweet_id.clone(),
+                            })
+                        }
+                        Err(error) => on_progress(ThreadProgress::RollbackFailed {
+                            tweet_id: tweet_id.clone(),
+                            error,
+                        }),
+                    }
+                }
+
+                return Err(anyhow!(
+                    "Thread failed at {}/{total} ({message}); rolled back {num_rolled_back}/{}",
+                    index + 1,
+                    posted.len()
+                ));
+            }
+        }
+    }
+
+    Ok(posted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitter_client::mock::MockTwitterClient;
+
+    #[test]
+    fn test_split_thread() {
+        let buffer = "First tweet\nstill first\n---\n\nSecond tweet\n  ---  \n---\nThird\n---\n";
+        assert_eq!(
+            split_thread(buffer),
+            vec!["First tweet\nstill first", "Second tweet", "Third"]
+        );
+        assert_eq!(split_thread("Just one"), vec!["Just one"]);
+        assert!(split_thread("\n---\n").is_empty());
+    }
+
+    #[test]
+    fn test_validate_thread() {
+        assert!(validate_thread(&["ok".to_string()]).is_ok());
+        assert_eq!(
+            validate_thread(&[]).unwrap_err().to_string(),
+            "Nothing to post"
+        );
+        assert_eq!(
+            validate_thread(&["ok".to_string(), "a".repeat(281)])
+                .unwrap_err()
+                .to_string(),
+            "Tweet 2/2 is too long (281/280)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_thread_rolls_back() {
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(
+            "POST /2/tweets",
+            r#"{"data": {"id": "10", "text": "First"}}"#,
+        );
+        twitter_client.fail("POST /2/tweets", "429 Too Many Requests");
+        twitter_client.respond("DELETE /2/tweets/10", r#"{"data": {"deleted": true}}"#);
+
+        let tweets = vec!["First".to_string(), "Second".to_string()];
+        let result = post_thread(&twitter_client, &tweets, None, |_| ()).await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Thread failed at 2/2 (429 Too Many Requests); rolled back 1/1"
+        );
+        assert_eq!(
+            twitter_client.requests(),
+            vec![
+                "POST /2/tweets?text=First",
+                "POST /2/tweets?text=Second&in_reply_to=10",
+                "DELETE /2/tweets/10"
+            ]
+        );
+    }
+}
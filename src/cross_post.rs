@@ -0,0 +1,213 @@
+//! Publishing what's posted from the compose box to other networks as well, each configured in
+//! [UserConfig::cross_post].  A cross-post happens only once the thread is on Twitter, and each
+//! network succeeds or fails on its own.
+//!
+//! [UserConfig::cross_post]: crate::user_config::UserConfig::cross_post
+
+use crate::http_client::post_json;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossPostConfig {
+    pub mastodon: Option<MastodonConfig>,
+    pub bluesky: Option<BlueskyConfig>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MastodonConfig {
+    /// e.g. "https://mastodon.social"
+    pub instance_url: String,
+    /// With the write:statuses scope
+    pub access_token: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlueskyConfig {
+    /// e.g. "me.bsky.social"
+    pub handle: String,
+    /// An app password, not the account password
+    pub app_password: String,
+    #[serde(default = "default_bluesky_service_url")]
+    pub service_url: String,
+}
+
+fn default_bluesky_service_url() -> String {
+    "https://bsky.social".to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    Mastodon,
+    Bluesky,
+}
+
+impl Target {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Mastodon => "Mastodon",
+            Target::Bluesky => "Bluesky",
+        }
+    }
+}
+
+impl CrossPostConfig {
+    /// The networks that are configured, i.e. that can be cross-posted to.
+    pub fn targets(&self) -> Vec<Target> {
+        let mut targets = Vec::new();
+        if self.mastodon.is_some() {
+            targets.push(Target::Mastodon);
+        }
+        if self.bluesky.is_some() {
+            targets.push(Target::Bluesky);
+        }
+        targets
+    }
+
+    /// Posts [tweets] to [target] as a thread, each replying to the previous.
+    pub async fn post_thread(&self, target: Target, tweets: &[String]) -> Result<()> {
+        let not_configured = || anyhow!("{} isn't configured", target.name());
+        match target {
+            Target::Mastodon => {
+                let config = self.mastodon.as_ref().ok_or_else(not_configured)?;
+                post_mastodon_thread(config, tweets).await
+            }
+            Target::Bluesky => {
+                let config = self.bluesky.as_ref().ok_or_else(not_configured)?;
+                post_bluesky_thread(config, tweets).await
+            }
+        }
+    }
+}
+
+/// A string field of a JSON response, or an error naming the missing field.
+fn field<'a>(value: &'a serde_json::Value, name: &str) -> Result<&'a str> {
+    value[name]
+        .as_str()
+        .ok_or(anyhow!("Expected `{name}` in response"))
+}
+
+async fn post_mastodon_thread(config: &MastodonConfig, tweets: &[String]) -> Result<()> {
+    let url = format!(
+        "{}/api/v1/statuses",
+        config.instance_url.trim_end_matches('/')
+    );
+    let mut in_reply_to_id: Option<String> = None;
+    for text in tweets {
+        let mut body = serde_json::json!({ "status": text });
+        if let Some(in_reply_to_id) = &in_reply_to_id {
+            body["in_reply_to_id"] = serde_json::json!(in_reply_to_id);
+        }
+        let authorization = format!("Bearer {}", config.access_token);
+        let status = post_json(&url, Some(&authorization), body).await?;
+        in_reply_to_id = Some(field(&status, "id")?.to_string());
+    }
+    Ok(())
+}
+
+/// A reference to a Bluesky record, as returned on creating it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct StrongRef {
+    uri: String,
+    cid: String,
+}
+
+/// The record of a Bluesky post; [reply] is the (root, parent) of the thread it continues.
+fn bluesky_post(
+    text: &str,
+    reply: Option<(&StrongRef, &StrongRef)>,
+    created_at: DateTime<Utc>,
+) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "$type": "app.bsky.feed.post",
+        "text": text,
+        "createdAt": created_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+    });
+    if let Some((root, parent)) = reply {
+        record["reply"] = serde_json::json!({ "root": root, "parent": parent });
+    }
+    record
+}
+
+async fn post_bluesky_thread(config: &BlueskyConfig, tweets: &[String]) -> Result<()> {
+    let service_url = config.service_url.trim_end_matches('/');
+    let session = post_json(
+        &format!("{service_url}/xrpc/com.atproto.server.createSession"),
+        None,
+        serde_json::json!({ "identifier": config.handle, "password": config.app_password }),
+    )
+    .await?;
+    let authorization = format!("Bearer {}", field(&session, "accessJwt")?);
+    let did = field(&session, "did")?;
+
+    let mut thread: Option<(StrongRef, StrongRef)> = None;
+    for text in tweets {
+        let record = bluesky_post(
+            text,
+            thread.as_ref().map(|(root, parent)| (root, parent)),
+            Utc::now(),
+        );
+        let created = post_json(
+            &format!("{service_url}/xrpc/com.atproto.repo.createRecord"),
+            Some(&authorization),
+            serde_json::json!({
+                "repo": did,
+                "collection": "app.bsky.feed.post",
+                "record": record,
+            }),
+        )
+        .await?;
+        let created: StrongRef = serde_json::from_value(created)?;
+        thread = Some(match thread {
+            Some((root, _)) => (root, created),
+            None => (created.clone(), created),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_config() {
+        let config: CrossPostConfig = serde_json::from_value(serde_json::json!({
+            "bluesky": {"handle": "me.bsky.social", "app_password": "xxxx"}
+        }))
+        .unwrap();
+        assert_eq!(config.targets(), vec![Target::Bluesky]);
+        assert_eq!(config.bluesky.unwrap().service_url, "https://bsky.social");
+        assert!(CrossPostConfig::default().targets().is_empty());
+    }
+
+    #[test]
+    fn test_bluesky_post() {
+        let created_at = Utc.with_ymd_and_hms(2023, 2, 1, 0, 0, 0).unwrap();
+        let root = StrongRef {
+            uri: "at://did:plc:me/app.bsky.feed.post/1".to_string(),
+            cid: "a".to_string(),
+        };
+        let parent = StrongRef {
+            uri: "at://did:plc:me/app.bsky.feed.post/2".to_string(),
+            cid: "b".to_string(),
+        };
+        assert_eq!(
+            bluesky_post("hi", None, created_at),
+            serde_json::json!({
+                "$type": "app.bsky.feed.post",
+                "text": "hi",
+                "createdAt": "2023-02-01T00:00:00.000Z",
+            })
+        );
+        assert_eq!(
+            bluesky_post("more", Some((&root, &parent)), created_at)["reply"],
+            serde_json::json!({
+                "root": {"uri": root.uri, "cid": "a"},
+                "parent": {"uri": parent.uri, "cid": "b"},
+            })
+        );
+    }
+}
@@ -0,0 +1,204 @@
+//! A Markdown digest of what starred accounts tweeted over a period, e.g. the last day, grouped by
+//! account.
+
+use crate::twitter_client::paginator::Paginator;
+use crate::twitter_client::{api, TwitterApi};
+use anyhow::{anyhow, Context, Error, Result};
+use chrono::{DateTime, Duration, Utc};
+use std::fmt::Write;
+
+/// When tweet ids start counting, in milliseconds since the Unix epoch
+const TWITTER_EPOCH_MILLIS: i64 = 1288834974657;
+
+/// A period like "90m", "24h", "7d" or "2w".
+pub fn parse_period(spec: &str) -> Result<Duration> {
+    let spec = spec.trim();
+    let unit_start = spec
+        .find(|ch: char| !ch.is_ascii_digit())
+        .ok_or(anyhow!("Expected a unit (m, h, d or w) after `{spec}`"))?;
+    let (amount, unit) = spec.split_at(unit_start);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow!("Expected a number then a unit, e.g. 24h, not `{spec}`"))?;
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        _ => Err(anyhow!("Expected a unit of m, h, d or w, not `{unit}`")),
+    }
+}
+
+/// The id a tweet posted at [time] would start from.  NB: tweet ids are snowflakes, which lead with
+/// the time they were made, so this stands in for a time wherever the API wants an id
+pub fn tweet_id_at(time: DateTime<Utc>) -> String {
+    let millis = (time.timestamp_millis() - TWITTER_EPOCH_MILLIS).max(0);
+    (millis << 22).to_string()
+}
+
+/// Each of [accounts]' tweets since [since], oldest first, leaving out the accounts with none.  An
+/// account that fails (e.g. protected) is left out too, and its error returned alongside.
+pub async fn collect(
+    twitter_client: &impl TwitterApi,
+    accounts: &[api::User],
+    since: DateTime<Utc>,
+) -> (Vec<(api::User, Vec<api::Tweet>)>, Vec<Error>) {
+    let since_id = tweet_id_at(since);
+    let mut digest = Vec::new();
+    let mut errors = Vec::new();
+    // NB: one account at a time, to go easy on the rate limit
+    for account in accounts {
+        let mut paginator = Paginator::new(|page_token| {
+            twitter_client.user_tweets_since(&account.id, Some(&since_id), page_token)
+        });
+        match paginator
+            .collect_all()
+            .await
+            .with_context(|| format!("Loading @{}'s tweets", account.username))
+        {
+            Ok(page) => {
+                let mut tweets: Vec<api::Tweet> = page
+                    .tweets
+                    .into_iter()
+                    .filter(|tweet| tweet.created_at >= since)
+                    .collect();
+                tweets.sort_by_key(|tweet| (tweet.created_at, tweet.id.clone()));
+                if !tweets.is_empty() {
+                    digest.push((account.clone(), tweets));
+                }
+            }
+            Err(err) => errors.push(err),
+        }
+    }
+    digest.sort_by_key(|(account, _)| account.username.to_lowercase());
+    (digest, errors)
+}
+
+pub fn to_markdown(
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    digest: &[(api::User, Vec<api::Tweet>)],
+) -> String {
+    let format = |time: DateTime<Utc>| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    let mut out = format!("# Digest: {} to {}\n", format(since), format(until));
+    if digest.is_empty() {
+        out.push_str("\nNothing new from starred accounts.\n");
+    }
+    for (account, tweets) in digest {
+        // NB: writing to a String can't fail
+        let _ = writeln!(
+            out,
+            "\n## @{} ({}) · {} tweet{}\n",
+            account.username,
+            account.name,
+            tweets.len(),
+            if tweets.len() == 1 { "" } else { "s" }
+        );
+        for tweet in tweets {
+            let _ = writeln!(
+                out,
+                "- {} {} ([link](https://twitter.com/{}/status/{}))",
+                tweet.local_created_at().format("%Y-%m-%d %H:%M"),
+                tweet
+                    .text
+                    .split_whitespace()
+                    .collect::<Vec<&str>>()
+                    .join(" "),
+                account.username,
+                tweet.id
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+    use crate::twitter_client::mock::MockTwitterClient;
+    use chrono::TimeZone;
+
+    fn page(tweets: &[(&str, &str)], next_token: Option<&str>) -> String {
+        let data: Vec<serde_json::Value> = tweets
+            .iter()
+            .map(|(id, created_at)| {
+                serde_json::json!({"id": id, "text": "hello\n world", "created_at": created_at, "author_id": "2"})
+            })
+            .collect();
+        serde_json::json!({
+            "data": data,
+            "includes": {"users": []},
+            "meta": {"result_count": tweets.len(), "next_token": next_token}
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!(parse_period("24h").unwrap(), Duration::hours(24));
+        assert_eq!(parse_period("90m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_period("2w").unwrap(), Duration::days(14));
+        assert!(parse_period("24").is_err());
+        assert!(parse_period("h").is_err());
+        assert!(parse_period("1y").is_err());
+    }
+
+    #[test]
+    fn test_tweet_id_at() {
+        let time = Utc
+            .timestamp_millis_opt(TWITTER_EPOCH_MILLIS + 1000)
+            .unwrap();
+        assert_eq!(tweet_id_at(time), (1000_i64 << 22).to_string());
+        assert_eq!(tweet_id_at(Utc.timestamp_opt(0, 0).unwrap()), "0");
+    }
+
+    #[tokio::test]
+    async fn test_collect() {
+        let twitter_client = MockTwitterClient::new();
+        let since = Utc.with_ymd_and_hms(2023, 2, 1, 0, 0, 0).unwrap();
+        twitter_client.respond(
+            "/2/users/2/tweets",
+            &page(&[("1003", "2023-02-01T03:00:00Z")], Some("page_2")),
+        );
+        twitter_client.respond(
+            "/2/users/2/tweets",
+            &page(
+                &[
+                    ("1002", "2023-02-01T02:00:00Z"),
+                    ("999", "2023-01-31T23:00:00Z"),
+                ],
+                None,
+            ),
+        );
+        twitter_client.respond("/2/users/3/tweets", &page(&[], None));
+        twitter_client.fail("/2/users/4/tweets", "401 Unauthorized");
+
+        let accounts = [
+            fixtures::user("4", "carol"),
+            fixtures::user("3", "bob"),
+            fixtures::user("2", "alice"),
+        ];
+        let (digest, errors) = collect(&twitter_client, &accounts, since).await;
+        let summary: Vec<(&str, Vec<&str>)> = digest
+            .iter()
+            .map(|(account, tweets)| {
+                let ids = tweets.iter().map(|tweet| tweet.id.as_str()).collect();
+                (account.username.as_str(), ids)
+            })
+            .collect();
+        // NB: the straggler from before [since] is left out
+        assert_eq!(summary, vec![("alice", vec!["1002", "1003"])]);
+        assert_eq!(errors.len(), 1);
+        assert!(format!("{:#}", errors[0]).starts_with("Loading @carol's tweets"));
+        let since_id = tweet_id_at(since);
+        assert!(twitter_client.requests().contains(&format!(
+            "/2/users/2/tweets?since_id={since_id}&pagination_token=page_2"
+        )));
+
+        let markdown = to_markdown(since, since + Duration::days(1), &digest);
+        assert!(markdown.contains("\n## @alice (ALICE) · 2 tweets\n\n- "));
+        assert!(markdown.contains(" hello world ([link](https://twitter.com/alice/status/1002))\n"));
+        assert!(to_markdown(since, since, &[]).contains("Nothing new"));
+    }
+}
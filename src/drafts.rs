@@ -0,0 +1,113 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DRAFTS_DIR: &str = "./var/drafts";
+
+/// A compose buffer persisted under [DRAFTS_DIR].  The file itself is the source of truth: the
+/// editor writes straight into it, so a draft survives even if the app dies mid-compose.
+///
+/// NB: the reply target, if any, is encoded into the file name as `<id>.re-<tweet_id>.txt`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Draft {
+    pub id: String,
+    pub path: PathBuf,
+    pub in_reply_to_tweet_id: Option<String>,
+    pub modified: DateTime<Local>,
+    pub text: String,
+}
+
+impl Draft {
+    /// Reserves a path for a new, empty draft and creates the file.
+    pub fn create(in_reply_to_tweet_id: Option<&str>) -> Result<Draft> {
+        fs::create_dir_all(DRAFTS_DIR)?;
+        let now = Local::now();
+        let id = now.format("%Y%m%d-%H%M%S%3f").to_string();
+        let file_name = match in_reply_to_tweet_id {
+            Some(tweet_id) => format!("{id}.re-{tweet_id}.txt"),
+            None => format!("{id}.txt"),
+        };
+        let path = Path::new(DRAFTS_DIR).join(file_name);
+        fs::write(&path, "")?;
+        Ok(Draft {
+            id,
+            path,
+            in_reply_to_tweet_id: in_reply_to_tweet_id.map(String::from),
+            modified: now,
+            text: String::new(),
+        })
+    }
+
+    fn load(path: &Path) -> Result<Option<Draft>> {
+        let Some(file_name) = path.file_name().and_then(|f| f.to_str()) else {
+            return Ok(None);
+        };
+        let Some(stem) = file_name.strip_suffix(".txt") else {
+            return Ok(None);
+        };
+        let (id, in_reply_to_tweet_id) = match stem.split_once(".re-") {
+            Some((id, tweet_id)) => (id.to_string(), Some(tweet_id.to_string())),
+            None => (stem.to_string(), None),
+        };
+        let modified: DateTime<Local> = fs::metadata(path)?.modified()?.into();
+        let text = fs::read_to_string(path)?;
+        Ok(Some(Draft {
+            id,
+            path: path.to_path_buf(),
+            in_reply_to_tweet_id,
+            modified,
+            text,
+        }))
+    }
+
+    /// Re-reads the draft text from disk, e.g. after it has been edited externally.
+    pub fn reload(&mut self) -> Result<()> {
+        self.text = fs::read_to_string(&self.path)?;
+        self.modified = fs::metadata(&self.path)?.modified()?.into();
+        Ok(())
+    }
+
+    pub fn save(&mut self, text: &str) -> Result<()> {
+        fs::write(&self.path, text)?;
+        self.text = text.to_string();
+        self.modified = Local::now();
+        Ok(())
+    }
+
+    pub fn delete(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        self.text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("<empty>")
+            .to_string()
+    }
+}
+
+/// Lists saved drafts, most recently modified first.
+pub fn list_drafts() -> Result<Vec<Draft>> {
+    let entries = match fs::read_dir(DRAFTS_DIR) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut drafts = Vec::new();
+    for entry in entries {
+        if let Some(draft) = Draft::load(&entry?.path())? {
+            drafts.push(draft);
+        }
+    }
+    drafts.sort_by_key(|draft| Reverse(draft.modified));
+    Ok(drafts)
+}
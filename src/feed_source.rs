@@ -0,0 +1,105 @@
+//! Which feed a tab shows, as written for `:tab` and in [UserConfig::feeds].
+//!
+//! [UserConfig::feeds]: crate::user_config::UserConfig::feeds
+
+use crate::user_config::normalize_tag;
+use anyhow::{anyhow, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Which feed a tab shows.  Written as for `:tab`, e.g. "home" or "search rust lang".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FeedSource {
+    #[default]
+    Home,
+    Mentions,
+    /// Mentions, replies to my tweets and quotes of my tweets, polled
+    Activity,
+    Search(String),
+    /// Username, without the leading '@'
+    User(String),
+    /// List name; "starred" for the starred accounts
+    List(String),
+    /// Starred accounts with this tag, without the leading '#'
+    Tag(String),
+    /// A local collection of saved tweets, see [crate::collections]
+    Collection(String),
+    /// Username: their tweets since the user last caught up on them, see
+    /// [crate::store::Store::load_catch_up].
+    /// Not saved with the session, since reopening it would find nothing new
+    CatchUp(String),
+}
+
+impl FromStr for FeedSource {
+    type Err = Error;
+
+    /// Empty means home.
+    fn from_str(spec: &str) -> Result<Self> {
+        let (kind, rest) = match spec.trim().split_once(char::is_whitespace) {
+            Some((kind, rest)) => (kind, rest.trim()),
+            None => (spec.trim(), ""),
+        };
+        match (kind, rest) {
+            ("" | "home", "") => Ok(FeedSource::Home),
+            ("mentions", "") => Ok(FeedSource::Mentions),
+            ("activity", "") => Ok(FeedSource::Activity),
+            ("search", query) if !query.is_empty() => Ok(FeedSource::Search(query.to_string())),
+            ("user", username) if !username.is_empty() => Ok(FeedSource::User(
+                username.trim_start_matches('@').to_string(),
+            )),
+            ("list", name) if !name.is_empty() => Ok(FeedSource::List(name.to_string())),
+            ("tag", tag) if !tag.is_empty() => Ok(FeedSource::Tag(normalize_tag(tag))),
+            ("collection", name) if !name.is_empty() => {
+                Ok(FeedSource::Collection(name.to_string()))
+            }
+            ("catchup", username) if !username.is_empty() => Ok(FeedSource::CatchUp(
+                username.trim_start_matches('@').to_string(),
+            )),
+            _ => Err(anyhow!(
+                "Expected home, mentions, activity, search <query>, user @<username>, list <name>, \
+                 tag <tag>, collection <name> or catchup @<username>, not `{spec}`"
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for FeedSource {
+    type Error = Error;
+
+    fn try_from(spec: String) -> Result<Self> {
+        spec.parse()
+    }
+}
+
+impl From<FeedSource> for String {
+    fn from(feed_source: FeedSource) -> Self {
+        match feed_source {
+            FeedSource::Home => "home".to_string(),
+            FeedSource::Mentions => "mentions".to_string(),
+            FeedSource::Activity => "activity".to_string(),
+            FeedSource::Search(query) => format!("search {query}"),
+            FeedSource::User(username) => format!("user @{username}"),
+            FeedSource::List(name) => format!("list {name}"),
+            FeedSource::Tag(tag) => format!("tag {tag}"),
+            FeedSource::Collection(name) => format!("collection {name}"),
+            FeedSource::CatchUp(username) => format!("catchup @{username}"),
+        }
+    }
+}
+
+impl FeedSource {
+    pub fn title(&self) -> String {
+        match self {
+            FeedSource::Home => "Home".to_string(),
+            FeedSource::Mentions => "Mentions".to_string(),
+            FeedSource::Activity => "Activity".to_string(),
+            FeedSource::Search(query) => format!("Search: {query}"),
+            FeedSource::User(username) => format!("@{username}"),
+            FeedSource::List(name) => format!("List: {name}"),
+            FeedSource::Tag(tag) => format!("#{tag}"),
+            FeedSource::Collection(name) => format!("Collection: {name}"),
+            FeedSource::CatchUp(username) => format!("Catch up: @{username}"),
+        }
+    }
+}
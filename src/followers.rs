@@ -0,0 +1,362 @@
+//! Follower snapshots: my followers list saved now and then under [FOLLOWERS_DIR] (one JSON file
+//! each, named by when it was taken), so that who followed and who unfollowed can be worked out by
+//! diffing two of them.  Also [Relationships], who I follow and who follows me, for badging authors.
+
+use crate::twitter_client::api;
+use crate::twitter_client::paginator::Paginator;
+use crate::twitter_client::TwitterApi;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const FOLLOWERS_DIR: &str = "./var/followers";
+
+/// Older snapshots are deleted as new ones are saved.
+const MAX_SNAPSHOTS: usize = 30;
+
+/// NB: sorts the same as the time it names, so the newest snapshot has the greatest file name
+const FILE_NAME_FORMAT: &str = "%Y%m%dT%H%M%S%.6fZ.json";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub taken_at: DateTime<Utc>,
+    pub followers: Vec<api::User>,
+}
+
+/// How I relate to another account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relationship {
+    Following,
+    FollowedBy,
+    Mutual,
+}
+
+/// Who I follow and who follows me, by user id, as of [Relationships::refreshed_at].
+#[derive(Debug, Clone, Default)]
+pub struct Relationships {
+    following: HashSet<String>,
+    followers: HashSet<String>,
+    /// None until first loaded, when there are no relationships to show
+    pub refreshed_at: Option<DateTime<Utc>>,
+}
+
+/// Who followed and who unfollowed between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowerDiff {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub new_followers: Vec<api::User>,
+    pub unfollowers: Vec<api::User>,
+    pub num_followers: usize,
+}
+
+impl Snapshot {
+    /// Fetches every page of [user_id]'s followers.  NB: unlike a feed, a partial list is no good
+    /// (everyone missing would look like an unfollow), so any failed page fails the snapshot
+    pub async fn take<A: TwitterApi>(twitter_client: &A, user_id: &str) -> Result<Self> {
+        let mut paginator =
+            Paginator::new(|page_token| twitter_client.followers(user_id, page_token));
+        Ok(Self {
+            taken_at: Utc::now(),
+            followers: paginator.collect_all().await?,
+        })
+    }
+
+    /// Saves to [dir], deleting all but the latest [MAX_SNAPSHOTS].
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(self.taken_at.format(FILE_NAME_FORMAT).to_string());
+        fs::write(path, serde_json::to_string(self)?)?;
+
+        let paths = snapshot_paths(dir)?;
+        for path in paths.iter().take(paths.len().saturating_sub(MAX_SNAPSHOTS)) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// The latest snapshot in [dir] taken no later than [at] (the oldest kept, if they're all
+    /// later), or the latest of all if [at] is None.
+    pub fn load_at(dir: &Path, at: Option<DateTime<Utc>>) -> Result<Option<Self>> {
+        let paths = snapshot_paths(dir)?;
+        let path = match at {
+            None => paths.last(),
+            Some(at) => {
+                let at_name = at.format(FILE_NAME_FORMAT).to_string();
+                paths
+                    .iter()
+                    .rev()
+                    .find(|path| path.file_name().is_some_and(|name| *name <= *at_name))
+                    .or(paths.first())
+            }
+        };
+        path.map(|path| Self::load(path)).transpose()
+    }
+
+    /// What changed from [earlier] to this snapshot.
+    pub fn diff(&self, earlier: &Snapshot) -> FollowerDiff {
+        let ids = |snapshot: &Snapshot| -> HashSet<String> {
+            snapshot
+                .followers
+                .iter()
+                .map(|user| user.id.clone())
+                .collect()
+        };
+        let (earlier_ids, ids) = (ids(earlier), ids(self));
+        FollowerDiff {
+            since: earlier.taken_at,
+            until: self.taken_at,
+            new_followers: self
+                .followers
+                .iter()
+                .filter(|user| !earlier_ids.contains(&user.id))
+                .cloned()
+                .collect(),
+            unfollowers: earlier
+                .followers
+                .iter()
+                .filter(|user| !ids.contains(&user.id))
+                .cloned()
+                .collect(),
+            num_followers: self.followers.len(),
+        }
+    }
+}
+
+impl Relationship {
+    /// Shown after the handle, e.g. "↔" for mutuals
+    pub fn badge(&self) -> &'static str {
+        match self {
+            Relationship::Following => "→",
+            Relationship::FollowedBy => "←",
+            Relationship::Mutual => "↔",
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Relationship::Following => "you follow",
+            Relationship::FollowedBy => "follows you",
+            Relationship::Mutual => "you follow each other",
+        }
+    }
+}
+
+impl Relationships {
+    /// Fetches every page of who [user_id] follows and who follows them.
+    pub async fn load<A: TwitterApi>(twitter_client: &A, user_id: &str) -> Result<Self> {
+        let mut following =
+            Paginator::new(|page_token| twitter_client.following(user_id, page_token));
+        let mut followers =
+            Paginator::new(|page_token| twitter_client.followers(user_id, page_token));
+        let (following, followers) =
+            tokio::try_join!(following.collect_all(), followers.collect_all())?;
+        let ids = |users: Vec<api::User>| users.into_iter().map(|user| user.id).collect();
+        Ok(Self {
+            following: ids(following),
+            followers: ids(followers),
+            refreshed_at: Some(Utc::now()),
+        })
+    }
+
+    pub fn get(&self, user_id: &str) -> Option<Relationship> {
+        match (
+            self.following.contains(user_id),
+            self.followers.contains(user_id),
+        ) {
+            (true, true) => Some(Relationship::Mutual),
+            (true, false) => Some(Relationship::Following),
+            (false, true) => Some(Relationship::FollowedBy),
+            (false, false) => None,
+        }
+    }
+}
+
+impl FollowerDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new_followers.is_empty() && self.unfollowers.is_empty()
+    }
+
+    /// E.g. "2 new followers, 1 unfollowed"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} new follower{}, {} unfollowed",
+            self.new_followers.len(),
+            if self.new_followers.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            self.unfollowers.len()
+        )
+    }
+
+    /// For showing in full, a section for each of the new followers and the unfollowers.
+    pub fn describe(&self) -> String {
+        let mut out = format!(
+            "{} followers; since {}: {}\n",
+            self.num_followers,
+            self.since.format("%Y-%m-%d %H:%M UTC"),
+            self.summary()
+        );
+        for (heading, users) in [
+            ("New followers", &self.new_followers),
+            ("Unfollowed you", &self.unfollowers),
+        ] {
+            // NB: writing to a String can't fail
+            let _ = write!(out, "\n{heading}\n");
+            if users.is_empty() {
+                out.push_str("  <none>\n");
+            }
+            for user in users {
+                let _ = writeln!(out, "  @{} ({})", user.username, user.name);
+            }
+        }
+        out
+    }
+}
+
+/// The snapshot files in [dir], oldest first.
+fn snapshot_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Takes and saves a snapshot, returning what changed since the one at [since] (see
+/// [Snapshot::load_at]), or None if it's the first.
+pub async fn check_followers<A: TwitterApi>(
+    twitter_client: &A,
+    user_id: &str,
+    dir: &Path,
+    since: Option<DateTime<Utc>>,
+) -> Result<Option<FollowerDiff>> {
+    let snapshot = Snapshot::take(twitter_client, user_id).await?;
+    let earlier = Snapshot::load_at(dir, since)?;
+    snapshot.save(dir)?;
+    Ok(earlier.map(|earlier| snapshot.diff(&earlier)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+    use crate::twitter_client::mock::MockTwitterClient;
+    use chrono::Duration;
+    use std::{env, process};
+
+    const FOLLOWERS: &str = "/2/users/1/followers";
+
+    #[tokio::test]
+    async fn test_check_followers() {
+        let dir = env::temp_dir().join(format!("twitter-tool-followers-{}", process::id()));
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(
+            FOLLOWERS,
+            r#"{"data": [{"id": "a", "name": "A", "username": "a"}],
+                "meta": {"result_count": 1, "next_token": "page_2"}}"#,
+        );
+        twitter_client.respond(
+            FOLLOWERS,
+            r#"{"data": [{"id": "b", "name": "B", "username": "b"}], "meta": {"result_count": 1}}"#,
+        );
+        twitter_client.respond(
+            FOLLOWERS,
+            r#"{"data": [{"id": "b", "name": "B", "username": "b"},
+                         {"id": "c", "name": "C", "username": "c"}],
+                "meta": {"result_count": 2}}"#,
+        );
+
+        let first = check_followers(&twitter_client, "1", &dir, None).await;
+        assert_eq!(first.unwrap(), None);
+        let diff = check_followers(&twitter_client, "1", &dir, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.new_followers, vec![fixtures::user("c", "c")]);
+        assert_eq!(diff.unfollowers, vec![fixtures::user("a", "a")]);
+        assert_eq!(diff.summary(), "1 new follower, 1 unfollowed");
+        assert!(diff.describe().contains("\nUnfollowed you\n  @a (A)\n"));
+
+        // NB: a failed page fails the whole snapshot
+        twitter_client.fail(FOLLOWERS, "429 Too Many Requests");
+        assert!(check_followers(&twitter_client, "1", &dir, None)
+            .await
+            .is_err());
+        assert_eq!(snapshot_paths(&dir).unwrap().len(), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_relationships() {
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(
+            "/2/users/1/following",
+            r#"{"data": [{"id": "a", "name": "A", "username": "a"},
+                         {"id": "b", "name": "B", "username": "b"}],
+                "meta": {"result_count": 2}}"#,
+        );
+        twitter_client.respond(
+            FOLLOWERS,
+            r#"{"data": [{"id": "b", "name": "B", "username": "b"},
+                         {"id": "c", "name": "C", "username": "c"}],
+                "meta": {"result_count": 2}}"#,
+        );
+
+        let relationships = Relationships::load(&twitter_client, "1").await.unwrap();
+        assert_eq!(relationships.get("a"), Some(Relationship::Following));
+        assert_eq!(relationships.get("b"), Some(Relationship::Mutual));
+        assert_eq!(relationships.get("c"), Some(Relationship::FollowedBy));
+        assert_eq!(relationships.get("d"), None);
+        assert!(relationships.refreshed_at.is_some());
+    }
+
+    #[test]
+    fn test_load_at() {
+        let dir = env::temp_dir().join(format!("twitter-tool-snapshots-{}", process::id()));
+        let start = Utc::now();
+        for (i, id) in ["a", "b", "c"].iter().enumerate() {
+            let snapshot = Snapshot {
+                taken_at: start + Duration::hours(i as i64),
+                followers: vec![fixtures::user(id, id)],
+            };
+            snapshot.save(&dir).unwrap();
+        }
+        let followers = |at| Snapshot::load_at(&dir, at).unwrap().unwrap().followers;
+        assert_eq!(followers(None), vec![fixtures::user("c", "c")]);
+        assert_eq!(
+            followers(Some(start + Duration::minutes(90))),
+            vec![fixtures::user("b", "b")]
+        );
+        assert_eq!(
+            followers(Some(start - Duration::hours(1))),
+            vec![fixtures::user("a", "a")]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+/// Replaces [path]'s contents with [bytes], creating its directory if need be.
+///
+/// NB: written to a temporary file beside [path] then renamed over it, so a crash mid-write leaves
+/// either the old contents or the new, never a truncated file.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = env::temp_dir().join(format!("twitter-tool-fs-util-{}", process::id()));
+        let path = dir.join("state.json");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
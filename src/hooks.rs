@@ -0,0 +1,173 @@
+//! Hooks run on new tweets from starred accounts, or matching an [alert rule], e.g. for alerting:
+//! each is given the tweet as JSON.  Configured in [UserConfig::hooks].
+//!
+//! [alert rule]: crate::alerts::AlertRule
+
+use crate::twitter_client::api;
+use crate::user_config::UserConfig;
+use anyhow::{anyhow, Error, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::process::Stdio;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::{process, time};
+
+/// How long a hook has to finish, so that a stuck one doesn't hold up watch mode or the UI's tasks
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shared by every webhook call
+fn webhook_client() -> &'static Client<HttpsConnector<HttpConnector>> {
+    static CLIENT: OnceLock<Client<HttpsConnector<HttpConnector>>> = OnceLock::new();
+    CLIENT.get_or_init(|| Client::builder().build(HttpsConnector::new()))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Hook {
+    /// POST the tweet to [url]
+    Webhook { url: String },
+    /// Run [command] with `sh -c`, the tweet on stdin.  NB: its output is discarded, since it would
+    /// garble the UI.
+    Command { command: String },
+}
+
+impl Hook {
+    pub async fn run(&self, tweet: &api::Tweet) -> Result<()> {
+        self.run_within(tweet, HOOK_TIMEOUT).await
+    }
+
+    /// Fails a hook that takes longer than [timeout], killing its command if it has one.
+    async fn run_within(&self, tweet: &api::Tweet, timeout: Duration) -> Result<()> {
+        let json = serde_json::to_vec(tweet)?;
+        match self {
+            Hook::Webhook { url } => {
+                let req = Request::post(url)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json))?;
+                let status = time::timeout(timeout, webhook_client().request(req))
+                    .await
+                    .map_err(|_| anyhow!("Webhook {url} timed out after {timeout:?}"))??
+                    .status();
+                if !status.is_success() {
+                    return Err(anyhow!("Webhook {url} responded {status}"));
+                }
+            }
+            Hook::Command { command } => {
+                let mut child = process::Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+                let mut stdin = child.stdin.take().unwrap();
+                let result = time::timeout(timeout, async {
+                    // NB: a command that doesn't read the tweet may well exit before it's written
+                    match stdin.write_all(&json).await {
+                        Err(error) if error.kind() != io::ErrorKind::BrokenPipe => {
+                            return Err(error)
+                        }
+                        _ => (),
+                    }
+                    // NB: closes stdin, so the command sees the end of the tweet
+                    drop(stdin);
+                    child.wait().await
+                })
+                .await;
+                let Ok(status) = result else {
+                    child.kill().await?;
+                    return Err(anyhow!("Hook `{command}` timed out after {timeout:?}"));
+                };
+                let status = status?;
+                if !status.success() {
+                    return Err(anyhow!("Hook `{command}` {status}"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs every hook on each of [tweets] that's by a starred account or matches an alert rule;
+/// returns what went wrong.
+pub async fn run_hooks(user_config: &UserConfig, tweets: &[api::Tweet]) -> Vec<Error> {
+    let mut errors = Vec::new();
+    for tweet in tweets {
+        let is_alert = user_config.alerts.iter().any(|rule| rule.matches(tweet));
+        if !user_config.is_starred(&tweet.author_id) && !is_alert {
+            continue;
+        }
+        for hook in user_config.hooks.iter() {
+            if let Err(error) = hook.run(tweet).await {
+                errors.push(error.context(format!("Running hook for tweet {}", tweet.id)));
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertRule;
+    use crate::store::fixtures;
+    use std::{env, fs, process};
+
+    #[tokio::test]
+    async fn test_run_hooks() {
+        let path = env::temp_dir().join(format!("twitter-tool-hook-{}", process::id()));
+        let mut user_config = UserConfig::default();
+        user_config.hooks = vec![
+            Hook::Command {
+                command: format!("cat >> {}", path.display()),
+            },
+            Hook::Command {
+                command: "exit 3".to_string(),
+            },
+        ];
+        let alice = fixtures::tweet("1", "alice", "hello", 0);
+        user_config.star_account(&alice.author("[unknown]"));
+        let bob = fixtures::tweet("2", "bob", "ignored", 1);
+        // NB: not starred, but matches an alert
+        user_config.alerts = vec![AlertRule {
+            keyword: "urgent".to_string(),
+            author: None,
+        }];
+        let carol = fixtures::tweet("3", "carol", "URGENT", 2);
+
+        let errors = run_hooks(&user_config, &[alice, bob, carol]).await;
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            format!("{:#}", errors[0]),
+            "Running hook for tweet 1: Hook `exit 3` exit status: 3"
+        );
+        let jsons: Vec<serde_json::Value> =
+            serde_json::Deserializer::from_str(&fs::read_to_string(&path).unwrap())
+                .into_iter()
+                .map(Result::unwrap)
+                .collect();
+        assert_eq!(jsons.len(), 2);
+        assert_eq!(jsons[0]["text"], "hello");
+        assert_eq!(jsons[1]["text"], "URGENT");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let hook = Hook::Command {
+            command: "sleep 10".to_string(),
+        };
+        let tweet = fixtures::tweet("1", "alice", "hello", 0);
+        let error = hook
+            .run_within(&tweet, Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Hook `sleep 10` timed out after 100ms");
+    }
+}
@@ -0,0 +1,81 @@
+//! JSON over HTTPS to services other than Twitter, e.g. for [crate::cross_post] and
+//! [crate::translate].
+
+use anyhow::{anyhow, Result};
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Request};
+use hyper_tls::HttpsConnector;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::time;
+
+/// How long a request gets, from sending it to reading the whole response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Shared by every request, so connections are pooled
+fn client() -> &'static Client<HttpsConnector<HttpConnector>> {
+    static CLIENT: OnceLock<Client<HttpsConnector<HttpConnector>>> = OnceLock::new();
+    CLIENT.get_or_init(|| Client::builder().build(HttpsConnector::new()))
+}
+
+/// POSTs [body] to [url], with [authorization] as the Authorization header if given, e.g.
+/// "Bearer ...".
+pub async fn post_json(
+    url: &str,
+    authorization: Option<&str>,
+    body: serde_json::Value,
+) -> Result<serde_json::Value> {
+    post_json_within(url, authorization, body, REQUEST_TIMEOUT).await
+}
+
+/// Fails a request that takes longer than [timeout], response body included.
+async fn post_json_within(
+    url: &str,
+    authorization: Option<&str>,
+    body: serde_json::Value,
+    timeout: Duration,
+) -> Result<serde_json::Value> {
+    let req = Request::post(url).header("Content-Type", "application/json");
+    let req = match authorization {
+        Some(authorization) => req.header("Authorization", authorization),
+        None => req,
+    };
+    let req = req.body(Body::from(serde_json::to_vec(&body)?))?;
+    let (status, resp) = time::timeout(timeout, async {
+        let resp = client().request(req).await?;
+        let status = resp.status();
+        Ok::<_, hyper::Error>((status, hyper::body::to_bytes(resp.into_body()).await?))
+    })
+    .await
+    .map_err(|_| anyhow!("{url} timed out after {timeout:?}"))??;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "{status}: {}",
+            String::from_utf8_lossy(&resp).trim()
+        ));
+    }
+    Ok(serde_json::from_slice(&resp)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_timeout() {
+        // NB: accepts the connection, but never responds
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let _accept = tokio::spawn(async move {
+            let (_stream, _) = listener.accept().await.unwrap();
+            time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let body = serde_json::json!({});
+        let error = post_json_within(&url, None, body, Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), format!("{url} timed out after 100ms"));
+    }
+}
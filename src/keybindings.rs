@@ -0,0 +1,355 @@
+//! Actions and the keys they can be bound to, as written in [UserConfig::keybindings].  Nothing
+//! here needs a terminal; which key does what in one is [KeyMap].
+//!
+//! [UserConfig::keybindings]: crate::user_config::UserConfig::keybindings
+//! [KeyMap]: crate::ui::action::KeyMap
+
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Something the user can do from the feed with a single key.  Keys are mapped to actions by the
+/// [KeyMap], and actions are carried out by whichever of [FeedPane::dispatch] and [UI::dispatch]
+/// knows how, so behaviours can be driven (and tested) without a terminal.
+///
+/// [KeyMap]: crate::ui::action::KeyMap
+/// [FeedPane::dispatch]: crate::ui::feed_pane::FeedPane::dispatch
+/// [UI::dispatch]: crate::ui::UI::dispatch
+///
+/// In the config, actions are named in snake_case, e.g. "open_tweet_finder" or "switch_tab_3".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Action {
+    // Feed
+    OpenTweetFinder,
+    Compose,
+    ComposeReply,
+    OpenDrafts,
+    InspectTweet,
+    OpenTweetInBrowser,
+    OpenTweetUrls,
+    CopyTweetAsMarkdown,
+    CopyTweetAsHtml,
+    JumpToParent,
+    OpenConversation,
+    TranslateTweet,
+    ShowEditHistory,
+    RevealSensitive,
+    LoadNextPage,
+    Refresh,
+    ToggleStarred,
+    CatchUpOnAuthor,
+    EditStarred,
+    EditNote,
+    AddToCollection,
+    SearchStarred,
+    OpenSearchBar,
+    FilterFeed,
+    ShowStarred,
+    ToggleMarked,
+    MarkRange,
+    OpenAuthors,
+    MuteAuthors,
+    // App
+    Redraw,
+    OpenCommandBar,
+    ShowErrors,
+    RetryLoad,
+    AbortTasks,
+    /// The 'g' of gt/gT
+    TabPrefix,
+    /// Zero-based
+    SwitchTab(usize),
+    Quit,
+}
+
+/// Every action but [Action::SwitchTab], which is named by number, by name
+const ACTION_NAMES: &[(&str, Action)] = &[
+    ("open_tweet_finder", Action::OpenTweetFinder),
+    ("compose", Action::Compose),
+    ("compose_reply", Action::ComposeReply),
+    ("open_drafts", Action::OpenDrafts),
+    ("inspect_tweet", Action::InspectTweet),
+    ("open_tweet_in_browser", Action::OpenTweetInBrowser),
+    ("open_tweet_urls", Action::OpenTweetUrls),
+    ("copy_tweet_as_markdown", Action::CopyTweetAsMarkdown),
+    ("copy_tweet_as_html", Action::CopyTweetAsHtml),
+    ("jump_to_parent", Action::JumpToParent),
+    ("open_conversation", Action::OpenConversation),
+    ("translate_tweet", Action::TranslateTweet),
+    ("show_edit_history", Action::ShowEditHistory),
+    ("reveal_sensitive", Action::RevealSensitive),
+    ("load_next_page", Action::LoadNextPage),
+    ("refresh", Action::Refresh),
+    ("toggle_starred", Action::ToggleStarred),
+    ("catch_up_on_author", Action::CatchUpOnAuthor),
+    ("edit_starred", Action::EditStarred),
+    ("edit_note", Action::EditNote),
+    ("add_to_collection", Action::AddToCollection),
+    ("search_starred", Action::SearchStarred),
+    ("open_search_bar", Action::OpenSearchBar),
+    ("filter_feed", Action::FilterFeed),
+    ("show_starred", Action::ShowStarred),
+    ("toggle_marked", Action::ToggleMarked),
+    ("mark_range", Action::MarkRange),
+    ("open_authors", Action::OpenAuthors),
+    ("mute_authors", Action::MuteAuthors),
+    ("redraw", Action::Redraw),
+    ("open_command_bar", Action::OpenCommandBar),
+    ("show_errors", Action::ShowErrors),
+    ("retry_load", Action::RetryLoad),
+    ("abort_tasks", Action::AbortTasks),
+    ("tab_prefix", Action::TabPrefix),
+    ("quit", Action::Quit),
+];
+
+impl TryFrom<String> for Action {
+    type Error = Error;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        if let Some(action) = ACTION_NAMES
+            .iter()
+            .find(|(action_name, _)| *action_name == name)
+            .map(|(_, action)| *action)
+        {
+            return Ok(action);
+        }
+        match name.strip_prefix("switch_tab_").map(str::parse::<usize>) {
+            Some(Ok(tab)) if tab >= 1 => Ok(Action::SwitchTab(tab - 1)),
+            _ => Err(anyhow!("Unknown action `{name}`")),
+        }
+    }
+}
+
+impl From<Action> for String {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::SwitchTab(index) => format!("switch_tab_{}", index + 1),
+            _ => ACTION_NAMES
+                .iter()
+                .find(|(_, a)| *a == action)
+                .map(|(name, _)| name.to_string())
+                .unwrap(),
+        }
+    }
+}
+
+impl Action {
+    pub fn describe(&self) -> String {
+        match self {
+            Action::OpenTweetFinder => "Find a tweet".to_string(),
+            Action::Compose => "Compose a thread".to_string(),
+            Action::ComposeReply => "Reply to the selected tweet".to_string(),
+            Action::OpenDrafts => "Show drafts".to_string(),
+            Action::InspectTweet => "Show the selected tweet's JSON".to_string(),
+            Action::OpenTweetInBrowser => "Open the selected tweet in the browser".to_string(),
+            Action::OpenTweetUrls => "Open the selected tweet's URLs".to_string(),
+            Action::CopyTweetAsMarkdown => {
+                "Copy the selected tweet as a Markdown quote".to_string()
+            }
+            Action::CopyTweetAsHtml => "Copy the selected tweet as an HTML embed".to_string(),
+            Action::JumpToParent => "Jump to the tweet being replied to".to_string(),
+            Action::OpenConversation => "Show the selected tweet's conversation".to_string(),
+            Action::TranslateTweet => "Translate the tweet in the tweet pane".to_string(),
+            Action::ShowEditHistory => {
+                "Show the other versions of the edited tweet in the tweet pane".to_string()
+            }
+            Action::RevealSensitive => "Reveal or collapse a sensitive tweet".to_string(),
+            Action::LoadNextPage => "Load the next page".to_string(),
+            Action::Refresh => "Reload the home timeline".to_string(),
+            Action::ToggleStarred => "Star or unstar the author".to_string(),
+            Action::CatchUpOnAuthor => {
+                "Open the author's tweets since you last caught up on them".to_string()
+            }
+            Action::EditStarred => "Edit the author's notes and tags, starring them".to_string(),
+            Action::EditNote => "Edit your note on the selected tweet".to_string(),
+            Action::AddToCollection => {
+                "Save the marked (or selected) tweets to a collection".to_string()
+            }
+            Action::SearchStarred => "Load tweets by starred accounts".to_string(),
+            Action::OpenSearchBar => "Search".to_string(),
+            Action::FilterFeed => "Filter the loaded feed as you type".to_string(),
+            Action::ShowStarred => "List starred accounts".to_string(),
+            Action::ToggleMarked => {
+                "Mark or unmark the selected tweet, for bulk actions".to_string()
+            }
+            Action::MarkRange => {
+                "Mark the tweets from the last one marked to the selected one".to_string()
+            }
+            Action::OpenAuthors => {
+                "Open the authors of the marked (or selected) tweets in tabs".to_string()
+            }
+            Action::MuteAuthors => {
+                "Mute the authors of the marked (or selected) tweets".to_string()
+            }
+            Action::Redraw => "Redraw the screen".to_string(),
+            Action::OpenCommandBar => "Enter a command".to_string(),
+            Action::ShowErrors => "Show errors".to_string(),
+            Action::RetryLoad => "Retry a failed load".to_string(),
+            Action::AbortTasks => "Cancel loads in flight".to_string(),
+            Action::TabPrefix => "gt/gT: next/previous tab".to_string(),
+            Action::SwitchTab(index) => format!("Go to tab {}", index + 1),
+            Action::Quit => "Quit".to_string(),
+        }
+    }
+}
+
+/// A key that can be bound: crossterm's `KeyCode`, less the keys that can't be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Char(char),
+    F(u8),
+    Esc,
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// A key as written in the config, e.g. "x", "G", "ctrl-f", "alt-enter" or "pagedown".  NB: shift
+/// is never set for characters, since it's already in the character
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key {
+    pub code: KeyCode,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("esc", KeyCode::Esc),
+    ("enter", KeyCode::Enter),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+impl TryFrom<String> for Key {
+    type Error = Error;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        let (mut ctrl, mut alt, mut shift) = (false, false, false);
+        let mut rest = spec.as_str();
+        // NB: "-" on its own is the minus key, not a separator
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" => ctrl = true,
+                "alt" => alt = true,
+                "shift" => shift = true,
+                _ => return Err(anyhow!("Unknown modifier `{modifier}` in key `{spec}`")),
+            };
+            rest = key;
+        }
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ if rest.eq_ignore_ascii_case("space") => KeyCode::Char(' '),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|(name, _)| rest.eq_ignore_ascii_case(name))
+                .map(|(_, code)| *code)
+                .or_else(|| {
+                    let n: u8 = rest.strip_prefix(['f', 'F'])?.parse().ok()?;
+                    (1..=12).contains(&n).then_some(KeyCode::F(n))
+                })
+                .ok_or(anyhow!("Unknown key `{spec}`"))?,
+        };
+        let shift = shift && !matches!(code, KeyCode::Char(_));
+        Ok(Self {
+            code,
+            ctrl,
+            alt,
+            shift,
+        })
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        let mut spec = String::new();
+        for (is_set, name) in [
+            (key.ctrl, "ctrl-"),
+            (key.alt, "alt-"),
+            (key.shift, "shift-"),
+        ] {
+            if is_set {
+                spec.push_str(name);
+            }
+        }
+        match key.code {
+            KeyCode::Char(' ') => spec.push_str("space"),
+            KeyCode::Char(ch) => spec.push(ch),
+            KeyCode::F(n) => spec.push_str(&format!("f{n}")),
+            code => spec.push_str(
+                NAMED_KEYS
+                    .iter()
+                    .find(|(_, c)| *c == code)
+                    .map_or("unknown", |(name, _)| name),
+            ),
+        }
+        spec
+    }
+}
+
+// NB: ordered by name, so keybindings are saved in a stable order
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        String::from(*self).cmp(&String::from(*other))
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_and_action_names() {
+        let parse = |spec: &str| Key::try_from(spec.to_string()).map(String::from);
+        assert_eq!(parse("ctrl-F").unwrap(), "ctrl-F");
+        assert_eq!(parse("Ctrl-Alt-pagedown").unwrap(), "ctrl-alt-pagedown");
+        assert_eq!(parse("-").unwrap(), "-");
+        assert_eq!(parse("space").unwrap(), "space");
+        assert_eq!(parse("f12").unwrap(), "f12");
+        assert_eq!(parse("shift-G").unwrap(), "G");
+        assert_eq!(
+            parse("hyper-x").unwrap_err().to_string(),
+            "Unknown modifier `hyper` in key `hyper-x`"
+        );
+        assert_eq!(parse("f13").unwrap_err().to_string(), "Unknown key `f13`");
+
+        let action = |name: &str| Action::try_from(name.to_string());
+        assert_eq!(action("compose_reply").unwrap(), Action::ComposeReply);
+        assert_eq!(action("switch_tab_3").unwrap(), Action::SwitchTab(2));
+        assert_eq!(String::from(Action::SwitchTab(2)), "switch_tab_3");
+        assert!(action("switch_tab_0").is_err());
+        assert_eq!(
+            action("launch").unwrap_err().to_string(),
+            "Unknown action `launch`"
+        );
+    }
+}
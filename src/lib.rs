@@ -1,5 +1,37 @@
+//! A Twitter client for the terminal.
+//!
+//! The [twitter_client] and [store] are usable on their own: with `default-features = false`, the
+//! crate leaves out the terminal UI (and crossterm), see [twitter_client::TwitterClientBuilder].
+
+pub mod alerts;
+#[cfg(feature = "tui")]
+pub mod analytics;
+pub mod backend;
+#[cfg(feature = "tui")]
+pub mod cli;
+pub mod collections;
+pub mod compose;
+pub mod cross_post;
+pub mod digest;
+pub mod drafts;
+pub mod feed_source;
+pub mod followers;
+pub mod fs_util;
+pub mod hooks;
+pub mod http_client;
+pub mod keybindings;
+pub mod media_cache;
+pub mod plugins;
+#[cfg(feature = "tui")]
+pub mod server;
+pub mod spam;
 pub mod store;
+pub mod text_measure;
+pub mod translate;
 pub mod twitter_client;
+#[cfg(feature = "tui")]
 pub mod ui;
+#[cfg(feature = "tui")]
 pub mod ui_framework;
+pub mod user_cache;
 pub mod user_config;
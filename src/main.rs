@@ -1,15 +1,130 @@
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand};
 use dotenvy::dotenv;
 use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use std::{env, fs, io};
-use twitter_tool::{twitter_client::TwitterClient, ui, user_config::UserConfig};
+use twitter_tool::backend::{ApiBackend, Backend, BackendKind, NitterBackend};
+use twitter_tool::cli::{self, OutputFormat};
+use twitter_tool::compose;
+use twitter_tool::digest;
+use twitter_tool::feed_source::FeedSource;
+use twitter_tool::server::ApiServer;
+use twitter_tool::twitter_client::{self, api, quota, ClientType, TwitterApi, TwitterClient};
+use twitter_tool::ui;
+use twitter_tool::ui::session::{self, Session};
+use twitter_tool::user_config::{self, UserConfig};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     login: bool,
+
+    /// Save every API response to a session capture in this directory
+    #[arg(long, global = true, value_name = "DIR")]
+    record: Option<PathBuf>,
+
+    /// Replay the session capture in this directory instead of calling Twitter
+    #[arg(long, global = true, value_name = "DIR", conflicts_with = "record")]
+    replay: Option<PathBuf>,
+
+    /// The config file
+    #[arg(long, global = true, value_name = "PATH", default_value = user_config::DEFAULT_PATH)]
+    config: PathBuf,
+
+    /// How subcommands that print results format them
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    format: OutputFormat,
+
+    /// Open the configured feeds rather than where the last session left off
+    #[arg(long, global = true)]
+    fresh: bool,
+
+    /// Where timeline, search and tweets read from; everything else needs the API
+    #[arg(long, global = true, value_enum, default_value_t = BackendKind::Api)]
+    backend: BackendKind,
+
+    /// The Nitter instance for --backend nitter
+    #[arg(long, global = true, default_value = "https://nitter.net")]
+    nitter_instance: String,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the interactive terminal UI (default)
+    Tui {
+        /// Start on the results of this search instead of the home timeline
+        #[arg(long, conflicts_with = "user")]
+        query: Option<String>,
+
+        /// Start on this user's tweets instead of the home timeline, e.g. @foo
+        #[arg(long)]
+        user: Option<String>,
+    },
+    /// Serve a read-only JSON API on localhost, reusing the authenticated client
+    Serve {
+        #[arg(short, long, default_value_t = 8787)]
+        port: u16,
+    },
+    /// Print the home timeline
+    Timeline,
+    /// Print recent tweets matching a search
+    Search { query: String },
+    /// Print a user, e.g. @foo
+    User { username: String },
+    /// Print a user's recent tweets, e.g. @foo
+    Tweets { username: String },
+    /// Print tweets matching a search as they appear, polling every --interval seconds
+    Watch {
+        query: String,
+
+        #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+    /// Print, or write to a file, a Markdown digest of starred accounts' tweets, grouped by account
+    Digest {
+        /// How far back to go, e.g. 90m, 24h, 7d or 2w
+        #[arg(long, default_value = "24h", value_parser = digest::parse_period)]
+        since: chrono::Duration,
+
+        /// Write the digest here instead of printing it
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Post a tweet, or a thread with "---" lines between tweets; prints the posted ids
+    Post {
+        /// The text, or - to read it from stdin
+        #[arg(required_unless_present = "file", conflicts_with = "file")]
+        text: Option<String>,
+
+        /// Read the text from this file, e.g. a draft
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Post in reply to this tweet id
+        #[arg(long)]
+        reply_to: Option<String>,
+    },
+}
+
+/// The tweets for [command] from [backend], if it's a command that only reads a timeline.
+async fn read_timeline(
+    backend: &dyn Backend,
+    command: &Command,
+) -> Option<Result<Vec<api::Tweet>>> {
+    match command {
+        Command::Timeline => Some(backend.home_timeline().await),
+        Command::Search { query } => Some(backend.search(query).await),
+        Command::Tweets { username } => {
+            Some(backend.user_tweets(username.trim_start_matches('@')).await)
+        }
+        _ => None,
+    }
 }
 
 #[tokio::main]
@@ -18,26 +133,196 @@ async fn main() -> Result<()> {
 
     dotenv().ok();
 
-    let twitter_client_id = env::var("TWITTER_CLIENT_ID")?;
-    let twitter_client_secret = env::var("TWITTER_CLIENT_SECRET")?;
-    let mut twitter_client = TwitterClient::new(&twitter_client_id, &twitter_client_secret);
-    twitter_client.load_auth().or_else(|_| {
-        eprintln!("No auth file found, must login");
-        Ok::<_, Infallible>(())
-    })?;
-    twitter_client.authorize(!args.login).await?;
-    twitter_client.save_auth()?;
-
-    let me = twitter_client.me().await?;
-    println!("{me:?}");
-
-    let user_config = match fs::read_to_string("./var/.user_config") {
-        Ok(file_contents) => serde_json::from_str::<UserConfig>(&file_contents)?,
-        Err(err) if err.kind() == io::ErrorKind::NotFound => UserConfig::default(),
-        Err(err) => panic!("Error reading user config: {:?}", err),
+    // NB: before authorizing, so a bad thread fails fast
+    let thread = match &args.command {
+        Some(Command::Post { text, file, .. }) => Some(cli::read_thread(
+            text.as_deref(),
+            file.as_deref(),
+            io::stdin(),
+        )?),
+        _ => None,
+    };
+
+    let user_config = UserConfig::load(&args.config)?;
+
+    // NB: before authorizing, since Nitter doesn't need it
+    if args.backend == BackendKind::Nitter {
+        let follows = user_config
+            .starred_accounts
+            .values()
+            .map(|account| account.user.clone())
+            .collect();
+        let backend = NitterBackend::new(&args.nitter_instance, follows);
+        let tweets = match &args.command {
+            Some(command) => read_timeline(&backend, command).await,
+            None => None,
+        }
+        .ok_or(anyhow!(
+            "Only timeline, search and tweets work with --backend nitter"
+        ))??;
+        return cli::write_results(&mut io::stdout(), args.format, &tweets);
+    }
+
+    let mut twitter_client = match &args.replay {
+        Some(dir) => {
+            let mut twitter_client = TwitterClient::builder("").build();
+            twitter_client.replay_from(dir)?;
+            twitter_client
+        }
+        None => {
+            let twitter_client_id =
+                env::var("TWITTER_CLIENT_ID").context("TWITTER_CLIENT_ID isn't set")?;
+            let mut twitter_client = match user_config.auth.client_type {
+                ClientType::Confidential => {
+                    let twitter_client_secret = env::var("TWITTER_CLIENT_SECRET").context(
+                        "TWITTER_CLIENT_SECRET isn't set; for a public client, set \
+                         auth.client_type = \"public\" in the config",
+                    )?;
+                    TwitterClient::builder(&twitter_client_id)
+                        .client_secret(&twitter_client_secret)
+                        .http_config(&user_config.http)
+                        .build()
+                }
+                ClientType::Public => TwitterClient::builder(&twitter_client_id)
+                    .http_config(&user_config.http)
+                    .build(),
+            };
+            twitter_client.load_auth().or_else(|_| {
+                eprintln!("No auth file found, must login");
+                Ok::<_, Infallible>(())
+            })?;
+            twitter_client.authorize(!args.login).await?;
+            twitter_client.save_auth()?;
+            twitter_client
+        }
+    };
+    if let Some(dir) = &args.record {
+        twitter_client.record_to(dir)?;
+    }
+    // NB: a replay doesn't touch the network, so it doesn't count against the quota
+    if args.replay.is_none() {
+        twitter_client.track_quota(Path::new(quota::QUOTA_PATH))?;
+    }
+
+    // NB: the UI starts with the cached user and refreshes it in the background, rather than
+    // leaving a blank terminal while waiting on the network.  Logging in again might switch
+    // accounts, and a replay has its own user, so neither trusts the cache.
+    let is_tui = matches!(args.command, None | Some(Command::Tui { .. }));
+    let cached_me = if is_tui && !args.login && args.replay.is_none() {
+        twitter_client::load_cached_me().ok()
+    } else {
+        None
+    };
+    let is_me_cached = cached_me.is_some();
+    let me = match cached_me {
+        Some(me) => me,
+        None => {
+            let me = twitter_client.me().await?;
+            twitter_client::save_cached_me(&me)?;
+            me
+        }
+    };
+    // NB: stderr, to keep stdout clean for the output of subcommands
+    eprintln!("{me:?}");
+
+    if let Some(command) = &args.command {
+        let backend = ApiBackend::new(twitter_client.clone(), &me);
+        if let Some(tweets) = read_timeline(&backend, command).await {
+            return cli::write_results(&mut io::stdout(), args.format, &tweets?);
+        }
+    }
+
+    match &args.command {
+        Some(Command::Serve { port }) => {
+            let server = ApiServer::new(twitter_client, &me, &user_config);
+            return server.serve(*port).await;
+        }
+        Some(Command::User { username }) => {
+            let user = twitter_client
+                .user_by_username(username.trim_start_matches('@'))
+                .await?;
+            return cli::write_results(&mut io::stdout(), args.format, &[user]);
+        }
+        Some(Command::Watch { query, interval }) => {
+            let interval = Duration::from_secs(*interval);
+            return cli::watch(
+                &twitter_client,
+                query,
+                interval,
+                args.format,
+                &user_config,
+                &mut io::stdout(),
+            )
+            .await;
+        }
+        Some(Command::Digest { since, output }) => {
+            let accounts: Vec<api::User> = user_config
+                .starred_accounts
+                .values()
+                .map(|account| account.user.clone())
+                .collect();
+            if accounts.is_empty() {
+                return Err(anyhow!("No starred accounts to digest"));
+            }
+            let until = chrono::Utc::now();
+            let (tweets, errors) =
+                digest::collect(&twitter_client, &accounts, until - *since).await;
+            for error in errors {
+                eprintln!("{error:#}");
+            }
+            let markdown = digest::to_markdown(until - *since, until, &tweets);
+            match output {
+                Some(path) => fs::write(path, markdown)
+                    .with_context(|| format!("Writing {}", path.display()))?,
+                None => print!("{markdown}"),
+            }
+            return Ok(());
+        }
+        Some(Command::Post { reply_to, .. }) => {
+            let thread = thread.unwrap_or_default();
+            let on_progress = |progress: compose::ThreadProgress| {
+                eprintln!("{}", progress.describe());
+            };
+            let tweet_ids =
+                compose::post_thread(&twitter_client, &thread, reply_to.clone(), on_progress)
+                    .await?;
+            for tweet_id in tweet_ids {
+                println!("{tweet_id}");
+            }
+            return Ok(());
+        }
+        Some(Command::Timeline | Command::Search { .. } | Command::Tweets { .. }) => {
+            unreachable!("Read above")
+        }
+        Some(Command::Tui { .. }) | None => (),
+    }
+
+    let startup_feeds = match args.command {
+        Some(Command::Tui {
+            query: Some(query), ..
+        }) => Some(vec![FeedSource::Search(query)]),
+        Some(Command::Tui {
+            user: Some(user), ..
+        }) => Some(vec![FeedSource::User(
+            user.trim_start_matches('@').to_string(),
+        )]),
+        _ => None,
+    };
+    // NB: asking for a particular feed, or --fresh, starts over
+    let session = match startup_feeds {
+        None if !args.fresh => Session::load(Path::new(session::SESSION_PATH))?,
+        _ => None,
     };
 
-    let mut ui = ui::UI::new(twitter_client, &me, &user_config);
-    ui.initialize();
+    let mut ui = ui::UI::new(twitter_client, &me, &user_config, &args.config);
+    match (session, startup_feeds) {
+        (Some(session), _) => ui.restore_session(session),
+        (None, Some(startup_feeds)) => ui.initialize(startup_feeds),
+        (None, None) if user_config.feeds.is_empty() => ui.initialize(vec![FeedSource::Home]),
+        (None, None) => ui.initialize(user_config.feeds.clone()),
+    }
+    if is_me_cached {
+        ui.refresh_twitter_user();
+    }
     ui.event_loop().await
 }
@@ -0,0 +1,295 @@
+//! Downloaded images (avatars, media previews) kept on disk under [MEDIA_CACHE_DIR], so that e.g.
+//! exporting tweets as images doesn't download the same avatar each time.
+//!
+//! Files are named by the SHA-256 of their contents, so URLs with the same image share a file, and
+//! an index maps URLs to them.  Past the size cap, the least recently used files are evicted.
+
+use crate::fs_util;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const MEDIA_CACHE_DIR: &str = "./var/media";
+
+const INDEX_FILE: &str = "index.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Entry {
+    size: u64,
+    /// When it was last read or written, on [Index::clock]
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Index {
+    /// Content hash by URL
+    urls: HashMap<String, String>,
+    /// By content hash
+    entries: HashMap<String, Entry>,
+    /// Counts up with each use, to order entries by recency
+    clock: u64,
+}
+
+impl Index {
+    fn total_size(&self) -> u64 {
+        self.entries.values().map(|entry| entry.size).sum()
+    }
+
+    fn touch(&mut self, hash: &str) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(hash) {
+            entry.last_used = self.clock;
+        }
+    }
+}
+
+/// Since launch, except [CacheStats::entries] and [CacheStats::size].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub size: u64,
+    pub max_size: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    pub fn describe(&self) -> String {
+        let mb = |bytes: u64| bytes as f64 / 1_000_000.0;
+        let lookups = self.hits + self.misses;
+        let mut out = String::new();
+        // NB: writing to a String can't fail
+        let _ = writeln!(
+            out,
+            "Files       {} ({:.1} of {:.1} MB)",
+            self.entries,
+            mb(self.size),
+            mb(self.max_size)
+        );
+        let _ = writeln!(
+            out,
+            "Hits        {} of {lookups}{}",
+            self.hits,
+            match lookups {
+                0 => String::new(),
+                _ => format!(" ({:.0}%)", self.hits as f64 * 100.0 / lookups as f64),
+            }
+        );
+        let _ = writeln!(out, "Evictions   {}", self.evictions);
+        out
+    }
+}
+
+#[derive(Debug)]
+pub struct MediaCache {
+    dir: PathBuf,
+    /// In bytes
+    max_size: u64,
+    index: Mutex<Index>,
+    stats: Mutex<CacheStats>,
+}
+
+impl MediaCache {
+    /// The cache in [dir], which is created on the first insert.  NB: an index that won't load is
+    /// started afresh, leaving its files to the next [MediaCache::clear]
+    pub fn open(dir: &Path, max_size: u64) -> Self {
+        let index = fs::read_to_string(dir.join(INDEX_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            dir: dir.to_path_buf(),
+            max_size,
+            index: Mutex::new(index),
+            stats: Mutex::new(CacheStats::default()),
+        }
+    }
+
+    /// The cached contents of [url], if any.
+    pub fn get(&self, url: &str) -> Option<Vec<u8>> {
+        let mut index = self.index.lock().unwrap();
+        let hash = index.urls.get(url).cloned();
+        // NB: a file deleted from under us is just a miss
+        let bytes = hash
+            .as_ref()
+            .and_then(|hash| fs::read(self.dir.join(hash)).ok());
+        let mut stats = self.stats.lock().unwrap();
+        match (hash, &bytes) {
+            (Some(hash), Some(_)) => {
+                index.touch(&hash);
+                stats.hits += 1;
+            }
+            _ => stats.misses += 1,
+        }
+        bytes
+    }
+
+    /// Caches [bytes] as the contents of [url], then evicts down to the size cap.
+    pub fn insert(&self, url: &str, bytes: &[u8]) -> Result<()> {
+        let hash: String = Sha256::digest(bytes)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let path = self.dir.join(&hash);
+        let mut index = self.index.lock().unwrap();
+        if !index.entries.contains_key(&hash) || !path.exists() {
+            fs::create_dir_all(&self.dir)?;
+            fs::write(path, bytes)?;
+            let size = bytes.len() as u64;
+            index
+                .entries
+                .insert(hash.clone(), Entry { size, last_used: 0 });
+        }
+        index.urls.insert(url.to_string(), hash.clone());
+        index.touch(&hash);
+        self.evict(&mut index)?;
+        self.save_index(&index)
+    }
+
+    /// The contents of [url], from the cache or else from [download] (which is then cached).
+    pub async fn fetch<F>(&self, url: &str, download: F) -> Result<Vec<u8>>
+    where
+        F: Future<Output = Result<Vec<u8>>>,
+    {
+        if let Some(bytes) = self.get(url) {
+            return Ok(bytes);
+        }
+        let bytes = download.await?;
+        self.insert(url, &bytes)?;
+        Ok(bytes)
+    }
+
+    /// Deletes everything cached, returning how many files the index knew of.
+    pub fn clear(&self) -> Result<usize> {
+        let mut index = self.index.lock().unwrap();
+        let num_entries = index.entries.len();
+        match fs::remove_dir_all(&self.dir) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                return Err(err).context("Clearing the media cache")
+            }
+            _ => (),
+        }
+        *index = Index::default();
+        Ok(num_entries)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let index = self.index.lock().unwrap();
+        CacheStats {
+            entries: index.entries.len(),
+            size: index.total_size(),
+            max_size: self.max_size,
+            ..*self.stats.lock().unwrap()
+        }
+    }
+
+    /// Deletes the least recently used files until the rest fit under [max_size], along with the
+    /// URLs that pointed to them.
+    fn evict(&self, index: &mut Index) -> Result<()> {
+        let mut total_size = index.total_size();
+        let mut by_recency: Vec<(u64, String)> = index
+            .entries
+            .iter()
+            .map(|(hash, entry)| (entry.last_used, hash.clone()))
+            .collect();
+        by_recency.sort();
+        let mut evicted = Vec::new();
+        for (_, hash) in by_recency {
+            if total_size <= self.max_size {
+                break;
+            }
+            if let Some(entry) = index.entries.remove(&hash) {
+                total_size -= entry.size;
+                fs::remove_file(self.dir.join(&hash))?;
+                evicted.push(hash);
+            }
+        }
+        if !evicted.is_empty() {
+            index.urls.retain(|_, hash| !evicted.contains(hash));
+            self.stats.lock().unwrap().evictions += evicted.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn save_index(&self, index: &Index) -> Result<()> {
+        fs_util::write_atomic(&self.dir.join(INDEX_FILE), &serde_json::to_vec(index)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("twitter-tool-media-{name}-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let dir = temp_dir("get");
+        let cache = MediaCache::open(&dir, 1_000);
+        assert_eq!(cache.get("https://a"), None);
+        cache.insert("https://a", b"avatar").unwrap();
+        // NB: the same image at another URL shares the file
+        cache.insert("https://b", b"avatar").unwrap();
+        assert_eq!(cache.get("https://a").as_deref(), Some(&b"avatar"[..]));
+        assert_eq!(cache.get("https://b").as_deref(), Some(&b"avatar"[..]));
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.size), (1, 6));
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+
+        // NB: the index is kept across launches
+        let cache = MediaCache::open(&dir, 1_000);
+        assert_eq!(cache.get("https://a").as_deref(), Some(&b"avatar"[..]));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = temp_dir("evict");
+        let cache = MediaCache::open(&dir, 10);
+        cache.insert("https://a", b"aaaa").unwrap();
+        cache.insert("https://b", b"bbbb").unwrap();
+        cache.get("https://a");
+        cache.insert("https://c", b"cccc").unwrap();
+        assert!(cache.get("https://a").is_some());
+        assert_eq!(cache.get("https://b"), None);
+        assert!(cache.get("https://c").is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.size, stats.evictions), (2, 8, 1));
+
+        assert_eq!(cache.clear().unwrap(), 2);
+        assert_eq!(cache.get("https://a"), None);
+        assert_eq!(cache.stats().entries, 0);
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_fetch() {
+        let dir = temp_dir("fetch");
+        let cache = MediaCache::open(&dir, 1_000);
+        let bytes = cache
+            .fetch("https://a", async { Ok(b"image".to_vec()) })
+            .await
+            .unwrap();
+        assert_eq!(bytes, b"image");
+        let bytes = cache
+            .fetch("https://a", async { panic!("Expected a cache hit") })
+            .await
+            .unwrap();
+        assert_eq!(bytes, b"image");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
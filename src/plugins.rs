@@ -0,0 +1,194 @@
+//! Plugins: external executables, configured in [UserConfig::plugins], that extend the app without
+//! recompiling it.  On each [PluginEvent], every plugin is run with the event as JSON on stdin, and
+//! may print [PluginCommand]s for the app to carry out, one JSON object per line.
+//!
+//! e.g. an event is `{"event": "tweet_selected", "tweet": {...}}` and a command is
+//! `{"command": "mute_user", "username": "foo"}`.
+//!
+//! [UserConfig::plugins]: crate::user_config::UserConfig::plugins
+
+use crate::twitter_client::api;
+use anyhow::{anyhow, Context, Error, Result};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::{process, time};
+
+/// How long a plugin gets to handle an event before it's killed
+const PLUGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plugin {
+    /// Path to the executable
+    pub path: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PluginEvent {
+    TweetSelected {
+        tweet: Box<api::Tweet>,
+    },
+    /// A thread (or single tweet) was posted from the app
+    TweetPosted {
+        tweet_ids: Vec<String>,
+        texts: Vec<String>,
+    },
+    /// The feed finished loading, e.g. on opening a tab or a refresh
+    FeedRefreshed {
+        tweet_ids: Vec<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum PluginCommand {
+    OpenUrl {
+        url: String,
+    },
+    /// Shown with the tweet in the tweet pane
+    AddNote {
+        tweet_id: String,
+        text: String,
+    },
+    /// Hides the user's tweets from feeds
+    MuteUser {
+        username: String,
+    },
+}
+
+impl Plugin {
+    pub async fn run(&self, event: &PluginEvent) -> Result<Vec<PluginCommand>> {
+        self.run_within(event, PLUGIN_TIMEOUT).await
+    }
+
+    /// Fails a plugin that takes longer than [timeout], killing it.
+    async fn run_within(
+        &self,
+        event: &PluginEvent,
+        timeout: Duration,
+    ) -> Result<Vec<PluginCommand>> {
+        let json = serde_json::to_vec(event)?;
+        let mut child = process::Command::new(&self.path)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            // NB: discarded, since it would garble the UI
+            .stderr(Stdio::null())
+            // NB: so that cancelling the task running it, e.g. on a newer selection, stops it too
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Starting plugin {}", self.path))?;
+        let mut stdin = child.stdin.take().unwrap();
+        let mut stdout = child.stdout.take().unwrap();
+        let result = time::timeout(timeout, async {
+            // NB: a plugin that doesn't care about the event may well exit before it's written
+            match stdin.write_all(&json).await {
+                Err(error) if error.kind() != io::ErrorKind::BrokenPipe => return Err(error),
+                _ => (),
+            }
+            drop(stdin);
+            let mut output = Vec::new();
+            stdout.read_to_end(&mut output).await?;
+            Ok((child.wait().await?, output))
+        })
+        .await;
+        let Ok(result) = result else {
+            child.kill().await?;
+            return Err(anyhow!("Plugin {} timed out after {timeout:?}", self.path));
+        };
+        let (status, output) = result?;
+        if !status.success() {
+            return Err(anyhow!("Plugin {} {}", self.path, status));
+        }
+        String::from_utf8_lossy(&output)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line)
+                    .with_context(|| format!("Plugin {} printed a bad command: {line}", self.path))
+            })
+            .collect()
+    }
+}
+
+/// Runs every plugin on [event]; returns the commands they printed, in plugin order, and what went
+/// wrong.  A failing plugin's commands are all dropped, but other plugins' still count.
+pub async fn run_plugins(
+    plugins: &[Plugin],
+    event: &PluginEvent,
+) -> (Vec<PluginCommand>, Vec<Error>) {
+    let mut commands = Vec::new();
+    let mut errors = Vec::new();
+    for plugin in plugins {
+        match plugin.run(event).await {
+            Ok(mut plugin_commands) => commands.append(&mut plugin_commands),
+            Err(error) => errors.push(error),
+        }
+    }
+    (commands, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Plugin {
+        Plugin {
+            path: "sh".to_string(),
+            args: vec!["-c".to_string(), script.to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_plugins() {
+        let event = PluginEvent::FeedRefreshed {
+            tweet_ids: vec!["1".to_string()],
+        };
+        let plugins = vec![
+            // NB: echoes back the event's tweet id, to check it got the event
+            sh(r#"id=$(sed 's/.*"tweet_ids":\["\([0-9]*\)".*/\1/')
+                  echo "{\"command\": \"add_note\", \"tweet_id\": \"$id\", \"text\": \"seen\"}"
+                  echo
+                  echo '{"command": "mute_user", "username": "bob"}'"#),
+            sh("echo '{\"command\": \"launch_missiles\"}'"),
+            sh("exit 1"),
+        ];
+
+        let (commands, errors) = run_plugins(&plugins, &event).await;
+        assert_eq!(
+            commands,
+            vec![
+                PluginCommand::AddNote {
+                    tweet_id: "1".to_string(),
+                    text: "seen".to_string()
+                },
+                PluginCommand::MuteUser {
+                    username: "bob".to_string()
+                }
+            ]
+        );
+        let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Plugin sh printed a bad command: {\"command\": \"launch_missiles\"}",
+                "Plugin sh exit status: 1"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let event = PluginEvent::FeedRefreshed { tweet_ids: vec![] };
+        let error = sh("sleep 10")
+            .run_within(&event, Duration::from_millis(100))
+            .await
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Plugin sh timed out after 100ms");
+    }
+}
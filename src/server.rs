@@ -0,0 +1,333 @@
+use crate::twitter_client::{api, TwitterApi, TwitterClient};
+use crate::user_config::UserConfig;
+use anyhow::Result;
+use hyper::body::Bytes;
+use hyper::header::HOST;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::form_urlencoded;
+
+const CACHE_TTL: Duration = Duration::from_secs(60);
+/// Past this many fresh entries, the oldest is evicted to make room
+const MAX_CACHE_ENTRIES: usize = 256;
+
+/// Read-only HTTP API over localhost, so that other local tools can reuse the authenticated client.
+///
+/// Endpoints (all GET, all JSON):
+/// - `/timeline`: home timeline, reverse chronological
+/// - `/users/<username>/tweets`: a user's tweets
+/// - `/search?q=<query>`: recent search passthrough
+/// - `/bookmarks`: the authenticated user's bookmarks
+/// - `/starred`: starred accounts from the user config
+///
+/// Twitter responses are cached for [CACHE_TTL] keyed by path and query.  Requests whose Host isn't
+/// `localhost:<port>` or `127.0.0.1:<port>` are refused, so that a web page can't reach the API by
+/// rebinding its own domain to 127.0.0.1.
+pub struct ApiServer<A: TwitterApi = TwitterClient> {
+    twitter_client: A,
+    twitter_user: api::User,
+    user_config: UserConfig,
+    cache: Mutex<HashMap<String, (Instant, Bytes)>>,
+}
+
+impl<A: TwitterApi> ApiServer<A> {
+    pub fn new(twitter_client: A, twitter_user: &api::User, user_config: &UserConfig) -> Self {
+        Self {
+            twitter_client,
+            twitter_user: twitter_user.clone(),
+            user_config: user_config.clone(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn serve(self, port: u16) -> Result<()> {
+        let this = Arc::new(self);
+        let addr = SocketAddr::from(([127, 0, 0, 1], port));
+        let make_service = make_service_fn(move |_conn| {
+            let this = this.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let this = this.clone();
+                    async move { Ok::<_, Infallible>(this.handle_request(req, port).await) }
+                }))
+            }
+        });
+
+        eprintln!("Listening on http://{addr}");
+        Server::bind(&addr).serve(make_service).await?;
+        Ok(())
+    }
+
+    async fn handle_request(&self, req: Request<Body>, port: u16) -> Response<Body> {
+        let host = req.headers().get(HOST).and_then(|host| host.to_str().ok());
+        let allowed_hosts = [format!("localhost:{port}"), format!("127.0.0.1:{port}")];
+        if !host.is_some_and(|host| allowed_hosts.iter().any(|allowed| allowed == host)) {
+            return error_response(StatusCode::FORBIDDEN, "Unexpected Host header");
+        }
+        if req.method() != Method::GET {
+            return error_response(StatusCode::METHOD_NOT_ALLOWED, "Only GET is supported");
+        }
+
+        let path = req.uri().path().to_string();
+        let params: HashMap<String, String> = req
+            .uri()
+            .query()
+            .map(|query| {
+                form_urlencoded::parse(query.as_bytes())
+                    .into_owned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+        let result = match segments.as_slice() {
+            ["starred"] => to_json(&self.user_config.starred_accounts),
+            ["timeline"] => {
+                self.cached(&path, || async {
+                    let (page, _) = self
+                        .twitter_client
+                        .timeline_reverse_chronological(&self.twitter_user.id, None)
+                        .await?;
+                    to_json(&page.tweets)
+                })
+                .await
+            }
+            ["users", username, "tweets"] => {
+                let username = username.trim_start_matches('@');
+                self.cached(&path, || async {
+                    let user = self.twitter_client.user_by_username(username).await?;
+                    let (page, _) = self.twitter_client.user_tweets(&user.id, None).await?;
+                    to_json(&page.tweets)
+                })
+                .await
+            }
+            ["search"] => match params.get("q") {
+                Some(query) => {
+                    let cache_key = format!("{path}?q={query}");
+                    self.cached(&cache_key, || async {
+                        let (page, _) = self.twitter_client.search_tweets(query).await?;
+                        to_json(&page.tweets)
+                    })
+                    .await
+                }
+                None => return error_response(StatusCode::BAD_REQUEST, "Missing `q` param"),
+            },
+            ["bookmarks"] => {
+                self.cached(&path, || async {
+                    let (page, _) = self
+                        .twitter_client
+                        .bookmarks(&self.twitter_user.id, None)
+                        .await?;
+                    to_json(&page.tweets)
+                })
+                .await
+            }
+            _ => return error_response(StatusCode::NOT_FOUND, "Not found"),
+        };
+
+        match result {
+            Ok(bytes) => Response::builder()
+                .header("Content-Type", "application/json")
+                .body(Body::from(bytes))
+                .unwrap(),
+            Err(err) => error_response(StatusCode::BAD_GATEWAY, &err.to_string()),
+        }
+    }
+
+    async fn cached<F, Fut>(&self, key: &str, f: F) -> Result<Bytes>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<Bytes>>,
+    {
+        {
+            let cache = self.cache.lock().unwrap();
+            if let Some((fetched_at, bytes)) = cache.get(key) {
+                if fetched_at.elapsed() < CACHE_TTL {
+                    return Ok(bytes.clone());
+                }
+            }
+        }
+
+        let bytes = f().await?;
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < CACHE_TTL);
+        if cache.len() >= MAX_CACHE_ENTRIES && !cache.contains_key(key) {
+            let oldest = cache
+                .iter()
+                .min_by_key(|(_, (fetched_at, _))| *fetched_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(key.to_string(), (Instant::now(), bytes.clone()));
+        Ok(bytes)
+    }
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<Bytes> {
+    Ok(Bytes::from(serde_json::to_vec(value)?))
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let body = serde_json::json!({ "error": message }).to_string();
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(body))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+    use crate::twitter_client::mock::{self, MockTwitterClient};
+
+    const PORT: u16 = 8080;
+    const TIMELINE: &str = "/2/users/1/timelines/reverse_chronological";
+
+    fn server(twitter_client: MockTwitterClient) -> ApiServer<MockTwitterClient> {
+        let twitter_user = fixtures::user("1", "me");
+        ApiServer::new(twitter_client, &twitter_user, &UserConfig::default())
+    }
+
+    fn request(method: Method, host: &str, uri: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(HOST, host)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn get(server: &ApiServer<MockTwitterClient>, uri: &str) -> (StatusCode, String) {
+        let response = server
+            .handle_request(request(Method::GET, "localhost:8080", uri), PORT)
+            .await;
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_routes() {
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond("/2/tweets/search/recent", mock::fixtures::MENTIONS);
+        let server = server(twitter_client.clone());
+
+        let (status, body) = get(&server, "/timeline").await;
+        assert_eq!(status, StatusCode::OK);
+        let tweets: Vec<api::Tweet> = serde_json::from_str(&body).unwrap();
+        let tweet_ids: Vec<&str> = tweets.iter().map(|tweet| tweet.id.as_str()).collect();
+        assert_eq!(tweet_ids, vec!["1005", "1004"]);
+
+        let (status, _) = get(&server, "/search?q=%40me").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            get(&server, "/starred").await,
+            (StatusCode::OK, "{}".to_string())
+        );
+
+        // NB: nothing queued for bookmarks, so the mock 404s, which is the upstream's fault
+        let (status, _) = get(&server, "/bookmarks").await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+        assert_eq!(
+            twitter_client.requests(),
+            vec![
+                TIMELINE.to_string(),
+                "/2/tweets/search/recent?query=@me".to_string(),
+                "/2/users/1/bookmarks".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let twitter_client = MockTwitterClient::new();
+        let server = server(twitter_client.clone());
+
+        let response = server
+            .handle_request(request(Method::POST, "localhost:8080", "/timeline"), PORT)
+            .await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(get(&server, "/nope").await.0, StatusCode::NOT_FOUND);
+        assert_eq!(
+            get(&server, "/search").await,
+            (
+                StatusCode::BAD_REQUEST,
+                r#"{"error":"Missing `q` param"}"#.to_string()
+            )
+        );
+        assert!(twitter_client.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_host() {
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_1);
+        let server = server(twitter_client.clone());
+
+        for host in ["evil.example:8080", "localhost:9090", "localhost"] {
+            let response = server
+                .handle_request(request(Method::GET, host, "/timeline"), PORT)
+                .await;
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{host}");
+        }
+        let response = server
+            .handle_request(request(Method::GET, "127.0.0.1:8080", "/timeline"), PORT)
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(twitter_client.requests(), vec![TIMELINE.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_2);
+        let server = server(twitter_client.clone());
+
+        let (_, first) = get(&server, "/timeline").await;
+        let (_, second) = get(&server, "/timeline").await;
+        assert_eq!(first, second);
+        assert_eq!(twitter_client.requests().len(), 1);
+
+        // NB: once the entry is stale, the next request goes upstream again
+        server.cache.lock().unwrap().get_mut("/timeline").unwrap().0 -= CACHE_TTL;
+        let (_, third) = get(&server, "/timeline").await;
+        assert_ne!(first, third);
+        assert_eq!(twitter_client.requests().len(), 2);
+
+        // NB: a full cache evicts its oldest entry, now /timeline, unless something is stale
+        for i in 1..MAX_CACHE_ENTRIES {
+            let entry = (Instant::now(), Bytes::new());
+            server.cache.lock().unwrap().insert(format!("/{i}"), entry);
+        }
+        server
+            .cached("/new", || async { Ok(Bytes::new()) })
+            .await
+            .unwrap();
+        {
+            let cache = server.cache.lock().unwrap();
+            assert_eq!(cache.len(), MAX_CACHE_ENTRIES);
+            assert!(!cache.contains_key("/timeline"));
+        }
+        server.cache.lock().unwrap().get_mut("/2").unwrap().0 -= CACHE_TTL;
+        server
+            .cached("/newer", || async { Ok(Bytes::new()) })
+            .await
+            .unwrap();
+        let cache = server.cache.lock().unwrap();
+        assert_eq!(cache.len(), MAX_CACHE_ENTRIES);
+        assert!(!cache.contains_key("/2"));
+        assert!(cache.contains_key("/1") && cache.contains_key("/new"));
+    }
+}
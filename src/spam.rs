@@ -0,0 +1,213 @@
+//! Heuristics for tweets that are likely spam or from bots: new accounts, accounts that follow far
+//! more than follow them, tweets that are just links, and the same text posted over and over.
+//! Each heuristic that fires is a [Signal]; tweets with enough of them are soft-hidden in the feed,
+//! behind a row that shows them.  Thresholds are in [UserConfig::spam].
+
+use crate::twitter_client::api;
+use crate::user_config::UserConfig;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpamThresholds {
+    pub enabled: bool,
+    /// How many signals it takes to hide a tweet
+    pub min_signals: usize,
+    /// Accounts younger than this are new
+    pub new_account_days: u32,
+    /// Followers as a percentage of following, below which an account is mostly following
+    pub min_follower_percent: u64,
+    /// Who's following fewer than this isn't judged by their follower ratio
+    pub min_following: u64,
+    /// How many tweets in a feed can have the same text before it's a duplicate
+    pub max_duplicates: usize,
+}
+
+impl Default for SpamThresholds {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_signals: 2,
+            new_account_days: 30,
+            min_follower_percent: 10,
+            min_following: 100,
+            max_duplicates: 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Signal {
+    NewAccount,
+    FollowerRatio,
+    LinkOnly,
+    Duplicate,
+}
+
+impl Signal {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Signal::NewAccount => "new account",
+            Signal::FollowerRatio => "few followers",
+            Signal::LinkOnly => "link only",
+            Signal::Duplicate => "duplicate",
+        }
+    }
+}
+
+/// The text [tweet] is compared by for duplicates: lowercased, without links or extra whitespace.
+fn normalized_text(tweet: &api::Tweet) -> String {
+    let words: Vec<String> = tweet
+        .text
+        .split_whitespace()
+        .filter(|word| !word.starts_with("http"))
+        .map(str::to_lowercase)
+        .collect();
+    words.join(" ")
+}
+
+/// Whether [tweet] is links (and mentions) and nothing else.
+fn is_link_only(tweet: &api::Tweet) -> bool {
+    let mut words = tweet.text.split_whitespace().peekable();
+    words.peek().is_some()
+        && tweet.text.contains("http")
+        && words.all(|word| word.starts_with("http") || word.starts_with('@'))
+}
+
+/// The signals [tweet] sets off; [num_duplicates] is how many tweets in its feed have its text.
+pub fn signals(
+    thresholds: &SpamThresholds,
+    tweet: &api::Tweet,
+    num_duplicates: usize,
+    now: DateTime<Utc>,
+) -> Vec<Signal> {
+    let mut signals = Vec::new();
+    let new_account_age = Duration::days(thresholds.new_account_days.into());
+    if tweet
+        .author_created_at
+        .is_some_and(|created_at| now - created_at < new_account_age)
+    {
+        signals.push(Signal::NewAccount);
+    }
+    if let Some(metrics) = tweet.author_public_metrics {
+        if metrics.following_count >= thresholds.min_following.max(1)
+            && metrics.followers_count * 100
+                < thresholds.min_follower_percent * metrics.following_count
+        {
+            signals.push(Signal::FollowerRatio);
+        }
+    }
+    // NB: for a retweet, what's judged is the retweeter's account; what they retweeted is shown
+    // however it looks, and retweets of the same tweet aren't duplicates
+    if tweet.retweeted_id().is_none() {
+        if is_link_only(tweet) {
+            signals.push(Signal::LinkOnly);
+        }
+        if num_duplicates > thresholds.max_duplicates {
+            signals.push(Signal::Duplicate);
+        }
+    }
+    signals
+}
+
+/// The tweets of [feed] to soft-hide, with the signals each set off.  The user's own tweets and
+/// those of starred accounts are never hidden.
+pub fn find_spam<'a>(
+    user_config: &UserConfig,
+    my_user_id: &str,
+    feed: impl IntoIterator<Item = &'a api::Tweet>,
+    now: DateTime<Utc>,
+) -> HashMap<String, Vec<Signal>> {
+    let thresholds = &user_config.spam;
+    if !thresholds.enabled {
+        return HashMap::new();
+    }
+    let feed: Vec<&api::Tweet> = feed.into_iter().collect();
+    let mut num_by_text: HashMap<String, usize> = HashMap::new();
+    for tweet in &feed {
+        let text = normalized_text(tweet);
+        if tweet.retweeted_id().is_none() && !text.is_empty() {
+            *num_by_text.entry(text).or_default() += 1;
+        }
+    }
+
+    feed.into_iter()
+        .filter(|tweet| tweet.author_id != my_user_id && !user_config.is_starred(&tweet.author_id))
+        .filter_map(|tweet| {
+            let num_duplicates = num_by_text
+                .get(&normalized_text(tweet))
+                .copied()
+                .unwrap_or(0);
+            let signals = signals(thresholds, tweet, num_duplicates, now);
+            (signals.len() >= thresholds.min_signals.max(1)).then(|| (tweet.id.clone(), signals))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+
+    fn now() -> DateTime<Utc> {
+        "2023-02-01T12:00:00Z".parse().unwrap()
+    }
+
+    fn bot(id: &str, text: &str) -> api::Tweet {
+        api::Tweet {
+            author_created_at: Some("2023-01-25T00:00:00Z".parse().unwrap()),
+            author_public_metrics: Some(api::UserPublicMetrics {
+                followers_count: 3,
+                following_count: 2_000,
+            }),
+            ..fixtures::tweet(id, "bot", text, 0)
+        }
+    }
+
+    #[test]
+    fn test_signals() {
+        let thresholds = SpamThresholds::default();
+        let tweet = bot("1", "https://t.co/abc @alice");
+        assert_eq!(
+            signals(&thresholds, &tweet, 1, now()),
+            vec![Signal::NewAccount, Signal::FollowerRatio, Signal::LinkOnly]
+        );
+        let tweet = fixtures::tweet("2", "alice", "buy now https://t.co/abc", 0);
+        assert_eq!(
+            signals(&thresholds, &tweet, 3, now()),
+            vec![Signal::Duplicate]
+        );
+        assert_eq!(signals(&thresholds, &tweet, 2, now()), vec![]);
+    }
+
+    #[test]
+    fn test_find_spam() {
+        let mut user_config = UserConfig::default();
+        let feed = [
+            bot("1", "hello"),
+            fixtures::tweet("2", "alice", "Buy now https://t.co/a", 0),
+            fixtures::tweet("3", "bob", "buy  now https://t.co/b", 0),
+            fixtures::tweet("4", "carol", "buy now", 0),
+            fixtures::tweet("5", "dave", "https://t.co/c", 0),
+        ];
+        let spam = find_spam(&user_config, "id_me", &feed, now());
+        let mut spam_ids: Vec<&str> = spam.keys().map(String::as_str).collect();
+        spam_ids.sort();
+        // NB: one signal isn't enough on its own
+        assert_eq!(spam_ids, vec!["1"]);
+
+        user_config.spam.min_signals = 1;
+        let spam = find_spam(&user_config, "id_me", &feed, now());
+        assert_eq!(spam.len(), 5);
+        assert_eq!(spam["3"], vec![Signal::Duplicate]);
+
+        user_config.star_account(&feed[0].author("[unknown]"));
+        let spam = find_spam(&user_config, "id_me", &feed[..2], now());
+        assert!(spam.is_empty());
+
+        user_config.spam.enabled = false;
+        assert!(find_spam(&user_config, "id_me", &feed, now()).is_empty());
+    }
+}
@@ -1,90 +1,837 @@
-use crate::twitter_client::{api, PagedResult, TwitterClient};
-use crate::user_config::UserConfig;
-use anyhow::{anyhow, Context, Result};
+use crate::alerts::AlertHit;
+use crate::collections::{self, Collection};
+use crate::followers::{Relationship, Relationships};
+use crate::twitter_client::paginator::Paginator;
+use crate::twitter_client::{api, PagedResult, TwitterApi, TwitterClient};
+use crate::user_cache::UserCache;
+use crate::user_config::{self, UserConfig};
+use anyhow::{anyhow, Context, Error, Result};
+use chrono::{DateTime, Local, Utc};
+use futures_util::future;
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::future::Future;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use tokio::sync::Mutex as AsyncMutex;
 
+/// For telling feeds apart, see [Store::feed_id]
+static NEXT_FEED_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// How far up a reply chain we'll go, in case of very long threads (or a cycle in bad data).
+const MAX_THREAD_DEPTH: usize = 50;
+
+/// How many of my tweets [Store::load_my_tweets] asks for.
+const MY_TWEETS_SIZE: usize = 200;
+
+/// How many tweets a feed page loads, however many pages of the API that takes.
+const FEED_PAGE_SIZE: usize = 100;
+
+/// How many pages [Store::load_feed_back_to] loads before giving up.
+const MAX_PAGES_BACK: usize = 40;
+
+/// How many hits [Store::alert_hits] remembers.
+const MAX_ALERT_HITS: usize = 100;
+
+/// Past [UserConfig::max_cached_tweets], tweets are evicted down to this fraction of it, so that
+/// eviction doesn't run on every page
+const EVICT_TO_FRACTION: f64 = 0.9;
+
+/// Progress of the latest load into a feed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum LoadState {
+    #[default]
+    Idle,
+    Loading,
+    /// The error, with its context chain
+    Failed(String),
+}
+
+/// The order a feed is shown in; see [Store::sorted_feed].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FeedSort {
+    /// As loaded
+    #[default]
+    Newest,
+    MostLiked,
+    MostRetweeted,
+    MostReplied,
+}
+
+impl FromStr for FeedSort {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "newest" => Ok(FeedSort::Newest),
+            "likes" => Ok(FeedSort::MostLiked),
+            "retweets" => Ok(FeedSort::MostRetweeted),
+            "replies" => Ok(FeedSort::MostReplied),
+            _ => Err(anyhow!(
+                "Expected newest, likes, retweets or replies, not `{name}`"
+            )),
+        }
+    }
+}
+
+impl FeedSort {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            FeedSort::Newest => "newest first",
+            FeedSort::MostLiked => "most liked first",
+            FeedSort::MostRetweeted => "most retweeted first",
+            FeedSort::MostReplied => "most replied first",
+        }
+    }
+
+    /// How [tweet] ranks, or None for [FeedSort::Newest], which keeps the feed's order.
+    fn key(&self, tweet: &api::Tweet) -> Option<i32> {
+        let metrics = tweet.public_metrics.as_ref();
+        let count = |count: fn(&api::PublicMetrics) -> i32| metrics.map(count).unwrap_or(0);
+        match self {
+            FeedSort::Newest => None,
+            FeedSort::MostLiked => Some(count(|m| m.like_count)),
+            FeedSort::MostRetweeted => Some(count(|m| m.retweet_count)),
+            FeedSort::MostReplied => Some(count(|m| m.reply_count)),
+        }
+    }
+}
+
+/// The tweets of a feed in one conversation, see [Store::conversations].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversation {
+    /// The id of the tweet that started it
+    pub conversation_id: String,
+    /// In feed order
+    pub tweet_ids: Vec<String>,
+    /// Usernames, in order of first appearance in the feed
+    pub participants: Vec<String>,
+}
+
+impl Conversation {
+    /// e.g. "@alice, @bob and 3 others are discussing"
+    pub fn describe(&self) -> String {
+        let usernames: Vec<String> = self
+            .participants
+            .iter()
+            .map(|username| format!("@{username}"))
+            .collect();
+        let who = match usernames.as_slice() {
+            [] => "[unknown]".to_string(),
+            [one] => one.clone(),
+            [first, second] => format!("{first} and {second}"),
+            [first, second, third] => format!("{first}, {second} and {third}"),
+            [first, second, rest @ ..] => format!("{first}, {second} and {} others", rest.len()),
+        };
+        let verb = if usernames.len() > 1 { "are" } else { "is" };
+        format!("{who} {verb} discussing")
+    }
+}
+
+/// The tweets of a feed by one author, see [Store::authors].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorGroup {
+    pub username: String,
+    /// In feed order
+    pub tweet_ids: Vec<String>,
+}
+
+/// Why a tweet is in the activity feed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    Mention,
+    /// Replying to one of my tweets
+    Reply,
+    /// Quoting one of my tweets
+    Quote,
+}
+
+impl ActivityKind {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            ActivityKind::Mention => "@",
+            ActivityKind::Reply => "↩",
+            ActivityKind::Quote => "❝",
+        }
+    }
+}
+
+/// Who two accounts both follow, and who only one of them does; see [Store::compare_following].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FollowingOverlap {
+    pub first: api::User,
+    pub second: api::User,
+    pub both: Vec<api::User>,
+    pub only_first: Vec<api::User>,
+    pub only_second: Vec<api::User>,
+}
+
+impl FollowingOverlap {
+    /// Each list is sorted by username.
+    pub fn new(
+        (first, first_following): (api::User, Vec<api::User>),
+        (second, second_following): (api::User, Vec<api::User>),
+    ) -> Self {
+        let first_ids: HashSet<&str> = first_following.iter().map(|u| u.id.as_str()).collect();
+        let second_ids: HashSet<&str> = second_following.iter().map(|u| u.id.as_str()).collect();
+        let sorted = |users: Vec<&api::User>| -> Vec<api::User> {
+            users
+                .into_iter()
+                .cloned()
+                .sorted_by_key(|user| user.username.to_lowercase())
+                .collect()
+        };
+        Self {
+            both: sorted(
+                first_following
+                    .iter()
+                    .filter(|user| second_ids.contains(user.id.as_str()))
+                    .collect(),
+            ),
+            only_first: sorted(
+                first_following
+                    .iter()
+                    .filter(|user| !second_ids.contains(user.id.as_str()))
+                    .collect(),
+            ),
+            only_second: sorted(
+                second_following
+                    .iter()
+                    .filter(|user| !first_ids.contains(user.id.as_str()))
+                    .collect(),
+            ),
+            first,
+            second,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut out = String::new();
+        for (heading, users) in [
+            ("Both follow".to_string(), &self.both),
+            (
+                format!("Only @{} follows", self.first.username),
+                &self.only_first,
+            ),
+            (
+                format!("Only @{} follows", self.second.username),
+                &self.only_second,
+            ),
+        ] {
+            out.push_str(&format!("{heading} ({})\n", users.len()));
+            for user in users {
+                out.push_str(&format!("  @{} ({})\n", user.username, user.name));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// When each cached tweet was last used, see [Store::evict_tweets].
+#[derive(Debug, Default)]
+struct TweetRecency {
+    clock: u64,
+    /// By tweet id; a tweet cached without going through [TweetRecency::touch] counts as oldest
+    last_used: HashMap<String, u64>,
+    num_evicted: u64,
+}
+
+impl TweetRecency {
+    fn touch(&mut self, tweet_id: &str) {
+        self.clock += 1;
+        self.last_used.insert(tweet_id.to_string(), self.clock);
+    }
+}
+
+#[derive(Debug, Default)]
+struct PageLoadState {
+    next_load_id: usize,
+    in_flight: Option<usize>,
+    /// Whether the next page was asked for while [in_flight] was loading
+    next_page_queued: bool,
+}
+
+/// A load of the home timeline in flight, from [Store::begin_page_load] until it's done or
+/// dropped, e.g. aborted as superseded.
+#[derive(Debug)]
+pub struct PageLoad {
+    state: Arc<Mutex<PageLoadState>>,
+    load_id: usize,
+}
+
+impl PageLoad {
+    /// Whether to load the next page after this one; if not, the load is over.  NB: decided under
+    /// the lock, so that a page asked for just as this finishes isn't lost
+    fn take_queued_next_page(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight != Some(self.load_id) {
+            return false;
+        }
+        if state.next_page_queued {
+            state.next_page_queued = false;
+            return true;
+        }
+        state.in_flight = None;
+        false
+    }
+}
+
+impl Drop for PageLoad {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight == Some(self.load_id) {
+            state.in_flight = None;
+            state.next_page_queued = false;
+        }
+    }
+}
+
+/// A feed sharing [Store::tweets], whose tweets are kept while it's open.
+#[derive(Debug)]
+struct FeedRef {
+    tweets_feed: Weak<Mutex<Vec<String>>>,
+    pinned_tweet_id: Weak<Mutex<Option<String>>>,
+}
+
 // NB: all the synchronization and interior mutability are encapsulated here for granularity.
 // Also it seems slightly nicer as an API?  Esp. since methods don't have to be &mut self.
 
 // CR: move Arc up
+/// Generic over the API client so tests can run against [mock::MockTwitterClient] offline.
+///
+/// [mock::MockTwitterClient]: crate::twitter_client::mock::MockTwitterClient
 #[derive(Debug)]
-pub struct Store {
-    pub twitter_client: TwitterClient,
+pub struct Store<A: TwitterApi = TwitterClient> {
+    pub twitter_client: A,
     pub twitter_user: api::User,
+    /// Capped at [UserConfig::max_cached_tweets], see [Store::evict_tweets]
     pub tweets: Arc<Mutex<HashMap<String, api::Tweet>>>,
+    tweet_recency: Arc<Mutex<TweetRecency>>,
+    /// Every feed sharing [tweets], including this one
+    feeds: Arc<Mutex<Vec<FeedRef>>>,
+    /// Tweets the API couldn't give us, e.g. deleted ones, so they aren't asked for again
+    pub unavailable_tweets: Arc<Mutex<HashMap<String, api::Unavailable>>>,
+    /// Unique to this store's feed, e.g. to key its loading tasks
+    pub feed_id: usize,
     pub tweets_feed: Arc<Mutex<Vec<String>>>,
+    /// How [tweets_feed] is shown; it's loaded newest first regardless
+    pub feed_sort: Arc<Mutex<FeedSort>>,
+    /// Shown first, e.g. the pinned tweet of a user's timeline, see [Store::load_user_timeline]
+    pub pinned_tweet_id: Arc<Mutex<Option<String>>>,
     pub tweets_feed_page_token: Arc<AsyncMutex<Option<String>>>,
+    /// See [Store::begin_page_load]
+    page_load: Arc<Mutex<PageLoadState>>,
+    pub load_state: Arc<Mutex<LoadState>>,
+    /// By tweet id, see [Store::translate_tweet]
+    pub translations: Arc<Mutex<HashMap<String, String>>>,
+    /// Sensitive tweets the user has chosen to see, see [Filters::collapses]
+    ///
+    /// [Filters::collapses]: crate::user_config::Filters::collapses
+    pub revealed_ids: Arc<Mutex<HashSet<String>>>,
+    /// For badging authors, see [Store::refresh_relationships]
+    pub relationships: Arc<Mutex<Relationships>>,
+    /// Everyone seen in responses, see [Store::user_by_username]
+    pub users: Arc<Mutex<UserCache>>,
+    /// Why tweets in responses were left out, since [Store::take_skipped_tweets] was last called
+    skipped_tweets: Arc<Mutex<Vec<String>>>,
+    /// Newest first, see [Store::record_alert_hits]
+    alert_hits: Arc<Mutex<VecDeque<AlertHit>>>,
     pub user_config: Arc<Mutex<UserConfig>>,
+    /// Where [user_config] is saved
+    user_config_path: PathBuf,
+    /// Tweets per feed page, see [FEED_PAGE_SIZE]
+    page_size: usize,
 }
 
-impl Store {
-    pub fn new(
-        twitter_client: TwitterClient,
-        twitter_user: &api::User,
-        user_config: &UserConfig,
-    ) -> Self {
-        Self {
+impl<A: TwitterApi> Store<A> {
+    pub fn new(twitter_client: A, twitter_user: &api::User, user_config: &UserConfig) -> Self {
+        let store = Self {
             twitter_client,
             twitter_user: twitter_user.clone(),
             tweets: Arc::new(Mutex::new(HashMap::new())),
+            tweet_recency: Arc::new(Mutex::new(TweetRecency::default())),
+            feeds: Arc::new(Mutex::new(Vec::new())),
+            unavailable_tweets: Arc::new(Mutex::new(HashMap::new())),
+            feed_id: NEXT_FEED_ID.fetch_add(1, Ordering::SeqCst),
             tweets_feed: Arc::new(Mutex::new(Vec::new())),
+            feed_sort: Arc::new(Mutex::new(FeedSort::default())),
+            pinned_tweet_id: Arc::new(Mutex::new(None)),
             tweets_feed_page_token: Arc::new(AsyncMutex::new(None)),
+            page_load: Arc::new(Mutex::new(PageLoadState::default())),
+            load_state: Arc::new(Mutex::new(LoadState::Idle)),
+            translations: Arc::new(Mutex::new(HashMap::new())),
+            revealed_ids: Arc::new(Mutex::new(HashSet::new())),
+            relationships: Arc::new(Mutex::new(Relationships::default())),
+            users: Arc::new(Mutex::new(UserCache::default())),
+            skipped_tweets: Arc::new(Mutex::new(Vec::new())),
+            alert_hits: Arc::new(Mutex::new(VecDeque::new())),
             user_config: Arc::new(Mutex::new(user_config.clone())),
-        }
+            user_config_path: PathBuf::from(user_config::DEFAULT_PATH),
+            page_size: FEED_PAGE_SIZE,
+        };
+        store.register_feed();
+        store
+    }
+
+    pub fn with_user_config_path(mut self, path: &Path) -> Self {
+        self.user_config_path = path.to_path_buf();
+        self
+    }
+
+    /// Starts from [users], e.g. the cache saved at the end of the last launch.
+    pub fn with_user_cache(self, users: UserCache) -> Self {
+        *self.users.lock().unwrap() = users;
+        self
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// A store for another feed (e.g. in another tab): shares the tweets and user config with this
+    /// one, but has its own feed and paging state.
+    pub fn new_feed(&self) -> Self {
+        let store = Self {
+            twitter_client: self.twitter_client.clone(),
+            twitter_user: self.twitter_user.clone(),
+            tweets: self.tweets.clone(),
+            tweet_recency: self.tweet_recency.clone(),
+            feeds: self.feeds.clone(),
+            unavailable_tweets: self.unavailable_tweets.clone(),
+            feed_id: NEXT_FEED_ID.fetch_add(1, Ordering::SeqCst),
+            tweets_feed: Arc::new(Mutex::new(Vec::new())),
+            feed_sort: Arc::new(Mutex::new(FeedSort::default())),
+            pinned_tweet_id: Arc::new(Mutex::new(None)),
+            tweets_feed_page_token: Arc::new(AsyncMutex::new(None)),
+            page_load: Arc::new(Mutex::new(PageLoadState::default())),
+            load_state: Arc::new(Mutex::new(LoadState::Idle)),
+            translations: self.translations.clone(),
+            revealed_ids: self.revealed_ids.clone(),
+            relationships: self.relationships.clone(),
+            users: self.users.clone(),
+            skipped_tweets: Arc::new(Mutex::new(Vec::new())),
+            alert_hits: self.alert_hits.clone(),
+            user_config: self.user_config.clone(),
+            user_config_path: self.user_config_path.clone(),
+            page_size: self.page_size,
+        };
+        store.register_feed();
+        store
+    }
+
+    /// NB: weakly, so that a closed feed's tweets can go
+    fn register_feed(&self) {
+        self.feeds.lock().unwrap().push(FeedRef {
+            tweets_feed: Arc::downgrade(&self.tweets_feed),
+            pinned_tweet_id: Arc::downgrade(&self.pinned_tweet_id),
+        });
     }
 
     pub fn save_user_config(&self) -> Result<()> {
-        let user_config = self.user_config.lock().unwrap();
-        let user_config = serde_json::to_string(&*user_config)?;
-        fs::write("./var/.user_config", user_config)?;
+        self.user_config
+            .lock()
+            .unwrap()
+            .save(&self.user_config_path)
+    }
+
+    /// The feed in the order of [feed_sort], most first, after the [pinned_tweet_id] if any; ties,
+    /// and tweets without metrics, keep their place in the feed.  NB: a retweet ranks by the tweet
+    /// it retweets, which is what's shown
+    pub fn sorted_feed(&self) -> Vec<String> {
+        let mut feed = self.tweets_feed.lock().unwrap().clone();
+        let feed_sort = *self.feed_sort.lock().unwrap();
+        if feed_sort != FeedSort::Newest {
+            let tweets = self.tweets.lock().unwrap();
+            feed.sort_by_cached_key(|tweet_id| {
+                let shown = shown_tweet(&tweets, tweet_id);
+                Reverse(shown.and_then(|tweet| feed_sort.key(tweet)).unwrap_or(0))
+            });
+        }
+        // NB: the pinned tweet goes first, however old it is
+        if let Some(pinned_tweet_id) = &*self.pinned_tweet_id.lock().unwrap() {
+            feed.retain(|tweet_id| tweet_id != pinned_tweet_id);
+            feed.insert(0, pinned_tweet_id.clone());
+        }
+        feed
+    }
+
+    /// [sorted_feed] bucketed by conversation, in the order each conversation first appears.
+    /// Retweets go with the conversation of the tweet they retweet.
+    pub fn conversations(&self) -> Vec<Conversation> {
+        let feed = self.sorted_feed();
+        let tweets = self.tweets.lock().unwrap();
+        let mut conversations: Vec<Conversation> = Vec::new();
+        let mut index_by_id: HashMap<String, usize> = HashMap::new();
+
+        for tweet_id in feed {
+            let Some(tweet) = shown_tweet(&tweets, &tweet_id) else {
+                continue;
+            };
+            let conversation_id = tweet.conversation_id.clone().unwrap_or(tweet.id.clone());
+            let index = *index_by_id
+                .entry(conversation_id.clone())
+                .or_insert_with(|| {
+                    conversations.push(Conversation {
+                        conversation_id,
+                        tweet_ids: Vec::new(),
+                        participants: Vec::new(),
+                    });
+                    conversations.len() - 1
+                });
+            let conversation = &mut conversations[index];
+            if let Some(username) = &tweet.author_username {
+                if !conversation.participants.contains(username) {
+                    conversation.participants.push(username.clone());
+                }
+            }
+            conversation.tweet_ids.push(tweet_id);
+        }
+        conversations
+    }
+
+    /// [sorted_feed] bucketed by author, most tweets first (ties in the order each author first
+    /// appears).  Retweets go with whoever retweeted, since that's who put them in the feed and
+    /// who muting hides.
+    pub fn authors(&self) -> Vec<AuthorGroup> {
+        let feed = self.sorted_feed();
+        let tweets = self.tweets.lock().unwrap();
+        let mut authors: Vec<AuthorGroup> = Vec::new();
+        let mut index_by_username: HashMap<String, usize> = HashMap::new();
+
+        for tweet_id in feed {
+            // NB: a tweet whose author didn't load can't be muted by username anyway
+            let Some(username) = tweets
+                .get(&tweet_id)
+                .and_then(|t| t.author_username.clone())
+            else {
+                continue;
+            };
+            let index = *index_by_username
+                .entry(username.to_lowercase())
+                .or_insert_with(|| {
+                    authors.push(AuthorGroup {
+                        username,
+                        tweet_ids: Vec::new(),
+                    });
+                    authors.len() - 1
+                });
+            authors[index].tweet_ids.push(tweet_id);
+        }
+        // NB: stable, so ties keep feed order
+        authors.sort_by_key(|author| Reverse(author.tweet_ids.len()));
+        authors
+    }
+
+    /// Why tweets were left out of the responses loaded since the last call, e.g. to log them.
+    pub fn take_skipped_tweets(&self) -> Vec<String> {
+        std::mem::take(&mut self.skipped_tweets.lock().unwrap())
+    }
+
+    /// Remembers [hits] for `:alerts`, forgetting the oldest past [MAX_ALERT_HITS].
+    pub fn record_alert_hits(&self, hits: &[AlertHit]) {
+        let mut alert_hits = self.alert_hits.lock().unwrap();
+        for hit in hits {
+            alert_hits.push_front(hit.clone());
+        }
+        alert_hits.truncate(MAX_ALERT_HITS);
+    }
+
+    /// Newest first.
+    pub fn alert_hits(&self) -> Vec<AlertHit> {
+        self.alert_hits.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Caches [page]'s tweets, and the tweets they reference, without touching the feed.
+    fn cache_tweet_page(&self, page: api::TweetPage) {
+        // NB: the page is about to go into a feed, so it isn't evicted as it's cached
+        let page_ids: HashSet<String> = page
+            .tweets
+            .iter()
+            .chain(page.referenced_tweets.iter())
+            .map(|tweet| tweet.id.clone())
+            .collect();
+        self.skipped_tweets.lock().unwrap().extend(page.skipped);
+        self.unavailable_tweets
+            .lock()
+            .unwrap()
+            .extend(page.unavailable);
+        {
+            // NB: authors are filled in from the response's included users
+            let mut users = self.users.lock().unwrap();
+            for tweet in page.tweets.iter().chain(page.referenced_tweets.iter()) {
+                if tweet.author_username.is_some() && tweet.author_name.is_some() {
+                    users.insert(tweet.author(""));
+                }
+            }
+        }
+        {
+            let mut tweets = self.tweets.lock().unwrap();
+            let mut recency = self.tweet_recency.lock().unwrap();
+            for tweet in page.tweets {
+                recency.touch(&tweet.id);
+                tweets.insert(tweet.id.clone(), tweet);
+            }
+            for tweet in page.referenced_tweets {
+                recency.touch(&tweet.id);
+                tweets.entry(tweet.id.clone()).or_insert(tweet);
+            }
+        }
+        self.evict_tweets(page_ids);
+    }
+
+    /// Past [UserConfig::max_cached_tweets], drops the least recently used tweets, except [kept]
+    /// and those in a feed (or shown by one, e.g. retweeted) or pinned to one.  Dropped tweets are
+    /// fetched again if they're needed, see [Store::load_tweets].
+    fn evict_tweets(&self, mut kept: HashSet<String>) {
+        let max_tweets = self.user_config.lock().unwrap().max_cached_tweets.get();
+        if self.tweets.lock().unwrap().len() <= max_tweets {
+            return;
+        }
+        // NB: the feeds are read before locking [tweets], so no lock is held while taking another
+        self.feeds.lock().unwrap().retain(|feed| {
+            let (Some(tweets_feed), Some(pinned_tweet_id)) =
+                (feed.tweets_feed.upgrade(), feed.pinned_tweet_id.upgrade())
+            else {
+                return false;
+            };
+            kept.extend(tweets_feed.lock().unwrap().iter().cloned());
+            kept.extend(pinned_tweet_id.lock().unwrap().clone());
+            true
+        });
+
+        let mut tweets = self.tweets.lock().unwrap();
+        let referenced_ids: Vec<String> = kept
+            .iter()
+            .filter_map(|tweet_id| tweets.get(tweet_id))
+            .flat_map(|tweet| tweet.referenced_tweets.iter().flatten())
+            .map(|reference| reference.id.clone())
+            .collect();
+        kept.extend(referenced_ids);
+        let mut recency = self.tweet_recency.lock().unwrap();
+        let mut by_recency: Vec<(u64, String)> = tweets
+            .keys()
+            .filter(|tweet_id| !kept.contains(*tweet_id))
+            .map(|tweet_id| {
+                let last_used = recency.last_used.get(tweet_id).copied().unwrap_or(0);
+                (last_used, tweet_id.clone())
+            })
+            .collect();
+        by_recency.sort();
+        let target_len = (max_tweets as f64 * EVICT_TO_FRACTION) as usize;
+        let num_evicted = tweets
+            .len()
+            .saturating_sub(target_len)
+            .min(by_recency.len());
+        for (_, tweet_id) in by_recency.into_iter().take(num_evicted) {
+            tweets.remove(&tweet_id);
+            recency.last_used.remove(&tweet_id);
+        }
+        recency.num_evicted += num_evicted as u64;
+    }
+
+    /// How many tweets are cached, and how many have been evicted since launch.
+    pub fn tweet_cache_stats(&self) -> (usize, u64) {
+        let num_cached = self.tweets.lock().unwrap().len();
+        (num_cached, self.tweet_recency.lock().unwrap().num_evicted)
+    }
+
+    /// Looks up @{username}, unless they're already cached.
+    pub async fn user_by_username(&self, username: &str) -> Result<api::User> {
+        if let Some(user) = self.users.lock().unwrap().by_username(username) {
+            return Ok(user.clone());
+        }
+        let user = self.twitter_client.user_by_username(username).await?;
+        self.users.lock().unwrap().insert(user.clone());
+        Ok(user)
+    }
+
+    pub fn get_tweet(&self, tweet_id: &str) -> Option<api::Tweet> {
+        let tweet = self.tweets.lock().unwrap().get(tweet_id).cloned();
+        if tweet.is_some() {
+            self.tweet_recency.lock().unwrap().touch(tweet_id);
+        }
+        tweet
+    }
+
+    /// Why [tweet_id] couldn't be loaded, if it couldn't.
+    pub fn unavailable_reason(&self, tweet_id: &str) -> Option<api::Unavailable> {
+        self.unavailable_tweets
+            .lock()
+            .unwrap()
+            .get(tweet_id)
+            .copied()
+    }
+
+    /// Fetches whichever of [tweet_ids] aren't cached yet, or known to be unavailable.
+    pub async fn load_tweets(&self, tweet_ids: &[String]) -> Result<()> {
+        let missing_ids: Vec<String> = {
+            let tweets = self.tweets.lock().unwrap();
+            let unavailable_tweets = self.unavailable_tweets.lock().unwrap();
+            tweet_ids
+                .iter()
+                .filter(|tweet_id| !tweets.contains_key(*tweet_id))
+                .filter(|tweet_id| !unavailable_tweets.contains_key(*tweet_id))
+                .cloned()
+                .collect()
+        };
+        if !missing_ids.is_empty() {
+            let page = self.twitter_client.tweets_by_ids(&missing_ids).await?;
+            self.cache_tweet_page(page);
+        }
         Ok(())
     }
 
-    // pub async fn load_tweet(&self, tweet_id: &str) {}
+    /// Fetches the other versions of [tweet_id], if it's been edited.
+    pub async fn load_edit_history(&self, tweet_id: &str) -> Result<()> {
+        let tweet = self
+            .get_tweet(tweet_id)
+            .ok_or(anyhow!("Tweet {tweet_id} isn't loaded"))?;
+        let version_ids: Vec<String> = tweet
+            .other_version_ids()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        if version_ids.is_empty() {
+            return Err(anyhow!("Tweet {tweet_id} hasn't been edited"));
+        }
+        self.load_tweets(&version_ids).await
+    }
+
+    /// Loads the thread around [tweet_id]: the reply chain above it, and the rest of its
+    /// conversation.
+    ///
+    /// NB: search only reaches back a week, so replies in older conversations may be missing.
+    pub async fn load_conversation(&self, tweet_id: &str) -> Result<()> {
+        let mut ancestor_id = Some(tweet_id.to_string());
+        for _ in 0..MAX_THREAD_DEPTH {
+            let Some(id) = ancestor_id else { break };
+            self.load_tweets(std::slice::from_ref(&id)).await?;
+            ancestor_id = self
+                .get_tweet(&id)
+                .and_then(|tweet| tweet.replied_to_id().map(String::from));
+        }
+
+        let conversation_id = self
+            .get_tweet(tweet_id)
+            .and_then(|tweet| tweet.conversation_id);
+        if let Some(conversation_id) = conversation_id {
+            let (page, _) = self
+                .twitter_client
+                .search_tweets(&format!("conversation_id:{conversation_id}"))
+                .await?;
+            self.cache_tweet_page(page);
+        }
+        Ok(())
+    }
+
+    /// Ids of the cached tweets that [tweet_id] is replying to, transitively, root first.
+    pub fn thread_ancestor_ids(&self, tweet_id: &str) -> Vec<String> {
+        thread_ancestor_ids(&self.tweets.lock().unwrap(), tweet_id)
+    }
+
+    /// Translates the cached tweet [tweet_id] with the configured service, keeping the
+    /// translation in [translations].  NB: only asked once per tweet
+    pub async fn translate_tweet(&self, tweet_id: &str) -> Result<()> {
+        if self.translations.lock().unwrap().contains_key(tweet_id) {
+            return Ok(());
+        }
+        let config = self.user_config.lock().unwrap().translate.clone();
+        let config = config.ok_or(anyhow!(
+            "No translation service configured, see [translate] in the config"
+        ))?;
+        let text = self
+            .get_tweet(tweet_id)
+            .ok_or(anyhow!("Tweet {tweet_id} isn't loaded"))?
+            .text;
+        let translation = config.translate(&text).await?;
+        self.translations
+            .lock()
+            .unwrap()
+            .insert(tweet_id.to_string(), translation);
+        Ok(())
+    }
+
+    /// Reveals [tweet_id] if it's collapsed as sensitive, or collapses it again.
+    pub fn toggle_revealed(&self, tweet_id: &str) {
+        let mut revealed_ids = self.revealed_ids.lock().unwrap();
+        if !revealed_ids.remove(tweet_id) {
+            revealed_ids.insert(tweet_id.to_string());
+        }
+    }
+
+    /// Whether [tweet] is shown as a placeholder.  NB: locks [user_config]
+    pub fn is_collapsed(&self, tweet: &api::Tweet) -> bool {
+        self.user_config.lock().unwrap().filters.collapses(tweet)
+            && !self.revealed_ids.lock().unwrap().contains(&tweet.id)
+    }
+
+    /// Refetches who I follow and who follows me.  NB: until then, the last ones fetched are shown
+    pub async fn refresh_relationships(&self) -> Result<()> {
+        let relationships =
+            Relationships::load(&self.twitter_client, &self.twitter_user.id).await?;
+        *self.relationships.lock().unwrap() = relationships;
+        Ok(())
+    }
+
+    /// How I relate to [user_id], if I follow them or they follow me.  None for myself.
+    pub fn relationship(&self, user_id: &str) -> Option<Relationship> {
+        if user_id == self.twitter_user.id {
+            return None;
+        }
+        self.relationships.lock().unwrap().get(user_id)
+    }
+
+    /// Ids of the cached direct replies to [tweet_id], oldest first.
+    pub fn reply_ids(&self, tweet_id: &str) -> Vec<String> {
+        reply_ids(&self.tweets.lock().unwrap(), tweet_id)
+    }
 
     // CR: need to sift results
-    // CR: need a fixed page size, then call the twitter_client as many times as needed to achieve
-    // the desired page effect
     pub async fn load_tweets_feed<
-        F: Future<Output = PagedResult<Vec<api::Tweet>>>,
+        F: Future<Output = PagedResult<api::TweetPage>>,
         G: Fn(Option<String>) -> F,
     >(
         &self,
         g: G,
         restart: bool,
     ) -> Result<()> {
-        let mut tweets_page_token = self
-            .tweets_feed_page_token
-            .try_lock()
-            .with_context(|| anyhow!("Already in-flight"))?;
-
-        let mut maybe_page_token = None;
-        // NB: require page token if continuing to next page
-        if !restart {
-            let next_page_token = tweets_page_token.as_ref().ok_or(anyhow!("No more pages"))?;
-            maybe_page_token = Some(next_page_token.clone());
-        }
+        // NB: waits out a load that's in flight, which is usually being aborted as superseded
+        let mut tweets_page_token = self.tweets_feed_page_token.lock().await;
 
-        let (new_tweets, page_token) = g(maybe_page_token).await?;
+        let mut paginator = if restart {
+            Paginator::new(g)
+        } else {
+            // NB: require page token if continuing to next page
+            let next_page_token = tweets_page_token.as_ref().ok_or(anyhow!("No more pages"))?;
+            Paginator::resume(g, next_page_token.clone())
+        };
+        let page = paginator.collect_n(self.page_size).await?;
         let mut new_tweets_reverse_chronological: Vec<String> = Vec::new();
 
-        *tweets_page_token = page_token;
+        *tweets_page_token = paginator.into_page_token();
 
         {
-            let mut tweets = self.tweets.lock().unwrap();
-            for tweet in new_tweets {
-                new_tweets_reverse_chronological.push(tweet.id.clone());
-                tweets.insert(tweet.id.clone(), tweet);
+            let user_config = self.user_config.lock().unwrap();
+            for tweet in page.tweets.iter() {
+                if !user_config.is_filtered(tweet) {
+                    new_tweets_reverse_chronological.push(tweet.id.clone());
+                }
             }
+            drop(user_config);
+            // NB: referenced tweets are cached so retweets and quotes can show the original, but
+            // aren't part of the feed
+            self.cache_tweet_page(page);
         }
         {
             let mut tweets_reverse_chronological = self.tweets_feed.lock().unwrap();
             if restart {
+                *self.pinned_tweet_id.lock().unwrap() = None;
                 *tweets_reverse_chronological = new_tweets_reverse_chronological;
             } else {
                 tweets_reverse_chronological.append(&mut new_tweets_reverse_chronological);
@@ -94,6 +841,34 @@ impl Store {
         Ok(())
     }
 
+    /// Starts a load of the home timeline, for [Store::load_home_pages].  Unless [restart], if one
+    /// is already in flight, the next page is queued to follow it instead (at most once, however
+    /// many times it's asked for), and there's nothing to start.
+    pub fn begin_page_load(&self, restart: bool) -> Option<PageLoad> {
+        let mut state = self.page_load.lock().unwrap();
+        if !restart && state.in_flight.is_some() {
+            state.next_page_queued = true;
+            return None;
+        }
+        let load_id = state.next_load_id;
+        state.next_load_id += 1;
+        state.in_flight = Some(load_id);
+        state.next_page_queued = false;
+        Some(PageLoad {
+            state: self.page_load.clone(),
+            load_id,
+        })
+    }
+
+    /// Loads a page of the home timeline, then the next page if it was queued meanwhile.
+    pub async fn load_home_pages(&self, page_load: PageLoad, restart: bool) -> Result<()> {
+        self.load_tweets_reverse_chronological(restart).await?;
+        while page_load.take_queued_next_page() {
+            self.load_tweets_reverse_chronological(false).await?;
+        }
+        Ok(())
+    }
+
     pub async fn load_tweets_reverse_chronological(&self, restart: bool) -> Result<()> {
         self.load_tweets_feed(
             move |maybe_page_token| async move {
@@ -106,6 +881,18 @@ impl Store {
         .await
     }
 
+    pub async fn load_mentions(&self, restart: bool) -> Result<()> {
+        self.load_tweets_feed(
+            move |maybe_page_token| async move {
+                self.twitter_client
+                    .mentions(&self.twitter_user.id, maybe_page_token)
+                    .await
+            },
+            restart,
+        )
+        .await
+    }
+
     pub async fn load_user_tweets(&self, user_id: &str, restart: bool) -> Result<()> {
         self.load_tweets_feed(
             move |maybe_page_token| async move {
@@ -118,14 +905,1239 @@ impl Store {
         .await
     }
 
-    pub async fn load_search_tweets(&self, query: &str, restart: bool) -> Result<()> {
+    /// Loads @{username}'s tweets, with the tweet pinned to their profile first.
+    pub async fn load_user_timeline(&self, username: &str) -> Result<()> {
+        let (user, pinned_tweet) = self.twitter_client.user_with_pinned_tweet(username).await?;
+        self.users.lock().unwrap().insert(user.clone());
+        self.load_user_tweets(&user.id, true).await.map_err(|err| {
+            if user.is_protected() {
+                anyhow!(
+                    "@{} is protected: only the followers they've approved can see their \
+                         tweets ({err})",
+                    user.username
+                )
+            } else {
+                err
+            }
+        })?;
+        let Some(pinned_tweet) = pinned_tweet else {
+            return Ok(());
+        };
+        if self.user_config.lock().unwrap().is_filtered(&pinned_tweet) {
+            return Ok(());
+        }
+        // NB: cached first, since the feed expects its tweets to be
+        let pinned_tweet_id = pinned_tweet.id.clone();
+        self.cache_tweet_page(api::TweetPage {
+            tweets: vec![pinned_tweet],
+            ..Default::default()
+        });
+        *self.pinned_tweet_id.lock().unwrap() = Some(pinned_tweet_id);
+        Ok(())
+    }
+
+    pub async fn load_list_tweets(&self, list_id: &str, restart: bool) -> Result<()> {
         self.load_tweets_feed(
-            move |_maybe_page_token| {
-                let query = query.clone();
-                async move { self.twitter_client.search_tweets(&query).await }
+            move |maybe_page_token| async move {
+                self.twitter_client
+                    .list_tweets(list_id, maybe_page_token)
+                    .await
             },
             restart,
         )
         .await
     }
+
+    /// Pages back through the feed with [load_next_page] until it reaches a tweet from before
+    /// [before], calling [on_page] with the time of the oldest tweet loaded after each page.  The
+    /// id of that tweet, i.e. the newest one before [before].
+    pub async fn load_feed_back_to<F: Future<Output = Result<()>>>(
+        &self,
+        before: DateTime<Utc>,
+        load_next_page: impl Fn() -> F,
+        on_page: impl Fn(DateTime<Utc>),
+    ) -> Result<String> {
+        let find = || {
+            let tweets = self.tweets.lock().unwrap();
+            let feed = self.tweets_feed.lock().unwrap();
+            let found = feed
+                .iter()
+                .find(|tweet_id| matches!(tweets.get(*tweet_id), Some(tweet) if tweet.created_at < before))
+                .cloned();
+            let oldest = feed
+                .last()
+                .and_then(|tweet_id| tweets.get(tweet_id))
+                .map(|tweet| tweet.created_at);
+            (found, oldest)
+        };
+
+        for _ in 0..MAX_PAGES_BACK {
+            let (found, oldest) = find();
+            if let Some(tweet_id) = found {
+                return Ok(tweet_id);
+            }
+            // NB: the token is only missing once the feed has run out
+            if self.tweets_feed_page_token.lock().await.is_none() {
+                return Err(match oldest {
+                    Some(oldest) => anyhow!(
+                        "The feed only goes back to {}",
+                        oldest.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+                    ),
+                    None => anyhow!("The feed is empty"),
+                });
+            }
+            load_next_page().await?;
+            if let (_, Some(oldest)) = find() {
+                on_page(oldest);
+            }
+        }
+        find()
+            .0
+            .ok_or(anyhow!("Gave up after {MAX_PAGES_BACK} pages"))
+    }
+
+    /// My recent tweets from the API, along with any others of mine already cached, newest first.
+    /// Doesn't touch the feed.
+    pub async fn load_my_tweets(&self) -> Result<Vec<api::Tweet>> {
+        let mut paginator = Paginator::new(|page_token| {
+            self.twitter_client
+                .user_tweets(&self.twitter_user.id, page_token)
+        });
+        let page = paginator.collect_n(MY_TWEETS_SIZE).await?;
+        self.cache_tweet_page(page);
+
+        let tweets = self.tweets.lock().unwrap();
+        Ok(tweets
+            .values()
+            .filter(|tweet| tweet.author_id == self.twitter_user.id)
+            .cloned()
+            .sorted_by_key(|tweet| Reverse((tweet.created_at, tweet.id.clone())))
+            .collect())
+    }
+
+    /// Fetches who [first_username] and [second_username] each follow, and compares.
+    pub async fn compare_following(
+        &self,
+        first_username: &str,
+        second_username: &str,
+    ) -> Result<FollowingOverlap> {
+        let load_following = |username: &str| {
+            let username = username.trim_start_matches('@').to_string();
+            async move {
+                let user = self.user_by_username(&username).await?;
+                let mut paginator = Paginator::new(|page_token| {
+                    self.twitter_client.following(&user.id, page_token)
+                });
+                let following = paginator
+                    .collect_all()
+                    .await
+                    .with_context(|| format!("Loading who @{username} follows"))?;
+                Ok::<_, Error>((user, following))
+            }
+        };
+        let (first, second) = future::try_join(
+            load_following(first_username),
+            load_following(second_username),
+        )
+        .await?;
+        Ok(FollowingOverlap::new(first, second))
+    }
+
+    /// Loads mentions (which include replies to my tweets) and quote tweets of my tweets into one
+    /// feed, newest first.  There's no paging; the feed is just refreshed, e.g. by polling.  Returns
+    /// the tweets that weren't in the feed before.
+    pub async fn load_activity(&self) -> Result<Vec<api::Tweet>> {
+        let username = &self.twitter_user.username;
+        let (mentions, _) = self
+            .twitter_client
+            .mentions(&self.twitter_user.id, None)
+            .await?;
+        // NB: there's no endpoint for quotes of all of a user's tweets, but a quote tweet carries
+        // the quoted tweet's URL
+        let (quotes, _) = self
+            .twitter_client
+            .search_tweets(&format!(
+                "url:\"twitter.com/{username}/status\" is:quote -from:{username}"
+            ))
+            .await?;
+
+        let user_config = self.user_config.lock().unwrap().clone();
+        let new_feed: Vec<String> = mentions
+            .tweets
+            .iter()
+            .chain(quotes.tweets.iter())
+            .filter(|tweet| tweet.author_id != self.twitter_user.id)
+            .filter(|tweet| !user_config.is_muted(tweet))
+            .sorted_by_key(|tweet| Reverse((tweet.created_at, tweet.id.clone())))
+            .map(|tweet| tweet.id.clone())
+            .dedup()
+            .collect();
+        self.cache_tweet_page(mentions);
+        self.cache_tweet_page(quotes);
+        *self.pinned_tweet_id.lock().unwrap() = None;
+        let prev_feed = std::mem::replace(&mut *self.tweets_feed.lock().unwrap(), new_feed.clone());
+        Ok(new_feed
+            .iter()
+            .filter(|tweet_id| !prev_feed.contains(tweet_id))
+            .filter_map(|tweet_id| self.get_tweet(tweet_id))
+            .collect())
+    }
+
+    /// Drops tweets by muted users from the feed, e.g. after muting someone.
+    pub fn hide_muted(&self) {
+        let tweets = self.tweets.lock().unwrap();
+        let user_config = self.user_config.lock().unwrap();
+        self.tweets_feed.lock().unwrap().retain(|tweet_id| {
+            tweets
+                .get(tweet_id)
+                .is_none_or(|tweet| !user_config.is_muted(tweet))
+        });
+    }
+
+    /// Whether [tweet_id] is newer than the last activity the user has seen.
+    pub fn is_activity_unread(&self, tweet_id: &str) -> bool {
+        let user_config = self.user_config.lock().unwrap();
+        match &user_config.activity_seen_id {
+            Some(seen_id) => is_newer_tweet_id(tweet_id, seen_id),
+            None => true,
+        }
+    }
+
+    pub fn num_unread_activity(&self) -> usize {
+        let tweets_feed = self.tweets_feed.lock().unwrap();
+        tweets_feed
+            .iter()
+            .filter(|tweet_id| self.is_activity_unread(tweet_id))
+            .count()
+    }
+
+    /// Marks everything currently in the feed as seen.
+    pub fn mark_activity_seen(&self) -> Result<()> {
+        let newest_id = {
+            let tweets_feed = self.tweets_feed.lock().unwrap();
+            tweets_feed
+                .iter()
+                .cloned()
+                .reduce(|a, b| if is_newer_tweet_id(&b, &a) { b } else { a })
+        };
+        let Some(newest_id) = newest_id else {
+            return Ok(());
+        };
+        if !self.is_activity_unread(&newest_id) {
+            return Ok(());
+        }
+        self.user_config.lock().unwrap().activity_seen_id = Some(newest_id);
+        self.save_user_config()
+    }
+
+    /// Writes the tweets of the current feed, in feed order, to [path]: as Markdown if it ends in
+    /// .md, otherwise as a JSON array.  Returns the number of tweets written.
+    pub fn export_feed(&self, path: &str) -> Result<usize> {
+        let tweets_feed = self.tweets_feed.lock().unwrap().clone();
+        self.export_tweets(&tweets_feed, path)
+    }
+
+    /// Writes [tweet_ids], in order, to [path], as for [Store::export_feed].
+    pub fn export_tweets(&self, tweet_ids: &[String], path: &str) -> Result<usize> {
+        let tweets = self.tweets.lock().unwrap();
+        let feed: Vec<&api::Tweet> = tweet_ids
+            .iter()
+            .filter_map(|tweet_id| tweets.get(tweet_id))
+            .collect();
+        match Path::new(path).file_stem() {
+            Some(title) if path.ends_with(".md") => {
+                let notes = &self.user_config.lock().unwrap().notes;
+                let markdown = collections::to_markdown(&title.to_string_lossy(), &feed, notes);
+                fs::write(path, markdown)?;
+            }
+            _ => fs::write(path, serde_json::to_string_pretty(&feed)?)?,
+        }
+        Ok(feed.len())
+    }
+
+    /// Makes the collection [name] the feed.  NB: a collection is a single page.
+    pub async fn load_collection(&self, name: &str) -> Result<()> {
+        let mut tweets_page_token = self.tweets_feed_page_token.lock().await;
+        let collection = Collection::open(Path::new(collections::COLLECTIONS_DIR), name)?;
+        *tweets_page_token = None;
+        *self.pinned_tweet_id.lock().unwrap() = None;
+        *self.tweets_feed.lock().unwrap() = collection
+            .page
+            .tweets
+            .iter()
+            .map(|tweet| tweet.id.clone())
+            .collect();
+        self.cache_tweet_page(collection.page);
+        Ok(())
+    }
+
+    /// Makes [username]'s tweets since the user last caught up on them the feed, however many pages
+    /// that takes, and moves the read marker up to the newest.  The first catch-up on an account is
+    /// just its latest page.  Returns the number of tweets in the feed.
+    pub async fn load_catch_up(&self, username: &str) -> Result<usize> {
+        let mut tweets_page_token = self.tweets_feed_page_token.lock().await;
+        let user = self.user_by_username(username).await?;
+        let read_marker = (self.user_config.lock().unwrap())
+            .read_markers
+            .get(&user.id)
+            .cloned();
+        let mut paginator = Paginator::new(|page_token| {
+            self.twitter_client
+                .user_tweets_since(&user.id, read_marker.as_deref(), page_token)
+        });
+        let page = match &read_marker {
+            // NB: all or nothing, since the marker moves past whatever was fetched
+            Some(_) => paginator.collect_all().await?,
+            None => paginator.next_page().await?.unwrap_or_default(),
+        };
+        let newest_id = page
+            .tweets
+            .iter()
+            .map(|tweet| tweet.id.clone())
+            .max_by_key(|tweet_id| (tweet_id.len(), tweet_id.clone()));
+
+        *tweets_page_token = None;
+        *self.pinned_tweet_id.lock().unwrap() = None;
+        let feed: Vec<String> = {
+            let user_config = self.user_config.lock().unwrap();
+            page.tweets
+                .iter()
+                .filter(|tweet| !user_config.is_filtered(tweet))
+                .map(|tweet| tweet.id.clone())
+                .collect()
+        };
+        let num_tweets = feed.len();
+        *self.tweets_feed.lock().unwrap() = feed;
+        self.cache_tweet_page(page);
+
+        if let Some(newest_id) = newest_id {
+            (self.user_config.lock().unwrap())
+                .read_markers
+                .insert(user.id, newest_id);
+            self.save_user_config()?;
+        }
+        Ok(num_tweets)
+    }
+
+    /// Pages with this feed's own page token, so searches in different tabs page independently.
+    /// Returns the search responses' meta, see [Store::describe_search_results].
+    pub async fn load_search_tweets(
+        &self,
+        query: &str,
+        restart: bool,
+    ) -> Result<Option<api::Meta>> {
+        let meta = Mutex::new(None);
+        let meta_ref = &meta;
+        self.load_tweets_feed(
+            move |maybe_page_token| async move {
+                let (page, next_token) = self
+                    .twitter_client
+                    .search_tweets_page(query, maybe_page_token)
+                    .await?;
+                let mut meta = meta_ref.lock().unwrap();
+                *meta = api::Meta::merge(meta.take(), page.meta.clone());
+                Ok((page, next_token))
+            },
+            restart,
+        )
+        .await?;
+        Ok(meta.into_inner().unwrap())
+    }
+
+    /// Adds the recent tweets matching [query] to the end of the feed, leaving out those already
+    /// in it, e.g. to build up a reading list from several searches.  Returns the search
+    /// response's meta, and how many tweets were added.
+    pub async fn append_search_tweets(&self, query: &str) -> Result<(Option<api::Meta>, usize)> {
+        let (page, _) = self.twitter_client.search_tweets(query).await?;
+        let meta = page.meta.clone();
+        let new_tweet_ids: Vec<String> = {
+            let user_config = self.user_config.lock().unwrap();
+            page.tweets
+                .iter()
+                .filter(|tweet| !user_config.is_filtered(tweet))
+                .map(|tweet| tweet.id.clone())
+                .collect()
+        };
+        self.cache_tweet_page(page);
+
+        let mut tweets_feed = self.tweets_feed.lock().unwrap();
+        let mut seen: HashSet<String> = tweets_feed.iter().cloned().collect();
+        let num_feed_tweets = tweets_feed.len();
+        for tweet_id in new_tweet_ids {
+            if seen.insert(tweet_id.clone()) {
+                tweets_feed.push(tweet_id);
+            }
+        }
+        Ok((meta, tweets_feed.len() - num_feed_tweets))
+    }
+
+    /// e.g. "12 results (newest 02-01 12:05, oldest 01-31 09:00)", from a search response's
+    /// [meta].
+    pub fn describe_search_results(&self, meta: Option<&api::Meta>) -> String {
+        let Some(meta) = meta else {
+            return "No results".to_string();
+        };
+        let results = match meta.result_count {
+            1 => "1 result".to_string(),
+            result_count => format!("{result_count} results"),
+        };
+        let time = |tweet_id: &Option<String>| {
+            let tweet = self.get_tweet(tweet_id.as_deref()?)?;
+            Some(tweet.local_created_at().format("%m-%d %H:%M").to_string())
+        };
+        match (time(&meta.newest_id), time(&meta.oldest_id)) {
+            (Some(newest), Some(oldest)) => format!("{results} (newest {newest}, oldest {oldest})"),
+            _ => results,
+        }
+    }
+}
+
+fn thread_ancestor_ids(tweets: &HashMap<String, api::Tweet>, tweet_id: &str) -> Vec<String> {
+    let mut ancestor_ids = Vec::new();
+    let mut tweet = tweets.get(tweet_id);
+    while let Some(parent_id) = tweet.and_then(|tweet| tweet.replied_to_id()) {
+        if ancestor_ids.len() == MAX_THREAD_DEPTH {
+            break;
+        }
+        ancestor_ids.push(parent_id.to_string());
+        tweet = tweets.get(parent_id);
+    }
+    ancestor_ids.reverse();
+    ancestor_ids
+}
+
+/// The tweet shown for [tweet_id] in a feed: for a retweet, the original, if it's cached.
+pub fn shown_tweet<'a>(
+    tweets: &'a HashMap<String, api::Tweet>,
+    tweet_id: &str,
+) -> Option<&'a api::Tweet> {
+    let tweet = tweets.get(tweet_id)?;
+    let retweeted = tweet.retweeted_id().and_then(|id| tweets.get(id));
+    Some(retweeted.unwrap_or(tweet))
+}
+
+/// How [tweet_id] involves [user_id], if it's in their activity feed.  Quotes take precedence,
+/// since a quote of my tweet usually also mentions me.
+pub fn activity_kind(
+    tweets: &HashMap<String, api::Tweet>,
+    user_id: &str,
+    tweet_id: &str,
+) -> ActivityKind {
+    let is_mine = |id: Option<&str>| {
+        id.and_then(|id| tweets.get(id))
+            .is_some_and(|tweet| tweet.author_id == user_id)
+    };
+    let Some(tweet) = tweets.get(tweet_id) else {
+        return ActivityKind::Mention;
+    };
+    if is_mine(tweet.quoted_id()) {
+        ActivityKind::Quote
+    } else if is_mine(tweet.replied_to_id()) {
+        ActivityKind::Reply
+    } else {
+        ActivityKind::Mention
+    }
+}
+
+/// Tweet ids are snowflakes, so a newer tweet has a bigger id; compare them as numbers.
+pub fn is_newer_tweet_id(tweet_id: &str, than_tweet_id: &str) -> bool {
+    (tweet_id.len(), tweet_id) > (than_tweet_id.len(), than_tweet_id)
+}
+
+fn reply_ids(tweets: &HashMap<String, api::Tweet>, tweet_id: &str) -> Vec<String> {
+    tweets
+        .values()
+        .filter(|tweet| tweet.replied_to_id() == Some(tweet_id))
+        .sorted_by_key(|tweet| (tweet.created_at, tweet.id.clone()))
+        .map(|tweet| tweet.id.clone())
+        .collect()
+}
+
+/// Fixtures for tests that need a store without talking to Twitter.
+#[cfg(test)]
+pub mod fixtures {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    /// A tweet by @{username} at [minute] past midnight on 2023-02-01, local time so it formats the
+    /// same in every timezone.
+    pub fn tweet(id: &str, username: &str, text: &str, minute: u32) -> api::Tweet {
+        let created_at = Local
+            .with_ymd_and_hms(2023, 2, 1, 0, minute, 0)
+            .unwrap()
+            .to_rfc3339();
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": text,
+            "created_at": created_at,
+            "author_id": format!("id_{username}"),
+            "author_username": username,
+            "author_name": username.to_uppercase(),
+            "conversation_id": id,
+        }))
+        .unwrap()
+    }
+
+    /// @{username}, whose name is [username] in capitals, as for [tweet]'s authors.
+    pub fn user(id: &str, username: &str) -> api::User {
+        api::User {
+            id: id.to_string(),
+            name: username.to_uppercase(),
+            username: username.to_string(),
+            protected: None,
+            verified_type: None,
+            affiliation: None,
+            profile_image_url: None,
+            created_at: None,
+            public_metrics: None,
+        }
+    }
+
+    /// A store whose feed is [tweets], in order.
+    pub fn store(tweets: Vec<api::Tweet>) -> Arc<Store> {
+        let twitter_user = user("id_me", "me");
+        let store = Store::new(
+            TwitterClient::new("", ""),
+            &twitter_user,
+            &UserConfig::default(),
+        );
+        *store.tweets_feed.lock().unwrap() = tweets.iter().map(|tweet| tweet.id.clone()).collect();
+        *store.tweets.lock().unwrap() = tweets
+            .into_iter()
+            .map(|tweet| (tweet.id.clone(), tweet))
+            .collect();
+        Arc::new(store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitter_client::mock::{self, MockTwitterClient};
+    use chrono::TimeZone;
+    use std::num::NonZeroUsize;
+
+    fn reply(id: &str, replied_to_id: Option<&str>, minute: u32) -> (String, api::Tweet) {
+        tweet(id, "1", &[("replied_to", replied_to_id)], minute)
+    }
+
+    fn tweet(
+        id: &str,
+        author_id: &str,
+        references: &[(&str, Option<&str>)],
+        minute: u32,
+    ) -> (String, api::Tweet) {
+        let referenced_tweets: Vec<serde_json::Value> = references
+            .iter()
+            .filter_map(|(kind, id)| id.map(|id| serde_json::json!({"type": kind, "id": id})))
+            .collect();
+        let tweet = serde_json::from_value(serde_json::json!({
+            "id": id,
+            "text": "",
+            "created_at": format!("2023-02-01T00:{minute:02}:00Z"),
+            "author_id": author_id,
+            "conversation_id": "a",
+            "referenced_tweets": referenced_tweets
+        }))
+        .unwrap();
+        (id.to_string(), tweet)
+    }
+
+    #[test]
+    fn test_thread() {
+        let tweets: HashMap<String, api::Tweet> = [
+            reply("a", None, 0),
+            reply("b", Some("a"), 1),
+            reply("d", Some("b"), 3),
+            reply("c", Some("b"), 2),
+            reply("e", Some("d"), 4),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(thread_ancestor_ids(&tweets, "e"), vec!["a", "b", "d"]);
+        assert!(thread_ancestor_ids(&tweets, "a").is_empty());
+        assert_eq!(reply_ids(&tweets, "b"), vec!["c", "d"]);
+        assert!(reply_ids(&tweets, "e").is_empty());
+
+        // NB: the chain stops at the first tweet that isn't cached, but includes its id
+        let tweets: HashMap<String, api::Tweet> = [reply("d", Some("b"), 3)].into_iter().collect();
+        assert_eq!(thread_ancestor_ids(&tweets, "d"), vec!["b"]);
+    }
+
+    #[test]
+    fn test_activity_kind() {
+        let tweets: HashMap<String, api::Tweet> = [
+            tweet("mine", "me", &[], 0),
+            tweet("theirs", "them", &[], 0),
+            tweet("mention", "them", &[], 1),
+            tweet("reply", "them", &[("replied_to", Some("mine"))], 2),
+            tweet(
+                "reply_elsewhere",
+                "them",
+                &[("replied_to", Some("theirs"))],
+                3,
+            ),
+            tweet(
+                "quote",
+                "them",
+                &[("replied_to", Some("mine")), ("quoted", Some("mine"))],
+                4,
+            ),
+        ]
+        .into_iter()
+        .collect();
+
+        let kind = |tweet_id| activity_kind(&tweets, "me", tweet_id);
+        assert_eq!(kind("mention"), ActivityKind::Mention);
+        assert_eq!(kind("reply"), ActivityKind::Reply);
+        assert_eq!(kind("reply_elsewhere"), ActivityKind::Mention);
+        assert_eq!(kind("quote"), ActivityKind::Quote);
+        assert_eq!(kind("missing"), ActivityKind::Mention);
+    }
+
+    #[test]
+    fn test_is_newer_tweet_id() {
+        assert!(is_newer_tweet_id("1626", "1625"));
+        assert!(is_newer_tweet_id("10000", "9999"));
+        assert!(!is_newer_tweet_id("1625", "1625"));
+        assert!(!is_newer_tweet_id("9999", "10000"));
+    }
+
+    #[test]
+    fn test_sorted_feed() {
+        let with_metrics = |(_, mut tweet): (String, api::Tweet), likes, retweets| {
+            tweet.public_metrics = Some(api::PublicMetrics {
+                retweet_count: retweets,
+                reply_count: 0,
+                like_count: likes,
+                quote_count: 0,
+            });
+            tweet
+        };
+        let (_, retweet) = tweet("rt", "2", &[("retweeted", Some("b"))], 3);
+        let store = fixtures::store(vec![
+            with_metrics(tweet("a", "2", &[], 2), 5, 1),
+            retweet,
+            tweet("c", "2", &[], 1).1,
+            with_metrics(tweet("d", "2", &[], 0), 5, 0),
+        ]);
+        store.tweets.lock().unwrap().insert(
+            "b".to_string(),
+            with_metrics(tweet("b", "3", &[], 0), 10, 0),
+        );
+
+        assert_eq!(store.sorted_feed(), vec!["a", "rt", "c", "d"]);
+        *store.feed_sort.lock().unwrap() = FeedSort::MostLiked;
+        assert_eq!(store.sorted_feed(), vec!["rt", "a", "d", "c"]);
+        *store.feed_sort.lock().unwrap() = FeedSort::MostRetweeted;
+        assert_eq!(store.sorted_feed(), vec!["a", "rt", "c", "d"]);
+        assert_eq!(
+            "replies".parse::<FeedSort>().unwrap(),
+            FeedSort::MostReplied
+        );
+        assert!("oldest".parse::<FeedSort>().is_err());
+    }
+
+    fn mock_store() -> (MockTwitterClient, Store<MockTwitterClient>) {
+        let twitter_client = MockTwitterClient::new();
+        let me = api::User {
+            id: "1".to_string(),
+            name: "Me".to_string(),
+            username: "me".to_string(),
+            protected: None,
+            verified_type: None,
+            affiliation: None,
+            profile_image_url: None,
+            created_at: None,
+            public_metrics: None,
+        };
+        // NB: the fixture pages are two tweets long
+        let store =
+            Store::new(twitter_client.clone(), &me, &UserConfig::default()).with_page_size(2);
+        (twitter_client, store)
+    }
+
+    fn feed(store: &Store<MockTwitterClient>) -> Vec<String> {
+        store.tweets_feed.lock().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_load_tweets_feed_pages() {
+        let (twitter_client, store) = mock_store();
+        let timeline = "/2/users/1/timelines/reverse_chronological";
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_2);
+
+        store.load_tweets_reverse_chronological(true).await.unwrap();
+        assert_eq!(feed(&store), vec!["1005", "1004"]);
+        // NB: the quoted tweet is cached but not in the feed
+        let quoted = store.get_tweet("900").unwrap();
+        assert_eq!(quoted.author_username.as_deref(), Some("me"));
+        // NB: as are their authors
+        let users = store.users.lock().unwrap();
+        assert_eq!(
+            users.by_username("alice").map(|user| user.id.as_str()),
+            Some("2")
+        );
+        assert!(users.by_id("1").is_some());
+        drop(users);
+
+        store
+            .load_tweets_reverse_chronological(false)
+            .await
+            .unwrap();
+        assert_eq!(feed(&store), vec!["1005", "1004", "1003"]);
+        assert!(store.get_tweet("800").is_some());
+
+        let error = store.load_tweets_reverse_chronological(false).await;
+        assert_eq!(error.unwrap_err().to_string(), "No more pages");
+        assert_eq!(
+            twitter_client.requests(),
+            vec![
+                timeline.to_string(),
+                format!("{timeline}?pagination_token=page_2")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_tweets_feed_past_cache_cap() {
+        let (twitter_client, store) = mock_store();
+        store.user_config.lock().unwrap().max_cached_tweets = NonZeroUsize::new(1).unwrap();
+        let timeline = "/2/users/1/timelines/reverse_chronological";
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_2);
+
+        // NB: a page bigger than the cap is kept whole, since the feed is about to show it
+        store.load_tweets_reverse_chronological(true).await.unwrap();
+        assert_eq!(feed(&store), vec!["1005", "1004"]);
+        assert!(store.get_tweet("1005").is_some());
+        assert!(store.get_tweet("1004").is_some());
+        assert!(store.get_tweet("900").is_some());
+
+        store
+            .load_tweets_reverse_chronological(false)
+            .await
+            .unwrap();
+        for tweet_id in feed(&store) {
+            assert!(store.get_tweet(&tweet_id).is_some());
+        }
+    }
+
+    #[test]
+    fn test_conversations() {
+        let in_conversation = |id: &str, username: &str, conversation_id: &str| api::Tweet {
+            conversation_id: Some(conversation_id.to_string()),
+            ..fixtures::tweet(id, username, "", 0)
+        };
+        let retweet = api::Tweet {
+            referenced_tweets: Some(vec![api::TweetReference {
+                r#type: "retweeted".to_string(),
+                id: "a".to_string(),
+            }]),
+            ..fixtures::tweet("rt", "dave", "", 0)
+        };
+        let store = fixtures::store(vec![
+            in_conversation("c", "bob", "a"),
+            fixtures::tweet("x", "erin", "", 0),
+            in_conversation("b", "carol", "a"),
+            retweet,
+            in_conversation("d", "bob", "a"),
+        ]);
+        store
+            .tweets
+            .lock()
+            .unwrap()
+            .insert("a".to_string(), in_conversation("a", "alice", "a"));
+
+        let conversations = store.conversations();
+        assert_eq!(conversations.len(), 2);
+        assert_eq!(conversations[0].conversation_id, "a");
+        assert_eq!(conversations[0].tweet_ids, vec!["c", "b", "rt", "d"]);
+        assert_eq!(conversations[0].participants, vec!["bob", "carol", "alice"]);
+        assert_eq!(
+            conversations[0].describe(),
+            "@bob, @carol and @alice are discussing"
+        );
+        assert_eq!(conversations[1].tweet_ids, vec!["x"]);
+        assert_eq!(conversations[1].describe(), "@erin is discussing");
+    }
+
+    #[test]
+    fn test_authors() {
+        let store = fixtures::store(vec![
+            fixtures::tweet("5", "alice", "", 5),
+            fixtures::tweet("4", "bob", "", 4),
+            fixtures::tweet("3", "Bob", "", 3),
+            fixtures::tweet("2", "carol", "", 2),
+            fixtures::tweet("1", "dave", "", 1),
+        ]);
+        let authors: Vec<(String, Vec<String>)> = store
+            .authors()
+            .into_iter()
+            .map(|author| (author.username, author.tweet_ids))
+            .collect();
+        let group = |username: &str, tweet_ids: &[&str]| {
+            let tweet_ids = tweet_ids.iter().map(|id| id.to_string()).collect();
+            (username.to_string(), tweet_ids)
+        };
+        // NB: usernames are case-insensitive, and ties keep feed order
+        assert_eq!(
+            authors,
+            vec![
+                group("bob", &["4", "3"]),
+                group("alice", &["5"]),
+                group("carol", &["2"]),
+                group("dave", &["1"]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_feed_back_to() {
+        let (twitter_client, store) = mock_store();
+        let timeline = "/2/users/1/timelines/reverse_chronological";
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_2);
+        store.load_tweets_reverse_chronological(true).await.unwrap();
+
+        let minute = |minute: u32| Utc.with_ymd_and_hms(2023, 2, 1, 0, minute, 0).unwrap();
+        let pages = Mutex::new(Vec::new());
+        let load_next_page = || store.load_tweets_reverse_chronological(false);
+        let on_page = |oldest| pages.lock().unwrap().push(oldest);
+
+        let tweet_id = store
+            .load_feed_back_to(minute(5), load_next_page, on_page)
+            .await
+            .unwrap();
+        assert_eq!(tweet_id, "1004");
+        assert!(pages.lock().unwrap().is_empty());
+
+        let tweet_id = store
+            .load_feed_back_to(minute(4), load_next_page, on_page)
+            .await
+            .unwrap();
+        assert_eq!(tweet_id, "1003");
+        assert_eq!(*pages.lock().unwrap(), vec![minute(3)]);
+
+        let error = store
+            .load_feed_back_to(minute(3), load_next_page, on_page)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().starts_with("The feed only goes back to"));
+    }
+
+    #[tokio::test]
+    async fn test_queued_next_page() {
+        let (twitter_client, store) = mock_store();
+        let timeline = "/2/users/1/timelines/reverse_chronological";
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_2);
+
+        let page_load = store.begin_page_load(true).unwrap();
+        // NB: pressed twice while loading, but queued once
+        assert!(store.begin_page_load(false).is_none());
+        assert!(store.begin_page_load(false).is_none());
+        store.load_home_pages(page_load, true).await.unwrap();
+        assert_eq!(feed(&store), vec!["1005", "1004", "1003"]);
+        assert_eq!(twitter_client.requests().len(), 2);
+
+        // NB: nor does an aborted load leave one in flight
+        drop(store.begin_page_load(false).unwrap());
+        assert!(store.begin_page_load(false).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_search_results() {
+        let (twitter_client, store) = mock_store();
+        let search = "/2/tweets/search/recent";
+        twitter_client.respond(search, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(search, mock::fixtures::TIMELINE_PAGE_2);
+        twitter_client.respond(search, mock::fixtures::TIMELINE_PAGE_1);
+
+        let meta = store.load_search_tweets("rust", true).await.unwrap();
+        let time = |tweet_id: &str| {
+            let tweet = store.get_tweet(tweet_id).unwrap();
+            tweet.local_created_at().format("%m-%d %H:%M").to_string()
+        };
+        assert_eq!(
+            store.describe_search_results(meta.as_ref()),
+            format!(
+                "2 results (newest {}, oldest {})",
+                time("1005"),
+                time("1004")
+            )
+        );
+
+        let (meta, num_added) = store.append_search_tweets("go").await.unwrap();
+        assert_eq!(meta.unwrap().result_count, 1);
+        assert_eq!(num_added, 1);
+        assert_eq!(feed(&store), vec!["1005", "1004", "1003"]);
+        // NB: what's already in the feed isn't added again
+        let (_, num_added) = store.append_search_tweets("rust").await.unwrap();
+        assert_eq!(num_added, 0);
+        assert_eq!(feed(&store), vec!["1005", "1004", "1003"]);
+        assert_eq!(store.describe_search_results(None), "No results");
+    }
+
+    #[tokio::test]
+    async fn test_search_pages_per_feed() {
+        let (twitter_client, store) = mock_store();
+        let other_store = store.new_feed();
+        let search = "/2/tweets/search/recent";
+        twitter_client.respond(search, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(search, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(search, mock::fixtures::TIMELINE_PAGE_2);
+
+        store.load_search_tweets("rust", true).await.unwrap();
+        other_store.load_search_tweets("go", true).await.unwrap();
+        // NB: the other search doesn't clobber this one's feed or next page
+        let meta = store.load_search_tweets("rust", false).await.unwrap();
+        assert_eq!(meta.unwrap().result_count, 1);
+        assert_eq!(feed(&store), vec!["1005", "1004", "1003"]);
+        assert_eq!(feed(&other_store), vec!["1005", "1004"]);
+        assert_eq!(
+            twitter_client.requests().last().unwrap(),
+            &format!("{search}?query=rust&next_token=page_2")
+        );
+        let error = store.load_search_tweets("rust", false).await.unwrap_err();
+        assert_eq!(error.to_string(), "No more pages");
+    }
+
+    #[tokio::test]
+    async fn test_muted_users() {
+        let (twitter_client, store) = mock_store();
+        let timeline = "/2/users/1/timelines/reverse_chronological";
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_1);
+
+        store.user_config.lock().unwrap().mute_username("@Bob");
+        store.load_tweets_reverse_chronological(true).await.unwrap();
+        assert_eq!(feed(&store), vec!["1005"]);
+
+        store.user_config.lock().unwrap().mute_username("alice");
+        store.hide_muted();
+        assert!(feed(&store).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_tweets_feed_error() {
+        let (twitter_client, store) = mock_store();
+        let timeline = "/2/users/1/timelines/reverse_chronological";
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.fail(timeline, "503 Service Unavailable: over capacity");
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_2);
+
+        store.load_tweets_reverse_chronological(true).await.unwrap();
+        let error = store.load_tweets_reverse_chronological(false).await;
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "503 Service Unavailable: over capacity"
+        );
+        assert_eq!(feed(&store), vec!["1005", "1004"]);
+
+        // NB: a failed page leaves the page token alone, so it can be retried
+        store
+            .load_tweets_reverse_chronological(false)
+            .await
+            .unwrap();
+        assert_eq!(feed(&store), vec!["1005", "1004", "1003"]);
+        assert_eq!(
+            twitter_client.requests().last().unwrap(),
+            &format!("{timeline}?pagination_token=page_2")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_my_tweets() {
+        let (twitter_client, store) = mock_store();
+        let user_tweets = "/2/users/1/tweets";
+        twitter_client.respond(user_tweets, mock::fixtures::TIMELINE_PAGE_2);
+        let (id, cached) = tweet("2000", "1", &[], 59);
+        store.tweets.lock().unwrap().insert(id, cached);
+
+        let tweets = store.load_my_tweets().await.unwrap();
+        let ids: Vec<&str> = tweets.iter().map(|tweet| tweet.id.as_str()).collect();
+        assert_eq!(ids, vec!["2000"]);
+        assert!(store.get_tweet("1003").is_some());
+        assert!(feed(&store).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_user_timeline() {
+        let (twitter_client, store) = mock_store();
+        twitter_client.respond(
+            "/2/users/by/username/alice",
+            r#"{
+                "data": {"id": "2", "name": "Alice", "username": "alice", "pinned_tweet_id": "500"},
+                "includes": {"tweets": [
+                    {"id": "500", "text": "pinned", "created_at": "2022-01-01T00:00:00.000Z", "author_id": "2"}
+                ]}
+            }"#,
+        );
+        twitter_client.respond("/2/users/2/tweets", mock::fixtures::TIMELINE_PAGE_1);
+
+        store.load_user_timeline("alice").await.unwrap();
+        assert_eq!(store.sorted_feed(), vec!["500", "1005", "1004"]);
+        let pinned = store.get_tweet("500").unwrap();
+        assert_eq!(pinned.author_username.as_deref(), Some("alice"));
+
+        // NB: another feed in its place isn't pinned
+        let timeline = "/2/users/1/timelines/reverse_chronological";
+        twitter_client.respond(timeline, mock::fixtures::TIMELINE_PAGE_1);
+        store.load_tweets_reverse_chronological(true).await.unwrap();
+        assert_eq!(store.sorted_feed(), vec!["1005", "1004"]);
+    }
+
+    #[tokio::test]
+    async fn test_load_catch_up() {
+        let (twitter_client, store) = mock_store();
+        let config_path =
+            std::env::temp_dir().join(format!("twitter-tool-catch-up-{}.toml", std::process::id()));
+        let store = store.with_user_config_path(&config_path);
+        twitter_client.respond(
+            "/2/users/by/username/alice",
+            r#"{"data": {"id": "2", "name": "Alice", "username": "alice"}}"#,
+        );
+        let tweets = "/2/users/2/tweets";
+
+        // NB: the first time, just the latest page, though there are more
+        twitter_client.respond(tweets, mock::fixtures::TIMELINE_PAGE_1);
+        assert_eq!(store.load_catch_up("alice").await.unwrap(), 2);
+        assert_eq!(feed(&store), vec!["1005", "1004"]);
+        let read_marker = |store: &Store<MockTwitterClient>| {
+            store
+                .user_config
+                .lock()
+                .unwrap()
+                .read_markers
+                .get("2")
+                .cloned()
+        };
+        assert_eq!(read_marker(&store).as_deref(), Some("1005"));
+
+        // NB: then every page since
+        let tweet = |id: &str, next_token: Option<&str>| {
+            serde_json::json!({
+                "data": [{"id": id, "text": "hi", "created_at": "2023-02-02T00:00:00.000Z", "author_id": "2"}],
+                "includes": {"users": [{"id": "2", "name": "Alice", "username": "alice"}]},
+                "meta": {"result_count": 1, "next_token": next_token}
+            })
+            .to_string()
+        };
+        twitter_client.respond(tweets, &tweet("1007", Some("page_2")));
+        twitter_client.respond(tweets, &tweet("1006", None));
+        assert_eq!(store.load_catch_up("alice").await.unwrap(), 2);
+        assert_eq!(feed(&store), vec!["1007", "1006"]);
+        assert_eq!(read_marker(&store).as_deref(), Some("1007"));
+        assert_eq!(
+            twitter_client.requests()[2..],
+            [
+                "/2/users/2/tweets?since_id=1005",
+                "/2/users/2/tweets?since_id=1005&pagination_token=page_2"
+            ]
+        );
+
+        // NB: a failed page leaves the marker where it was
+        twitter_client.fail(tweets, "503 Service Unavailable");
+        assert!(store.load_catch_up("alice").await.is_err());
+        assert_eq!(read_marker(&store).as_deref(), Some("1007"));
+
+        std::fs::remove_file(&config_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_load_protected_user_timeline() {
+        let (twitter_client, store) = mock_store();
+        twitter_client.respond(
+            "/2/users/by/username/alice",
+            r#"{"data": {"id": "2", "name": "Alice", "username": "alice", "protected": true}}"#,
+        );
+        twitter_client.respond(
+            "/2/users/2/tweets",
+            r#"{"errors": [{"resource_type": "user", "resource_id": "2", "title": "Authorization Error",
+                            "detail": "Sorry, you are not authorized to see the user with id: [2].",
+                            "type": "https://api.twitter.com/2/problems/not-authorized-for-resource"}]}"#,
+        );
+
+        let error = store.load_user_timeline("alice").await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "@alice is protected: only the followers they've approved can see their tweets (Sorry, \
+             you are not authorized to see the user with id: [2].)"
+        );
+        let users = store.users.lock().unwrap();
+        assert!(users.by_username("alice").unwrap().is_protected());
+    }
+
+    #[tokio::test]
+    async fn test_compare_following() {
+        let (twitter_client, store) = mock_store();
+        let users = |ids: &[&str]| {
+            let users: Vec<String> = ids
+                .iter()
+                .map(|id| format!(r#"{{"id": "{id}", "name": "{id}", "username": "{id}"}}"#))
+                .collect();
+            format!(
+                r#"{{"data": [{}], "meta": {{"result_count": 0}}}}"#,
+                users.join(",")
+            )
+        };
+        twitter_client.respond(
+            "/2/users/by/username/alice",
+            r#"{"data": {"id": "2", "name": "Alice", "username": "alice"}}"#,
+        );
+        twitter_client.respond(
+            "/2/users/by/username/bob",
+            r#"{"data": {"id": "3", "name": "Bob", "username": "bob"}}"#,
+        );
+        twitter_client.respond("/2/users/2/following", &users(&["y", "x", "z"]));
+        twitter_client.respond("/2/users/3/following", &users(&["z", "w", "x"]));
+
+        let overlap = store.compare_following("@alice", "bob").await.unwrap();
+        let usernames = |users: &[api::User]| -> Vec<String> {
+            users.iter().map(|user| user.username.clone()).collect()
+        };
+        assert_eq!(usernames(&overlap.both), vec!["x", "z"]);
+        assert_eq!(usernames(&overlap.only_first), vec!["y"]);
+        assert_eq!(usernames(&overlap.only_second), vec!["w"]);
+        assert!(overlap
+            .describe()
+            .starts_with("Both follow (2)\n  @x (x)\n  @z (z)\n\nOnly @alice follows (1)\n"));
+
+        // NB: @alice is cached by now, so isn't looked up again
+        twitter_client.respond("/2/users/2/following", &users(&["x"]));
+        let error = store
+            .compare_following("alice", "nobody")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().starts_with("404"));
+        let lookups = twitter_client
+            .requests()
+            .into_iter()
+            .filter(|request| request == "/2/users/by/username/alice")
+            .count();
+        assert_eq!(lookups, 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_activity() {
+        let (twitter_client, store) = mock_store();
+        twitter_client.respond("/2/users/1/mentions", mock::fixtures::MENTIONS);
+        twitter_client.respond("/2/tweets/search/recent", mock::fixtures::QUOTES);
+
+        let new_tweets = store.load_activity().await.unwrap();
+        // NB: the quote also mentions me, but is only in the feed once
+        assert_eq!(feed(&store), vec!["1010", "1008", "1004"]);
+        assert_eq!(new_tweets.len(), 3);
+
+        twitter_client.respond("/2/users/1/mentions", mock::fixtures::MENTIONS);
+        twitter_client.respond("/2/tweets/search/recent", mock::fixtures::QUOTES);
+        assert!(store.load_activity().await.unwrap().is_empty());
+        let tweets = store.tweets.lock().unwrap();
+        assert_eq!(activity_kind(&tweets, "1", "1010"), ActivityKind::Mention);
+        assert_eq!(activity_kind(&tweets, "1", "1008"), ActivityKind::Reply);
+        assert_eq!(activity_kind(&tweets, "1", "1004"), ActivityKind::Quote);
+    }
+
+    #[tokio::test]
+    async fn test_load_tweets_only_fetches_missing() {
+        let (twitter_client, store) = mock_store();
+        twitter_client.respond("/2/users/1/mentions", mock::fixtures::MENTIONS);
+        store.load_mentions(true).await.unwrap();
+
+        let error = store
+            .load_tweets(&["1010".to_string(), "1".to_string(), "2".to_string()])
+            .await;
+        assert_eq!(error.unwrap_err().to_string(), "404 Not Found: /2/tweets");
+        assert_eq!(
+            twitter_client.requests().last().unwrap(),
+            "/2/tweets?ids=1,2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_conversation_with_deleted_parent() {
+        let (twitter_client, store) = mock_store();
+        let (id, reply) = tweet("2", "3", &[("replied_to", Some("1"))], 2);
+        store.tweets.lock().unwrap().insert(id, reply);
+        twitter_client.respond(
+            "/2/tweets",
+            r#"{"errors": [{"resource_type": "tweet", "resource_id": "1", "title": "Not Found Error",
+                            "type": "https://api.twitter.com/2/problems/resource-not-found"}]}"#,
+        );
+        // NB: no results means no `data` at all
+        twitter_client.respond(
+            "/2/tweets/search/recent",
+            r#"{"meta": {"result_count": 0}}"#,
+        );
+
+        store.load_conversation("2").await.unwrap();
+        assert_eq!(
+            store.unavailable_reason("1"),
+            Some(api::Unavailable::NotFound)
+        );
+        // NB: not asked for again
+        store.load_tweets(&["1".to_string()]).await.unwrap();
+        let lookups: Vec<String> = twitter_client
+            .requests()
+            .into_iter()
+            .filter(|request| request.starts_with("/2/tweets?"))
+            .collect();
+        assert_eq!(lookups, vec!["/2/tweets?ids=1"]);
+    }
+
+    #[test]
+    fn test_record_alert_hits() {
+        let (_, store) = mock_store();
+        let rule = crate::alerts::AlertRule {
+            keyword: "rust".to_string(),
+            author: None,
+        };
+        let hits: Vec<AlertHit> = (0..MAX_ALERT_HITS + 1)
+            .map(|i| AlertHit {
+                rule: rule.clone(),
+                tweet: fixtures::tweet(&i.to_string(), "alice", "rust", 0),
+            })
+            .collect();
+        store.record_alert_hits(&hits);
+        // NB: shared with other feeds, e.g. so `:alerts` sees the home feed's
+        let alert_hits = store.new_feed().alert_hits();
+        assert_eq!(alert_hits.len(), MAX_ALERT_HITS);
+        assert_eq!(alert_hits[0].tweet.id, MAX_ALERT_HITS.to_string());
+        assert_eq!(alert_hits.last().unwrap().tweet.id, "1");
+    }
+
+    #[test]
+    fn test_evict_tweets() {
+        let store = fixtures::store(vec![fixtures::tweet("1", "alice", "in the feed", 0)]);
+        store.user_config.lock().unwrap().max_cached_tweets = NonZeroUsize::new(4).unwrap();
+        let cache = |tweet_id: &str| {
+            store.cache_tweet_page(api::TweetPage {
+                tweets: vec![fixtures::tweet(tweet_id, "bob", "hi", 1)],
+                ..Default::default()
+            })
+        };
+        for tweet_id in ["2", "3", "4"] {
+            cache(tweet_id);
+        }
+        assert_eq!(store.tweet_cache_stats(), (4, 0));
+        store.get_tweet("2");
+        // NB: down to 90% of the cap, least recently used first, but keeping the feed's
+        cache("5");
+        let mut tweet_ids: Vec<String> = store.tweets.lock().unwrap().keys().cloned().collect();
+        tweet_ids.sort();
+        assert_eq!(tweet_ids, vec!["1", "2", "5"]);
+        assert_eq!(store.tweet_cache_stats(), (3, 2));
+
+        // NB: a closed feed's tweets can go
+        let other_feed = store.new_feed();
+        *other_feed.tweets_feed.lock().unwrap() = vec!["2".to_string()];
+        drop(other_feed);
+        for tweet_id in ["6", "7"] {
+            cache(tweet_id);
+        }
+        assert!(store.get_tweet("1").is_some());
+        assert!(store.get_tweet("2").is_none());
+    }
 }
@@ -0,0 +1,130 @@
+//! Tweet length as Twitter counts it (twitter-text v3 rules), so the compose counter agrees with
+//! the API about what is too long.
+//!
+//! - Code points in the [LIGHT_RANGES] (Latin, Greek, Cyrillic, etc. and some punctuation) weigh 1,
+//!   everything else (notably CJK) weighs 2.
+//! - An emoji counts as 2 regardless of how many code points make it up (ZWJ sequences, skin
+//!   tones, flags, keycaps).
+//! - Every URL counts as [URL_LENGTH], regardless of its actual length.
+//!
+//! NB: twitter-text NFC-normalizes before counting; we don't, so decomposed input may overcount.
+
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const MAX_WEIGHTED_LENGTH: usize = 280;
+pub const URL_LENGTH: usize = 23;
+
+const LIGHT_RANGES: [(u32, u32); 4] = [
+    (0x0000, 0x10FF),
+    (0x2000, 0x200D),
+    (0x2010, 0x201F),
+    (0x2032, 0x2037),
+];
+
+const EMOJI_RANGES: [(u32, u32); 8] = [
+    (0x1F000, 0x1FAFF),
+    (0x2600, 0x27BF),
+    (0x2300, 0x23FF),
+    (0x2B00, 0x2BFF),
+    (0x1F1E6, 0x1F1FF),
+    (0x3030, 0x3030),
+    (0x303D, 0x303D),
+    (0x3297, 0x3299),
+];
+
+fn in_ranges(ch: char, ranges: &[(u32, u32)]) -> bool {
+    let ch = ch as u32;
+    ranges.iter().any(|&(from, to)| from <= ch && ch <= to)
+}
+
+fn is_emoji_grapheme(grapheme: &str) -> bool {
+    let starts_with_emoji = grapheme
+        .chars()
+        .next()
+        .is_some_and(|ch| in_ranges(ch, &EMOJI_RANGES));
+    // NB: variation selector 16 and the keycap combiner turn e.g. digits into emoji
+    starts_with_emoji || grapheme.contains(['\u{FE0F}', '\u{20E3}'])
+}
+
+fn weigh_text(text: &str) -> usize {
+    text.graphemes(true)
+        .map(|grapheme| {
+            if is_emoji_grapheme(grapheme) {
+                2
+            } else {
+                grapheme
+                    .chars()
+                    .map(|ch| if in_ranges(ch, &LIGHT_RANGES) { 1 } else { 2 })
+                    .sum()
+            }
+        })
+        .sum()
+}
+
+fn url_regex() -> Regex {
+    Regex::new(concat!(
+        r"(?i)\b(?:https?://[^\s]+",
+        r"|(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+",
+        r"(?:com|net|org|io|co|dev|app|ly|me|gg|xyz|ai|info|edu|gov|us|uk)\b(?:/[^\s]*)?)",
+    ))
+    .unwrap()
+}
+
+/// The length of [text] as Twitter counts it against [MAX_WEIGHTED_LENGTH].
+pub fn weighted_length(text: &str) -> usize {
+    let re_url = url_regex();
+    let mut length = 0;
+    let mut last_end = 0;
+
+    for url in re_url.find_iter(text) {
+        length += weigh_text(&text[last_end..url.start()]) + URL_LENGTH;
+        last_end = url.end();
+    }
+    length + weigh_text(&text[last_end..])
+}
+
+pub fn is_within_limit(text: &str) -> bool {
+    weighted_length(text) <= MAX_WEIGHTED_LENGTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latin() {
+        assert_eq!(weighted_length("hello world"), 11);
+        assert_eq!(weighted_length("café — “quoted”"), 15);
+        assert!(is_within_limit(&"a".repeat(280)));
+        assert!(!is_within_limit(&"a".repeat(281)));
+    }
+
+    #[test]
+    fn test_cjk() {
+        assert_eq!(weighted_length("日本語"), 6);
+        assert!(is_within_limit(&"字".repeat(140)));
+        assert!(!is_within_limit(&"字".repeat(141)));
+    }
+
+    #[test]
+    fn test_emoji() {
+        assert_eq!(weighted_length("😀"), 2);
+        assert_eq!(weighted_length("👍🏽"), 2);
+        assert_eq!(weighted_length("👨‍👩‍👧‍👦"), 2);
+        assert_eq!(weighted_length("🇯🇵"), 2);
+        assert_eq!(weighted_length("❤️"), 2);
+        assert_eq!(weighted_length("hi 😀"), 5);
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(weighted_length("https://example.com"), 23);
+        assert_eq!(
+            weighted_length("see https://example.com/a/very/long/path?with=query&and=more ok"),
+            4 + 23 + 3
+        );
+        assert_eq!(weighted_length("twitter.com/foo"), 23);
+        assert_eq!(weighted_length("a.b"), 3);
+    }
+}
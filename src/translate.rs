@@ -0,0 +1,125 @@
+//! Translating tweets through a translation service, configured in [UserConfig::translate].
+//! Nothing is sent anywhere until the user asks for a translation.
+//!
+//! [UserConfig::translate]: crate::user_config::UserConfig::translate
+
+use crate::http_client::post_json;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    DeepL,
+    LibreTranslate,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranslateConfig {
+    pub provider: Provider,
+    /// e.g. "https://api-free.deepl.com" or "https://libretranslate.com"
+    pub url: String,
+    /// Required by DeepL; optional for self-hosted LibreTranslate
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// The language to translate into, e.g. "en"
+    #[serde(default = "default_target_lang")]
+    pub target_lang: String,
+}
+
+fn default_target_lang() -> String {
+    "en".to_string()
+}
+
+impl TranslateConfig {
+    /// [text] in [TranslateConfig::target_lang], from whatever language the service detects.
+    pub async fn translate(&self, text: &str) -> Result<String> {
+        let (url, authorization, body) = self.request(text);
+        let resp = post_json(&url, authorization.as_deref(), body).await?;
+        self.parse_response(&resp)
+    }
+
+    /// The (url, Authorization header, body) of a request to translate [text].
+    fn request(&self, text: &str) -> (String, Option<String>, serde_json::Value) {
+        let url = self.url.trim_end_matches('/');
+        match self.provider {
+            Provider::DeepL => (
+                format!("{url}/v2/translate"),
+                self.api_key
+                    .as_ref()
+                    .map(|api_key| format!("DeepL-Auth-Key {api_key}")),
+                serde_json::json!({
+                    "text": [text],
+                    "target_lang": self.target_lang.to_uppercase(),
+                }),
+            ),
+            Provider::LibreTranslate => {
+                let mut body = serde_json::json!({
+                    "q": text,
+                    "source": "auto",
+                    "target": self.target_lang,
+                    "format": "text",
+                });
+                if let Some(api_key) = &self.api_key {
+                    body["api_key"] = serde_json::json!(api_key);
+                }
+                (format!("{url}/translate"), None, body)
+            }
+        }
+    }
+
+    fn parse_response(&self, resp: &serde_json::Value) -> Result<String> {
+        let translation = match self.provider {
+            Provider::DeepL => resp["translations"][0]["text"].as_str(),
+            Provider::LibreTranslate => resp["translatedText"].as_str(),
+        };
+        translation
+            .map(str::to_string)
+            .ok_or(anyhow!("No translation in response: {resp}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(provider: Provider) -> TranslateConfig {
+        TranslateConfig {
+            provider,
+            url: "https://translate.example.com/".to_string(),
+            api_key: Some("key".to_string()),
+            target_lang: default_target_lang(),
+        }
+    }
+
+    #[test]
+    fn test_deepl() {
+        let config = config(Provider::DeepL);
+        let (url, authorization, body) = config.request("Hallo Welt");
+        assert_eq!(url, "https://translate.example.com/v2/translate");
+        assert_eq!(authorization.as_deref(), Some("DeepL-Auth-Key key"));
+        assert_eq!(
+            body,
+            serde_json::json!({"text": ["Hallo Welt"], "target_lang": "EN"})
+        );
+
+        let resp = serde_json::json!({
+            "translations": [{"detected_source_language": "DE", "text": "Hello world"}]
+        });
+        assert_eq!(config.parse_response(&resp).unwrap(), "Hello world");
+        assert!(config.parse_response(&serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_libretranslate() {
+        let config = config(Provider::LibreTranslate);
+        let (url, authorization, body) = config.request("Hallo Welt");
+        assert_eq!(url, "https://translate.example.com/translate");
+        assert_eq!(authorization, None);
+        assert_eq!(body["api_key"], "key");
+        assert_eq!(body["target"], "en");
+
+        let resp = serde_json::json!({"translatedText": "Hello world"});
+        assert_eq!(config.parse_response(&resp).unwrap(), "Hello world");
+    }
+}
@@ -1,11 +1,72 @@
 use chrono::{DateTime, Local, Utc};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Response<Data, Includes> {
     pub data: Data,
     pub includes: Option<Includes>,
     pub meta: Option<Meta>,
+    /// What couldn't be loaded, alongside whatever could
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<PartialError>,
+}
+
+/// An error about one of the objects asked for, or referenced, e.g. a deleted tweet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartialError {
+    pub title: Option<String>,
+    /// e.g. "tweet"
+    pub resource_type: Option<String>,
+    pub resource_id: Option<String>,
+    pub detail: Option<String>,
+    /// A URL naming the kind of error, e.g. "https://api.twitter.com/2/problems/resource-not-found"
+    pub r#type: Option<String>,
+}
+
+/// Why a tweet couldn't be loaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unavailable {
+    NotFound,
+    NotAuthorized,
+    Other,
+}
+
+impl PartialError {
+    pub fn describe(&self) -> String {
+        self.detail
+            .clone()
+            .or(self.title.clone())
+            .unwrap_or(format!("{self:?}"))
+    }
+
+    /// The tweet this error is about and why it couldn't be loaded; None if it's not about a tweet.
+    pub fn unavailable_tweet(&self) -> Option<(String, Unavailable)> {
+        if self.resource_type.as_deref() != Some("tweet") {
+            return None;
+        }
+        let reason = match self.r#type.as_deref() {
+            Some(r#type) if r#type.ends_with("/resource-not-found") => Unavailable::NotFound,
+            Some(r#type) if r#type.ends_with("/not-authorized-for-resource") => {
+                Unavailable::NotAuthorized
+            }
+            _ => Unavailable::Other,
+        };
+        Some((self.resource_id.clone()?, reason))
+    }
+}
+
+impl Unavailable {
+    /// e.g. "deleted"
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Unavailable::NotFound => "deleted",
+            Unavailable::NotAuthorized => "protected or withheld",
+            Unavailable::Other => "couldn't be loaded",
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -16,28 +77,118 @@ pub struct Meta {
     pub oldest_id: Option<String>,
 }
 
+impl Meta {
+    /// A page's [first] meta followed by the next page's, [next].
+    pub fn merge(first: Option<Meta>, next: Option<Meta>) -> Option<Meta> {
+        match (first, next) {
+            (Some(first), Some(next)) => Some(Meta {
+                next_token: next.next_token,
+                result_count: first.result_count + next.result_count,
+                newest_id: first.newest_id.or(next.newest_id),
+                oldest_id: next.oldest_id.or(first.oldest_id),
+            }),
+            (first, next) => first.or(next),
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct User {
     pub id: String,
     pub name: String,
     pub username: String,
+    /// Whether only approved followers can see their tweets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protected: Option<bool>,
+    /// "blue", "business" or "government"; "none" if not verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified_type: Option<String>,
+    /// The organization the account is affiliated with, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub affiliation: Option<Affiliation>,
+    /// Their avatar, 48x48
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile_image_url: Option<String>,
+    /// When the account was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_metrics: Option<UserPublicMetrics>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct UserPublicMetrics {
+    pub followers_count: u64,
+    pub following_count: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Affiliation {
+    /// e.g. the organization's name
+    pub description: Option<String>,
+}
+
+impl User {
+    pub fn is_protected(&self) -> bool {
+        self.protected == Some(true)
+    }
+
+    /// The organization named by [User::affiliation], if any.
+    pub fn affiliation_description(&self) -> Option<String> {
+        self.affiliation
+            .as_ref()
+            .and_then(|affiliation| affiliation.description.clone())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct List {
+    pub id: String,
+    pub name: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Tweet {
     pub id: String,
     pub text: String,
-    pub created_at: DateTime<Local>,
+    /// NB: kept in UTC as the API sends it; shown in local time, see [Tweet::local_created_at]
+    pub created_at: DateTime<Utc>,
     pub author_id: String,
     pub author_username: Option<String>,
     pub author_name: Option<String>,
+    /// Filled in from the response's includes, like [Tweet::author_username]
+    pub author_protected: Option<bool>,
+    /// See [User::verified_type]
+    pub author_verified_type: Option<String>,
+    /// See [User::affiliation]
+    pub author_affiliation: Option<String>,
+    /// See [User::profile_image_url]
+    pub author_profile_image_url: Option<String>,
+    /// See [User::created_at]
+    pub author_created_at: Option<DateTime<Utc>>,
+    /// See [User::public_metrics]
+    pub author_public_metrics: Option<UserPublicMetrics>,
     pub conversation_id: Option<String>,
     pub referenced_tweets: Option<Vec<TweetReference>>,
     pub attachments: Option<Attachments>,
     pub public_metrics: Option<PublicMetrics>,
+    pub entities: Option<Entities>,
+    /// As detected by Twitter, a BCP 47 tag like "en"; "und" if it couldn't tell
+    pub lang: Option<String>,
+    /// Whether the tweet's links or media may be sensitive, as flagged by Twitter
+    pub possibly_sensitive: Option<bool>,
+    /// The attached media, filled in from the response's includes by [Attachments::media_keys]
+    pub media: Option<Vec<Media>>,
+    /// Every version of the tweet, oldest first, this one included
+    pub edit_history_tweet_ids: Option<Vec<String>>,
+    pub edit_controls: Option<EditControls>,
 }
 
 impl Tweet {
+    pub fn local_created_at(&self) -> DateTime<Local> {
+        self.created_at.with_timezone(&Local)
+    }
+
     pub fn author(&self, fill_unknown_with: &str) -> User {
         User {
             id: self.author_id.clone(),
@@ -49,8 +200,165 @@ impl Tweet {
                 .author_username
                 .clone()
                 .unwrap_or(fill_unknown_with.to_string()),
+            protected: self.author_protected,
+            verified_type: self.author_verified_type.clone(),
+            affiliation: self
+                .author_affiliation
+                .clone()
+                .map(|description| Affiliation {
+                    description: Some(description),
+                }),
+            profile_image_url: self.author_profile_image_url.clone(),
+            created_at: self.author_created_at,
+            public_metrics: self.author_public_metrics,
         }
     }
+
+    /// URLs in the tweet, in order of appearance, with t.co links unwrapped where the entities
+    /// tell us the destination.
+    pub fn urls(&self) -> Vec<String> {
+        let url_entities = self
+            .entities
+            .as_ref()
+            .and_then(|entities| entities.urls.as_ref());
+        let re_url = Regex::new(r"https?://[^\s]+").unwrap();
+        let mut urls: Vec<String> = Vec::new();
+
+        for url in re_url.find_iter(&self.text) {
+            // NB: sentence punctuation directly after a URL is almost never part of it
+            let url = url
+                .as_str()
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', '"', '\'']);
+            let expanded_url = url_entities
+                .and_then(|url_entities| url_entities.iter().find(|entity| entity.url == url))
+                .and_then(|entity| entity.expanded_url.clone())
+                .unwrap_or(url.to_string());
+            if !urls.contains(&expanded_url) {
+                urls.push(expanded_url);
+            }
+        }
+
+        urls
+    }
+
+    /// The id of the tweet this one references as [kind], i.e. "retweeted", "quoted" or
+    /// "replied_to".
+    pub fn referenced_tweet_id(&self, kind: &str) -> Option<&str> {
+        self.referenced_tweets
+            .iter()
+            .flatten()
+            .find(|reference| reference.r#type == kind)
+            .map(|reference| reference.id.as_str())
+    }
+
+    pub fn retweeted_id(&self) -> Option<&str> {
+        self.referenced_tweet_id("retweeted")
+    }
+
+    pub fn quoted_id(&self) -> Option<&str> {
+        self.referenced_tweet_id("quoted")
+    }
+
+    pub fn replied_to_id(&self) -> Option<&str> {
+        self.referenced_tweet_id("replied_to")
+    }
+
+    /// The ids of the tweet's other versions, oldest first.
+    pub fn other_version_ids(&self) -> Vec<&str> {
+        self.edit_history_tweet_ids
+            .iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|&id| id != self.id)
+            .collect()
+    }
+
+    /// Whether this is the latest version of a tweet that's been edited.
+    pub fn is_edited(&self) -> bool {
+        !self.other_version_ids().is_empty() && !self.is_superseded()
+    }
+
+    /// Whether there's a newer version of the tweet than this one.
+    pub fn is_superseded(&self) -> bool {
+        self.edit_history_tweet_ids
+            .as_ref()
+            .and_then(|ids| ids.last())
+            .is_some_and(|latest_id| *latest_id != self.id)
+    }
+
+    pub fn is_possibly_sensitive(&self) -> bool {
+        self.possibly_sensitive == Some(true)
+    }
+
+    /// Whether every attached photo or GIF has alt text; None if there aren't any.
+    pub fn is_media_described(&self) -> Option<bool> {
+        let mut images = self
+            .media
+            .iter()
+            .flatten()
+            .filter(|media| media.r#type != "video")
+            .peekable();
+        images.peek()?;
+        Some(images.all(Media::is_described))
+    }
+}
+
+/// A page of tweets, with the tweets they reference (retweeted, quoted, replied to) when the API
+/// included them.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TweetPage {
+    pub tweets: Vec<Tweet>,
+    pub referenced_tweets: Vec<Tweet>,
+    /// Why each tweet in the response that didn't parse was left out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+    /// Tweets asked for or referenced that the API couldn't give us, by id
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub unavailable: HashMap<String, Unavailable>,
+    /// The response's result count and id range, e.g. to say how much a search found
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+/// The abbreviated tweet returned by the create tweet endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CreatedTweet {
+    pub id: String,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Entities {
+    pub urls: Option<Vec<UrlEntity>>,
+    pub mentions: Option<Vec<MentionEntity>>,
+    pub hashtags: Option<Vec<TagEntity>>,
+    pub cashtags: Option<Vec<TagEntity>>,
+}
+
+/// NB: [start] and [end] index into the tweet text in code points, not bytes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UrlEntity {
+    pub start: usize,
+    pub end: usize,
+    pub url: String,
+    pub expanded_url: Option<String>,
+    pub display_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MentionEntity {
+    pub start: usize,
+    pub end: usize,
+    pub username: String,
+    pub id: Option<String>,
+}
+
+/// A hashtag or cashtag; [tag] excludes the leading '#' or '$'.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TagEntity {
+    pub start: usize,
+    pub end: usize,
+    pub tag: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -65,6 +373,35 @@ pub struct Attachments {
     pub media_keys: Option<Vec<String>>,
 }
 
+/// A photo, GIF or video attached to a tweet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Media {
+    pub media_key: String,
+    /// "photo", "animated_gif" or "video"
+    pub r#type: String,
+    /// Photos only
+    pub url: Option<String>,
+    /// GIFs and videos
+    pub preview_image_url: Option<String>,
+    pub alt_text: Option<String>,
+}
+
+impl Media {
+    pub fn is_described(&self) -> bool {
+        self.alt_text
+            .as_ref()
+            .is_some_and(|alt_text| !alt_text.trim().is_empty())
+    }
+}
+
+/// Whether, and until when, the tweet can still be edited.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EditControls {
+    pub edits_remaining: i32,
+    pub is_edit_eligible: bool,
+    pub editable_until: DateTime<Utc>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PublicMetrics {
     pub retweet_count: i32,
@@ -72,3 +409,75 @@ pub struct PublicMetrics {
     pub like_count: i32,
     pub quote_count: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_unwraps_tco() {
+        let tweet: Tweet = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "text": "read this https://t.co/abc and https://example.com/x, also https://t.co/abc",
+            "created_at": "2023-02-01T00:00:00Z",
+            "author_id": "2",
+            "entities": {
+                "urls": [{
+                    "start": 10,
+                    "end": 33,
+                    "url": "https://t.co/abc",
+                    "expanded_url": "https://blog.example.com/post",
+                    "display_url": "blog.example.com/post"
+                }]
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            tweet.urls(),
+            vec!["https://blog.example.com/post", "https://example.com/x"]
+        );
+    }
+
+    #[test]
+    fn test_referenced_tweet_ids() {
+        let tweet: Tweet = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "text": "so true",
+            "created_at": "2023-02-01T00:00:00Z",
+            "author_id": "2",
+            "referenced_tweets": [
+                {"type": "replied_to", "id": "3"},
+                {"type": "quoted", "id": "4"}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(tweet.retweeted_id(), None);
+        assert_eq!(tweet.quoted_id(), Some("4"));
+        assert_eq!(tweet.replied_to_id(), Some("3"));
+    }
+
+    #[test]
+    fn test_edit_history() {
+        let tweet = |id: &str, edit_history_tweet_ids: &[&str]| -> Tweet {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "text": "typo",
+                "created_at": "2023-02-01T00:00:00Z",
+                "author_id": "2",
+                "edit_history_tweet_ids": edit_history_tweet_ids,
+            }))
+            .unwrap()
+        };
+        let unedited = tweet("1", &["1"]);
+        assert!(!unedited.is_edited() && !unedited.is_superseded());
+        assert!(unedited.other_version_ids().is_empty());
+
+        let latest = tweet("3", &["1", "3"]);
+        assert!(latest.is_edited() && !latest.is_superseded());
+        assert_eq!(latest.other_version_ids(), vec!["1"]);
+
+        let earliest = tweet("1", &["1", "3"]);
+        assert!(!earliest.is_edited() && earliest.is_superseded());
+        assert_eq!(earliest.other_version_ids(), vec!["3"]);
+    }
+}
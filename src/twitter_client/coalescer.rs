@@ -0,0 +1,241 @@
+//! Tweet lookups made at about the same time (e.g. by thread resolution, hydration and the feed)
+//! share `GET /2/tweets?ids=` calls: ids are gathered for [COALESCE_WINDOW], then fetched in
+//! batches of up to [MAX_IDS_PER_LOOKUP], and an id that's already being fetched isn't asked for
+//! again.
+
+use crate::twitter_client::api;
+use crate::twitter_client::paginator::Page;
+use anyhow::{anyhow, Result};
+use futures_util::future::{self, BoxFuture, FutureExt, Shared};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+/// How long a batch waits for more ids before it's sent
+const COALESCE_WINDOW: Duration = Duration::from_millis(10);
+
+/// The most ids `GET /2/tweets` takes at once
+const MAX_IDS_PER_LOOKUP: usize = 100;
+
+/// NB: an error is kept as its message, since a batch's result goes to each of its callers
+type BatchResult = std::result::Result<Arc<api::TweetPage>, String>;
+
+/// A batch's id, and its lookup, shared by everyone waiting on it
+type Batch = (usize, Shared<BoxFuture<'static, BatchResult>>);
+
+#[derive(Default)]
+struct State {
+    next_batch_id: usize,
+    /// The batch still taking ids, until its window is up or it's full
+    open: Option<Batch>,
+    /// The ids each batch will ask for, by batch id, until it's sent
+    pending: HashMap<usize, Vec<String>>,
+    /// The batch each id is being fetched in, by tweet id, until the batch is done
+    in_flight: HashMap<String, Batch>,
+}
+
+/// Shared between clones, so that clones of a client coalesce with each other.
+#[derive(Clone, Default)]
+pub struct TweetCoalescer {
+    state: Arc<Mutex<State>>,
+}
+
+impl fmt::Debug for TweetCoalescer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("TweetCoalescer")
+            .field("in_flight", &state.in_flight.len())
+            .finish()
+    }
+}
+
+impl TweetCoalescer {
+    /// [tweet_ids], whichever batches they end up in; [fetch] looks up a batch of ids.  The page
+    /// has only the tweets asked for, but might reference tweets (or be missing ones) that other
+    /// callers asked for in the same batch.
+    pub async fn lookup<F, Fut>(&self, tweet_ids: &[String], fetch: F) -> Result<api::TweetPage>
+    where
+        F: Fn(Vec<String>) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = Result<api::TweetPage>> + Send + 'static,
+    {
+        let batches: Vec<Batch> = {
+            let mut state = self.state.lock().unwrap();
+            let mut batches: HashMap<usize, Batch> = HashMap::new();
+            for tweet_id in tweet_ids {
+                let batch = match state.in_flight.get(tweet_id) {
+                    Some(batch) => batch.clone(),
+                    None => {
+                        let batch = self.open_batch(&mut state, &fetch);
+                        let pending = state.pending.entry(batch.0).or_default();
+                        pending.push(tweet_id.clone());
+                        if pending.len() >= MAX_IDS_PER_LOOKUP {
+                            state.open = None;
+                        }
+                        state.in_flight.insert(tweet_id.clone(), batch.clone());
+                        batch
+                    }
+                };
+                batches.entry(batch.0).or_insert(batch);
+            }
+            batches.into_values().collect()
+        };
+
+        let requested: HashSet<&String> = tweet_ids.iter().collect();
+        let mut page = api::TweetPage::default();
+        for result in future::join_all(batches.into_iter().map(|(_, lookup)| lookup)).await {
+            let mut batch_page = result.map_err(|err| anyhow!(err))?.as_ref().clone();
+            batch_page
+                .tweets
+                .retain(|tweet| requested.contains(&tweet.id));
+            page.append(batch_page);
+        }
+        Ok(page)
+    }
+
+    /// The open batch, or a new one if it's been sent or filled.
+    fn open_batch<F, Fut>(&self, state: &mut State, fetch: &F) -> Batch
+    where
+        F: Fn(Vec<String>) -> Fut + Clone + Send + 'static,
+        Fut: Future<Output = Result<api::TweetPage>> + Send + 'static,
+    {
+        if let Some(batch) = &state.open {
+            return batch.clone();
+        }
+        let batch_id = state.next_batch_id;
+        state.next_batch_id += 1;
+        // NB: weak, since the state holds on to the lookup
+        let weak_state: Weak<Mutex<State>> = Arc::downgrade(&self.state);
+        let fetch = fetch.clone();
+        let lookup = async move {
+            tokio::time::sleep(COALESCE_WINDOW).await;
+            let tweet_ids = match weak_state.upgrade() {
+                Some(state) => {
+                    let mut state = state.lock().unwrap();
+                    if state.open.as_ref().map(|(id, _)| *id) == Some(batch_id) {
+                        state.open = None;
+                    }
+                    state.pending.remove(&batch_id).unwrap_or_default()
+                }
+                None => Vec::new(),
+            };
+            let result = fetch(tweet_ids.clone())
+                .await
+                .map(Arc::new)
+                .map_err(|err| format!("{err:#}"));
+            if let Some(state) = weak_state.upgrade() {
+                let mut state = state.lock().unwrap();
+                for tweet_id in tweet_ids {
+                    if state.in_flight.get(&tweet_id).map(|(id, _)| *id) == Some(batch_id) {
+                        state.in_flight.remove(&tweet_id);
+                    }
+                }
+            }
+            result
+        }
+        .boxed()
+        .shared();
+        let batch = (batch_id, lookup);
+        state.open = Some(batch.clone());
+        batch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Looks up any ids but "bad", recording each batch asked for.
+    fn fetcher(
+        batches: &Arc<Mutex<Vec<Vec<String>>>>,
+    ) -> impl Fn(Vec<String>) -> BoxFuture<'static, Result<api::TweetPage>> + Clone {
+        let batches = batches.clone();
+        move |tweet_ids: Vec<String>| {
+            batches.lock().unwrap().push(tweet_ids.clone());
+            async move {
+                if tweet_ids.iter().any(|id| id == "bad") {
+                    return Err(anyhow!("400 Bad Request"));
+                }
+                let tweets = tweet_ids
+                    .iter()
+                    .map(|id| {
+                        serde_json::from_value(serde_json::json!({
+                            "id": id,
+                            "text": "hello",
+                            "created_at": "2023-02-01T12:00:00Z",
+                            "author_id": "2"
+                        }))
+                        .unwrap()
+                    })
+                    .collect();
+                Ok(api::TweetPage {
+                    tweets,
+                    ..Default::default()
+                })
+            }
+            .boxed()
+        }
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn tweet_ids(page: api::TweetPage) -> Vec<String> {
+        let mut tweet_ids: Vec<String> = page.tweets.into_iter().map(|t| t.id).collect();
+        tweet_ids.sort();
+        tweet_ids
+    }
+
+    #[tokio::test]
+    async fn test_coalesces_lookups() {
+        let coalescer = TweetCoalescer::default();
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let fetch = fetcher(&batches);
+        let (first_ids, second_ids) = (ids(&["1", "2"]), ids(&["2", "3"]));
+        let (first, second) = futures_util::join!(
+            coalescer.lookup(&first_ids, fetch.clone()),
+            coalescer.lookup(&second_ids, fetch.clone())
+        );
+        assert_eq!(tweet_ids(first.unwrap()), ids(&["1", "2"]));
+        assert_eq!(tweet_ids(second.unwrap()), ids(&["2", "3"]));
+        assert_eq!(*batches.lock().unwrap(), vec![ids(&["1", "2", "3"])]);
+
+        // NB: once it's done, an id is looked up again
+        let third = coalescer.lookup(&ids(&["1"]), fetch).await.unwrap();
+        assert_eq!(tweet_ids(third), ids(&["1"]));
+        assert_eq!(batches.lock().unwrap().len(), 2);
+        assert!(coalescer.state.lock().unwrap().in_flight.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batches_lookups() {
+        let coalescer = TweetCoalescer::default();
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let tweet_ids: Vec<String> = (0..MAX_IDS_PER_LOOKUP + 1).map(|i| i.to_string()).collect();
+        let page = coalescer
+            .lookup(&tweet_ids, fetcher(&batches))
+            .await
+            .unwrap();
+        assert_eq!(page.tweets.len(), MAX_IDS_PER_LOOKUP + 1);
+        let mut batch_sizes: Vec<usize> = batches.lock().unwrap().iter().map(Vec::len).collect();
+        batch_sizes.sort();
+        assert_eq!(batch_sizes, vec![1, MAX_IDS_PER_LOOKUP]);
+    }
+
+    #[tokio::test]
+    async fn test_shares_errors() {
+        let coalescer = TweetCoalescer::default();
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let fetch = fetcher(&batches);
+        let (first_ids, second_ids) = (ids(&["1"]), ids(&["bad"]));
+        let (first, second) = futures_util::join!(
+            coalescer.lookup(&first_ids, fetch.clone()),
+            coalescer.lookup(&second_ids, fetch.clone())
+        );
+        assert_eq!(first.unwrap_err().to_string(), "400 Bad Request");
+        assert_eq!(second.unwrap_err().to_string(), "400 Bad Request");
+        assert_eq!(batches.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,142 @@
+//! The HTTPS connector for API requests: [HttpsConnector] offering HTTP/2 via ALPN.
+//!
+//! NB: hyper-tls doesn't tell hyper what ALPN negotiated, so hyper would speak HTTP/1 over a
+//! connection the server thinks is HTTP/2.  [AlpnConnector] wraps its streams to report it.
+
+use anyhow::{Context as _, Result};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use hyper::body::Bytes;
+use hyper::client::connect::{Connected, Connection};
+use hyper::client::HttpConnector;
+use hyper::header::HeaderValue;
+use hyper::service::Service;
+use hyper::Uri;
+use hyper_tls::{HttpsConnector, MaybeHttpsStream};
+use std::future::Future;
+use std::io::{self, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Clone, Debug)]
+pub struct AlpnConnector(HttpsConnector<HttpConnector>);
+
+impl AlpnConnector {
+    /// NB: panics if the platform's TLS can't be set up, as [HttpsConnector::new] does
+    pub fn new(connect_timeout: Duration) -> Self {
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(Some(connect_timeout));
+        let tls = native_tls::TlsConnector::builder()
+            .request_alpns(&["h2", "http/1.1"])
+            .build()
+            .expect("Setting up TLS");
+        Self(HttpsConnector::from((
+            http,
+            tokio_native_tls::TlsConnector::from(tls),
+        )))
+    }
+}
+
+impl Service<Uri> for AlpnConnector {
+    type Response = AlpnStream;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<AlpnStream, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connecting = self.0.call(uri);
+        Box::pin(async move { Ok(AlpnStream(connecting.await?)) })
+    }
+}
+
+pub struct AlpnStream(MaybeHttpsStream<TcpStream>);
+
+impl Connection for AlpnStream {
+    fn connected(&self) -> Connected {
+        let connected = self.0.connected();
+        match &self.0 {
+            MaybeHttpsStream::Https(tls) => match tls.get_ref().negotiated_alpn() {
+                Ok(Some(protocol)) if protocol == b"h2" => connected.negotiated_h2(),
+                _ => connected,
+            },
+            MaybeHttpsStream::Http(_) => connected,
+        }
+    }
+}
+
+impl AsyncRead for AlpnStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AlpnStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+/// Undoes the response's Content-Encoding, if it's one we asked for.
+pub fn decode_body(content_encoding: Option<&HeaderValue>, body: Bytes) -> Result<Bytes> {
+    let mut decoded = Vec::new();
+    match content_encoding.map(HeaderValue::as_bytes) {
+        Some(b"gzip") => GzDecoder::new(&body[..]).read_to_end(&mut decoded),
+        // NB: HTTP's "deflate" is zlib-wrapped
+        Some(b"deflate") => ZlibDecoder::new(&body[..]).read_to_end(&mut decoded),
+        _ => return Ok(body),
+    }
+    .context("Decompressing the response")?;
+    Ok(Bytes::from(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn test_decode_body() {
+        let json = br#"{"data":[]}"#;
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(json).unwrap();
+        let gzip = Bytes::from(gzip.finish().unwrap());
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(json).unwrap();
+        let zlib = Bytes::from(zlib.finish().unwrap());
+
+        let decode = |encoding: Option<&str>, body: &Bytes| {
+            let encoding = encoding.map(|encoding| HeaderValue::from_str(encoding).unwrap());
+            decode_body(encoding.as_ref(), body.clone())
+        };
+        assert_eq!(&decode(Some("gzip"), &gzip).unwrap()[..], json);
+        assert_eq!(&decode(Some("deflate"), &zlib).unwrap()[..], json);
+        assert_eq!(&decode(None, &Bytes::from_static(json)).unwrap()[..], json);
+        assert!(decode(Some("gzip"), &zlib).is_err());
+    }
+}
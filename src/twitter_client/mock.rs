@@ -0,0 +1,252 @@
+use crate::twitter_client::{
+    api, parse_tweet_page, parse_user_with_pinned_tweet, PagedResult, TwitterApi,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Canned API responses, as the real API would send them.
+pub mod fixtures {
+    /// Two tweets by @alice and @bob (one quoting a tweet of @me's), with a next page
+    pub const TIMELINE_PAGE_1: &str = include_str!("fixtures/timeline_page_1.json");
+    /// The last page: a retweet by @alice, and the tweet it retweets
+    pub const TIMELINE_PAGE_2: &str = include_str!("fixtures/timeline_page_2.json");
+    /// A mention of @me by @bob, and a reply to @me's tweet by @carol
+    pub const MENTIONS: &str = include_str!("fixtures/mentions.json");
+    /// @bob's quote of @me's tweet, which also mentions @me
+    pub const QUOTES: &str = include_str!("fixtures/quotes.json");
+}
+
+/// Stands in for [TwitterClient] offline: serves canned responses queued per endpoint, and records
+/// every request made, e.g. to check which page tokens were sent.
+///
+/// [TwitterClient]: crate::twitter_client::TwitterClient
+#[derive(Debug, Clone, Default)]
+pub struct MockTwitterClient {
+    /// Raw response bodies, or an error, by endpoint path, e.g. "/2/users/1/mentions"
+    responses: Arc<Mutex<HashMap<String, VecDeque<Result<String, String>>>>>,
+    /// Path and query of each request, oldest first
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockTwitterClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues [body] as the response to the next request to [path] not already answered.
+    pub fn respond(&self, path: &str, body: &str) {
+        self.queue(path, Ok(body.to_string()));
+    }
+
+    /// Queues a failure, like an HTTP error, as the response to the next request to [path].
+    pub fn fail(&self, path: &str, error: &str) {
+        self.queue(path, Err(error.to_string()));
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn queue(&self, path: &str, response: Result<String, String>) {
+        let mut responses = self.responses.lock().unwrap();
+        responses
+            .entry(path.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// NB: like the real API, a request nothing was queued for is a 404
+    fn request(&self, path: &str, query: &[(&str, Option<&str>)]) -> Result<String> {
+        let query: Vec<String> = query
+            .iter()
+            .filter_map(|(key, value)| value.map(|value| format!("{key}={value}")))
+            .collect();
+        let request = if query.is_empty() {
+            path.to_string()
+        } else {
+            format!("{path}?{}", query.join("&"))
+        };
+        self.requests.lock().unwrap().push(request);
+
+        let response = self
+            .responses
+            .lock()
+            .unwrap()
+            .get_mut(path)
+            .and_then(VecDeque::pop_front);
+        match response {
+            Some(Ok(body)) => Ok(body),
+            Some(Err(error)) => Err(anyhow!("{error}")),
+            None => Err(anyhow!("404 Not Found: {path}")),
+        }
+    }
+
+    fn request_data<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self.request(path, &[])?;
+        let resp: api::Response<T, ()> = serde_json::from_str(&body)?;
+        Ok(resp.data)
+    }
+
+    fn request_users(
+        &self,
+        path: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>> {
+        let body = self.request(path, &[("pagination_token", pagination_token.as_deref())])?;
+        let resp: api::Response<Option<Vec<api::User>>, ()> = serde_json::from_str(&body)?;
+        let next_pagination_token = resp.meta.and_then(|meta| meta.next_token);
+        Ok((resp.data.unwrap_or_default(), next_pagination_token))
+    }
+
+    fn request_tweet_page(
+        &self,
+        path: &str,
+        query: &[(&str, Option<&str>)],
+    ) -> PagedResult<api::TweetPage> {
+        let body = self.request(path, query)?;
+        parse_tweet_page(body.as_bytes())
+    }
+}
+
+#[async_trait]
+impl TwitterApi for MockTwitterClient {
+    async fn me(&self) -> Result<api::User> {
+        self.request_data("/2/users/me")
+    }
+
+    async fn user_by_username(&self, username: &str) -> Result<api::User> {
+        self.request_data(&format!("/2/users/by/username/{username}"))
+    }
+
+    async fn user_with_pinned_tweet(
+        &self,
+        username: &str,
+    ) -> Result<(api::User, Option<api::Tweet>)> {
+        let body = self.request(&format!("/2/users/by/username/{username}"), &[])?;
+        parse_user_with_pinned_tweet(body.as_bytes())
+    }
+
+    async fn user_tweets_since(
+        &self,
+        user_id: &str,
+        since_id: Option<&str>,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.request_tweet_page(
+            &format!("/2/users/{user_id}/tweets"),
+            &[
+                ("since_id", since_id),
+                ("pagination_token", pagination_token.as_deref()),
+            ],
+        )
+    }
+
+    async fn mentions(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.request_tweet_page(
+            &format!("/2/users/{user_id}/mentions"),
+            &[("pagination_token", pagination_token.as_deref())],
+        )
+    }
+
+    async fn timeline_reverse_chronological(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.request_tweet_page(
+            &format!("/2/users/{user_id}/timelines/reverse_chronological"),
+            &[("pagination_token", pagination_token.as_deref())],
+        )
+    }
+
+    async fn bookmarks(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.request_tweet_page(
+            &format!("/2/users/{user_id}/bookmarks"),
+            &[("pagination_token", pagination_token.as_deref())],
+        )
+    }
+
+    async fn owned_lists(&self, user_id: &str) -> Result<Vec<api::List>> {
+        let lists: Option<Vec<api::List>> =
+            self.request_data(&format!("/2/users/{user_id}/owned_lists"))?;
+        Ok(lists.unwrap_or_default())
+    }
+
+    async fn followers(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>> {
+        self.request_users(&format!("/2/users/{user_id}/followers"), pagination_token)
+    }
+
+    async fn following(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>> {
+        self.request_users(&format!("/2/users/{user_id}/following"), pagination_token)
+    }
+
+    async fn list_tweets(
+        &self,
+        list_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.request_tweet_page(
+            &format!("/2/lists/{list_id}/tweets"),
+            &[("pagination_token", pagination_token.as_deref())],
+        )
+    }
+
+    async fn tweets_by_ids(&self, tweet_ids: &[String]) -> Result<api::TweetPage> {
+        let ids = tweet_ids.join(",");
+        let (page, _) = self.request_tweet_page("/2/tweets", &[("ids", Some(&ids))])?;
+        Ok(page)
+    }
+
+    async fn search_tweets_since(
+        &self,
+        query: &str,
+        since_id: Option<&str>,
+        next_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.request_tweet_page(
+            "/2/tweets/search/recent",
+            &[
+                ("query", Some(query)),
+                ("since_id", since_id),
+                ("next_token", next_token.as_deref()),
+            ],
+        )
+    }
+
+    async fn post_tweet(
+        &self,
+        text: &str,
+        in_reply_to_tweet_id: Option<&str>,
+    ) -> Result<api::CreatedTweet> {
+        let body = self.request(
+            "POST /2/tweets",
+            &[("text", Some(text)), ("in_reply_to", in_reply_to_tweet_id)],
+        )?;
+        let resp: api::Response<api::CreatedTweet, ()> = serde_json::from_str(&body)?;
+        Ok(resp.data)
+    }
+
+    async fn delete_tweet(&self, tweet_id: &str) -> Result<()> {
+        self.request(&format!("DELETE /2/tweets/{tweet_id}"), &[])?;
+        Ok(())
+    }
+}
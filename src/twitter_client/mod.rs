@@ -1,33 +1,232 @@
 pub mod api;
+pub mod coalescer;
+pub mod connector;
+#[cfg(test)]
+pub mod mock;
+pub mod paginator;
+pub mod quota;
+pub mod recording;
+pub mod validators;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Error, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use coalescer::TweetCoalescer;
+use connector::{decode_body, AlpnConnector};
 use hyper::body::Bytes;
-use hyper::client::HttpConnector;
+use hyper::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
 use hyper::server::conn::Http;
-use hyper::{Body, Client, Method, Request, Uri};
-use hyper_tls::HttpsConnector;
+use hyper::{Body, Client, Method, Request, StatusCode, Uri};
 use oauth2::basic::{BasicClient, BasicTokenResponse};
 use oauth2::reqwest::async_http_client;
 use oauth2::{
-    AccessToken, AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge,
-    RedirectUrl, RefreshToken, Scope, TokenResponse, TokenUrl,
+    AccessToken, AuthType, AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken,
+    PkceCodeChallenge, RedirectUrl, RefreshToken, Scope, TokenResponse, TokenUrl,
 };
+use quota::{QuotaTracker, RateLimit};
+use recording::{Recorder, Replayer};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::num::NonZeroU64;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 use std::{fs, process};
 use tokio::net::TcpListener;
 use tokio::sync::Mutex;
+use tokio::time::error::Elapsed;
 use url::Url;
+use validators::ValidatorCache;
 
 pub type PagedResult<T> = Result<(T, Option<String>)>;
 
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// The user fields we display, or score tweets by (see [crate::spam])
+const USER_FIELDS: &str =
+    "username,protected,verified_type,affiliation,profile_image_url,created_at,public_metrics";
+
+/// The tweet fields we display
+const TWEET_FIELDS: &str = concat!(
+    "created_at,attachments,referenced_tweets,public_metrics,conversation_id,entities,lang,",
+    "possibly_sensitive,edit_history_tweet_ids,edit_controls"
+);
+
+/// Timeouts and retries for API requests.  Configured in [crate::user_config::UserConfig::http].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub connect_timeout_secs: NonZeroU64,
+    /// For the response to start, and again for its body
+    pub read_timeout_secs: NonZeroU64,
+    /// How many more times a GET is tried after a transient failure
+    pub max_retries: u32,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: NonZeroU64::new(10).unwrap(),
+            read_timeout_secs: NonZeroU64::new(30).unwrap(),
+            max_retries: 2,
+        }
+    }
+}
+
+/// NB: timeline pages are large (100 tweets with expansions), so responses are compressed, and
+/// HTTP/2 lets requests share a connection.  Idle connections are kept for reuse between refreshes.
+fn https_client(http_config: &HttpConfig) -> Client<AlpnConnector> {
+    let connect_timeout = Duration::from_secs(http_config.connect_timeout_secs.get());
+    Client::builder()
+        .pool_idle_timeout(Duration::from_secs(90))
+        .http2_adaptive_window(true)
+        .build::<_, Body>(AlpnConnector::new(connect_timeout))
+}
+
+/// Whether [error] is worth retrying: the connection failed or dropped, or the response timed out.
+fn is_transient_error(error: &Error) -> bool {
+    if error.is::<Elapsed>() {
+        return true;
+    }
+    match error.downcast_ref::<hyper::Error>() {
+        Some(error) => error.is_connect() || error.is_incomplete_message() || error.is_closed(),
+        None => false,
+    }
+}
+
+/// [error] from reading followers or following, with a hint if it's a 403: sessions signed in
+/// before the app asked for `follows.read` don't have it.
+fn follows_error(error: Error) -> Error {
+    if error.to_string().starts_with("403") {
+        error.context("Reading follows needs the follows.read permission; run with --login again")
+    } else {
+        error
+    }
+}
+
+/// How the app is registered with Twitter: confidential clients have a secret, public ones
+/// (native and single-page apps) rely on PKCE alone.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientType {
+    #[default]
+    Confidential,
+    Public,
+}
+
+/// The authenticated user as of the last [TwitterApi::me], so the UI can start without waiting on
+/// the network
+const CACHED_ME_PATH: &str = "./var/.me";
+
+pub fn load_cached_me() -> Result<api::User> {
+    let str = fs::read_to_string(CACHED_ME_PATH)?;
+    Ok(serde_json::from_str(&str)?)
+}
+
+pub fn save_cached_me(me: &api::User) -> Result<()> {
+    fs::write(CACHED_ME_PATH, serde_json::to_string(me)?)?;
+    Ok(())
+}
+
 #[derive(Debug, Clone)]
 pub struct TwitterClient {
-    https_client: Client<HttpsConnector<HttpConnector>>,
+    https_client: Client<AlpnConnector>,
+    twitter_client_id: String,
+    /// None for a public client, which authorizes with PKCE alone
+    twitter_client_secret: Option<String>,
+    twitter_auth: TwitterAuth,
+    http_config: HttpConfig,
+    recorder: Option<Arc<Recorder>>,
+    /// If set, responses come from a capture instead of the network
+    replayer: Option<Arc<Replayer>>,
+    quota_tracker: Option<Arc<QuotaTracker>>,
+    tweet_coalescer: TweetCoalescer,
+    validator_cache: ValidatorCache,
+}
+
+/// Configures a [TwitterClient], e.g. for use from another crate:
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use twitter_tool::twitter_client::{TwitterApi, TwitterClient};
+/// # async fn example() -> anyhow::Result<()> {
+/// let twitter_client = TwitterClient::builder("client id")
+///     .timeout(Duration::from_secs(10))
+///     .auth("access token", None)
+///     .build();
+/// let me = twitter_client.me().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TwitterClientBuilder {
     twitter_client_id: String,
-    twitter_client_secret: String,
+    twitter_client_secret: Option<String>,
     twitter_auth: TwitterAuth,
+    http_config: HttpConfig,
+}
+
+impl TwitterClientBuilder {
+    /// For an app registered as confidential; without a secret, the client is public and
+    /// authorizes with PKCE alone.
+    pub fn client_secret(mut self, twitter_client_secret: &str) -> Self {
+        self.twitter_client_secret = Some(twitter_client_secret.to_string());
+        self
+    }
+
+    /// Tokens obtained elsewhere, instead of [TwitterClient::authorize] or
+    /// [TwitterClient::load_auth].
+    pub fn auth(mut self, access_token: &str, refresh_token: Option<&str>) -> Self {
+        self.twitter_auth = TwitterAuth {
+            access_token: Some(AccessToken::new(access_token.to_string())),
+            refresh_token: refresh_token.map(|token| RefreshToken::new(token.to_string())),
+        };
+        self
+    }
+
+    /// How long to wait for a response to start, and again for its body.  NB: rounded up to
+    /// whole seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.http_config.read_timeout_secs = whole_secs(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http_config.connect_timeout_secs = whole_secs(timeout);
+        self
+    }
+
+    /// How many more times a GET is tried after a transient failure.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.http_config.max_retries = max_retries;
+        self
+    }
+
+    pub fn http_config(mut self, http_config: &HttpConfig) -> Self {
+        self.http_config = http_config.clone();
+        self
+    }
+
+    pub fn build(self) -> TwitterClient {
+        TwitterClient {
+            https_client: https_client(&self.http_config),
+            twitter_client_id: self.twitter_client_id,
+            twitter_client_secret: self.twitter_client_secret,
+            twitter_auth: self.twitter_auth,
+            http_config: self.http_config,
+            recorder: None,
+            replayer: None,
+            quota_tracker: None,
+            tweet_coalescer: TweetCoalescer::default(),
+            validator_cache: ValidatorCache::default(),
+        }
+    }
+}
+
+fn whole_secs(duration: Duration) -> NonZeroU64 {
+    let secs = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    NonZeroU64::new(secs).unwrap_or(NonZeroU64::MIN)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,20 +236,60 @@ struct TwitterAuth {
 }
 
 impl TwitterClient {
-    pub fn new(twitter_client_id: &str, twitter_client_secret: &str) -> Self {
-        let https = HttpsConnector::new();
-        let https_client = Client::builder().build::<_, hyper::Body>(https);
-        Self {
-            https_client,
+    pub fn builder(twitter_client_id: &str) -> TwitterClientBuilder {
+        TwitterClientBuilder {
             twitter_client_id: twitter_client_id.to_string(),
-            twitter_client_secret: twitter_client_secret.to_string(),
+            twitter_client_secret: None,
             twitter_auth: TwitterAuth {
                 access_token: None,
                 refresh_token: None,
             },
+            http_config: HttpConfig::default(),
         }
     }
 
+    pub fn new(twitter_client_id: &str, twitter_client_secret: &str) -> Self {
+        Self::builder(twitter_client_id)
+            .client_secret(twitter_client_secret)
+            .build()
+    }
+
+    /// A client for an app registered as public (e.g. a native app), which has no secret.
+    pub fn new_public(twitter_client_id: &str) -> Self {
+        Self::builder(twitter_client_id).build()
+    }
+
+    pub fn with_http_config(mut self, http_config: &HttpConfig) -> Self {
+        self.https_client = https_client(http_config);
+        self.http_config = http_config.clone();
+        self
+    }
+
+    /// Saves every API response from now on to a capture in [dir], see [recording].
+    pub fn record_to(&mut self, dir: &Path) -> Result<()> {
+        self.recorder = Some(Arc::new(Recorder::new(dir)?));
+        Ok(())
+    }
+
+    /// Serves API responses from the capture in [dir] instead of the network; nothing is sent to
+    /// Twitter, so no auth is needed.
+    pub fn replay_from(&mut self, dir: &Path) -> Result<()> {
+        self.replayer = Some(Arc::new(Replayer::load(dir)?));
+        Ok(())
+    }
+
+    /// Counts every call to the network from now on, on top of the usage saved in [path], see
+    /// [quota].
+    pub fn track_quota(&mut self, path: &Path) -> Result<()> {
+        self.quota_tracker = Some(Arc::new(QuotaTracker::new(path)?));
+        Ok(())
+    }
+
+    /// See [quota::QuotaUsage::tightest_rate_limit]; None unless quota is tracked.
+    pub fn tightest_rate_limit(&self) -> Option<(String, RateLimit)> {
+        self.quota_tracker.as_ref()?.tightest_rate_limit(Utc::now())
+    }
+
     pub fn save_auth(&self) -> Result<()> {
         let str = serde_json::to_string(&self.twitter_auth)?;
         fs::write("./var/.oauth", str)?;
@@ -64,20 +303,29 @@ impl TwitterClient {
     }
 
     pub async fn authorize(&mut self, use_refresh_token: bool) -> Result<()> {
-        let oauth_client = BasicClient::new(
+        let mut oauth_client = BasicClient::new(
             ClientId::new(self.twitter_client_id.clone()),
-            Some(ClientSecret::new(self.twitter_client_secret.clone())),
+            self.twitter_client_secret.clone().map(ClientSecret::new),
             AuthUrl::new("https://twitter.com/i/oauth2/authorize".to_string())?,
             Some(TokenUrl::new(
                 "https://api.twitter.com/2/oauth2/token".to_string(),
             )?),
         )
         .set_redirect_uri(RedirectUrl::new("https://localhost:8080".to_string())?);
+        // NB: without a secret there's nothing to put in a Basic auth header; public clients send
+        // their id in the token request body instead
+        if self.twitter_client_secret.is_none() {
+            oauth_client = oauth_client.set_auth_type(AuthType::RequestBody);
+        }
         let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
         let (auth_url, _csrf_token) = oauth_client
             .authorize_url(CsrfToken::new_random)
             .add_scope(Scope::new("tweet.read".to_string()))
+            .add_scope(Scope::new("tweet.write".to_string()))
             .add_scope(Scope::new("users.read".to_string()))
+            .add_scope(Scope::new("bookmark.read".to_string()))
+            .add_scope(Scope::new("list.read".to_string()))
+            .add_scope(Scope::new("follows.read".to_string()))
             .add_scope(Scope::new("offline.access".to_string()))
             .set_pkce_challenge(pkce_challenge)
             .url();
@@ -168,108 +416,778 @@ impl TwitterClient {
         Ok(())
     }
 
-    async fn authenticated_get(&self, uri: &Url) -> Result<Bytes> {
-        let access_token = self
-            .twitter_auth
-            .access_token
-            .as_ref()
-            .ok_or(anyhow!("Unauthorized"))?;
-        let req = Request::builder()
-            .method(Method::GET)
-            .uri(uri.to_string())
-            .header("Authorization", format!("Bearer {}", access_token.secret()))
-            .body(Body::empty())?;
-        let resp = self.https_client.request(req).await?;
-        let resp = hyper::body::to_bytes(resp.into_body()).await?;
+    async fn authenticated_request(
+        &self,
+        method: Method,
+        uri: &Url,
+        json_body: Option<serde_json::Value>,
+    ) -> Result<Bytes> {
+        let (status, resp) = match &self.replayer {
+            Some(replayer) => replayer.replay(&method, uri)?,
+            None => {
+                let access_token = self
+                    .twitter_auth
+                    .access_token
+                    .as_ref()
+                    .ok_or(anyhow!("Unauthorized"))?;
+                let body = json_body
+                    .map(|json| serde_json::to_vec(&json))
+                    .transpose()?;
+                let build_request = || {
+                    let req = Request::builder()
+                        .method(method.clone())
+                        .uri(uri.to_string())
+                        .header("Authorization", format!("Bearer {}", access_token.secret()))
+                        .header(ACCEPT_ENCODING, "gzip, deflate");
+                    match &body {
+                        Some(body) => req
+                            .header("Content-Type", "application/json")
+                            .body(Body::from(body.clone())),
+                        None => req.body(Body::empty()),
+                    }
+                };
+                self.send_with_retries(&method, build_request).await?
+            }
+        };
+        if let Some(recorder) = &self.recorder {
+            recorder.record(&method, uri, status, &resp)?;
+        }
+        if !status.is_success() {
+            return Err(anyhow!(
+                "{status}: {}",
+                String::from_utf8_lossy(&resp).trim()
+            ));
+        }
         Ok(resp)
     }
 
-    pub async fn me(&self) -> Result<api::User> {
+    /// Sends the request made by [build_request], retrying transient failures (connection errors,
+    /// timeouts and 502/503/504s) up to [HttpConfig::max_retries] times with exponential backoff.
+    /// NB: only GETs are retried, since e.g. retrying a POST could post a tweet twice
+    async fn send_with_retries(
+        &self,
+        method: &Method,
+        build_request: impl Fn() -> hyper::http::Result<Request<Body>>,
+    ) -> Result<(StatusCode, Bytes)> {
+        let max_attempts = match *method {
+            Method::GET => self.http_config.max_retries + 1,
+            _ => 1,
+        };
+        let mut attempt = 1;
+        loop {
+            let result = self.send(build_request()?).await;
+            let is_transient = match &result {
+                Ok((status, _)) => matches!(status.as_u16(), 502..=504),
+                Err(error) => is_transient_error(error),
+            };
+            if !is_transient || attempt >= max_attempts {
+                return result;
+            }
+            tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+            attempt += 1;
+        }
+    }
+
+    /// One attempt at [req], giving up if the response or its body takes longer than the read
+    /// timeout.  A GET is made conditional where possible, see [validators].
+    async fn send(&self, mut req: Request<Body>) -> Result<(StatusCode, Bytes)> {
+        let read_timeout = Duration::from_secs(self.http_config.read_timeout_secs.get());
+        let timed_out = || format!("No response within {}s", read_timeout.as_secs());
+        self.validator_cache.prepare(&mut req);
+        let (method, uri) = (req.method().clone(), req.uri().clone());
+        let path = uri.path().to_string();
+        // NB: downloads, e.g. of media, don't count against the API's quota
+        let is_api_call = req.uri().host() == Some("api.twitter.com");
+        let resp = tokio::time::timeout(read_timeout, self.https_client.request(req))
+            .await
+            .with_context(timed_out)??;
+        if let (Some(quota_tracker), true) = (&self.quota_tracker, is_api_call) {
+            // NB: best-effort; failing to save the count shouldn't fail the call it counts
+            let _ = quota_tracker.record(&method, &path, resp.headers());
+        }
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let body = tokio::time::timeout(read_timeout, hyper::body::to_bytes(resp.into_body()))
+            .await
+            .with_context(timed_out)??;
+        let body = decode_body(headers.get(CONTENT_ENCODING), body)?;
+        Ok(self
+            .validator_cache
+            .resolve(&method, &uri.to_string(), status, &headers, body))
+    }
+
+    /// Downloads [url] without auth, e.g. an avatar or media preview.
+    pub async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        if self.replayer.is_some() {
+            return Err(anyhow!("Not downloading {url} while replaying a capture"));
+        }
+        let build_request = || {
+            Request::builder()
+                .method(Method::GET)
+                .uri(url)
+                .header(ACCEPT_ENCODING, "gzip, deflate")
+                .body(Body::empty())
+        };
+        let (status, body) = self.send_with_retries(&Method::GET, build_request).await?;
+        if !status.is_success() {
+            return Err(anyhow!("{status}: downloading {url}"));
+        }
+        Ok(body.to_vec())
+    }
+
+    async fn authenticated_get(&self, uri: &Url) -> Result<Bytes> {
+        self.authenticated_request(Method::GET, uri, None).await
+    }
+
+    async fn get_tweets_with_users(
+        &self,
+        uri: &mut Url,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        uri.query_pairs_mut().append_pair("max_results", "100");
+        if let Some(pagination_token) = pagination_token {
+            uri.query_pairs_mut()
+                .append_pair("pagination_token", &pagination_token);
+        }
+        self.get_tweet_page(uri).await
+    }
+
+    async fn get_users(
+        &self,
+        uri: &mut Url,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>> {
+        uri.query_pairs_mut().append_pair("max_results", "1000");
+        if let Some(pagination_token) = pagination_token {
+            uri.query_pairs_mut()
+                .append_pair("pagination_token", &pagination_token);
+        }
+        let bytes = self.authenticated_get(uri).await.map_err(follows_error)?;
+        // NB: no users means no `data` at all
+        let resp: api::Response<Option<Vec<api::User>>, ()> = serde_json::from_slice(&bytes)?;
+        let next_pagination_token = resp.meta.and_then(|meta| meta.next_token);
+        Ok((resp.data.unwrap_or_default(), next_pagination_token))
+    }
+
+    /// Fetches tweets from [uri] with the fields and expansions we display, filling in authors.
+    async fn get_tweet_page(&self, uri: &mut Url) -> PagedResult<api::TweetPage> {
+        uri.query_pairs_mut()
+            .append_pair("tweet.fields", TWEET_FIELDS)
+            .append_pair("user.fields", USER_FIELDS)
+            .append_pair("media.fields", "type,url,preview_image_url,alt_text")
+            .append_pair(
+                "expansions",
+                "author_id,referenced_tweets.id,referenced_tweets.id.author_id,attachments.media_keys",
+            );
+        let bytes = self.authenticated_get(uri).await?;
+        parse_tweet_page(&bytes)
+    }
+}
+
+/// The Twitter API endpoints we use.  [TwitterClient] calls the real thing; tests can stand in
+/// something offline, e.g. [mock::MockTwitterClient].
+#[async_trait]
+pub trait TwitterApi: Clone + Send + Sync + 'static {
+    async fn me(&self) -> Result<api::User>;
+
+    async fn user_by_username(&self, username: &str) -> Result<api::User>;
+
+    /// The user, and the tweet pinned to their profile if any.
+    async fn user_with_pinned_tweet(
+        &self,
+        username: &str,
+    ) -> Result<(api::User, Option<api::Tweet>)>;
+
+    async fn user_tweets(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.user_tweets_since(user_id, None, pagination_token)
+            .await
+    }
+
+    /// [user_id]'s tweets, only those newer than [since_id] if given.
+    async fn user_tweets_since(
+        &self,
+        user_id: &str,
+        since_id: Option<&str>,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage>;
+
+    async fn mentions(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage>;
+
+    async fn timeline_reverse_chronological(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage>;
+
+    async fn bookmarks(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage>;
+
+    async fn owned_lists(&self, user_id: &str) -> Result<Vec<api::List>>;
+
+    async fn followers(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>>;
+
+    /// The accounts [user_id] follows.
+    async fn following(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>>;
+
+    async fn list_tweets(
+        &self,
+        list_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage>;
+
+    /// Looks up tweets by id; deleted or protected tweets are left out.  NB: [TwitterClient]
+    /// coalesces lookups made at about the same time, see [coalescer]
+    async fn tweets_by_ids(&self, tweet_ids: &[String]) -> Result<api::TweetPage>;
+
+    async fn search_tweets(&self, query: &str) -> PagedResult<api::TweetPage> {
+        self.search_tweets_since(query, None, None).await
+    }
+
+    async fn search_tweets_page(
+        &self,
+        query: &str,
+        next_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        self.search_tweets_since(query, None, next_token).await
+    }
+
+    /// Recent tweets matching [query], only those newer than [since_id] if given.  NB: search is
+    /// paged by `next_token` rather than `pagination_token`
+    async fn search_tweets_since(
+        &self,
+        query: &str,
+        since_id: Option<&str>,
+        next_token: Option<String>,
+    ) -> PagedResult<api::TweetPage>;
+
+    async fn post_tweet(
+        &self,
+        text: &str,
+        in_reply_to_tweet_id: Option<&str>,
+    ) -> Result<api::CreatedTweet>;
+
+    async fn delete_tweet(&self, tweet_id: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl TwitterApi for TwitterClient {
+    async fn me(&self) -> Result<api::User> {
         let uri = Url::parse("https://api.twitter.com/2/users/me")?;
         let bytes = self.authenticated_get(&uri).await?;
         let resp: api::Response<api::User, ()> = serde_json::from_slice(&bytes)?;
         Ok(resp.data)
     }
 
-    pub async fn user_by_username(&self, username: &str) -> Result<api::User> {
+    async fn user_by_username(&self, username: &str) -> Result<api::User> {
         let mut uri = Url::parse(&format!(
             "https://api.twitter.com/2/users/by/username/{username}"
         ))?;
-        uri.query_pairs_mut().append_pair("user.fields", "username");
+        uri.query_pairs_mut()
+            .append_pair("user.fields", USER_FIELDS);
         let bytes = self.authenticated_get(&uri).await?;
         let resp: api::Response<api::User, ()> = serde_json::from_slice(&bytes)?;
         Ok(resp.data)
     }
 
-    async fn get_tweets_with_users(
+    async fn user_with_pinned_tweet(
         &self,
-        uri: &mut Url,
-        pagination_token: Option<String>,
-    ) -> PagedResult<Vec<api::Tweet>> {
+        username: &str,
+    ) -> Result<(api::User, Option<api::Tweet>)> {
+        let mut uri = Url::parse(&format!(
+            "https://api.twitter.com/2/users/by/username/{username}"
+        ))?;
         uri.query_pairs_mut()
-            .append_pair(
-                "tweet.fields",
-                "created_at,attachments,referenced_tweets,public_metrics,conversation_id",
-            )
-            .append_pair("user.fields", "username")
-            .append_pair("expansions", "author_id")
-            .append_pair("max_results", "100");
-        if let Some(pagination_token) = pagination_token {
-            uri.query_pairs_mut()
-                .append_pair("pagination_token", &pagination_token);
-        }
+            .append_pair("user.fields", &format!("{USER_FIELDS},pinned_tweet_id"))
+            .append_pair("tweet.fields", TWEET_FIELDS)
+            .append_pair("expansions", "pinned_tweet_id");
         let bytes = self.authenticated_get(&uri).await?;
+        parse_user_with_pinned_tweet(&bytes)
+    }
 
-        #[derive(Debug, Serialize, Deserialize)]
-        struct Includes {
-            users: Vec<api::User>,
+    async fn user_tweets_since(
+        &self,
+        user_id: &str,
+        since_id: Option<&str>,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        let mut uri = Url::parse(&format!("https://api.twitter.com/2/users/{user_id}/tweets"))?;
+        if let Some(since_id) = since_id {
+            uri.query_pairs_mut().append_pair("since_id", since_id);
         }
-
-        let resp: api::Response<Vec<api::Tweet>, Includes> = serde_json::from_slice(&bytes)?;
-        let next_pagination_token = resp.meta.and_then(|meta| meta.next_token);
-        let includes = resp.includes.ok_or(anyhow!("Expected `includes`"))?;
-        let users: HashMap<String, &api::User> = includes
-            .users
-            .iter()
-            .map(|user| (user.id.clone(), user))
-            .collect();
-        let tweets: Vec<api::Tweet> = resp
-            .data
-            .iter()
-            .map(|tweet| api::Tweet {
-                author_username: users
-                    .get(&tweet.author_id)
-                    .map(|user| user.username.clone()),
-                author_name: users.get(&tweet.author_id).map(|user| user.name.clone()),
-                ..tweet.clone()
-            })
-            .collect();
-        Ok((tweets, next_pagination_token))
+        self.get_tweets_with_users(&mut uri, pagination_token).await
     }
 
-    pub async fn user_tweets(
+    async fn mentions(
         &self,
         user_id: &str,
         pagination_token: Option<String>,
-    ) -> PagedResult<Vec<api::Tweet>> {
-        let mut uri = Url::parse(&format!("https://api.twitter.com/2/users/{user_id}/tweets"))?;
+    ) -> PagedResult<api::TweetPage> {
+        let mut uri = Url::parse(&format!(
+            "https://api.twitter.com/2/users/{user_id}/mentions"
+        ))?;
         self.get_tweets_with_users(&mut uri, pagination_token).await
     }
 
-    pub async fn timeline_reverse_chronological(
+    async fn timeline_reverse_chronological(
         &self,
         user_id: &str,
         pagination_token: Option<String>,
-    ) -> PagedResult<Vec<api::Tweet>> {
+    ) -> PagedResult<api::TweetPage> {
         let mut uri = Url::parse(&format!(
             "https://api.twitter.com/2/users/{user_id}/timelines/reverse_chronological"
         ))?;
         self.get_tweets_with_users(&mut uri, pagination_token).await
     }
 
-    pub async fn search_tweets(&self, query: &str) -> PagedResult<Vec<api::Tweet>> {
+    async fn bookmarks(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        let mut uri = Url::parse(&format!(
+            "https://api.twitter.com/2/users/{user_id}/bookmarks"
+        ))?;
+        self.get_tweets_with_users(&mut uri, pagination_token).await
+    }
+
+    async fn owned_lists(&self, user_id: &str) -> Result<Vec<api::List>> {
+        let uri = Url::parse(&format!(
+            "https://api.twitter.com/2/users/{user_id}/owned_lists"
+        ))?;
+        let bytes = self.authenticated_get(&uri).await?;
+        let resp: api::Response<Option<Vec<api::List>>, ()> = serde_json::from_slice(&bytes)?;
+        Ok(resp.data.unwrap_or_default())
+    }
+
+    async fn followers(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>> {
+        let mut uri = Url::parse(&format!(
+            "https://api.twitter.com/2/users/{user_id}/followers"
+        ))?;
+        self.get_users(&mut uri, pagination_token).await
+    }
+
+    async fn following(
+        &self,
+        user_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<Vec<api::User>> {
+        let mut uri = Url::parse(&format!(
+            "https://api.twitter.com/2/users/{user_id}/following"
+        ))?;
+        self.get_users(&mut uri, pagination_token).await
+    }
+
+    async fn list_tweets(
+        &self,
+        list_id: &str,
+        pagination_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
+        let mut uri = Url::parse(&format!("https://api.twitter.com/2/lists/{list_id}/tweets"))?;
+        self.get_tweets_with_users(&mut uri, pagination_token).await
+    }
+
+    async fn tweets_by_ids(&self, tweet_ids: &[String]) -> Result<api::TweetPage> {
+        let twitter_client = self.clone();
+        let fetch = move |tweet_ids: Vec<String>| {
+            let twitter_client = twitter_client.clone();
+            async move {
+                let mut uri = Url::parse("https://api.twitter.com/2/tweets")?;
+                uri.query_pairs_mut()
+                    .append_pair("ids", &tweet_ids.join(","));
+                let (page, _) = twitter_client.get_tweet_page(&mut uri).await?;
+                Ok(page)
+            }
+        };
+        self.tweet_coalescer.lookup(tweet_ids, fetch).await
+    }
+
+    async fn search_tweets_since(
+        &self,
+        query: &str,
+        since_id: Option<&str>,
+        next_token: Option<String>,
+    ) -> PagedResult<api::TweetPage> {
         let mut uri = Url::parse("https://api.twitter.com/2/tweets/search/recent")?;
-        uri.query_pairs_mut().append_pair("query", query);
-        self.get_tweets_with_users(&mut uri, None).await
+        uri.query_pairs_mut()
+            .append_pair("query", query)
+            .append_pair("max_results", "100");
+        if let Some(since_id) = since_id {
+            uri.query_pairs_mut().append_pair("since_id", since_id);
+        }
+        if let Some(next_token) = next_token {
+            uri.query_pairs_mut().append_pair("next_token", &next_token);
+        }
+        self.get_tweet_page(&mut uri).await
+    }
+
+    async fn post_tweet(
+        &self,
+        text: &str,
+        in_reply_to_tweet_id: Option<&str>,
+    ) -> Result<api::CreatedTweet> {
+        let uri = Url::parse("https://api.twitter.com/2/tweets")?;
+        let mut body = serde_json::json!({ "text": text });
+        if let Some(in_reply_to_tweet_id) = in_reply_to_tweet_id {
+            body["reply"] = serde_json::json!({ "in_reply_to_tweet_id": in_reply_to_tweet_id });
+        }
+        let bytes = self
+            .authenticated_request(Method::POST, &uri, Some(body))
+            .await?;
+        let resp: api::Response<api::CreatedTweet, ()> = serde_json::from_slice(&bytes)?;
+        Ok(resp.data)
+    }
+
+    async fn delete_tweet(&self, tweet_id: &str) -> Result<()> {
+        let uri = Url::parse(&format!("https://api.twitter.com/2/tweets/{tweet_id}"))?;
+        self.authenticated_request(Method::DELETE, &uri, None)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Parses a user lookup with the `pinned_tweet_id` expansion.  NB: the pinned tweet is by the
+/// user, so there's no need to expand its author
+fn parse_user_with_pinned_tweet(bytes: &[u8]) -> Result<(api::User, Option<api::Tweet>)> {
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Includes {
+        #[serde(default)]
+        tweets: Vec<api::Tweet>,
+    }
+
+    let resp: api::Response<api::User, Includes> = serde_json::from_slice(bytes)?;
+    let user = resp.data;
+    let pinned_tweet = resp
+        .includes
+        .and_then(|includes| includes.tweets.into_iter().next())
+        .map(|tweet| api::Tweet {
+            author_username: Some(user.username.clone()),
+            author_name: Some(user.name.clone()),
+            author_protected: user.protected,
+            author_verified_type: user.verified_type.clone(),
+            author_affiliation: user.affiliation_description(),
+            author_profile_image_url: user.profile_image_url.clone(),
+            author_created_at: user.created_at,
+            author_public_metrics: user.public_metrics,
+            ..tweet
+        });
+    Ok((user, pinned_tweet))
+}
+
+/// Parses a response of tweets with their expansions, filling in authors from the included users
+/// and media.
+///
+/// NB: tweets are parsed one by one, so that one malformed tweet is left out (see
+/// [api::TweetPage::skipped]) rather than failing the whole page.  Likewise tweets the API couldn't
+/// give us, e.g. deleted ones, are noted in [api::TweetPage::unavailable]; if none of them could be
+/// had, there's no `data` at all
+fn parse_tweet_page(bytes: &[u8]) -> PagedResult<api::TweetPage> {
+    #[derive(Debug, Default, Serialize, Deserialize)]
+    struct Includes {
+        users: Vec<api::User>,
+        #[serde(default)]
+        tweets: Vec<serde_json::Value>,
+        #[serde(default)]
+        media: Vec<api::Media>,
+    }
+
+    let resp: api::Response<Option<Vec<serde_json::Value>>, Includes> =
+        serde_json::from_slice(bytes)?;
+    let meta = resp.meta;
+    let next_pagination_token = meta.as_ref().and_then(|meta| meta.next_token.clone());
+    let data = resp.data.unwrap_or_default();
+    let includes = match resp.includes {
+        Some(includes) => includes,
+        None if data.is_empty() => Includes::default(),
+        None => return Err(anyhow!("Expected `includes`")),
+    };
+    // NB: with nothing to show, an error that isn't about a tweet is about the request itself, e.g.
+    // the timeline of a protected account
+    if data.is_empty() {
+        let error = resp
+            .errors
+            .iter()
+            .find(|error| error.unavailable_tweet().is_none());
+        if let Some(error) = error {
+            return Err(anyhow!("{}", error.describe()));
+        }
+    }
+    let unavailable = resp
+        .errors
+        .iter()
+        .filter_map(api::PartialError::unavailable_tweet)
+        .collect();
+    let users: HashMap<String, &api::User> = includes
+        .users
+        .iter()
+        .map(|user| (user.id.clone(), user))
+        .collect();
+    let media: HashMap<&str, &api::Media> = includes
+        .media
+        .iter()
+        .map(|media| (media.media_key.as_str(), media))
+        .collect();
+    let mut skipped = Vec::new();
+    let mut parse_with_author = |value: serde_json::Value| {
+        let id = value
+            .get("id")
+            .and_then(|id| id.as_str())
+            .unwrap_or("[unknown]")
+            .to_string();
+        match serde_json::from_value::<api::Tweet>(value) {
+            Ok(tweet) => Some(api::Tweet {
+                author_username: users
+                    .get(&tweet.author_id)
+                    .map(|user| user.username.clone()),
+                author_name: users.get(&tweet.author_id).map(|user| user.name.clone()),
+                author_protected: users.get(&tweet.author_id).and_then(|user| user.protected),
+                author_verified_type: users
+                    .get(&tweet.author_id)
+                    .and_then(|user| user.verified_type.clone()),
+                author_affiliation: users
+                    .get(&tweet.author_id)
+                    .and_then(|user| user.affiliation_description()),
+                author_profile_image_url: users
+                    .get(&tweet.author_id)
+                    .and_then(|user| user.profile_image_url.clone()),
+                author_created_at: users.get(&tweet.author_id).and_then(|user| user.created_at),
+                author_public_metrics: users
+                    .get(&tweet.author_id)
+                    .and_then(|user| user.public_metrics),
+                media: tweet
+                    .attachments
+                    .as_ref()
+                    .and_then(|attachments| attachments.media_keys.as_ref())
+                    .map(|media_keys| {
+                        media_keys
+                            .iter()
+                            .filter_map(|key| media.get(key.as_str()).map(|&media| media.clone()))
+                            .collect()
+                    }),
+                ..tweet
+            }),
+            Err(err) => {
+                skipped.push(format!("Skipped malformed tweet {id}: {err}"));
+                None
+            }
+        }
+    };
+    let tweets = data
+        .into_iter()
+        .filter_map(&mut parse_with_author)
+        .collect();
+    let referenced_tweets = includes
+        .tweets
+        .into_iter()
+        .filter_map(&mut parse_with_author)
+        .collect();
+    let page = api::TweetPage {
+        tweets,
+        referenced_tweets,
+        skipped,
+        unavailable,
+        meta,
+    };
+    Ok((page, next_pagination_token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_follows_error() {
+        let error = follows_error(anyhow!("403 Forbidden: {{}}"));
+        assert_eq!(
+            format!("{error:#}"),
+            "Reading follows needs the follows.read permission; run with --login again: \
+             403 Forbidden: {}"
+        );
+        let error = follows_error(anyhow!("429 Too Many Requests"));
+        assert_eq!(error.to_string(), "429 Too Many Requests");
+    }
+
+    #[test]
+    fn test_parse_tweet_page_skips_malformed_tweets() {
+        let json = r#"{
+            "data": [
+                {"id": "1", "text": "ok", "created_at": "2023-02-01T00:01:00.000Z", "author_id": "2"},
+                {"id": "2", "text": "no timestamp", "author_id": "2"},
+                {"id": "3", "text": "ok", "created_at": "2023-02-01T00:03:00.000Z", "author_id": "2"}
+            ],
+            "includes": {
+                "users": [{"id": "2", "name": "Alice", "username": "alice"}],
+                "tweets": [{"id": "4", "created_at": "2023-02-01T00:00:00.000Z"}]
+            },
+            "meta": {"result_count": 3, "next_token": "page_2"}
+        }"#;
+        let (page, next_token) = parse_tweet_page(json.as_bytes()).unwrap();
+        let ids: Vec<&str> = page.tweets.iter().map(|tweet| tweet.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "3"]);
+        assert_eq!(page.tweets[1].author_username.as_deref(), Some("alice"));
+        assert!(page.referenced_tweets.is_empty());
+        assert_eq!(page.skipped.len(), 2);
+        assert!(
+            page.skipped[0].starts_with("Skipped malformed tweet 2: missing field `created_at`")
+        );
+        assert_eq!(next_token.as_deref(), Some("page_2"));
+    }
+
+    #[test]
+    fn test_parse_tweet_page_fills_in_author_badges() {
+        let json = r#"{
+            "data": [
+                {"id": "1", "text": "ok", "created_at": "2023-02-01T00:01:00.000Z", "author_id": "2"}
+            ],
+            "includes": {
+                "users": [{
+                    "id": "2",
+                    "name": "Alice",
+                    "username": "alice",
+                    "verified_type": "business",
+                    "affiliation": {"description": "Acme", "url": "https://acme.example.com"}
+                }]
+            },
+            "meta": {"result_count": 1}
+        }"#;
+        let (page, _) = parse_tweet_page(json.as_bytes()).unwrap();
+        let tweet = &page.tweets[0];
+        assert_eq!(tweet.author_verified_type.as_deref(), Some("business"));
+        assert_eq!(tweet.author_affiliation.as_deref(), Some("Acme"));
+        assert_eq!(
+            tweet.author("").affiliation_description().as_deref(),
+            Some("Acme")
+        );
+    }
+
+    #[test]
+    fn test_parse_tweet_page_notes_unavailable_tweets() {
+        let json = r#"{
+            "errors": [
+                {"resource_type": "tweet", "resource_id": "1", "parameter": "ids",
+                 "title": "Not Found Error", "detail": "Could not find tweet with ids: [1].",
+                 "type": "https://api.twitter.com/2/problems/resource-not-found"},
+                {"resource_type": "tweet", "resource_id": "2", "parameter": "ids",
+                 "title": "Authorization Error", "detail": "Sorry, you are not authorized.",
+                 "type": "https://api.twitter.com/2/problems/not-authorized-for-resource"}
+            ]
+        }"#;
+        let (page, _) = parse_tweet_page(json.as_bytes()).unwrap();
+        assert!(page.tweets.is_empty());
+        assert_eq!(
+            page.unavailable,
+            HashMap::from([
+                ("1".to_string(), api::Unavailable::NotFound),
+                ("2".to_string(), api::Unavailable::NotAuthorized),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_tweet_page_fills_in_media() {
+        let json = r#"{
+            "data": [
+                {"id": "1", "text": "pics", "created_at": "2023-02-01T00:01:00.000Z", "author_id": "2",
+                 "attachments": {"media_keys": ["3_1", "3_2"]}},
+                {"id": "2", "text": "none", "created_at": "2023-02-01T00:02:00.000Z", "author_id": "2"}
+            ],
+            "includes": {
+                "users": [{"id": "2", "name": "Alice", "username": "alice"}],
+                "media": [
+                    {"media_key": "3_1", "type": "photo", "url": "https://pbs.twimg.com/1.jpg",
+                     "alt_text": "A cat"},
+                    {"media_key": "3_2", "type": "photo", "url": "https://pbs.twimg.com/2.jpg"}
+                ]
+            }
+        }"#;
+        let (page, _) = parse_tweet_page(json.as_bytes()).unwrap();
+        let media = page.tweets[0].media.as_ref().unwrap();
+        assert_eq!(media.len(), 2);
+        assert_eq!(media[0].alt_text.as_deref(), Some("A cat"));
+        assert_eq!(page.tweets[0].is_media_described(), Some(false));
+        assert!(page.tweets[1].media.is_none());
+        assert_eq!(page.tweets[1].is_media_described(), None);
+    }
+
+    #[test]
+    fn test_builder() {
+        let twitter_client = TwitterClient::builder("id")
+            .timeout(Duration::from_millis(1500))
+            .connect_timeout(Duration::ZERO)
+            .max_retries(0)
+            .auth("token", None)
+            .build();
+        assert_eq!(
+            twitter_client.http_config,
+            HttpConfig {
+                connect_timeout_secs: NonZeroU64::new(1).unwrap(),
+                read_timeout_secs: NonZeroU64::new(2).unwrap(),
+                max_retries: 0,
+            }
+        );
+        assert_eq!(twitter_client.twitter_client_secret, None);
+        assert!(twitter_client.twitter_auth.access_token.is_some());
+        assert!(TwitterClient::new("id", "secret")
+            .twitter_client_secret
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_hung_request_times_out_and_retries() {
+        // NB: accepts connections but never responds
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let num_connections = Arc::new(AtomicUsize::new(0));
+        let counter = num_connections.clone();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                sockets.push(socket);
+            }
+        });
+
+        let mut client = TwitterClient::new("", "").with_http_config(&HttpConfig {
+            read_timeout_secs: NonZeroU64::new(1).unwrap(),
+            max_retries: 1,
+            ..HttpConfig::default()
+        });
+        client.twitter_auth.access_token = Some(AccessToken::new("token".to_string()));
+        let uri = Url::parse(&format!("http://{addr}/2/users/me")).unwrap();
+
+        let error = client.authenticated_get(&uri).await.unwrap_err();
+        assert_eq!(error.to_string(), "No response within 1s");
+        assert_eq!(num_connections.load(Ordering::SeqCst), 2);
+
+        // NB: not retried
+        let error = client
+            .authenticated_request(Method::POST, &uri, Some(serde_json::json!({})))
+            .await
+            .unwrap_err();
+        assert!(is_transient_error(&error));
+        assert_eq!(num_connections.load(Ordering::SeqCst), 3);
     }
 }
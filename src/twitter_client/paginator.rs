@@ -0,0 +1,194 @@
+//! Walks any paged endpoint by its pagination tokens, so callers can ask for "n tweets" rather than
+//! whatever page size the API happens to return.
+
+use crate::twitter_client::{api, PagedResult};
+use anyhow::Result;
+use std::future::Future;
+use std::marker::PhantomData;
+
+/// What a paged endpoint returns, enough to merge pages together.
+pub trait Page: Default {
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn append(&mut self, other: Self);
+}
+
+impl Page for api::TweetPage {
+    fn len(&self) -> usize {
+        self.tweets.len()
+    }
+
+    fn append(&mut self, other: Self) {
+        self.tweets.extend(other.tweets);
+        for tweet in other.referenced_tweets {
+            if !self
+                .referenced_tweets
+                .iter()
+                .any(|kept| kept.id == tweet.id)
+            {
+                self.referenced_tweets.push(tweet);
+            }
+        }
+        self.skipped.extend(other.skipped);
+        self.unavailable.extend(other.unavailable);
+        self.meta = api::Meta::merge(self.meta.take(), other.meta);
+    }
+}
+
+impl Page for Vec<api::User> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn append(&mut self, other: Self) {
+        self.extend(other);
+    }
+}
+
+/// [fetch] is called with the page token of the page to get, None for the first page.
+pub struct Paginator<T, G> {
+    fetch: G,
+    page_token: Option<String>,
+    /// Whether the page at [page_token] is past the first, i.e. None means there are no more
+    started: bool,
+    _page: PhantomData<T>,
+}
+
+impl<T, F, G> Paginator<T, G>
+where
+    T: Page,
+    F: Future<Output = PagedResult<T>>,
+    G: FnMut(Option<String>) -> F,
+{
+    /// Starts from the first page.
+    pub fn new(fetch: G) -> Self {
+        Self {
+            fetch,
+            page_token: None,
+            started: false,
+            _page: PhantomData,
+        }
+    }
+
+    /// Picks up at [page_token], e.g. one saved from an earlier paginator.
+    pub fn resume(fetch: G, page_token: String) -> Self {
+        Self {
+            fetch,
+            page_token: Some(page_token),
+            started: true,
+            _page: PhantomData,
+        }
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.started && self.page_token.is_none()
+    }
+
+    /// The token of the next page to get; None once exhausted.
+    pub fn page_token(&self) -> Option<&String> {
+        self.page_token.as_ref()
+    }
+
+    pub fn into_page_token(self) -> Option<String> {
+        self.page_token
+    }
+
+    /// None once exhausted.  NB: a failed fetch leaves the page token alone, so it can be retried
+    pub async fn next_page(&mut self) -> Result<Option<T>> {
+        if self.is_exhausted() {
+            return Ok(None);
+        }
+        let (page, page_token) = (self.fetch)(self.page_token.clone()).await?;
+        self.page_token = page_token;
+        self.started = true;
+        Ok(Some(page))
+    }
+
+    /// At least [n] items merged from as many pages as it takes, or fewer if the pages run out.
+    /// NB: if a page fails after others came in, those are returned and the error is left for the
+    /// next call to run into, rather than dropping what was fetched
+    pub async fn collect_n(&mut self, n: usize) -> Result<T> {
+        let mut collected = T::default();
+        while collected.len() < n {
+            match self.next_page().await {
+                Ok(Some(page)) if page.is_empty() && !self.is_exhausted() => {
+                    // NB: an empty page with a next token is possible, e.g. if everything on it was
+                    // deleted; keep going
+                }
+                Ok(Some(page)) => collected.append(page),
+                Ok(None) => break,
+                Err(err) if collected.is_empty() => return Err(err),
+                Err(_) => break,
+            }
+        }
+        Ok(collected)
+    }
+
+    /// Every remaining page merged.  NB: unlike [Self::collect_n], any failed page fails the lot,
+    /// for when a partial result would be misleading, e.g. a followers list
+    pub async fn collect_all(&mut self) -> Result<T> {
+        let mut collected = T::default();
+        while let Some(page) = self.next_page().await? {
+            collected.append(page);
+        }
+        Ok(collected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitter_client::mock::{self, MockTwitterClient};
+    use crate::twitter_client::TwitterApi;
+
+    const TIMELINE: &str = "/2/users/1/timelines/reverse_chronological";
+
+    fn ids(page: &api::TweetPage) -> Vec<&str> {
+        page.tweets.iter().map(|tweet| tweet.id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_collect_n() {
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_2);
+
+        let mut paginator = Paginator::new(|page_token| {
+            twitter_client.timeline_reverse_chronological("1", page_token)
+        });
+        let page = paginator.collect_n(3).await.unwrap();
+        assert_eq!(ids(&page), vec!["1005", "1004", "1003"]);
+        assert_eq!(page.referenced_tweets.len(), 2);
+        assert!(paginator.is_exhausted());
+        assert!(paginator.next_page().await.unwrap().is_none());
+        assert_eq!(twitter_client.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_collect_n_partial_failure() {
+        let twitter_client = MockTwitterClient::new();
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_1);
+        twitter_client.fail(TIMELINE, "503 Service Unavailable: over capacity");
+        twitter_client.respond(TIMELINE, mock::fixtures::TIMELINE_PAGE_2);
+
+        let mut paginator = Paginator::new(|page_token| {
+            twitter_client.timeline_reverse_chronological("1", page_token)
+        });
+        let page = paginator.collect_n(3).await.unwrap();
+        assert_eq!(ids(&page), vec!["1005", "1004"]);
+        assert_eq!(paginator.page_token().map(String::as_str), Some("page_2"));
+
+        let page_token = paginator.into_page_token().unwrap();
+        let mut paginator = Paginator::resume(
+            |page_token| twitter_client.timeline_reverse_chronological("1", page_token),
+            page_token,
+        );
+        let page = paginator.collect_n(3).await.unwrap();
+        assert_eq!(ids(&page), vec!["1003"]);
+        assert!(paginator.is_exhausted());
+    }
+}
@@ -0,0 +1,411 @@
+//! How much of the API's quotas has been used: calls per endpoint per day, and the rate limit each
+//! endpoint last reported, kept across launches under [QUOTA_PATH] so that reads on the free and
+//! basic tiers can be budgeted.  See [super::TwitterClient::track_quota] and `:budget`.
+
+use crate::fs_util;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use hyper::header::HeaderMap;
+use hyper::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const QUOTA_PATH: &str = "./var/quota.json";
+
+/// How many days of calls are kept; enough for this month and the last
+const RETENTION_DAYS: i64 = 62;
+
+/// What an endpoint's `x-rate-limit-*` headers said.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// When [remaining] goes back up to [limit]
+    pub reset: DateTime<Utc>,
+    pub observed_at: DateTime<Utc>,
+}
+
+impl RateLimit {
+    /// None unless [headers] has all three of the rate limit headers.
+    pub fn from_headers(headers: &HeaderMap, observed_at: DateTime<Utc>) -> Option<Self> {
+        let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+        let reset = i64::try_from(header("x-rate-limit-reset")?).ok()?;
+        Some(Self {
+            limit: header("x-rate-limit-limit")?,
+            remaining: header("x-rate-limit-remaining")?,
+            reset: Utc.timestamp_opt(reset, 0).single()?,
+            observed_at,
+        })
+    }
+
+    /// When the remaining calls run out at [calls_per_hour], if that's before they reset.
+    pub fn exhausted_at(&self, calls_per_hour: f64) -> Option<DateTime<Utc>> {
+        if self.remaining == 0 {
+            return Some(self.observed_at);
+        }
+        if calls_per_hour <= 0.0 {
+            return None;
+        }
+        let secs = self.remaining as f64 / calls_per_hour * 3600.0;
+        let exhausted_at = self.observed_at + Duration::seconds(secs as i64);
+        (exhausted_at < self.reset).then_some(exhausted_at)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndpointUsage {
+    /// By local date
+    pub calls_by_day: BTreeMap<NaiveDate, u64>,
+    /// As of the latest response that had one
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl EndpointUsage {
+    fn calls_since(&self, first_day: NaiveDate) -> u64 {
+        self.calls_by_day
+            .range(first_day..)
+            .map(|(_, calls)| calls)
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuotaUsage {
+    /// By [endpoint]
+    pub endpoints: BTreeMap<String, EndpointUsage>,
+}
+
+impl QuotaUsage {
+    /// Empty if nothing's been saved yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs_util::write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    /// Counts a call to [endpoint] on [today], forgetting days past [RETENTION_DAYS].
+    pub fn record(&mut self, endpoint: &str, today: NaiveDate, rate_limit: Option<RateLimit>) {
+        let usage = self.endpoints.entry(endpoint.to_string()).or_default();
+        *usage.calls_by_day.entry(today).or_default() += 1;
+        if rate_limit.is_some() {
+            usage.rate_limit = rate_limit;
+        }
+        let cutoff = today - Duration::days(RETENTION_DAYS);
+        for usage in self.endpoints.values_mut() {
+            usage.calls_by_day.retain(|day, _| *day > cutoff);
+        }
+    }
+
+    /// Across endpoints, from [first_day] on.
+    pub fn calls_since(&self, first_day: NaiveDate) -> u64 {
+        self.endpoints
+            .values()
+            .map(|usage| usage.calls_since(first_day))
+            .sum()
+    }
+
+    /// The endpoint with the smallest share of its rate limit left, of those yet to reset.
+    pub fn tightest_rate_limit(&self, now: DateTime<Utc>) -> Option<(&str, RateLimit)> {
+        self.endpoints
+            .iter()
+            .filter_map(|(endpoint, usage)| {
+                let rate_limit = usage
+                    .rate_limit
+                    .filter(|rate_limit| rate_limit.reset > now)?;
+                Some((endpoint.as_str(), rate_limit))
+            })
+            // NB: remaining / limit, compared without dividing
+            .min_by(|(_, a), (_, b)| (a.remaining * b.limit).cmp(&(b.remaining * a.limit)))
+    }
+
+    /// Calls today and this month, against [monthly_budget] if any, then each endpoint's calls and
+    /// rate limit, with when it would run out at today's pace.
+    pub fn describe(&self, now: DateTime<Local>, monthly_budget: Option<u64>) -> String {
+        if self.endpoints.is_empty() {
+            return "No API calls recorded yet".to_string();
+        }
+        let today = now.date_naive();
+        let month_start = today.with_day(1).unwrap();
+        let hours_today = now.num_seconds_from_midnight() as f64 / 3600.0;
+        let days_this_month = today.day0() as f64 + hours_today / 24.0;
+
+        let calls_this_month = self.calls_since(month_start);
+        let mut out = format!(
+            "{} calls today, {calls_this_month} this month",
+            self.calls_since(today)
+        );
+        // NB: writing to a String can't fail
+        if let Some(budget) = monthly_budget {
+            let _ = write!(out, " of a budget of {budget}");
+            let calls_per_day = calls_this_month as f64 / days_this_month.max(1.0 / 24.0);
+            if calls_this_month >= budget {
+                out.push_str(", which is used up");
+            } else if calls_per_day > 0.0 {
+                let days_left = (budget - calls_this_month) as f64 / calls_per_day;
+                let runs_out = today + Duration::days(days_left as i64);
+                if (runs_out.year(), runs_out.month()) == (today.year(), today.month()) {
+                    let _ = write!(out, "; at this pace it runs out on {runs_out}");
+                } else {
+                    out.push_str("; at this pace it lasts the month");
+                }
+            }
+        }
+        out.push_str("\n\n");
+
+        let _ = writeln!(
+            out,
+            "{:<44} {:>6} {:>6}  {:<20} Runs out",
+            "Endpoint", "Today", "Month", "Rate limit"
+        );
+        for (endpoint, usage) in self.endpoints.iter() {
+            let calls_today = usage.calls_since(today);
+            let (rate_limit, runs_out) = match &usage.rate_limit {
+                Some(rate_limit) if rate_limit.reset > now => {
+                    let calls_per_hour = calls_today as f64 / hours_today.max(1.0 / 60.0);
+                    let reset = rate_limit.reset.with_timezone(&Local).format("%H:%M");
+                    let runs_out = match rate_limit.exhausted_at(calls_per_hour) {
+                        Some(at) => at.with_timezone(&Local).format("%H:%M").to_string(),
+                        None => "-".to_string(),
+                    };
+                    let remaining = format!("{}/{}", rate_limit.remaining, rate_limit.limit);
+                    (format!("{remaining} until {reset}"), runs_out)
+                }
+                Some(rate_limit) => (format!("{} (reset)", rate_limit.limit), "-".to_string()),
+                None => ("?".to_string(), "-".to_string()),
+            };
+            let _ = writeln!(
+                out,
+                "{endpoint:<44} {calls_today:>6} {:>6}  {rate_limit:<20} {runs_out}",
+                usage.calls_since(month_start)
+            );
+        }
+        out
+    }
+}
+
+/// e.g. "GET /2/users/:id/tweets": ids and usernames are left out of the path, so that calls to an
+/// endpoint count together whichever user or tweet they're for.
+pub fn endpoint(method: &Method, path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        let is_id = !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit());
+        let is_username = segments.last() == Some(&"username") && segment != "username";
+        // NB: the leading "2" is the API version, not an id
+        if is_id && segments.len() > 1 {
+            segments.push(":id");
+        } else if is_username {
+            segments.push(":username");
+        } else {
+            segments.push(segment);
+        }
+    }
+    format!("{method} {}", segments.join("/"))
+}
+
+/// Counts a [super::TwitterClient]'s calls, saving to its file after each.
+#[derive(Debug)]
+pub struct QuotaTracker {
+    path: PathBuf,
+    usage: Mutex<QuotaUsage>,
+}
+
+impl QuotaTracker {
+    /// Carries on from the usage saved in [path], if any.
+    pub fn new(path: &Path) -> Result<Self> {
+        let usage = QuotaUsage::load(path)
+            .with_context(|| format!("Loading API quota usage from {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            usage: Mutex::new(usage),
+        })
+    }
+
+    pub fn tightest_rate_limit(&self, now: DateTime<Utc>) -> Option<(String, RateLimit)> {
+        let usage = self.usage.lock().unwrap();
+        let (endpoint, rate_limit) = usage.tightest_rate_limit(now)?;
+        Some((endpoint.to_string(), rate_limit))
+    }
+
+    pub fn record(&self, method: &Method, path: &str, headers: &HeaderMap) -> Result<()> {
+        let mut usage = self.usage.lock().unwrap();
+        usage.record(
+            &endpoint(method, path),
+            Local::now().date_naive(),
+            RateLimit::from_headers(headers, Utc::now()),
+        );
+        usage.save(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+    use std::{env, process};
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, month, day).unwrap()
+    }
+
+    fn rate_limit(remaining: u64, observed_at: DateTime<Utc>) -> RateLimit {
+        RateLimit {
+            limit: 180,
+            remaining,
+            reset: observed_at + Duration::minutes(15),
+            observed_at,
+        }
+    }
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(
+            endpoint(&Method::GET, "/2/users/123/tweets"),
+            "GET /2/users/:id/tweets"
+        );
+        assert_eq!(
+            endpoint(&Method::GET, "/2/users/by/username/alice"),
+            "GET /2/users/by/username/:username"
+        );
+        assert_eq!(endpoint(&Method::POST, "/2/tweets"), "POST /2/tweets");
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let observed_at = Utc.timestamp_opt(1_000, 0).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-rate-limit-limit", HeaderValue::from_static("180"));
+        headers.insert("x-rate-limit-remaining", HeaderValue::from_static("179"));
+        assert_eq!(RateLimit::from_headers(&headers, observed_at), None);
+        headers.insert("x-rate-limit-reset", HeaderValue::from_static("1900"));
+        assert_eq!(
+            RateLimit::from_headers(&headers, observed_at),
+            Some(RateLimit {
+                limit: 180,
+                remaining: 179,
+                reset: Utc.timestamp_opt(1_900, 0).unwrap(),
+                observed_at,
+            })
+        );
+    }
+
+    #[test]
+    fn test_exhausted_at() {
+        let observed_at = Utc.timestamp_opt(0, 0).unwrap();
+        // NB: 10 calls left at 60 an hour runs out in 10 minutes, before the reset in 15
+        assert_eq!(
+            rate_limit(10, observed_at).exhausted_at(60.0),
+            Some(observed_at + Duration::minutes(10))
+        );
+        assert_eq!(rate_limit(20, observed_at).exhausted_at(60.0), None);
+        assert_eq!(rate_limit(20, observed_at).exhausted_at(0.0), None);
+        assert_eq!(
+            rate_limit(0, observed_at).exhausted_at(0.0),
+            Some(observed_at)
+        );
+    }
+
+    #[test]
+    fn test_record() {
+        let mut usage = QuotaUsage::default();
+        let observed_at = Utc.timestamp_opt(0, 0).unwrap();
+        usage.record("GET /2/tweets", date(1, 1), None);
+        usage.record("GET /2/tweets", date(2, 1), None);
+        usage.record(
+            "GET /2/tweets",
+            date(2, 1),
+            Some(rate_limit(5, observed_at)),
+        );
+        usage.record("GET /2/tweets", date(2, 1), None);
+        usage.record("POST /2/tweets", date(2, 2), None);
+        assert_eq!(usage.calls_since(date(2, 1)), 4);
+        assert_eq!(usage.calls_since(date(1, 1)), 5);
+        // NB: a response without rate limit headers doesn't forget the last one seen
+        assert_eq!(
+            usage.endpoints["GET /2/tweets"].rate_limit,
+            Some(rate_limit(5, observed_at))
+        );
+
+        usage.record("POST /2/tweets", date(5, 1), None);
+        assert_eq!(usage.calls_since(date(1, 1)), 1);
+    }
+
+    #[test]
+    fn test_tightest_rate_limit() {
+        let now = Utc.timestamp_opt(0, 0).unwrap();
+        let mut usage = QuotaUsage::default();
+        assert_eq!(usage.tightest_rate_limit(now), None);
+        usage.record("GET /2/tweets", date(1, 1), Some(rate_limit(90, now)));
+        let users_limit = RateLimit {
+            limit: 15,
+            ..rate_limit(5, now)
+        };
+        usage.record("GET /2/users/me", date(1, 1), Some(users_limit));
+        // NB: a third left of 15 is tighter than half of 180, and a limit that's reset is ignored
+        usage.record(
+            "GET /2/lists",
+            date(1, 1),
+            Some(rate_limit(0, now - Duration::hours(1))),
+        );
+        assert_eq!(
+            usage.tightest_rate_limit(now),
+            Some(("GET /2/users/me", users_limit))
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let now = Local.with_ymd_and_hms(2023, 2, 11, 12, 0, 0).unwrap();
+        let today = now.date_naive();
+        let mut usage = QuotaUsage::default();
+        assert_eq!(usage.describe(now, None), "No API calls recorded yet");
+
+        let observed_at = now.with_timezone(&Utc);
+        for _ in 0..12 {
+            usage.record("GET /2/tweets", today, None);
+        }
+        usage.record("GET /2/tweets", today, Some(rate_limit(0, observed_at)));
+        usage.record("GET /2/users/me", date(2, 1), None);
+        // NB: 14 calls in 10.5 days leaves 6 for another 4.5 days
+        let described = usage.describe(now, Some(20));
+        assert!(described.starts_with(
+            "13 calls today, 14 this month of a budget of 20; at this pace it runs out on 2023-02-15\n"
+        ));
+        assert!(described.contains("0/180 until "));
+        assert!(usage.describe(now, Some(14)).contains("which is used up"));
+        assert!(usage
+            .describe(now, Some(1000))
+            .contains("it lasts the month"));
+    }
+
+    #[test]
+    fn test_tracker() {
+        let path = env::temp_dir().join(format!("twitter-tool-quota-{}.json", process::id()));
+        let _ = fs::remove_file(&path);
+        let tracker = QuotaTracker::new(&path).unwrap();
+        tracker
+            .record(&Method::GET, "/2/users/1/tweets", &HeaderMap::new())
+            .unwrap();
+        // NB: a later launch carries on from the saved usage
+        let tracker = QuotaTracker::new(&path).unwrap();
+        tracker
+            .record(&Method::GET, "/2/users/2/tweets", &HeaderMap::new())
+            .unwrap();
+        let usage = QuotaUsage::load(&path).unwrap();
+        assert_eq!(usage.endpoints.len(), 1);
+        assert_eq!(usage.calls_since(NaiveDate::MIN), 2);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+//! Session captures of API traffic: a [Recorder] saves every raw API response to a directory, and a
+//! [Replayer] serves them back instead of the network, so a session on real-world data can be
+//! reproduced deterministically (e.g. attached to a bug report).
+//!
+//! A capture is a directory of response bodies (0000.json, 0001.json, ...) plus index.jsonl, one
+//! line per request with its method, URI and response status.  Request headers, and so the access
+//! token, are never saved.
+
+use anyhow::{anyhow, Result};
+use hyper::body::Bytes;
+use hyper::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use url::Url;
+
+const INDEX_FILE: &str = "index.jsonl";
+
+/// (method, URI)
+type RequestKey = (String, String);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    uri: String,
+    status: u16,
+    /// Relative to the capture directory
+    body_file: String,
+}
+
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+    /// The index, and the number of exchanges in it
+    index: Mutex<(File, usize)>,
+}
+
+impl Recorder {
+    /// Starts a new capture in [dir], replacing any capture already there.
+    pub fn new(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let index = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(dir.join(INDEX_FILE))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            index: Mutex::new((index, 0)),
+        })
+    }
+
+    pub fn record(
+        &self,
+        method: &Method,
+        uri: &Url,
+        status: StatusCode,
+        body: &[u8],
+    ) -> Result<()> {
+        let mut index = self.index.lock().unwrap();
+        let (index_file, num_exchanges) = &mut *index;
+        let exchange = Exchange {
+            method: method.to_string(),
+            uri: uri.to_string(),
+            status: status.as_u16(),
+            body_file: format!("{num_exchanges:04}.json"),
+        };
+        fs::write(self.dir.join(&exchange.body_file), body)?;
+        writeln!(index_file, "{}", serde_json::to_string(&exchange)?)?;
+        *num_exchanges += 1;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Replayer {
+    /// Recorded (status, body) by request, in the order they were recorded
+    responses: Mutex<HashMap<RequestKey, VecDeque<(StatusCode, Bytes)>>>,
+}
+
+impl Replayer {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let index = fs::read_to_string(dir.join(INDEX_FILE))
+            .map_err(|error| anyhow!("Can't read capture in {}: {error}", dir.display()))?;
+        let mut responses: HashMap<_, VecDeque<_>> = HashMap::new();
+        for line in index.lines().filter(|line| !line.trim().is_empty()) {
+            let exchange: Exchange = serde_json::from_str(line)?;
+            let body = fs::read(dir.join(&exchange.body_file))?;
+            responses
+                .entry((exchange.method, exchange.uri))
+                .or_default()
+                .push_back((StatusCode::from_u16(exchange.status)?, Bytes::from(body)));
+        }
+        Ok(Self {
+            responses: Mutex::new(responses),
+        })
+    }
+
+    /// The next recorded response to the same request.  Once those run out, the last one is served
+    /// again, e.g. for polling.
+    pub fn replay(&self, method: &Method, uri: &Url) -> Result<(StatusCode, Bytes)> {
+        let mut responses = self.responses.lock().unwrap();
+        let queue = responses
+            .get_mut(&(method.to_string(), uri.to_string()))
+            .filter(|queue| !queue.is_empty())
+            .ok_or(anyhow!("Not in the capture: {method} {uri}"))?;
+        if queue.len() > 1 {
+            Ok(queue.pop_front().unwrap())
+        } else {
+            Ok(queue[0].clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = env::temp_dir().join(format!("twitter-tool-capture-{}", process::id()));
+        let timeline = Url::parse("https://api.twitter.com/2/users/1/timelines").unwrap();
+        let me = Url::parse("https://api.twitter.com/2/users/me").unwrap();
+
+        let recorder = Recorder::new(&dir).unwrap();
+        recorder
+            .record(&Method::GET, &timeline, StatusCode::OK, b"first")
+            .unwrap();
+        recorder
+            .record(&Method::GET, &me, StatusCode::UNAUTHORIZED, b"nope")
+            .unwrap();
+        recorder
+            .record(&Method::GET, &timeline, StatusCode::OK, b"second")
+            .unwrap();
+
+        let replayer = Replayer::load(&dir).unwrap();
+        let replay = |method, uri| {
+            let (status, body) = replayer.replay(method, uri).unwrap();
+            (status.as_u16(), String::from_utf8(body.to_vec()).unwrap())
+        };
+        assert_eq!(replay(&Method::GET, &timeline), (200, "first".to_string()));
+        assert_eq!(replay(&Method::GET, &timeline), (200, "second".to_string()));
+        assert_eq!(replay(&Method::GET, &timeline), (200, "second".to_string()));
+        assert_eq!(replay(&Method::GET, &me), (401, "nope".to_string()));
+        assert!(replayer.replay(&Method::DELETE, &me).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
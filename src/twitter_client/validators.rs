@@ -0,0 +1,209 @@
+//! Conditional requests: the validators (ETag, Last-Modified) of recent GET responses are kept by
+//! URL and sent back as If-None-Match and If-Modified-Since, so that a refresh of something
+//! unchanged comes back as an empty 304 and is served from here.  Endpoints that don't send
+//! validators are left alone.
+//!
+//! NB: kept in memory only, since what's worth it is refreshing within a launch
+
+use hyper::body::Bytes;
+use hyper::header::{
+    HeaderMap, HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use hyper::{Body, Method, Request, StatusCode};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// How many responses are kept; the oldest is dropped first
+const MAX_ENTRIES: usize = 64;
+
+struct Entry {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body: Bytes,
+}
+
+#[derive(Default)]
+struct Entries {
+    /// By URL
+    entries: HashMap<String, Entry>,
+    /// URLs, oldest first
+    order: VecDeque<String>,
+}
+
+/// Shared between clones, like [super::coalescer::TweetCoalescer].
+#[derive(Clone, Default)]
+pub struct ValidatorCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl fmt::Debug for ValidatorCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let entries = self.entries.lock().unwrap();
+        f.debug_struct("ValidatorCache")
+            .field("entries", &entries.entries.len())
+            .finish()
+    }
+}
+
+impl ValidatorCache {
+    /// Makes [req] conditional on the validators of the last response from its URL, if it's a GET
+    /// and there was one.
+    pub fn prepare(&self, req: &mut Request<Body>) {
+        if req.method() != Method::GET {
+            return;
+        }
+        let entries = self.entries.lock().unwrap();
+        let Some(entry) = entries.entries.get(&req.uri().to_string()) else {
+            return;
+        };
+        if let Some(etag) = &entry.etag {
+            req.headers_mut().insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            req.headers_mut()
+                .insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
+
+    /// The response to go with: for a 304, the body it says is unchanged; otherwise [body], kept
+    /// for next time if it came with validators.
+    pub fn resolve(
+        &self,
+        method: &Method,
+        uri: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: Bytes,
+    ) -> (StatusCode, Bytes) {
+        if method != Method::GET {
+            return (status, body);
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if status == StatusCode::NOT_MODIFIED {
+            return match entries.entries.get(uri) {
+                Some(entry) => (StatusCode::OK, entry.body.clone()),
+                // NB: e.g. evicted while the request was in flight
+                None => (status, body),
+            };
+        }
+        let etag = headers.get(ETAG).cloned();
+        let last_modified = headers.get(LAST_MODIFIED).cloned();
+        if !status.is_success() || (etag.is_none() && last_modified.is_none()) {
+            return (status, body);
+        }
+
+        let entry = Entry {
+            etag,
+            last_modified,
+            body: body.clone(),
+        };
+        if entries.entries.insert(uri.to_string(), entry).is_none() {
+            entries.order.push_back(uri.to_string());
+        }
+        while entries.order.len() > MAX_ENTRIES {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.entries.remove(&oldest);
+            }
+        }
+        (status, body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "https://api.twitter.com/2/users/1/tweets";
+
+    fn get(uri: &str) -> Request<Body> {
+        Request::builder().uri(uri).body(Body::empty()).unwrap()
+    }
+
+    fn with_etag(etag: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ETAG, HeaderValue::from_static(etag));
+        headers
+    }
+
+    #[test]
+    fn test_not_modified() {
+        let cache = ValidatorCache::default();
+        let mut req = get(URI);
+        cache.prepare(&mut req);
+        assert!(req.headers().is_empty());
+
+        let body = Bytes::from_static(b"{\"data\": []}");
+        cache.resolve(
+            &Method::GET,
+            URI,
+            StatusCode::OK,
+            &with_etag("\"v1\""),
+            body.clone(),
+        );
+        let mut req = get(URI);
+        cache.prepare(&mut req);
+        assert_eq!(req.headers()[IF_NONE_MATCH], "\"v1\"");
+        assert_eq!(req.headers().get(IF_MODIFIED_SINCE), None);
+
+        let resolved = cache.resolve(
+            &Method::GET,
+            URI,
+            StatusCode::NOT_MODIFIED,
+            &HeaderMap::new(),
+            Bytes::new(),
+        );
+        assert_eq!(resolved, (StatusCode::OK, body));
+    }
+
+    #[test]
+    fn test_leaves_alone_without_validators() {
+        let cache = ValidatorCache::default();
+        let body = Bytes::from_static(b"{}");
+        cache.resolve(
+            &Method::GET,
+            URI,
+            StatusCode::OK,
+            &HeaderMap::new(),
+            body.clone(),
+        );
+        cache.resolve(
+            &Method::POST,
+            URI,
+            StatusCode::OK,
+            &with_etag("\"v1\""),
+            body.clone(),
+        );
+        cache.resolve(
+            &Method::GET,
+            URI,
+            StatusCode::TOO_MANY_REQUESTS,
+            &with_etag("\"v1\""),
+            body,
+        );
+        let mut req = get(URI);
+        cache.prepare(&mut req);
+        assert!(req.headers().is_empty());
+    }
+
+    #[test]
+    fn test_drops_oldest() {
+        let cache = ValidatorCache::default();
+        for i in 0..=MAX_ENTRIES {
+            let uri = format!("{URI}?page={i}");
+            cache.resolve(
+                &Method::GET,
+                &uri,
+                StatusCode::OK,
+                &with_etag("\"v1\""),
+                Bytes::new(),
+            );
+        }
+        let mut oldest = get(&format!("{URI}?page=0"));
+        cache.prepare(&mut oldest);
+        assert!(oldest.headers().is_empty());
+        let mut newest = get(&format!("{URI}?page={MAX_ENTRIES}"));
+        cache.prepare(&mut newest);
+        assert_eq!(newest.headers()[IF_NONE_MATCH], "\"v1\"");
+    }
+}
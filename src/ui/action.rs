@@ -0,0 +1,206 @@
+use crate::keybindings::{self, Action, Key};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+
+/// Which key does what, in the feed.
+pub struct KeyMap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Action>,
+}
+
+impl KeyMap {
+    /// NB: shift is ignored for characters, since it's already in the character
+    fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+        match code {
+            KeyCode::Char(_) => (code, modifiers - KeyModifiers::SHIFT),
+            _ => (code, modifiers),
+        }
+    }
+
+    pub fn bind(&mut self, code: KeyCode, modifiers: KeyModifiers, action: Action) {
+        self.bindings
+            .insert(Self::normalize(code, modifiers), action);
+    }
+
+    /// The default bindings, overridden by [bindings] from the config.
+    pub fn with_bindings(mut self, bindings: &BTreeMap<Key, Action>) -> Self {
+        for (key, action) in bindings {
+            let (code, modifiers) = Self::crossterm_key(key);
+            self.bind(code, modifiers, *action);
+        }
+        self
+    }
+
+    /// [key] as crossterm reports it.
+    fn crossterm_key(key: &Key) -> (KeyCode, KeyModifiers) {
+        let code = match key.code {
+            keybindings::KeyCode::Char(ch) => KeyCode::Char(ch),
+            keybindings::KeyCode::F(n) => KeyCode::F(n),
+            keybindings::KeyCode::Esc => KeyCode::Esc,
+            keybindings::KeyCode::Enter => KeyCode::Enter,
+            keybindings::KeyCode::Tab => KeyCode::Tab,
+            keybindings::KeyCode::BackTab => KeyCode::BackTab,
+            keybindings::KeyCode::Backspace => KeyCode::Backspace,
+            keybindings::KeyCode::Delete => KeyCode::Delete,
+            keybindings::KeyCode::Up => KeyCode::Up,
+            keybindings::KeyCode::Down => KeyCode::Down,
+            keybindings::KeyCode::Left => KeyCode::Left,
+            keybindings::KeyCode::Right => KeyCode::Right,
+            keybindings::KeyCode::Home => KeyCode::Home,
+            keybindings::KeyCode::End => KeyCode::End,
+            keybindings::KeyCode::PageUp => KeyCode::PageUp,
+            keybindings::KeyCode::PageDown => KeyCode::PageDown,
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for (is_set, modifier) in [
+            (key.ctrl, KeyModifiers::CONTROL),
+            (key.alt, KeyModifiers::ALT),
+            (key.shift, KeyModifiers::SHIFT),
+        ] {
+            if is_set {
+                modifiers |= modifier;
+            }
+        }
+        (code, modifiers)
+    }
+
+    pub fn get(&self, event: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .get(&Self::normalize(event.code, event.modifiers))
+            .copied()
+    }
+
+    /// One binding per line, e.g. "Ctrl-p     Find a tweet", sorted by key.
+    pub fn help(&self) -> String {
+        self.bindings
+            .iter()
+            .map(|(&(code, modifiers), action)| {
+                let key = match code {
+                    KeyCode::Char(ch) => ch.to_string(),
+                    _ => format!("{code:?}"),
+                };
+                let key = if modifiers.contains(KeyModifiers::CONTROL) {
+                    format!("Ctrl-{key}")
+                } else {
+                    key
+                };
+                (key, action.describe())
+            })
+            .sorted()
+            .map(|(key, description)| format!("{key:<10} {description}"))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let mut key_map = Self {
+            bindings: HashMap::new(),
+        };
+        let none = KeyModifiers::NONE;
+        key_map.bind(
+            KeyCode::Char('p'),
+            KeyModifiers::CONTROL,
+            Action::OpenTweetFinder,
+        );
+        for (ch, action) in [
+            ('c', Action::Compose),
+            ('C', Action::ComposeReply),
+            ('D', Action::OpenDrafts),
+            ('i', Action::InspectTweet),
+            ('o', Action::OpenTweetInBrowser),
+            ('u', Action::OpenTweetUrls),
+            ('y', Action::CopyTweetAsMarkdown),
+            ('Y', Action::CopyTweetAsHtml),
+            ('p', Action::JumpToParent),
+            ('t', Action::OpenConversation),
+            ('T', Action::TranslateTweet),
+            ('e', Action::ShowEditHistory),
+            ('x', Action::RevealSensitive),
+            ('n', Action::LoadNextPage),
+            ('r', Action::Refresh),
+            ('S', Action::ToggleStarred),
+            ('U', Action::CatchUpOnAuthor),
+            ('A', Action::EditStarred),
+            ('N', Action::EditNote),
+            ('a', Action::AddToCollection),
+            ('s', Action::SearchStarred),
+            ('/', Action::OpenSearchBar),
+            ('f', Action::FilterFeed),
+            ('*', Action::ShowStarred),
+            (' ', Action::ToggleMarked),
+            ('V', Action::MarkRange),
+            ('O', Action::OpenAuthors),
+            ('M', Action::MuteAuthors),
+            (':', Action::OpenCommandBar),
+            ('E', Action::ShowErrors),
+            ('R', Action::RetryLoad),
+            ('X', Action::AbortTasks),
+            ('g', Action::TabPrefix),
+            ('q', Action::Quit),
+        ] {
+            key_map.bind(KeyCode::Char(ch), none, action);
+        }
+        for index in 0..9 {
+            let ch = char::from_digit(index as u32 + 1, 10).unwrap();
+            key_map.bind(KeyCode::Char(ch), none, Action::SwitchTab(index));
+        }
+        key_map.bind(KeyCode::Esc, none, Action::Redraw);
+        key_map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_map() {
+        let key_map = KeyMap::default();
+        let key = |code, modifiers| key_map.get(&KeyEvent::new(code, modifiers));
+
+        assert_eq!(
+            key(KeyCode::Char('p'), KeyModifiers::NONE),
+            Some(Action::JumpToParent)
+        );
+        assert_eq!(
+            key(KeyCode::Char('p'), KeyModifiers::CONTROL),
+            Some(Action::OpenTweetFinder)
+        );
+        // NB: terminals report shift along with uppercase characters
+        assert_eq!(
+            key(KeyCode::Char('C'), KeyModifiers::SHIFT),
+            Some(Action::ComposeReply)
+        );
+        assert_eq!(
+            key(KeyCode::Char('3'), KeyModifiers::NONE),
+            Some(Action::SwitchTab(2))
+        );
+        assert_eq!(key(KeyCode::Char('c'), KeyModifiers::CONTROL), None);
+        assert_eq!(key(KeyCode::Down, KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn test_with_bindings() {
+        let bindings = BTreeMap::from([
+            (
+                Key::try_from("ctrl-f".to_string()).unwrap(),
+                Action::OpenSearchBar,
+            ),
+            (
+                Key::try_from("shift-pagedown".to_string()).unwrap(),
+                Action::LoadNextPage,
+            ),
+        ]);
+        let key_map = KeyMap::default().with_bindings(&bindings);
+        assert_eq!(
+            key_map.get(&KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL)),
+            Some(Action::OpenSearchBar)
+        );
+        assert_eq!(
+            key_map.get(&KeyEvent::new(KeyCode::PageDown, KeyModifiers::SHIFT)),
+            Some(Action::LoadNextPage)
+        );
+    }
+}
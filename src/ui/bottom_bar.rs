@@ -1,16 +1,31 @@
 use crate::store::Store;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_width;
 use crate::ui_framework::{bounding_box::BoundingBox, Input, Render};
-use anyhow::Result;
+use crate::user_config::StatusSegment;
+use anyhow::{Error, Result};
+use chrono::{DateTime, Local};
 use crossterm::event::KeyEvent;
 use crossterm::style::Color;
 use crossterm::terminal::{self, ClearType};
 use crossterm::{cursor, queue, style};
-use std::io::{Stdout, Write};
+use std::io::Write;
 use std::sync::Arc;
 
+/// A row of segments, whose order and visibility are [crate::user_config::UserConfig::status_bar].
 pub struct BottomBar {
     store: Arc<Store>,
+    /// The current tab's title
+    feed_name: String,
+    /// (selected line, lines) of the current feed
+    position: Option<(usize, usize)>,
+    num_unread: usize,
+    /// As last rendered, so that it's redrawn only when the minute changes
+    clock: String,
     num_tasks_in_flight: usize,
+    /// (time, error) of every error so far, oldest first
+    errors: Vec<(DateTime<Local>, String)>,
+    num_unseen_errors: usize,
     should_render: bool,
 }
 
@@ -18,15 +33,124 @@ impl BottomBar {
     pub fn new(store: &Arc<Store>) -> Self {
         Self {
             store: store.clone(),
+            feed_name: String::new(),
+            position: None,
+            num_unread: 0,
+            clock: String::new(),
             num_tasks_in_flight: 0,
+            errors: Vec::new(),
+            num_unseen_errors: 0,
             should_render: true,
         }
     }
 
+    /// Points the bar at the feed of the current tab.
+    pub fn set_store(&mut self, store: &Arc<Store>) {
+        self.store = store.clone();
+        self.should_render = true;
+    }
+
+    pub fn set_feed_name(&mut self, feed_name: &str) {
+        if self.feed_name != feed_name {
+            self.feed_name = feed_name.to_string();
+            self.should_render = true;
+        }
+    }
+
+    pub fn set_position(&mut self, position: Option<(usize, usize)>) {
+        if self.position != position {
+            self.position = position;
+            self.should_render = true;
+        }
+    }
+
+    pub fn set_num_unread(&mut self, num_unread: usize) {
+        if self.num_unread != num_unread {
+            self.num_unread = num_unread;
+            self.should_render = true;
+        }
+    }
+
+    /// Redraws the clock if the time it shows is out of date.
+    pub fn tick_clock(&mut self) {
+        if self.clock != Self::clock_text() {
+            self.should_render = true;
+        }
+    }
+
+    fn clock_text() -> String {
+        Local::now().format("%H:%M").to_string()
+    }
+
     pub fn set_num_tasks_in_flight(&mut self, n: usize) {
         self.num_tasks_in_flight = n;
         self.should_render = true;
     }
+
+    pub fn push_error(&mut self, error: &Error) {
+        // NB: alternate formatting includes the context chain
+        self.errors.push((Local::now(), format!("{error:#}")));
+        self.num_unseen_errors += 1;
+        self.should_render = true;
+    }
+
+    /// Every error so far, newest first, one per paragraph.
+    pub fn get_error_history(&self) -> String {
+        self.errors
+            .iter()
+            .rev()
+            .map(|(time, error)| format!("{}  {error}", time.format("%H:%M:%S")))
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    }
+
+    pub fn mark_errors_seen(&mut self) {
+        self.num_unseen_errors = 0;
+        self.should_render = true;
+    }
+
+    /// What [segment] shows, or None if there's nothing to.
+    fn segment_text(&mut self, segment: StatusSegment, feed_length: usize) -> Option<String> {
+        match segment {
+            StatusSegment::FeedName => (!self.feed_name.is_empty()).then(|| self.feed_name.clone()),
+            StatusSegment::Position => self
+                .position
+                .map(|(line_no, num_lines)| format!("{line_no}/{num_lines}")),
+            StatusSegment::Unread => {
+                (self.num_unread > 0).then(|| format!("{} unread", self.num_unread))
+            }
+            StatusSegment::Tasks => {
+                (self.num_tasks_in_flight > 0).then(|| format!("[* {}]", self.num_tasks_in_flight))
+            }
+            StatusSegment::Tweets => Some(format!("{feed_length} tweets")),
+            StatusSegment::RateLimit => {
+                let (_, rate_limit) = self.store.twitter_client.tightest_rate_limit()?;
+                Some(format!(
+                    "{}/{} calls left",
+                    rate_limit.remaining, rate_limit.limit
+                ))
+            }
+            StatusSegment::Clock => {
+                self.clock = Self::clock_text();
+                Some(self.clock.clone())
+            }
+            StatusSegment::Errors => {
+                if self.num_unseen_errors == 0 {
+                    return None;
+                }
+                let (_, error) = self.errors.last()?;
+                let error = error.lines().next().unwrap_or("");
+                Some(if self.num_unseen_errors > 1 {
+                    format!(
+                        " ! {error} (+{} more, E to view) ",
+                        self.num_unseen_errors - 1
+                    )
+                } else {
+                    format!(" ! {error} (E to view) ")
+                })
+            }
+        }
+    }
 }
 
 impl Render for BottomBar {
@@ -38,21 +162,43 @@ impl Render for BottomBar {
         self.should_render = true;
     }
 
-    fn render(&mut self, stdout: &mut Stdout, bounding_box: BoundingBox) -> Result<()> {
-        let tweets_reverse_chronological = self.store.tweets_feed.lock().unwrap();
-        let feed_length = tweets_reverse_chronological.len();
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let feed_length = self.store.tweets_feed.lock().unwrap().len();
+        let segments = self.store.user_config.lock().unwrap().status_bar.clone();
+        let texts: Vec<(StatusSegment, String)> = segments
+            .into_iter()
+            .filter_map(|segment| Some((segment, self.segment_text(segment, feed_length)?)))
+            .collect();
 
         queue!(stdout, cursor::MoveTo(bounding_box.left, bounding_box.top))?;
-        queue!(stdout, style::SetForegroundColor(Color::Black))?;
-        queue!(stdout, style::SetBackgroundColor(Color::White))?;
-
-        if self.num_tasks_in_flight > 0 {
-            queue!(
-                stdout,
-                style::Print(format!("[* {}] ", self.num_tasks_in_flight))
-            )?;
+        let mut remaining_width = bounding_box.width as usize;
+        let mut prev_segment = None;
+        for (segment, text) in texts {
+            // NB: errors stand apart from the bar, which is black on white
+            let is_error = segment == StatusSegment::Errors;
+            if let Some(prev_segment) = prev_segment {
+                let separator = if is_error || prev_segment == StatusSegment::Errors {
+                    queue!(stdout, style::ResetColor)?;
+                    "  "
+                } else {
+                    " "
+                };
+                let separator = text_width::truncate(separator, remaining_width);
+                queue!(stdout, style::Print(separator))?;
+                remaining_width -= text_width::width(separator);
+            }
+            let (fg, bg) = if is_error {
+                (Color::White, Color::Red)
+            } else {
+                (Color::Black, Color::White)
+            };
+            queue!(stdout, style::SetForegroundColor(fg))?;
+            queue!(stdout, style::SetBackgroundColor(bg))?;
+            let text = text_width::truncate(&text, remaining_width);
+            queue!(stdout, style::Print(text))?;
+            remaining_width -= text_width::width(text);
+            prev_segment = Some(segment);
         }
-        queue!(stdout, style::Print(format!("{feed_length} tweets")))?;
         queue!(stdout, style::ResetColor)?;
         queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
 
@@ -74,3 +220,59 @@ impl Input for BottomBar {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+    use crate::ui_framework::testing;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_render() {
+        let store = fixtures::store(vec![
+            fixtures::tweet("1", "alice", "", 0),
+            fixtures::tweet("2", "alice", "", 1),
+        ]);
+        let mut bottom_bar = BottomBar::new(&store);
+        bottom_bar.set_num_tasks_in_flight(3);
+        bottom_bar.push_error(&anyhow!("first"));
+        bottom_bar.push_error(&anyhow!("second\nmore detail").context("loading"));
+
+        let screen = testing::render_to_screen(&mut bottom_bar, 40, 1);
+        assert_eq!(screen.to_text(), "[* 3] 2 tweets   ! loading: second (+1 m");
+        assert_eq!(screen.style_at(0, 0), "0;38;5;0;48;5;15");
+        assert_eq!(screen.style_at(20, 0), "0;38;5;15;48;5;9");
+
+        bottom_bar.set_num_tasks_in_flight(0);
+        bottom_bar.mark_errors_seen();
+        assert_eq!(testing::render_to_text(&mut bottom_bar, 40, 1), "2 tweets");
+    }
+
+    #[test]
+    fn test_segments() {
+        let store = fixtures::store(vec![fixtures::tweet("1", "alice", "", 0)]);
+        store.user_config.lock().unwrap().status_bar = vec![
+            StatusSegment::Errors,
+            StatusSegment::FeedName,
+            StatusSegment::Position,
+            StatusSegment::Unread,
+            StatusSegment::RateLimit,
+        ];
+        let mut bottom_bar = BottomBar::new(&store);
+        bottom_bar.set_feed_name("Home");
+        bottom_bar.set_position(Some((1, 1)));
+        bottom_bar.set_num_unread(2);
+        bottom_bar.push_error(&anyhow!("oops"));
+
+        // NB: quota isn't tracked, so there's no rate limit to show
+        let screen = testing::render_to_screen(&mut bottom_bar, 40, 1);
+        assert_eq!(screen.to_text(), " ! oops (E to view)   Home 1/1 2 unread");
+        assert_eq!(screen.style_at(1, 0), "0;38;5;15;48;5;9");
+        assert_eq!(screen.style_at(22, 0), "0;38;5;0;48;5;15");
+
+        bottom_bar.mark_errors_seen();
+        bottom_bar.set_num_unread(0);
+        assert_eq!(testing::render_to_text(&mut bottom_bar, 40, 1), "Home 1/1");
+    }
+}
@@ -0,0 +1,440 @@
+use crate::feed_source::FeedSource;
+use crate::store::FeedSort;
+use crate::ui::search_bar::SearchBar;
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::{Input, Render};
+use anyhow::{anyhow, Result};
+use chrono::NaiveDate;
+use crossterm::event::{KeyCode, KeyEvent};
+
+const TAB_USAGE: &str = ":tab [home|mentions|activity|search <query>|user @<username>|list <name>|\
+                         tag <tag>|collection <name>|catchup @<username>]";
+
+/// (name, usage, description) for each command, in the order shown by `:help`.
+pub const COMMANDS: [(&str, &str, &str); 23] = [
+    (
+        "search",
+        ":search <query>",
+        "Open recent tweets matching query in a tab of their own",
+    ),
+    (
+        "append",
+        ":append <query>",
+        "Add recent tweets matching query to the end of the current feed",
+    ),
+    ("user", ":user @<username>", "Load a user's tweets"),
+    (
+        "list",
+        ":list <name>",
+        "Load a list's tweets (\"starred\" for starred accounts)",
+    ),
+    (
+        "collection",
+        ":collection <name>",
+        "Load a collection of saved tweets",
+    ),
+    (
+        "sort",
+        ":sort <newest|likes|retweets|replies>",
+        "Reorder the current feed, e.g. most liked first",
+    ),
+    (
+        "conversations",
+        ":conversations",
+        "Group the current feed by conversation, or ungroup it",
+    ),
+    (
+        "authors",
+        ":authors",
+        "Group the current feed by author, most tweets first, or ungroup it",
+    ),
+    (
+        "goto",
+        ":goto <yyyy-mm-dd>",
+        "Page back through the home or user feed to a date",
+    ),
+    ("tab", TAB_USAGE, "Open a feed in a new tab"),
+    (
+        "columns",
+        ":columns [2-4]",
+        "Show tabs side by side, from the current one; without a count, toggle them",
+    ),
+    ("tabclose", ":tabclose", "Close the current tab"),
+    (
+        "export",
+        ":export [path]",
+        "Write the current feed to a JSON file, or Markdown if path ends in .md; \
+         write the selected tweet as an image if path ends in .png",
+    ),
+    (
+        "metrics",
+        ":metrics",
+        "Toggle retweet, like and reply counts in the feed",
+    ),
+    (
+        "analytics",
+        ":analytics",
+        "Show engagement with my recent tweets",
+    ),
+    (
+        "compare",
+        ":compare @<username> @<username>",
+        "Show who both users follow, and who only one does",
+    ),
+    (
+        "followers",
+        ":followers",
+        "Show who followed and unfollowed since the last :followers",
+    ),
+    (
+        "alerts",
+        ":alerts",
+        "Show recent tweets that matched alert rules",
+    ),
+    (
+        "budget",
+        ":budget",
+        "Show API calls per endpoint today and this month, and when rate limits run out",
+    ),
+    (
+        "cache-clear",
+        ":cache-clear",
+        "Delete the downloaded avatars and media",
+    ),
+    ("debug", ":debug", "Show media cache statistics"),
+    ("quit", ":quit", "Quit"),
+    ("help", ":help", "Show this help"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Search(String),
+    Append(String),
+    User(String),
+    List(String),
+    Collection(String),
+    Sort(FeedSort),
+    Goto(NaiveDate),
+    Conversations,
+    Authors,
+    Tab(FeedSource),
+    TabClose,
+    /// How many, if given
+    Columns(Option<usize>),
+    Export(Option<String>),
+    Metrics,
+    Analytics,
+    Compare(String, String),
+    Followers,
+    Alerts,
+    Budget,
+    CacheClear,
+    Debug,
+    Quit,
+    Help,
+}
+
+impl Command {
+    /// Parses a command line (without the leading ':').  Unambiguous prefixes of command names are
+    /// accepted, e.g. `:q` or `:se rust`.
+    pub fn parse(line: &str) -> Result<Command> {
+        let line = line.trim();
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (line, ""),
+        };
+
+        let candidates: Vec<&str> = COMMANDS
+            .iter()
+            .map(|(command, ..)| *command)
+            .filter(|command| command.starts_with(name))
+            .collect();
+        let name = match candidates.as_slice() {
+            _ if name.is_empty() => return Err(anyhow!("No command given, try :help")),
+            [command] => *command,
+            _ if candidates.contains(&name) => name,
+            [] => return Err(anyhow!("Unknown command: {name}")),
+            _ => return Err(anyhow!("Ambiguous command: {name}")),
+        };
+
+        let require_arg = |usage: &str| {
+            if arg.is_empty() {
+                Err(anyhow!("Usage: {usage}"))
+            } else {
+                Ok(arg.to_string())
+            }
+        };
+
+        match name {
+            "search" => Ok(Command::Search(require_arg(":search <query>")?)),
+            "append" => Ok(Command::Append(require_arg(":append <query>")?)),
+            "user" => Ok(Command::User(
+                require_arg(":user @<username>")?
+                    .trim_start_matches('@')
+                    .to_string(),
+            )),
+            "list" => Ok(Command::List(require_arg(":list <name>")?)),
+            "collection" => Ok(Command::Collection(require_arg(":collection <name>")?)),
+            "sort" => Ok(Command::Sort(arg.parse()?)),
+            "conversations" => Ok(Command::Conversations),
+            "authors" => Ok(Command::Authors),
+            "goto" => Ok(Command::Goto(
+                NaiveDate::parse_from_str(arg, "%Y-%m-%d")
+                    .map_err(|_| anyhow!("Usage: :goto <yyyy-mm-dd>"))?,
+            )),
+            "tab" => Ok(Command::Tab(
+                arg.parse().map_err(|_| anyhow!("Usage: {TAB_USAGE}"))?,
+            )),
+            "tabclose" => Ok(Command::TabClose),
+            "columns" => match arg {
+                "" => Ok(Command::Columns(None)),
+                _ => match arg.parse() {
+                    Ok(num_columns @ 2..=4) => Ok(Command::Columns(Some(num_columns))),
+                    _ => Err(anyhow!("Usage: :columns [2-4]")),
+                },
+            },
+            "export" => Ok(Command::Export(
+                Some(arg.to_string()).filter(|a| !a.is_empty()),
+            )),
+            "metrics" => Ok(Command::Metrics),
+            "analytics" => Ok(Command::Analytics),
+            "compare" => match arg.split_whitespace().collect::<Vec<&str>>()[..] {
+                [first, second] => Ok(Command::Compare(
+                    first.trim_start_matches('@').to_string(),
+                    second.trim_start_matches('@').to_string(),
+                )),
+                _ => Err(anyhow!("Usage: :compare @<username> @<username>")),
+            },
+            "followers" => Ok(Command::Followers),
+            "alerts" => Ok(Command::Alerts),
+            "budget" => Ok(Command::Budget),
+            "cache-clear" => Ok(Command::CacheClear),
+            "debug" => Ok(Command::Debug),
+            "quit" => Ok(Command::Quit),
+            _ => Ok(Command::Help),
+        }
+    }
+
+    pub fn help() -> String {
+        COMMANDS
+            .iter()
+            .map(|(_, usage, description)| format!("{usage:<20} {description}"))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+/// Completes the command name of [line]: to the full name if there's one candidate, otherwise to
+/// the longest common prefix of the candidates.
+pub fn complete(line: &str) -> Option<String> {
+    if line.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let candidates: Vec<&str> = COMMANDS
+        .iter()
+        .map(|(command, ..)| *command)
+        .filter(|command| command.starts_with(line))
+        .collect();
+
+    match candidates.as_slice() {
+        [] => None,
+        [command] => Some(format!("{command} ")),
+        [first, rest @ ..] => {
+            let common_length = rest.iter().fold(first.len(), |length, command| {
+                first
+                    .chars()
+                    .zip(command.chars())
+                    .take(length)
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            });
+            Some(first[..common_length].to_string())
+        }
+    }
+}
+
+/// Vim-style `:` command line with Tab completion and Up/Down history.
+pub struct CommandBar {
+    search_bar: SearchBar,
+    history: Vec<String>,
+    history_index: Option<usize>,
+}
+
+impl CommandBar {
+    pub fn new() -> Self {
+        Self {
+            search_bar: SearchBar::with_prompt(":"),
+            history: Vec::new(),
+            history_index: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.search_bar.clear();
+        self.history_index = None;
+    }
+
+    /// Takes the entered line, recording it in the history.
+    pub fn submit(&mut self) -> String {
+        let line = self.search_bar.get_text();
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        self.clear();
+        line
+    }
+
+    fn recall_history(&mut self, delta: isize) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let last = self.history.len() as isize - 1;
+        let index = match self.history_index {
+            None if delta < 0 => last,
+            None => return,
+            Some(index) => index as isize + delta,
+        };
+
+        if index > last {
+            self.history_index = None;
+            self.search_bar.set_text("");
+        } else {
+            let index = index.max(0) as usize;
+            self.history_index = Some(index);
+            let line = self.history[index].clone();
+            self.search_bar.set_text(&line);
+        }
+    }
+}
+
+impl Default for CommandBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Render for CommandBar {
+    fn should_render(&self) -> bool {
+        self.search_bar.should_render()
+    }
+
+    fn invalidate(&mut self) {
+        self.search_bar.invalidate();
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        self.search_bar.render(stdout, bounding_box)
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        self.search_bar.get_cursor()
+    }
+}
+
+impl Input for CommandBar {
+    fn handle_focus(&mut self) {
+        self.search_bar.handle_focus();
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Tab => {
+                if let Some(completed) = complete(&self.search_bar.get_text()) {
+                    self.search_bar.set_text(&completed);
+                }
+            }
+            KeyCode::Up => self.recall_history(-1),
+            KeyCode::Down => self.recall_history(1),
+            _ => return self.search_bar.handle_key_event(event),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Command::parse("search rust lang").unwrap(),
+            Command::Search("rust lang".to_string())
+        );
+        assert_eq!(
+            Command::parse("user @foo").unwrap(),
+            Command::User("foo".to_string())
+        );
+        assert_eq!(Command::parse("q").unwrap(), Command::Quit);
+        assert_eq!(Command::parse("b").unwrap(), Command::Budget);
+        assert_eq!(Command::parse("cache").unwrap(), Command::CacheClear);
+        assert!(Command::parse("c").is_err());
+        assert_eq!(Command::parse("export").unwrap(), Command::Export(None));
+        assert!(Command::parse("search").is_err());
+        assert!(Command::parse("frobnicate").is_err());
+        assert_eq!(Command::parse("al").unwrap(), Command::Alerts);
+        assert_eq!(
+            Command::parse("ap rust lang").unwrap(),
+            Command::Append("rust lang".to_string())
+        );
+        assert!(Command::parse("a").is_err());
+        assert_eq!(
+            Command::parse("tab").unwrap(),
+            Command::Tab(FeedSource::Home)
+        );
+        assert_eq!(
+            Command::parse("tab search rust lang").unwrap(),
+            Command::Tab(FeedSource::Search("rust lang".to_string()))
+        );
+        assert_eq!(
+            Command::parse("tab activity").unwrap(),
+            Command::Tab(FeedSource::Activity)
+        );
+        assert_eq!(Command::parse("tabc").unwrap(), Command::TabClose);
+        assert_eq!(Command::parse("colu").unwrap(), Command::Columns(None));
+        assert_eq!(
+            Command::parse("columns 3").unwrap(),
+            Command::Columns(Some(3))
+        );
+        assert!(Command::parse("columns 5").is_err());
+        assert!(Command::parse("col").is_err());
+        assert_eq!(
+            Command::parse("so likes").unwrap(),
+            Command::Sort(FeedSort::MostLiked)
+        );
+        assert!(Command::parse("sort").is_err());
+        assert_eq!(
+            Command::parse("goto 2023-01-15").unwrap(),
+            Command::Goto(NaiveDate::from_ymd_opt(2023, 1, 15).unwrap())
+        );
+        assert!(Command::parse("goto yesterday").is_err());
+        assert_eq!(Command::parse("conv").unwrap(), Command::Conversations);
+        assert_eq!(Command::parse("au").unwrap(), Command::Authors);
+        assert_eq!(
+            Command::parse("compare @alice bob").unwrap(),
+            Command::Compare("alice".to_string(), "bob".to_string())
+        );
+        assert!(Command::parse("compare @alice").is_err());
+        assert!(Command::parse("tab search").is_err());
+        assert_eq!(
+            Command::parse("tab collection rust papers").unwrap(),
+            Command::Tab(FeedSource::Collection("rust papers".to_string()))
+        );
+        assert_eq!(
+            Command::parse("tab catchup @alice").unwrap(),
+            Command::Tab(FeedSource::CatchUp("alice".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(complete("se"), Some("search ".to_string()));
+        assert_eq!(complete("e"), Some("export ".to_string()));
+        assert_eq!(complete("x"), None);
+        assert_eq!(complete("f"), Some("followers ".to_string()));
+        assert_eq!(complete("search ru"), None);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::compose;
+use crate::cross_post;
+use crate::drafts::Draft;
+use crate::text_measure;
+use crate::ui::InternalEvent;
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_area::TextArea;
+use crate::ui_framework::{Component, Input, Render};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{self, Color};
+use crossterm::{cursor, queue};
+use std::io::Write;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// In-app compose box.  Every edit is written through to the open [Draft], so nothing typed here
+/// is lost if the app goes down.
+pub struct ComposePane {
+    events: UnboundedSender<InternalEvent>,
+    draft: Option<Draft>,
+    text_area: Component<TextArea>,
+    /// Networks this post can also go to, and whether it will
+    cross_post: Vec<(cross_post::Target, bool)>,
+    should_render: bool,
+}
+
+impl ComposePane {
+    pub fn new(events: &UnboundedSender<InternalEvent>) -> Self {
+        let text_area = TextArea::new()
+            .with_char_limit(
+                text_measure::MAX_WEIGHTED_LENGTH,
+                text_measure::weighted_length,
+            )
+            .with_segment_delimiter(compose::THREAD_DELIMITER);
+
+        Self {
+            events: events.clone(),
+            draft: None,
+            text_area: Component::new(text_area),
+            cross_post: Vec::new(),
+            should_render: true,
+        }
+    }
+
+    /// Opens [draft] for editing, cross-posting to every one of [cross_post_targets] unless toggled
+    /// off.  Replies aren't cross-posted by default, since what they reply to is only on Twitter.
+    pub fn open_draft(&mut self, draft: Draft, cross_post_targets: Vec<cross_post::Target>) {
+        self.text_area.component.set_text(&draft.text);
+        let is_reply = draft.in_reply_to_tweet_id.is_some();
+        self.cross_post = cross_post_targets
+            .into_iter()
+            .map(|target| (target, !is_reply))
+            .collect();
+        self.draft = Some(draft);
+        self.should_render = true;
+    }
+
+    /// Closes the compose box, returning the draft if it has any content.  Empty drafts are
+    /// deleted rather than left lying around.
+    pub fn close_draft(&mut self) -> Option<Draft> {
+        let draft = self.draft.take()?;
+        self.text_area.component.clear();
+
+        if compose::split_thread(&draft.text).is_empty() {
+            if let Err(err) = draft.delete() {
+                self.events.send(InternalEvent::LogError(err)).unwrap();
+            }
+            None
+        } else {
+            Some(draft)
+        }
+    }
+
+    /// The networks to cross-post to, as toggled.
+    pub fn get_cross_post_targets(&self) -> Vec<cross_post::Target> {
+        self.cross_post
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(target, _)| *target)
+            .collect()
+    }
+
+    fn toggle_cross_post(&mut self, target: cross_post::Target) -> bool {
+        match self.cross_post.iter_mut().find(|(t, _)| *t == target) {
+            Some((_, enabled)) => {
+                *enabled = !*enabled;
+                self.should_render = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn autosave(&mut self) {
+        if let Some(draft) = &mut self.draft {
+            let text = self.text_area.component.get_text();
+            if text != draft.text {
+                if let Err(err) = draft.save(&text) {
+                    self.events.send(InternalEvent::LogError(err)).unwrap();
+                }
+            }
+        }
+    }
+}
+
+impl Render for ComposePane {
+    fn should_render(&self) -> bool {
+        self.should_render || self.text_area.component.should_render()
+    }
+
+    fn invalidate(&mut self) {
+        self.should_render = true;
+        self.text_area.component.invalidate();
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left, top, width, ..
+        } = bounding_box;
+
+        if self.should_render {
+            let title = match self
+                .draft
+                .as_ref()
+                .and_then(|d| d.in_reply_to_tweet_id.as_ref())
+            {
+                Some(tweet_id) => format!("Reply to {tweet_id}"),
+                None => "Compose".to_string(),
+            };
+            let cross_post: String = self
+                .cross_post
+                .iter()
+                .map(|(target, enabled)| {
+                    let key = match target {
+                        cross_post::Target::Mastodon => "^T",
+                        cross_post::Target::Bluesky => "^B",
+                    };
+                    let check = if *enabled { "x" } else { " " };
+                    format!(" [{check}] {} {key}", target.name())
+                })
+                .collect();
+            let hint = format!(
+                "{title}{cross_post}  (^O post, ^S save, Esc close, '{}' splits tweets)",
+                compose::THREAD_DELIMITER
+            );
+            let hint: String = hint.chars().take(width as usize).collect();
+
+            queue!(stdout, cursor::MoveTo(left, top))?;
+            queue!(stdout, style::Print(" ".repeat(width as usize)))?;
+            queue!(stdout, cursor::MoveTo(left, top))?;
+            queue!(stdout, style::SetForegroundColor(Color::DarkGrey))?;
+            queue!(stdout, style::Print(hint))?;
+            queue!(stdout, style::ResetColor)?;
+            self.text_area.component.invalidate();
+            self.should_render = false;
+        }
+
+        self.text_area.bounding_box = BoundingBox {
+            top: top + 1,
+            height: bounding_box.height.saturating_sub(1),
+            ..bounding_box
+        };
+        self.text_area.render_if_necessary(stdout)?;
+
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        let (x, y) = self.text_area.component.get_cursor();
+        (x, y + 1)
+    }
+}
+
+impl Input for ComposePane {
+    fn handle_focus(&mut self) {
+        self.invalidate();
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        if event.modifiers.contains(KeyModifiers::CONTROL) {
+            match event.code {
+                KeyCode::Char('t') => return self.toggle_cross_post(cross_post::Target::Mastodon),
+                KeyCode::Char('b') => return self.toggle_cross_post(cross_post::Target::Bluesky),
+                _ => (),
+            }
+        }
+        let handled = self.text_area.component.handle_key_event(event);
+        if handled {
+            self.autosave();
+        }
+        handled
+    }
+}
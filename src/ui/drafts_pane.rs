@@ -0,0 +1,162 @@
+use crate::drafts::{self, Draft};
+use crate::ui::InternalEvent;
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::scroll_buffer::{ScrollBuffer, TextSegment};
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use crossterm::style::{Color, Colors};
+use crossterm::{cursor, queue, style};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+pub struct DraftsPane {
+    events: UnboundedSender<InternalEvent>,
+    drafts: Vec<Draft>,
+    scroll_buffer: ScrollBuffer,
+    should_update_scroll_buffer: bool,
+    /// Set from async tasks (e.g. a confirmed delete) to have the drafts reloaded on next render
+    should_reload: Arc<AtomicBool>,
+    display_width: usize,
+}
+
+impl DraftsPane {
+    pub fn new(events: &UnboundedSender<InternalEvent>) -> Self {
+        Self {
+            events: events.clone(),
+            drafts: Vec::new(),
+            scroll_buffer: ScrollBuffer::new(),
+            should_update_scroll_buffer: true,
+            should_reload: Arc::new(AtomicBool::new(false)),
+            display_width: 0,
+        }
+    }
+
+    pub fn reload(&mut self) {
+        match drafts::list_drafts() {
+            Ok(drafts) => self.drafts = drafts,
+            Err(err) => self.events.send(InternalEvent::LogError(err)).unwrap(),
+        }
+        self.should_update_scroll_buffer = true;
+    }
+
+    pub fn get_should_reload(&self) -> Arc<AtomicBool> {
+        self.should_reload.clone()
+    }
+
+    pub fn get_selected_draft(&self) -> Option<&Draft> {
+        self.drafts.get(self.scroll_buffer.get_cursor_line())
+    }
+
+    fn update_scroll_buffer(&mut self) {
+        let header = format!(
+            "Drafts ({}): Enter resume  e edit  p post  x delete",
+            self.drafts.len()
+        );
+        self.scroll_buffer.set_header(vec![vec![TextSegment::color(
+            text_width::truncate(&header, self.display_width),
+            Colors::new(Color::DarkGrey, Color::Reset),
+        )]]);
+        self.scroll_buffer.clear();
+
+        if self.drafts.is_empty() {
+            self.scroll_buffer
+                .push(vec![TextSegment::plain("<no drafts>")]);
+        }
+
+        for draft in &self.drafts {
+            let draft_time = draft.modified.format("%m-%d %H:%M:%S");
+            let draft_time = format!("{draft_time}  >  ");
+            let mut line = vec![TextSegment::color(
+                &draft_time,
+                Colors::new(Color::DarkGrey, Color::Reset),
+            )];
+            let mut used_length = text_width::width(&draft_time);
+
+            if let Some(tweet_id) = &draft.in_reply_to_tweet_id {
+                let reply_marker = format!("↪ {tweet_id} ");
+                used_length += text_width::width(&reply_marker);
+                line.push(TextSegment::color(
+                    &reply_marker,
+                    Colors::new(Color::DarkCyan, Color::Reset),
+                ));
+            }
+
+            let remaining_length = self.display_width.saturating_sub(used_length);
+            let summary = draft.summary();
+            let lines = text_width::wrap(&summary, remaining_length);
+            if lines.len() == 1 {
+                line.push(TextSegment::plain(lines[0]));
+            } else if lines.len() > 1 {
+                let lines = text_width::wrap(&summary, remaining_length.saturating_sub(1));
+                line.push(TextSegment::plain(&format!("{}…", lines[0])));
+            }
+
+            self.scroll_buffer.push(line);
+        }
+
+        let y = self.scroll_buffer.get_cursor_line();
+        self.scroll_buffer.move_cursor_to(0, y);
+        self.should_update_scroll_buffer = false;
+    }
+}
+
+impl Render for DraftsPane {
+    fn should_render(&self) -> bool {
+        self.should_update_scroll_buffer
+            || self.should_reload.load(Ordering::SeqCst)
+            || self.scroll_buffer.should_render()
+    }
+
+    fn invalidate(&mut self) {
+        self.scroll_buffer.invalidate();
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left,
+            top,
+            width,
+            height,
+        } = bounding_box;
+
+        if self.should_reload.swap(false, Ordering::SeqCst) {
+            self.reload();
+        }
+        if self.should_update_scroll_buffer || self.display_width != width as usize {
+            self.display_width = width as usize;
+            self.update_scroll_buffer();
+        }
+
+        if self.scroll_buffer.should_render() {
+            let str_clear = " ".repeat(width as usize);
+            for y_offset in 0..height {
+                queue!(stdout, cursor::MoveTo(left, top + y_offset))?;
+                queue!(stdout, style::Print(&str_clear))?;
+            }
+
+            self.scroll_buffer.render(stdout, bounding_box)?;
+        }
+
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        self.scroll_buffer.get_cursor()
+    }
+}
+
+impl Input for DraftsPane {
+    fn handle_focus(&mut self) {
+        self.reload();
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        self.scroll_buffer.handle_key_event(event)
+    }
+}
@@ -0,0 +1,160 @@
+//! A tweet as a snippet for pasting into notes and blog posts, with links written out in full.
+
+use crate::twitter_client::api;
+use crate::ui::tweet_text::{self, SpanKind};
+use crate::ui_framework::scroll_buffer::Payload;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbedFormat {
+    /// A blockquote, attributed with the author and a dated permalink
+    Markdown,
+    /// Like the `blockquote.twitter-tweet` that Twitter's oEmbed endpoint returns, minus the script
+    Html,
+}
+
+impl EmbedFormat {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            EmbedFormat::Markdown => "Markdown",
+            EmbedFormat::Html => "HTML",
+        }
+    }
+}
+
+pub fn permalink(tweet: &api::Tweet) -> String {
+    match &tweet.author_username {
+        Some(username) => format!("https://twitter.com/{username}/status/{}", tweet.id),
+        None => format!("https://twitter.com/t/status/{}", tweet.id),
+    }
+}
+
+pub fn embed(tweet: &api::Tweet, format: EmbedFormat) -> String {
+    match format {
+        EmbedFormat::Markdown => markdown(tweet),
+        EmbedFormat::Html => html(tweet),
+    }
+}
+
+fn markdown(tweet: &api::Tweet) -> String {
+    let mut text = String::new();
+    for span in tweet_text::spans(tweet) {
+        match (span.kind, &span.payload) {
+            (SpanKind::Url, Some(Payload::Url(url))) => {
+                text.push_str(&format!("[{}]({url})", span.text))
+            }
+            (SpanKind::Mention, Some(Payload::User(username))) => {
+                text.push_str(&format!("[{}](https://twitter.com/{username})", span.text))
+            }
+            _ => text.push_str(&span.text),
+        }
+    }
+    let author = tweet.author("[unknown]");
+    let date = tweet.local_created_at().format("%Y-%m-%d");
+
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|line| format!("> {line}").trim_end().to_string())
+        .collect();
+    lines.push(">".to_string());
+    lines.push(format!(
+        "> — {} (@{}) [{date}]({})",
+        author.name,
+        author.username,
+        permalink(tweet)
+    ));
+    lines.join("\n")
+}
+
+fn html(tweet: &api::Tweet) -> String {
+    let mut text = String::new();
+    for span in tweet_text::spans(tweet) {
+        let span_text = escape_html(&span.text).replace('\n', "<br>");
+        match (span.kind, &span.payload) {
+            (SpanKind::Url, Some(Payload::Url(url))) => {
+                text.push_str(&format!("<a href=\"{}\">{span_text}</a>", escape_html(url)))
+            }
+            (SpanKind::Mention, Some(Payload::User(username))) => text.push_str(&format!(
+                "<a href=\"https://twitter.com/{}\">{span_text}</a>",
+                escape_html(username)
+            )),
+            _ => text.push_str(&span_text),
+        }
+    }
+    let author = tweet.author("[unknown]");
+    let lang = tweet.lang.as_deref().unwrap_or("und");
+    let date = tweet.local_created_at().format("%B %-d, %Y");
+
+    format!(
+        "<blockquote class=\"twitter-tweet\"><p lang=\"{}\" dir=\"ltr\">{text}</p>&mdash; {} (@{}) <a href=\"{}\">{date}</a></blockquote>",
+        escape_html(lang),
+        escape_html(&author.name),
+        escape_html(&author.username),
+        escape_html(&permalink(tweet))
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tweet() -> api::Tweet {
+        let mut tweet: api::Tweet = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "text": "hey @ferris, see https://t.co/abc\n<3 & more",
+            "created_at": "2023-02-01T12:00:00Z",
+            "author_id": "2",
+            "lang": "en",
+            "entities": {
+                "urls": [{
+                    "start": 17,
+                    "end": 33,
+                    "url": "https://t.co/abc",
+                    "expanded_url": "https://blog.example.com/post",
+                    "display_url": "blog.example.com/post"
+                }],
+                "mentions": [{"start": 4, "end": 11, "username": "ferris", "id": "3"}]
+            }
+        }))
+        .unwrap();
+        tweet.author_username = Some("alice".to_string());
+        tweet.author_name = Some("Alice".to_string());
+        tweet
+    }
+
+    #[test]
+    fn test_markdown() {
+        let tweet = tweet();
+        let date = tweet.local_created_at().format("%Y-%m-%d");
+        assert_eq!(
+            embed(&tweet, EmbedFormat::Markdown),
+            format!(
+                "> hey [@ferris](https://twitter.com/ferris), see [blog.example.com/post](https://blog.example.com/post)\n\
+                 > <3 & more\n\
+                 >\n\
+                 > — Alice (@alice) [{date}](https://twitter.com/alice/status/1)"
+            )
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let tweet = tweet();
+        let date = tweet.local_created_at().format("%B %-d, %Y");
+        assert_eq!(
+            embed(&tweet, EmbedFormat::Html),
+            format!(
+                "<blockquote class=\"twitter-tweet\"><p lang=\"en\" dir=\"ltr\">hey \
+                 <a href=\"https://twitter.com/ferris\">@ferris</a>, see \
+                 <a href=\"https://blog.example.com/post\">blog.example.com/post</a><br>&lt;3 &amp; more</p>\
+                 &mdash; Alice (@alice) <a href=\"https://twitter.com/alice/status/1\">{date}</a></blockquote>"
+            )
+        );
+    }
+}
@@ -1,93 +1,483 @@
-use crate::store::Store;
-use crate::twitter_client::api;
+use crate::alerts;
+use crate::collections::{self, Collection};
+use crate::compose;
+use crate::cross_post;
+use crate::drafts::Draft;
+use crate::feed_source::FeedSource;
+use crate::hooks;
+use crate::keybindings::Action;
+use crate::plugins::{self, PluginCommand, PluginEvent};
+use crate::spam::{self, Signal};
+use crate::store::{self, AuthorGroup, Conversation, FeedSort, LoadState, Store};
+use crate::twitter_client::{api, TwitterApi};
+use crate::ui::action::KeyMap;
+use crate::ui::compose_pane::ComposePane;
+use crate::ui::drafts_pane::DraftsPane;
+use crate::ui::embed::{self, EmbedFormat};
 use crate::ui::search_bar::SearchBar;
-use crate::ui::tweet_pane::TweetPane;
+use crate::ui::session::FeedPosition;
+use crate::ui::task_manager::TaskKey;
+use crate::ui::toasts::NotifyLevel;
+use crate::ui::tweet_pane_stack::TweetPaneStack;
+use crate::ui::tweet_text;
 use crate::ui::InternalEvent;
-use crate::ui_framework::scroll_buffer::{ScrollBuffer, TextSegment};
-use crate::ui_framework::{bounding_box::BoundingBox, Component, Input, Render};
+use crate::ui_framework::focus::{FocusContainer, FocusRing};
+use crate::ui_framework::form::Form;
+use crate::ui_framework::picker::Picker;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::scroll_buffer::{self, ScrollBuffer, TextSegment};
+use crate::ui_framework::text_width;
+use crate::ui_framework::{bounding_box::BoundingBox, Component, Input, Render, TICK_INTERVAL};
+use crate::user_config;
 use anyhow::{anyhow, Result};
-use crossterm::event::{KeyCode, KeyEvent};
-use crossterm::style::{Color, Colors};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Attribute, Attributes, Color, Colors};
 use crossterm::{cursor, queue, style};
 use regex::Regex;
-use std::io::{Stdout, Write};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::io::Write;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::{fs, process};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
 use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
+
+const MIN_QUOTE_PREVIEW_WIDTH: usize = 12;
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// The most tabs [Action::OpenAuthors] opens at once, i.e. as many as there are tab keys
+const MAX_AUTHOR_TABS: usize = 9;
+
+/// Narrower than this, the feed and the tweet pane take turns at the whole width
+const MIN_SPLIT_WIDTH: u16 = 80;
+
+/// How long the selection has to stay put before plugins hear of it, so that scrolling through the
+/// feed doesn't run them on every tweet passed over
+const TWEET_SELECTED_DEBOUNCE: Duration = Duration::from_millis(300);
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[repr(u8)]
-enum Focus {
+pub enum Focus {
     FeedPane,
     TweetPaneStack,
     SearchBar,
+    FilterBar,
+    DraftsPane,
+    ComposePane,
+    TweetFinder,
+    UrlPicker,
+}
+
+/// How far a [FeedPane::do_goto_date] has got, shared with the task paging back through the feed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+enum Goto {
+    #[default]
+    Idle,
+    /// Going back to this date; the time of the oldest tweet loaded so far
+    Paging(NaiveDate, Option<DateTime<Local>>),
+    /// Arrived at this tweet, to be selected once it's drawn
+    Found(String),
+}
+
+/// How the feed pane's width is shared, see [FeedPane::layout].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PaneLayout {
+    /// The feed on the left, the tweet pane (or whatever's in its place) on the right
+    Split,
+    FeedOnly,
+    TweetPaneOnly,
 }
 
 pub struct FeedPane {
     events: UnboundedSender<InternalEvent>,
     store: Arc<Store>,
+    key_map: Arc<KeyMap>,
     scroll_buffer: ScrollBuffer,
     should_update_scroll_buffer: Arc<AtomicBool>,
     should_render: bool,
     display_width: usize,
-    focus: Focus,
+    /// Whether feed lines are marked with the kind of activity and whether it's unread
+    is_activity: bool,
+    /// Whether the feed is the home timeline, the one feed [Self::do_load_page_of_tweets] pages
+    is_home_timeline: bool,
+    /// The query, if the feed is a search, which is paged and refreshed instead of the home timeline
+    search_query: Option<String>,
+    /// For timing the loading spinner
+    created_at: Instant,
+    /// Tab cycles between the feed and the tweet pane stack; the rest are modal
+    focus: FocusRing<Focus>,
     tweet_selected_id: String,
-    tweet_pane: Component<TweetPane>,
+    /// The feed as shown, i.e. the tweets matching the filter, if any; one per line
+    visible_feed: Vec<String>,
+    /// Whether it's one of several feeds side by side, in which case the feed takes the whole width
+    /// and the tweet pane is only shown while it has focus
+    is_column: bool,
+    /// As last rendered, see [FeedPane::layout]
+    last_layout: Option<PaneLayout>,
+    /// Whether there's a line per conversation rather than per tweet, see [Store::conversations]
+    group_by_conversation: bool,
+    /// Whether there's a line per author rather than per tweet, see [Store::authors]
+    group_by_author: bool,
+    /// Lowercased usernames whose tweets are listed under their line, when grouped by author
+    expanded_authors: HashSet<String>,
+    /// Runs of likely spam hidden behind a row, by the id of the first (which is the row's entry in
+    /// [Self::visible_feed]), see [crate::spam]
+    hidden_runs: HashMap<String, Vec<String>>,
+    /// Likely spam that's been shown anyway
+    shown_spam_ids: HashSet<String>,
+    /// Tweets marked for a bulk action, see [Self::take_marked_tweet_ids]
+    marked_ids: HashSet<String>,
+    /// Where [Action::MarkRange] starts from: the tweet last marked or unmarked
+    mark_anchor: Option<String>,
+    /// A saved position to restore once the feed has loaded
+    pending_position: Option<FeedPosition>,
+    goto: Arc<Mutex<Goto>>,
+    tweet_pane_stack: Component<TweetPaneStack>,
     search_bar: Component<SearchBar>,
+    /// Narrows the feed to tweets containing its text, see [tweet_matches]
+    filter_bar: Component<SearchBar>,
+    drafts_pane: Component<DraftsPane>,
+    compose_pane: Component<ComposePane>,
+    tweet_finder: Component<Picker<String>>,
+    url_picker: Component<Picker<String>>,
 }
 
 impl FeedPane {
-    pub fn new(events: &UnboundedSender<InternalEvent>, store: &Arc<Store>) -> Self {
+    pub fn new(
+        events: &UnboundedSender<InternalEvent>,
+        store: &Arc<Store>,
+        key_map: &Arc<KeyMap>,
+    ) -> Self {
         let tweet_selected_id = String::from("0");
-        let tweet_pane = Component::new(TweetPane::new(events, store, &tweet_selected_id));
+        let tweet_pane_stack =
+            Component::new(TweetPaneStack::new(events, store, &tweet_selected_id));
         let search_bar = Component::new(SearchBar::new());
+        let filter_bar = Component::new(SearchBar::with_prompt("filter: "));
+        let drafts_pane = Component::new(DraftsPane::new(events));
+        let compose_pane = Component::new(ComposePane::new(events));
+        let tweet_finder = Component::new(Picker::new("Find tweet (Esc to cancel)", vec![]));
+        let url_picker = Component::new(Picker::new("Open which URL? (Esc to cancel)", vec![]));
 
         Self {
             events: events.clone(),
             store: store.clone(),
+            key_map: key_map.clone(),
             scroll_buffer: ScrollBuffer::new(),
             should_update_scroll_buffer: Arc::new(AtomicBool::new(true)),
             should_render: true,
             display_width: 0,
-            focus: Focus::FeedPane,
+            is_activity: false,
+            is_home_timeline: false,
+            search_query: None,
+            created_at: Instant::now(),
+            focus: FocusRing::new(vec![Focus::FeedPane, Focus::TweetPaneStack]),
             tweet_selected_id,
-            tweet_pane,
+            visible_feed: Vec::new(),
+            is_column: false,
+            last_layout: None,
+            group_by_conversation: false,
+            group_by_author: false,
+            expanded_authors: HashSet::new(),
+            hidden_runs: HashMap::new(),
+            shown_spam_ids: HashSet::new(),
+            marked_ids: HashSet::new(),
+            mark_anchor: None,
+            pending_position: None,
+            goto: Arc::new(Mutex::new(Goto::Idle)),
+            tweet_pane_stack,
             search_bar,
+            filter_bar,
+            drafts_pane,
+            compose_pane,
+            tweet_finder,
+            url_picker,
         }
     }
 
     pub fn get_selected_tweet_id(&self) -> Option<String> {
         let line_no = self.scroll_buffer.get_cursor_line();
-        {
-            let feed = self.store.tweets_feed.lock().unwrap();
-            if let Some(tweet_id) = feed.get(line_no as usize) {
-                return Some(tweet_id.clone());
+        self.visible_feed.get(line_no).cloned()
+    }
+
+    /// The selected line, counting from 1, and how many lines the feed has; None while it's empty.
+    pub fn get_line_position(&self) -> Option<(usize, usize)> {
+        let line_no = self.scroll_buffer.get_cursor_line();
+        (line_no < self.visible_feed.len()).then_some((line_no + 1, self.visible_feed.len()))
+    }
+
+    pub fn get_position(&self) -> FeedPosition {
+        // NB: a tab that hasn't been shown since it was restored hasn't moved
+        if let Some(position) = &self.pending_position {
+            return position.clone();
+        }
+        FeedPosition {
+            selected_tweet_id: self.get_selected_tweet_id(),
+            cursor_line: self.scroll_buffer.get_cursor_line(),
+            display_offset: self.scroll_buffer.get_display_offset(),
+            tweet_pane_ids: self.tweet_pane_stack.component.tweet_ids(),
+        }
+    }
+
+    /// Moves to [position] once the feed has loaded.
+    pub fn restore_position(&mut self, position: FeedPosition) {
+        self.pending_position = Some(position);
+        self.redraw_feed();
+    }
+
+    fn apply_pending_position(&mut self) {
+        let located = self
+            .pending_position
+            .as_ref()
+            .and_then(|position| position.locate(&self.visible_feed));
+        let Some((line, display_offset)) = located else {
+            return;
+        };
+        let position = self.pending_position.take().unwrap();
+        self.scroll_buffer.scroll_to(display_offset, line);
+        if let Some(tweet_id) = self.get_selected_tweet_id() {
+            self.set_selected_tweet_id(&tweet_id);
+        }
+        // NB: the bottom pane is the selected tweet; the rest were drilled into from it
+        if position.tweet_pane_ids.first() == Some(&self.tweet_selected_id) {
+            for tweet_id in position.tweet_pane_ids.iter().skip(1) {
+                self.tweet_pane_stack.component.push(tweet_id);
+            }
+        }
+    }
+
+    /// Rebuilds the feed lines on the next render, e.g. after a display setting changed.
+    pub fn redraw_feed(&self) {
+        self.should_update_scroll_buffer
+            .store(true, Ordering::SeqCst);
+    }
+
+    /// The row after the feed showing a spinner while it's loading, or why the load failed.
+    fn status_line(&self) -> Option<Vec<TextSegment>> {
+        match &*self.store.load_state.lock().unwrap() {
+            LoadState::Idle => None,
+            LoadState::Loading => {
+                let frame =
+                    (self.created_at.elapsed().as_millis() / TICK_INTERVAL.as_millis()) as usize;
+                let spinner = SPINNER_FRAMES[frame % SPINNER_FRAMES.len()];
+                let str_loading = match &*self.goto.lock().unwrap() {
+                    Goto::Paging(date, Some(oldest)) => format!(
+                        "{spinner} going back to {date}, at {} (X to cancel)",
+                        oldest.format("%Y-%m-%d")
+                    ),
+                    Goto::Paging(date, None) => {
+                        format!("{spinner} going back to {date} (X to cancel)")
+                    }
+                    _ => format!("{spinner} loading..."),
+                };
+                Some(vec![TextSegment::color(
+                    &str_loading,
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                )])
+            }
+            LoadState::Failed(error) => {
+                let error = error.lines().next().unwrap_or("");
+                let str_error = format!("! load failed: {error} (R to retry)");
+                Some(vec![TextSegment::color(
+                    text_width::truncate(&str_error, self.display_width),
+                    Colors::new(Color::Red, Color::Reset),
+                )])
             }
         }
-        None
+    }
+
+    pub fn set_activity(&mut self, is_activity: bool) {
+        if self.is_activity != is_activity {
+            self.is_activity = is_activity;
+            self.redraw_feed();
+        }
+    }
+
+    pub fn set_column(&mut self, is_column: bool) {
+        if self.is_column != is_column {
+            self.is_column = is_column;
+            self.invalidate();
+        }
+    }
+
+    pub fn toggle_group_by_conversation(&mut self) {
+        self.group_by_conversation = !self.group_by_conversation;
+        self.group_by_author = false;
+        self.redraw_feed();
+    }
+
+    pub fn toggle_group_by_author(&mut self) {
+        self.group_by_author = !self.group_by_author;
+        self.group_by_conversation = false;
+        self.expanded_authors.clear();
+        self.redraw_feed();
+    }
+
+    /// Lists the selected tweet's author's tweets under their line, or folds them back up.
+    fn do_toggle_selected_author_expanded(&mut self) {
+        let tweets = self.store.tweets.lock().unwrap();
+        let Some(username) = tweets
+            .get(&self.tweet_selected_id)
+            .and_then(|tweet| tweet.author_username.as_ref())
+        else {
+            return;
+        };
+        let username = username.to_lowercase();
+        drop(tweets);
+        if !self.expanded_authors.remove(&username) {
+            self.expanded_authors.insert(username);
+        }
+        self.redraw_feed();
+    }
+
+    pub fn set_home_timeline(&mut self, is_home_timeline: bool) {
+        self.is_home_timeline = is_home_timeline;
+    }
+
+    pub fn set_search_query(&mut self, search_query: Option<&str>) {
+        self.search_query = search_query.map(str::to_string);
+    }
+
+    /// The filter, lowercased, if there is one.
+    fn get_filter(&self) -> Option<String> {
+        let filter = self.filter_bar.component.get_text();
+        (!filter.trim().is_empty()).then(|| filter.trim().to_lowercase())
     }
 
     fn update_scroll_buffer(&mut self) {
         self.scroll_buffer.clear();
+        self.visible_feed.clear();
+        self.hidden_runs.clear();
+        let filter = self.get_filter();
+        let feed_sort = *self.store.feed_sort.lock().unwrap();
+        let feed = self.store.sorted_feed();
+        let pinned_tweet_id = self.store.pinned_tweet_id.lock().unwrap().clone();
+        let conversations = self
+            .group_by_conversation
+            .then(|| self.store.conversations());
+        let authors = self.group_by_author.then(|| self.store.authors());
 
         let tweets = self.store.tweets.lock().unwrap();
-        let tweets_reverse_chronological = self.store.tweets_feed.lock().unwrap();
         let user_config = self.store.user_config.lock().unwrap();
+        let revealed_ids = self.store.revealed_ids.lock().unwrap();
+        let is_collapsed = |tweet: &api::Tweet| {
+            user_config.filters.collapses(tweet) && !revealed_ids.contains(&tweet.id)
+        };
 
-        let re_newlines = Regex::new(r"[\r\n]+").unwrap();
         let str_unknown = String::from("[unknown]");
+        let theme = &user_config.theme;
+
+        // NB: grouped by conversation or author, there's a line per group instead
+        let ungrouped_feed = match (&conversations, &authors) {
+            (None, None) => &feed[..],
+            _ => &[][..],
+        };
+        let spam = spam::find_spam(
+            &user_config,
+            &self.store.twitter_user.id,
+            ungrouped_feed
+                .iter()
+                .filter_map(|tweet_id| tweets.get(tweet_id)),
+            Utc::now(),
+        );
+        let mut lines: Vec<FeedLine> = Vec::new();
+        for tweet_id in ungrouped_feed.iter() {
+            let tweet = &tweets.get(tweet_id).unwrap();
+            if let Some(filter) = &filter {
+                // NB: a retweet matches on the original tweet too, which is what's shown
+                let retweeted = tweet.retweeted_id().and_then(|id| tweets.get(id));
+                if !tweet_matches(tweet, filter)
+                    && !retweeted.is_some_and(|t| tweet_matches(t, filter))
+                {
+                    continue;
+                }
+            }
+            let is_hidden = spam.contains_key(tweet_id)
+                && !self.shown_spam_ids.contains(tweet_id)
+                && pinned_tweet_id.as_ref() != Some(tweet_id);
+            match lines.last_mut() {
+                Some(FeedLine::Hidden(run)) if is_hidden => run.push(tweet_id.clone()),
+                _ if is_hidden => lines.push(FeedLine::Hidden(vec![tweet_id.clone()])),
+                _ => lines.push(FeedLine::Tweet(tweet_id)),
+            }
+        }
 
-        for tweet_id in tweets_reverse_chronological.iter() {
+        for line in lines {
+            let tweet_id = match line {
+                FeedLine::Tweet(tweet_id) => tweet_id,
+                FeedLine::Hidden(run) => {
+                    let mut signals: Vec<Signal> = run
+                        .iter()
+                        .flat_map(|tweet_id| spam[tweet_id].clone())
+                        .collect();
+                    signals.sort();
+                    signals.dedup();
+                    self.visible_feed.push(run[0].clone());
+                    self.scroll_buffer
+                        .push(hidden_run_line(&run, &signals, &tweets, theme));
+                    self.hidden_runs.insert(run[0].clone(), run);
+                    continue;
+                }
+            };
             let tweet = &tweets.get(tweet_id).unwrap();
+            self.visible_feed.push(tweet_id.clone());
             let mut segments: Vec<TextSegment> = Vec::new();
 
-            let tweet_time = tweet.created_at.format("%m-%d %H:%M:%S");
+            let tweet_time = tweet.local_created_at().format("%m-%d %H:%M:%S");
             let tweet_time = format!("{tweet_time}  >  ");
             segments.push(TextSegment::color(
                 &tweet_time,
-                Colors::new(Color::DarkGrey, Color::Reset),
+                Colors::new(theme.timestamp.0.into(), Color::Reset),
             ));
+            if self.marked_ids.contains(tweet_id) {
+                segments.push(TextSegment::new(
+                    "● ",
+                    Colors::new(Color::Yellow, Color::Reset),
+                    Attributes::from(Attribute::Bold),
+                ));
+            }
+            if pinned_tweet_id.as_ref() == Some(tweet_id) {
+                segments.push(TextSegment::color(
+                    "📌 ",
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                ));
+            }
+
+            if self.is_activity {
+                let activity_kind =
+                    store::activity_kind(&tweets, &self.store.twitter_user.id, tweet_id);
+                let activity = format!("{} ", activity_kind.icon());
+                // NB: [user_config] is already locked
+                let is_unread = match &user_config.activity_seen_id {
+                    Some(seen_id) => store::is_newer_tweet_id(tweet_id, seen_id),
+                    None => true,
+                };
+                segments.push(if is_unread {
+                    TextSegment::new(
+                        &activity,
+                        Colors::new(Color::Yellow, Color::Reset),
+                        Attributes::from(Attribute::Bold),
+                    )
+                } else {
+                    TextSegment::color(&activity, Colors::new(Color::DarkGrey, Color::Reset))
+                });
+            }
+
+            // NB: a retweet's own text is a truncated "RT @author: ..."; show the original instead
+            let retweeted = tweet.retweeted_id().and_then(|id| tweets.get(id));
+            if retweeted.is_some() {
+                let retweeter = tweet.author_username.as_ref().unwrap_or(&str_unknown);
+                segments.push(TextSegment::color(
+                    &format!("🔁 @{retweeter} "),
+                    Colors::new(theme.retweeter.0.into(), Color::Reset),
+                ));
+            }
+            let tweet = retweeted.unwrap_or(tweet);
 
             let tweet_author = tweet.author_username.as_ref().unwrap_or(&str_unknown);
             let tweet_author = format!("@{tweet_author} ");
@@ -95,48 +485,321 @@ impl FeedPane {
             segments.push(TextSegment::color(
                 &tweet_author,
                 if is_starred {
-                    Colors::new(Color::Yellow, Color::Reset)
+                    Colors::new(theme.starred_author.0.into(), Color::Reset)
                 } else {
-                    Colors::new(Color::DarkCyan, Color::Reset)
+                    Colors::new(theme.author.0.into(), Color::Reset)
                 },
             ));
+            if user_config.show_badges {
+                segments.append(&mut tweet_text::author_badges(tweet, false));
+            }
+            if tweet.author_protected == Some(true) {
+                segments.push(TextSegment::color(
+                    "🔒 ",
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                ));
+            }
+            if let Some(relationship) = self.store.relationship(&tweet.author_id) {
+                segments.push(TextSegment::color(
+                    &format!("{} ", relationship.badge()),
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                ));
+            }
+            if tweet.is_edited() {
+                segments.push(TextSegment::color(
+                    "✎ ",
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                ));
+            }
+            // NB: a '!' marks images without alt text
+            match tweet.is_media_described() {
+                Some(true) => segments.push(TextSegment::color(
+                    "🖼 ",
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                )),
+                Some(false) => segments.push(TextSegment::color(
+                    "🖼! ",
+                    Colors::new(Color::Yellow, Color::Reset),
+                )),
+                None => {}
+            }
+
+            let str_metrics = match &tweet.public_metrics {
+                Some(metrics) if user_config.show_public_metrics => {
+                    format!("  {}", format_public_metrics(metrics))
+                }
+                _ => String::new(),
+            };
 
-            let formatted = re_newlines.replace_all(&tweet.text, "⏎ ");
-            let used_length = tweet_time.len() + tweet_author.len();
+            let used_length = segments.iter().map(TextSegment::width).sum::<usize>()
+                + text_width::width(&str_metrics);
             let remaining_length = self.display_width.saturating_sub(used_length);
-            let lines = textwrap::wrap(&formatted, remaining_length);
-            if lines.len() == 1 {
-                segments.push(TextSegment::plain(&lines[0]));
-            } else if lines.len() > 1 {
-                // Rewrap lines to accommodate ellipsis (…), which may knock out a word
-                let remaining_length = remaining_length.saturating_sub(1) as usize;
-                let lines = textwrap::wrap(&formatted, remaining_length);
-                segments.push(TextSegment::plain(&lines[0]));
-                segments.push(TextSegment::plain("…"));
+            // NB: too narrow for a useful preview
+            let quoted = tweet
+                .quoted_id()
+                .and_then(|id| tweets.get(id))
+                .filter(|_| remaining_length >= MIN_QUOTE_PREVIEW_WIDTH * 2)
+                .filter(|quoted| !is_collapsed(quoted));
+
+            match quoted {
+                _ if is_collapsed(tweet) => {
+                    segments.push(tweet_text::sensitive_placeholder(remaining_length))
+                }
+                None => segments.append(&mut tweet_text::one_line(tweet, remaining_length)),
+                Some(quoted) => {
+                    let quoted_author = quoted.author_username.as_ref().unwrap_or(&str_unknown);
+                    let quoted_author = format!("  ❝ @{quoted_author}: ");
+                    let quoted_width = text_width::width(&quoted_author)
+                        + tweet_text::one_line(quoted, usize::MAX)
+                            .iter()
+                            .map(TextSegment::width)
+                            .sum::<usize>();
+
+                    // NB: the quoting text gets at least half, more if the quote is short
+                    let text_length =
+                        (remaining_length / 2).max(remaining_length.saturating_sub(quoted_width));
+                    let mut text = tweet_text::one_line(tweet, text_length);
+                    let quote_length = remaining_length.saturating_sub(
+                        text.iter().map(TextSegment::width).sum::<usize>()
+                            + text_width::width(&quoted_author),
+                    );
+                    segments.append(&mut text);
+                    segments.push(TextSegment::color(
+                        &quoted_author,
+                        Colors::new(Color::DarkGrey, Color::Reset),
+                    ));
+                    segments.append(&mut tweet_text::one_line(quoted, quote_length));
+                }
             }
+            segments.push(TextSegment::color(
+                &str_metrics,
+                Colors::new(theme.metrics.0.into(), Color::Reset),
+            ));
 
             self.scroll_buffer.push(segments);
         }
 
-        let y = self.scroll_buffer.get_cursor().1;
-        self.scroll_buffer.move_cursor_to(16, y as usize);
+        for conversation in conversations.iter().flatten() {
+            if let Some(filter) = &filter {
+                let matches = conversation.tweet_ids.iter().any(|tweet_id| {
+                    store::shown_tweet(&tweets, tweet_id)
+                        .is_some_and(|tweet| tweet_matches(tweet, filter))
+                });
+                if !matches {
+                    continue;
+                }
+            }
+            self.visible_feed.push(conversation.tweet_ids[0].clone());
+            self.scroll_buffer.push(conversation_line(
+                conversation,
+                &tweets,
+                theme,
+                self.display_width,
+                &is_collapsed,
+            ));
+        }
+
+        for author in authors.iter().flatten() {
+            let tweet_ids: Vec<&String> = author
+                .tweet_ids
+                .iter()
+                .filter(|tweet_id| match &filter {
+                    Some(filter) => store::shown_tweet(&tweets, tweet_id)
+                        .is_some_and(|tweet| tweet_matches(tweet, filter)),
+                    None => true,
+                })
+                .collect();
+            let Some((newest_id, rest)) = tweet_ids.split_first() else {
+                continue;
+            };
+            let is_expanded = self
+                .expanded_authors
+                .contains(&author.username.to_lowercase());
+            self.visible_feed.push(newest_id.to_string());
+            self.scroll_buffer.push(author_line(
+                author,
+                store::shown_tweet(&tweets, newest_id),
+                feed.len(),
+                is_expanded,
+                theme,
+                self.display_width,
+                &is_collapsed,
+            ));
+            // NB: the newest is on the author's line already
+            for tweet_id in rest.iter().filter(|_| is_expanded) {
+                let Some(tweet) = store::shown_tweet(&tweets, tweet_id) else {
+                    continue;
+                };
+                self.visible_feed.push(tweet_id.to_string());
+                let tweet_time = tweet.local_created_at().format("%m-%d %H:%M:%S");
+                let mut segments = vec![TextSegment::color(
+                    &format!("    {tweet_time}  >  "),
+                    Colors::new(theme.timestamp.0.into(), Color::Reset),
+                )];
+                let used_length = segments.iter().map(TextSegment::width).sum::<usize>();
+                let remaining_length = self.display_width.saturating_sub(used_length);
+                if is_collapsed(tweet) {
+                    segments.push(tweet_text::sensitive_placeholder(remaining_length));
+                } else {
+                    segments.append(&mut tweet_text::one_line(tweet, remaining_length));
+                }
+                self.scroll_buffer.push(segments);
+            }
+        }
+
+        // NB: the filter bar, when it's open, is drawn over the header
+        let mut str_view = Vec::new();
+        if feed_sort != FeedSort::Newest {
+            str_view.push(format!(
+                "sorted {}  (:sort newest to undo)",
+                feed_sort.describe()
+            ));
+        }
+        if conversations.is_some() {
+            str_view.push("by conversation  (t to open, :conversations to undo)".to_string());
+        }
+        if authors.is_some() {
+            str_view.push("by author  (t to expand, M to mute, :authors to undo)".to_string());
+        }
+        let str_view = str_view.join("  ");
+        let num_lines = match (conversations, authors) {
+            (Some(conversations), _) => conversations.len(),
+            (_, Some(authors)) => authors.len(),
+            (None, None) => feed.len(),
+        };
+        let header = match filter {
+            Some(_) => vec![vec![TextSegment::color(
+                &format!(
+                    "filter: {}  ({} of {num_lines}, f to edit)  {str_view}",
+                    self.filter_bar.component.get_text().trim(),
+                    self.visible_feed.len(),
+                ),
+                Colors::new(Color::DarkGrey, Color::Reset),
+            )]],
+            None if self.get_focus() == Focus::FilterBar => vec![vec![]],
+            None if !str_view.is_empty() => vec![vec![TextSegment::color(
+                &str_view,
+                Colors::new(Color::DarkGrey, Color::Reset),
+            )]],
+            None => vec![],
+        };
+        self.scroll_buffer.set_header(header);
+
+        drop(tweets);
+        drop(revealed_ids);
+        drop(user_config);
+        if let Some(status_line) = self.status_line() {
+            self.scroll_buffer.push(status_line);
+        }
+
+        let line_no = self.scroll_buffer.get_cursor_line();
+        self.scroll_buffer.move_cursor_to(16, line_no);
         self.should_update_scroll_buffer
             .store(false, Ordering::SeqCst);
     }
 
-    pub fn do_load_page_of_tweets(&self, restart: bool) {
+    /// Runs [load] in the background, tracking it in the store's load state so that the pane can
+    /// show a spinner while it's in flight, or the error if it fails.
+    fn spawn_load<F: Future<Output = Result<()>> + Send + 'static>(&self, load: F) {
         let events = self.events.clone();
         let store = self.store.clone();
         let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
 
+        *store.load_state.lock().unwrap() = LoadState::Loading;
+        should_update_scroll_buffer.store(true, Ordering::SeqCst);
+
         let task = tokio::spawn(async move {
-            match store.load_tweets_reverse_chronological(restart).await {
-                Ok(()) => should_update_scroll_buffer.store(true, Ordering::SeqCst),
+            let result = load.await;
+            *store.load_state.lock().unwrap() = match &result {
+                Ok(()) => LoadState::Idle,
+                Err(error) => LoadState::Failed(format!("{error:#}")),
+            };
+            let skipped_tweets = store.take_skipped_tweets();
+            if !skipped_tweets.is_empty() {
+                let message = format!("Skipped {} malformed tweet(s)", skipped_tweets.len());
+                events
+                    .send(InternalEvent::Notify(NotifyLevel::Warning, message))
+                    .unwrap();
+                for skipped in skipped_tweets {
+                    events
+                        .send(InternalEvent::LogError(anyhow!(skipped)))
+                        .unwrap();
+                }
+            }
+            match result {
+                Ok(()) => {
+                    let tweet_ids = store.tweets_feed.lock().unwrap().clone();
+                    spawn_plugins(&events, &store, PluginEvent::FeedRefreshed { tweet_ids });
+                }
                 Err(error) => events.send(InternalEvent::LogError(error)).unwrap(),
             }
+            should_update_scroll_buffer.store(true, Ordering::SeqCst);
+        });
+
+        let task_key = TaskKey::Feed(self.store.feed_id);
+        self.events
+            .send(InternalEvent::RegisterTask(Some(task_key), task))
+            .unwrap();
+    }
+
+    /// NB: the next page asked for while a page is loading follows it, rather than superseding it
+    pub fn do_load_page_of_tweets(&self, restart: bool) {
+        let Some(page_load) = self.store.begin_page_load(restart) else {
+            return;
+        };
+        let store = self.store.clone();
+        self.spawn_load(async move { store.load_home_pages(page_load, restart).await });
+    }
+
+    /// Reloads activity; when [run_hooks], runs the hooks on new tweets from starred accounts.
+    pub fn do_load_activity(&self, run_hooks: bool) {
+        let events = self.events.clone();
+        let store = self.store.clone();
+        self.spawn_load(async move {
+            let new_tweets = store.load_activity().await?;
+            if run_hooks {
+                let user_config = store.user_config.lock().unwrap().clone();
+                let hits = alerts::find_hits(&user_config, &new_tweets);
+                store.record_alert_hits(&hits);
+                if let Some(summary) = alerts::summary(&hits) {
+                    events.send(InternalEvent::Notify(NotifyLevel::Info, summary))?;
+                }
+                // NB: separately, so hooks aren't aborted along with a superseding load
+                let task = tokio::spawn({
+                    let events = events.clone();
+                    async move {
+                        for error in hooks::run_hooks(&user_config, &new_tweets).await {
+                            events.send(InternalEvent::LogError(error)).unwrap();
+                        }
+                    }
+                });
+                events.send(InternalEvent::RegisterTask(None, task))?;
+            }
+            Ok(())
         });
+    }
+
+    pub fn do_load_mentions(&self) {
+        let store = self.store.clone();
+        self.spawn_load(async move { store.load_mentions(true).await });
+    }
 
-        self.events.send(InternalEvent::RegisterTask(task)).unwrap();
+    /// Shows the selected tweet (for a retweet, the original) if it's collapsed as sensitive, or
+    /// collapses it again.
+    fn do_toggle_selected_revealed(&mut self) {
+        if let Some(run) = self.hidden_runs.get(&self.tweet_selected_id) {
+            self.shown_spam_ids.extend(run.iter().cloned());
+            self.redraw_feed();
+            return;
+        }
+        let Some(tweet) = self.get_selected_original_tweet() else {
+            return;
+        };
+        if tweet.is_possibly_sensitive() {
+            self.store.toggle_revealed(&tweet.id);
+            self.redraw_feed();
+            self.redraw_tweet_panes();
+        }
     }
 
     fn do_toggle_selected_tweet_starred(&mut self) {
@@ -164,7 +827,104 @@ impl FeedPane {
         }
     }
 
-    pub fn do_search(&self) {
+    /// Opens a form for the selected tweet's author's notes and tags; saving stars them.
+    fn do_edit_selected_author(&self) {
+        let Some(tweet) = self.get_selected_original_tweet() else {
+            return;
+        };
+        let author = tweet.author("[unknown]");
+        let (notes, tags) = match self
+            .store
+            .user_config
+            .lock()
+            .unwrap()
+            .starred_accounts
+            .get(&author.id)
+        {
+            Some(account) => (
+                account.notes.clone(),
+                account.tags.iter().cloned().collect::<Vec<_>>().join(" "),
+            ),
+            None => (String::new(), String::new()),
+        };
+
+        let (responder, answer) = oneshot::channel();
+        let form = Form::new(
+            &format!("@{} [{}]", author.username, author.name),
+            responder,
+        )
+        .with_field("Tags (e.g. rust ml)", &tags, 1)
+        .with_field("Notes", &notes, 4);
+        self.events.send(InternalEvent::Form(form)).unwrap();
+
+        let store = self.store.clone();
+        let events = self.events.clone();
+        let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
+        let task = tokio::spawn(async move {
+            let Ok(Some(values)) = answer.await else {
+                return;
+            };
+            store.user_config.lock().unwrap().annotate_account(
+                &author,
+                &values[1],
+                user_config::parse_tags(&values[0]),
+            );
+            match store.save_user_config() {
+                Ok(()) => should_update_scroll_buffer.store(true, Ordering::SeqCst),
+                Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
+            }
+        });
+        self.events
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    /// Opens a form for the selected tweet's note; saving an empty note removes it.
+    fn do_edit_selected_tweet_note(&self) {
+        if self.tweet_selected_id.is_empty() {
+            return;
+        }
+        let tweet_id = self.tweet_selected_id.clone();
+        let note = self
+            .store
+            .user_config
+            .lock()
+            .unwrap()
+            .notes
+            .get(&tweet_id)
+            .cloned()
+            .unwrap_or_default();
+
+        let (responder, answer) = oneshot::channel();
+        let form = Form::new(&format!("Note on tweet {tweet_id}"), responder).with_field(
+            "Note (private, kept in the config)",
+            &note,
+            4,
+        );
+        self.events.send(InternalEvent::Form(form)).unwrap();
+
+        let store = self.store.clone();
+        let events = self.events.clone();
+        let task = tokio::spawn(async move {
+            let Ok(Some(values)) = answer.await else {
+                return;
+            };
+            store
+                .user_config
+                .lock()
+                .unwrap()
+                .set_note(&tweet_id, &values[0]);
+            match store.save_user_config() {
+                Ok(()) => events.send(InternalEvent::UserConfigChanged).unwrap(),
+                Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
+            }
+        });
+        self.events
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    pub fn do_search(&mut self) {
         let search_term = self.search_bar.component.get_text();
 
         // CR: factor search stuff out to somewhere
@@ -178,25 +938,9 @@ impl FeedPane {
         }
 
         if let Some(twitter_username) = parse_twitter_handle(&search_term) {
-            let store = self.store.clone();
-            let events = self.events.clone();
-            let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
-
-            let task = tokio::spawn(async move {
-                match store
-                    .twitter_client
-                    .user_by_username(&twitter_username)
-                    .await
-                {
-                    Ok(user) => match store.load_user_tweets(&user.id, true).await {
-                        Ok(()) => should_update_scroll_buffer.store(true, Ordering::SeqCst),
-                        Err(error) => events.send(InternalEvent::LogError(error)).unwrap(),
-                    },
-                    Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
-                }
-            });
-
-            self.events.send(InternalEvent::RegisterTask(task)).unwrap();
+            self.do_load_user_tweets(&twitter_username);
+        } else if let Some(tweet_id) = parse_tweet_permalink(&search_term) {
+            self.do_open_tweet(&tweet_id);
         } else if search_term.is_empty() {
             self.do_load_page_of_tweets(true);
         } else {
@@ -209,207 +953,1424 @@ impl FeedPane {
         }
     }
 
-    pub fn do_search_starred_accounts(&self, restart: bool) {
-        let user_config = self.store.user_config.lock().unwrap();
-        let query = user_config
-            .starred_accounts
-            .keys()
-            .map(|user_id| format!("from:{}", user_id))
-            .collect::<Vec<String>>()
-            .join(" OR ");
-        drop(user_config);
+    pub fn do_load_user_tweets(&self, twitter_username: &str) {
+        let twitter_username = twitter_username.to_string();
+        let store = self.store.clone();
+        self.spawn_load(async move { store.load_user_timeline(&twitter_username).await });
+    }
 
+    /// Loads the first page of a search, or unless [restart], the next.
+    pub fn do_search_tweets(&self, query: &str, restart: bool) {
+        let query = query.to_string();
         let events = self.events.clone();
         let store = self.store.clone();
-        let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
-
-        let task = tokio::spawn(async move {
-            match store.load_search_tweets(&query, restart).await {
-                Ok(()) => should_update_scroll_buffer.store(true, Ordering::SeqCst),
-                Err(error) => events.send(InternalEvent::LogError(error)).unwrap(),
-            }
+        self.spawn_load(async move {
+            let meta = store.load_search_tweets(&query, restart).await?;
+            let results = store.describe_search_results(meta.as_ref());
+            events.send(InternalEvent::Notify(NotifyLevel::Info, results))?;
+            Ok(())
         });
-
-        self.events.send(InternalEvent::RegisterTask(task)).unwrap();
     }
 
-    pub fn log_selected_tweet(&self) {
-        self.events
-            .send(InternalEvent::LogTweet(self.tweet_selected_id.clone()))
-            .unwrap();
+    /// Adds the results of a search to the end of the feed, rather than replacing it.
+    pub fn do_append_search_tweets(&self, query: &str) {
+        let query = query.to_string();
+        let events = self.events.clone();
+        let store = self.store.clone();
+        self.spawn_load(async move {
+            let (meta, num_added) = store.append_search_tweets(&query).await?;
+            let results = store.describe_search_results(meta.as_ref());
+            let message = format!("{results}; {num_added} added to the feed");
+            events.send(InternalEvent::Notify(NotifyLevel::Info, message))?;
+            Ok(())
+        });
     }
 
-    pub fn do_open_selected_tweet(&self) {
-        // NB: lol... https://developer.twitter.com/en/blog/community/2020/getting-to-the-canonical-url-for-a-tweet
-        process::Command::new("open")
-            .arg(format!(
-                "https://twitter.com/t/status/{}",
-                self.tweet_selected_id
-            ))
-            .output()
-            .expect(&format!("Failed to open tweet in browser"));
+    /// Loads the tweets of the authenticated user's list called [name] (case-insensitive).
+    pub fn do_load_list_tweets(&self, name: &str) {
+        let name = name.to_string();
+        let store = self.store.clone();
+        self.spawn_load(async move {
+            let lists = store
+                .twitter_client
+                .owned_lists(&store.twitter_user.id)
+                .await?;
+            let list = lists
+                .iter()
+                .find(|list| list.name.eq_ignore_ascii_case(&name))
+                .ok_or(anyhow!("No list named {name}"))?;
+            store.load_list_tweets(&list.id, true).await
+        });
     }
-}
 
-impl Render for FeedPane {
-    fn should_render(&self) -> bool {
-        self.should_update_scroll_buffer.load(Ordering::SeqCst)
-            || self.scroll_buffer.should_render()
-            || self.tweet_pane.component.should_render()
-            || self.search_bar.component.should_render()
-            || self.should_render
+    pub fn do_load_collection(&self, name: &str) {
+        let name = name.to_string();
+        let store = self.store.clone();
+        self.spawn_load(async move { store.load_collection(&name).await });
     }
 
-    fn invalidate(&mut self) {
-        self.scroll_buffer.invalidate();
-        self.tweet_pane.component.invalidate();
-        self.search_bar.component.invalidate();
-        self.should_render = true;
+    pub fn do_load_catch_up(&self, twitter_username: &str) {
+        let twitter_username = twitter_username.to_string();
+        let store = self.store.clone();
+        let events = self.events.clone();
+        self.spawn_load(async move {
+            if store.load_catch_up(&twitter_username).await? == 0 {
+                let message = format!("Nothing new from @{twitter_username}");
+                events
+                    .send(InternalEvent::Notify(NotifyLevel::Info, message))
+                    .unwrap();
+            }
+            Ok(())
+        });
     }
 
-    fn render(&mut self, stdout: &mut Stdout, bounding_box: BoundingBox) -> Result<()> {
-        // CR-someday: does using SeqCst have a performance impact?  Frankly, we already use Mutex
-        // in the render loop, so I'm not sure it matters.
-        let BoundingBox { left, width, .. } = bounding_box;
-        let half_width = ((width as usize) / 2).saturating_sub(1);
-
-        if self.should_update_scroll_buffer.load(Ordering::SeqCst)
-            || self.display_width != half_width as usize
-        {
-            self.display_width = half_width;
-            self.update_scroll_buffer();
+    /// Opens a tab of the selected tweet's author's tweets since the user last caught up on them.
+    fn do_catch_up_on_selected_author(&self) {
+        let Some(tweet) = self.get_selected_original_tweet() else {
+            return;
+        };
+        if let Some(username) = tweet.author_username {
+            self.events
+                .send(InternalEvent::OpenTab(FeedSource::CatchUp(username)))
+                .unwrap();
         }
+    }
 
-        if self.focus == Focus::SearchBar {
-            // CR: this bounding_box concept is superfluous
-            self.search_bar.bounding_box = BoundingBox {
-                width: half_width as u16,
-                height: 1,
-                ..bounding_box
+    /// Pages back through the home timeline, or [twitter_username]'s tweets, until it reaches
+    /// [date], then selects the newest tweet from that day or before.
+    pub fn do_goto_date(&self, date: NaiveDate, twitter_username: Option<&str>) {
+        let twitter_username = twitter_username.map(str::to_string);
+        // NB: tweets from [date] are those from before the following (local) midnight
+        let before = date
+            .succ_opt()
+            .and_then(|next_date| next_date.and_hms_opt(0, 0, 0))
+            .and_then(|midnight| Local.from_local_datetime(&midnight).earliest());
+        let Some(before) = before else {
+            self.events
+                .send(InternalEvent::LogError(anyhow!("Can't go to {date}")))
+                .unwrap();
+            return;
+        };
+
+        let store = self.store.clone();
+        let goto = self.goto.clone();
+        let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
+        *goto.lock().unwrap() = Goto::Paging(date, None);
+        self.spawn_load(async move {
+            let user_id = match twitter_username {
+                Some(twitter_username) => Some(store.user_by_username(&twitter_username).await?.id),
+                None => None,
+            };
+            let load_next_page = || async {
+                match &user_id {
+                    Some(user_id) => store.load_user_tweets(user_id, false).await,
+                    None => store.load_tweets_reverse_chronological(false).await,
+                }
+            };
+            let on_page = |oldest: DateTime<Utc>| {
+                *goto.lock().unwrap() = Goto::Paging(date, Some(oldest.with_timezone(&Local)));
+                should_update_scroll_buffer.store(true, Ordering::SeqCst);
             };
-            self.search_bar.render_if_necessary(stdout)?;
 
-            // CR: need a generic [clear] method
-            let str_clear = " ".repeat(half_width);
-            queue!(stdout, cursor::MoveTo(left, bounding_box.top + 1))?;
-            queue!(stdout, style::Print(str_clear))?;
+            let result = store
+                .load_feed_back_to(before.with_timezone(&Utc), load_next_page, on_page)
+                .await;
+            *goto.lock().unwrap() = match &result {
+                Ok(tweet_id) => Goto::Found(tweet_id.clone()),
+                Err(_) => Goto::Idle,
+            };
+            result.map(|_| ())
+        });
+    }
 
-            self.scroll_buffer.render(
+    /// Opens a form for the name of a collection to save the selected tweet (for a retweet, the
+    /// original) into; a new name starts a new collection.
+    fn do_add_marked_to_collection(&mut self) {
+        let tweets = self.take_marked_or_selected_tweets();
+        if tweets.is_empty() {
+            return;
+        }
+        let dir = Path::new(collections::COLLECTIONS_DIR);
+        let title = match collections::list_collections(dir) {
+            Ok(names) if !names.is_empty() => {
+                format!("Add to collection (have: {})", names.join(", "))
+            }
+            Ok(_) => "Add to a new collection".to_string(),
+            Err(err) => {
+                self.events.send(InternalEvent::LogError(err)).unwrap();
+                return;
+            }
+        };
+        let (responder, answer) = oneshot::channel();
+        let form = Form::new(&title, responder).with_field("Collection", "", 1);
+        self.events.send(InternalEvent::Form(form)).unwrap();
+
+        let tweets: Vec<(api::Tweet, Vec<api::Tweet>)> = tweets
+            .into_iter()
+            .map(|tweet| {
+                let referenced_tweets = tweet
+                    .quoted_id()
+                    .and_then(|quoted_id| self.store.get_tweet(quoted_id))
+                    .into_iter()
+                    .collect();
+                (tweet, referenced_tweets)
+            })
+            .collect();
+        let events = self.events.clone();
+        let task = tokio::spawn(async move {
+            let Ok(Some(values)) = answer.await else {
+                return;
+            };
+            let num_tweets = tweets.len();
+            let result = Collection::open(dir, &values[0]).and_then(|mut collection| {
+                let mut num_added = 0;
+                for (tweet, referenced_tweets) in tweets {
+                    if collection.add(&tweet, referenced_tweets) {
+                        num_added += 1;
+                    }
+                }
+                collection.save()?;
+                Ok((collection.name, num_added))
+            });
+            let event = match result {
+                Ok((name, 1)) if num_tweets == 1 => {
+                    InternalEvent::Notify(NotifyLevel::Info, format!("Added to {name}"))
+                }
+                Ok((name, 0)) if num_tweets == 1 => {
+                    InternalEvent::Notify(NotifyLevel::Info, format!("Already in {name}"))
+                }
+                Ok((name, num_added)) => InternalEvent::Notify(
+                    NotifyLevel::Info,
+                    format!("Added {num_added} of {num_tweets} to {name}"),
+                ),
+                Err(err) => InternalEvent::LogError(err),
+            };
+            events.send(event).unwrap();
+        });
+        self.events
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    /// Loads tweets by the starred accounts, or only those tagged [tag].
+    pub fn do_search_starred_accounts(&self, tag: Option<&str>, restart: bool) {
+        let user_config = self.store.user_config.lock().unwrap();
+        let user_ids = match tag {
+            Some(tag) => user_config.tagged_account_ids(tag),
+            None => user_config.starred_accounts.keys().cloned().collect(),
+        };
+        drop(user_config);
+        let query = user_ids
+            .iter()
+            .map(|user_id| format!("from:{}", user_id))
+            .collect::<Vec<String>>()
+            .join(" OR ");
+
+        let store = self.store.clone();
+        let tag = tag.map(str::to_string);
+        self.spawn_load(async move {
+            match tag {
+                Some(tag) if query.is_empty() => Err(anyhow!("No starred accounts tagged #{tag}")),
+                _ => store.load_search_tweets(&query, restart).await.map(|_| ()),
+            }
+        });
+    }
+
+    /// Carries out [action] if it's about the feed; returns false otherwise.
+    pub fn dispatch(&mut self, action: Action) -> bool {
+        match action {
+            Action::OpenTweetFinder => self.do_open_tweet_finder(),
+            Action::Compose => self.do_compose_thread(None),
+            Action::ComposeReply => {
+                let in_reply_to_tweet_id = self.get_selected_tweet_id();
+                self.do_compose_thread(in_reply_to_tweet_id)
+            }
+            Action::OpenDrafts => self.push_focus(Focus::DraftsPane),
+            Action::InspectTweet => self.log_selected_tweet(),
+            Action::OpenTweetInBrowser => self.do_open_selected_tweet(),
+            Action::OpenTweetUrls => self.do_open_selected_tweet_urls(),
+            Action::CopyTweetAsMarkdown => self.do_copy_selected_tweet(EmbedFormat::Markdown),
+            Action::CopyTweetAsHtml => self.do_copy_selected_tweet(EmbedFormat::Html),
+            Action::JumpToParent => self.do_jump_to_parent(),
+            Action::OpenConversation if self.group_by_author => {
+                self.do_toggle_selected_author_expanded()
+            }
+            Action::OpenConversation => self.do_open_conversation(),
+            Action::TranslateTweet => self.tweet_pane_stack.component.do_translate(),
+            Action::ShowEditHistory => self.tweet_pane_stack.component.do_load_edit_history(),
+            Action::RevealSensitive => self.do_toggle_selected_revealed(),
+            Action::LoadNextPage | Action::Refresh => {
+                let restart = action == Action::Refresh;
+                match &self.search_query {
+                    Some(query) => self.do_search_tweets(query, restart),
+                    None => self.do_load_page_of_tweets(restart),
+                }
+            }
+            Action::ToggleStarred => self.do_toggle_selected_tweet_starred(),
+            Action::CatchUpOnAuthor => self.do_catch_up_on_selected_author(),
+            Action::EditStarred => self.do_edit_selected_author(),
+            Action::EditNote => self.do_edit_selected_tweet_note(),
+            Action::AddToCollection => self.do_add_marked_to_collection(),
+            Action::ToggleMarked => self.do_toggle_marked(),
+            Action::MarkRange => self.do_mark_range(),
+            Action::OpenAuthors => self.do_open_marked_authors(),
+            Action::MuteAuthors => self.do_mute_marked_authors(),
+            Action::SearchStarred => self.do_search_starred_accounts(None, true),
+            Action::OpenSearchBar => {
+                self.push_focus(Focus::SearchBar);
+                self.should_render = true;
+            }
+            Action::FilterFeed => {
+                self.push_focus(Focus::FilterBar);
+                self.redraw_feed();
+            }
+            Action::ShowStarred => {
+                {
+                    let user_config = self.store.user_config.lock().unwrap();
+                    let starred_accounts = user_config.starred_accounts.values();
+                    let out = starred_accounts
+                        .map(|account| {
+                            let mut line =
+                                format!("@{} [{}]", account.user.username, account.user.name);
+                            for tag in account.tags.iter() {
+                                line.push_str(&format!(" #{tag}"));
+                            }
+                            if !account.notes.is_empty() {
+                                line.push_str(&format!("\n    {}", account.notes));
+                            }
+                            line
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n");
+                    // CR: okay, maybe handle the error here
+                    fs::write("/tmp/starred_accounts", out).unwrap();
+                }
+
+                // CR: also handle the errors here
+                let mut subshell = process::Command::new("less")
+                    .args(["/tmp/starred_accounts"])
+                    .spawn()
+                    .unwrap();
+                subshell.wait().unwrap();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Opens the compose box on a fresh draft.
+    pub fn do_compose_thread(&mut self, in_reply_to_tweet_id: Option<String>) {
+        match Draft::create(in_reply_to_tweet_id.as_deref()) {
+            Ok(draft) => self.do_resume_draft(draft),
+            Err(err) => self.events.send(InternalEvent::LogError(err)).unwrap(),
+        }
+    }
+
+    /// Asks the user to confirm via a prompt on the bottom bar; resolves to false if they decline
+    /// or the prompt goes away unanswered.
+    fn confirm(&self, message: &str) -> oneshot::Receiver<bool> {
+        let (responder, answer) = oneshot::channel();
+        self.events
+            .send(InternalEvent::Confirm(message.to_string(), responder))
+            .unwrap();
+        answer
+    }
+
+    fn do_delete_draft(&self, draft: Draft) {
+        let answer = self.confirm("Delete draft?");
+        let events = self.events.clone();
+        let should_reload_drafts = self.drafts_pane.component.get_should_reload();
+
+        let task = tokio::spawn(async move {
+            if answer.await != Ok(true) {
+                return;
+            }
+            if let Err(err) = draft.delete() {
+                events.send(InternalEvent::LogError(err)).unwrap();
+            }
+            should_reload_drafts.store(true, Ordering::SeqCst);
+        });
+
+        self.events
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    fn do_resume_draft(&mut self, draft: Draft) {
+        let cross_post_targets = self.store.user_config.lock().unwrap().cross_post.targets();
+        self.compose_pane
+            .component
+            .open_draft(draft, cross_post_targets);
+        // NB: from the drafts pane, compose takes its place rather than going on top
+        if self.get_focus() == Focus::DraftsPane {
+            self.set_focus(Focus::ComposePane);
+        } else {
+            self.push_focus(Focus::ComposePane);
+        }
+    }
+
+    /// Opens a fuzzy finder over "@author text" of every tweet in the store, newest first.
+    /// NB: notes are part of each item, so the finder doubles as a search of them
+    fn do_open_tweet_finder(&mut self) {
+        let items = {
+            let notes = self.store.user_config.lock().unwrap().notes.clone();
+            let tweets = self.store.tweets.lock().unwrap();
+            let mut tweets: Vec<&api::Tweet> = tweets.values().collect();
+            tweets.sort_by_key(|tweet| Reverse(tweet.created_at));
+
+            let re_newlines = Regex::new(r"[\r\n]+").unwrap();
+            tweets
+                .iter()
+                .map(|tweet| {
+                    let author = tweet.author_username.as_deref().unwrap_or("[unknown]");
+                    let text = re_newlines.replace_all(&tweet.text, "⏎ ");
+                    let label = match notes.get(&tweet.id) {
+                        Some(note) => {
+                            format!("@{author} {text} ✎ {}", re_newlines.replace_all(note, " "))
+                        }
+                        None => format!("@{author} {text}"),
+                    };
+                    (label, tweet.id.clone())
+                })
+                .collect()
+        };
+        self.tweet_finder.component.set_items(items);
+        self.push_focus(Focus::TweetFinder);
+    }
+
+    /// Closes the fuzzy finder; jumps to [tweet_id] in the feed if it's there, otherwise just opens
+    /// it in the tweet pane.
+    fn do_close_tweet_finder(&mut self, tweet_id: Option<String>) {
+        self.pop_focus();
+        self.scroll_buffer.invalidate();
+        self.should_render = true;
+
+        if let Some(tweet_id) = tweet_id {
+            self.select_tweet(&tweet_id);
+        }
+    }
+
+    /// Jumps to [tweet_id] in the feed if it's there, otherwise just opens it in the tweet pane.
+    fn select_tweet(&mut self, tweet_id: &String) {
+        let feed_index = self.visible_feed.iter().position(|id| id == tweet_id);
+        if let Some(feed_index) = feed_index {
+            self.scroll_buffer.move_cursor_to(16, feed_index);
+        }
+        self.set_selected_tweet_id(tweet_id);
+    }
+
+    /// Shows [tweet_id] in the tweet pane, letting plugins know if it's newly selected.
+    fn set_selected_tweet_id(&mut self, tweet_id: &String) {
+        if self.tweet_selected_id != *tweet_id {
+            if let Some(tweet) = self.store.get_tweet(tweet_id) {
+                spawn_plugins(
+                    &self.events,
+                    &self.store,
+                    PluginEvent::TweetSelected {
+                        tweet: Box::new(tweet),
+                    },
+                );
+            }
+        }
+        self.tweet_selected_id = tweet_id.clone();
+        self.tweet_pane_stack.component.set_tweet_id(tweet_id);
+        if self.wants_next_page() {
+            self.do_load_page_of_tweets(false);
+        }
+    }
+
+    /// Whether the selection is within [UserConfig::prefetch_distance] of the bottom of the feed,
+    /// and there's a next page that isn't already loading.
+    ///
+    /// [UserConfig::prefetch_distance]: crate::user_config::UserConfig::prefetch_distance
+    fn wants_next_page(&self) -> bool {
+        if !self.is_home_timeline {
+            return false;
+        }
+        let prefetch_distance = self.store.user_config.lock().unwrap().prefetch_distance;
+        let feed_length = self.visible_feed.len();
+        if prefetch_distance == 0
+            || feed_length == 0
+            || self.scroll_buffer.get_cursor_line() + prefetch_distance < feed_length
+        {
+            return false;
+        }
+        // NB: not after a failed load either, or every keypress would retry it
+        if *self.store.load_state.lock().unwrap() != LoadState::Idle {
+            return false;
+        }
+        // NB: the token is locked while a load is in flight
+        matches!(self.store.tweets_feed_page_token.try_lock(), Ok(page_token) if page_token.is_some())
+    }
+
+    /// Rebuilds the tweet panes on the next render, e.g. after a note was added.
+    pub fn redraw_tweet_panes(&self) {
+        self.tweet_pane_stack.component.redraw();
+    }
+
+    /// Carries out a command printed by a plugin.  NB: mutes and notes go into the user config,
+    /// which all tabs share, so the caller redraws them all.
+    pub fn do_plugin_command(&mut self, command: PluginCommand) -> Result<()> {
+        match command {
+            PluginCommand::OpenUrl { url } => open_in_browser(&url),
+            PluginCommand::AddNote { tweet_id, text } => {
+                self.store
+                    .user_config
+                    .lock()
+                    .unwrap()
+                    .set_note(&tweet_id, &text);
+                self.store.save_user_config()?;
+            }
+            PluginCommand::MuteUser { username } => {
+                self.store
+                    .user_config
+                    .lock()
+                    .unwrap()
+                    .mute_username(&username);
+                self.store.save_user_config()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks or unmarks the selected tweet for a bulk action.
+    fn do_toggle_marked(&mut self) {
+        let Some(tweet_id) = self.get_selected_tweet_id() else {
+            return;
+        };
+        if !self.marked_ids.remove(&tweet_id) {
+            self.marked_ids.insert(tweet_id.clone());
+        }
+        self.mark_anchor = Some(tweet_id);
+        self.redraw_feed();
+    }
+
+    /// Marks the tweets from the one last marked or unmarked to the selected one, as shown.
+    fn do_mark_range(&mut self) {
+        let line_no = self.scroll_buffer.get_cursor_line();
+        if line_no >= self.visible_feed.len() {
+            return;
+        }
+        let anchor_line_no = self
+            .mark_anchor
+            .as_ref()
+            .and_then(|anchor_id| self.visible_feed.iter().position(|id| id == anchor_id))
+            .unwrap_or(line_no);
+        let (start, end) = (anchor_line_no.min(line_no), anchor_line_no.max(line_no));
+        self.marked_ids
+            .extend(self.visible_feed[start..=end].iter().cloned());
+        self.mark_anchor = Some(self.visible_feed[line_no].clone());
+        self.redraw_feed();
+        let message = format!("{} tweets marked", self.marked_count());
+        self.events
+            .send(InternalEvent::Notify(NotifyLevel::Info, message))
+            .unwrap();
+    }
+
+    /// How many of the marked tweets are shown.
+    fn marked_count(&self) -> usize {
+        self.visible_feed
+            .iter()
+            .filter(|tweet_id| self.marked_ids.contains(*tweet_id))
+            .count()
+    }
+
+    /// The marked tweets that are shown, in feed order, unmarking them all.
+    pub fn take_marked_tweet_ids(&mut self) -> Vec<String> {
+        let marked_ids: Vec<String> = self
+            .visible_feed
+            .iter()
+            .filter(|tweet_id| self.marked_ids.contains(*tweet_id))
+            .cloned()
+            .collect();
+        self.marked_ids.clear();
+        self.mark_anchor = None;
+        self.redraw_feed();
+        marked_ids
+    }
+
+    /// What a bulk action applies to: the marked tweets (unmarking them), or if none are marked,
+    /// the selected tweet.  For retweets, the originals, which are what the feed lines show.
+    fn take_marked_or_selected_tweets(&mut self) -> Vec<api::Tweet> {
+        let marked_ids = self.take_marked_tweet_ids();
+        if marked_ids.is_empty() {
+            return self.get_selected_original_tweet().into_iter().collect();
+        }
+        let tweets = self.store.tweets.lock().unwrap();
+        marked_ids
+            .iter()
+            .filter_map(|tweet_id| store::shown_tweet(&tweets, tweet_id).cloned())
+            .collect()
+    }
+
+    /// The authors of the marked (or selected) tweets, each once, in feed order.
+    fn take_marked_authors(&mut self) -> Vec<String> {
+        let mut usernames: Vec<String> = Vec::new();
+        for tweet in self.take_marked_or_selected_tweets() {
+            if let Some(username) = tweet.author_username {
+                if !usernames.contains(&username) {
+                    usernames.push(username);
+                }
+            }
+        }
+        usernames
+    }
+
+    /// Opens a tab for each author of the marked (or selected) tweets, up to [MAX_AUTHOR_TABS].
+    fn do_open_marked_authors(&mut self) {
+        let usernames = self.take_marked_authors();
+        if usernames.len() > MAX_AUTHOR_TABS {
+            let message = format!(
+                "Opening the first {MAX_AUTHOR_TABS} of {} authors",
+                usernames.len()
+            );
+            self.events
+                .send(InternalEvent::Notify(NotifyLevel::Warning, message))
+                .unwrap();
+        }
+        for username in usernames.into_iter().take(MAX_AUTHOR_TABS) {
+            self.events
+                .send(InternalEvent::OpenTab(FeedSource::User(username)))
+                .unwrap();
+        }
+    }
+
+    /// Mutes the authors of the marked (or selected) tweets, once confirmed.
+    fn do_mute_marked_authors(&mut self) {
+        let my_username = self.store.twitter_user.username.clone();
+        let usernames: Vec<String> = self
+            .take_marked_authors()
+            .into_iter()
+            .filter(|username| !username.eq_ignore_ascii_case(&my_username))
+            .collect();
+        if usernames.is_empty() {
+            return;
+        }
+        let mentions: Vec<String> = usernames.iter().map(|u| format!("@{u}")).collect();
+        let answer = self.confirm(&format!("Mute {}?", mentions.join(", ")));
+        let store = self.store.clone();
+        let events = self.events.clone();
+        let task = tokio::spawn(async move {
+            if answer.await != Ok(true) {
+                return;
+            }
+            {
+                let mut user_config = store.user_config.lock().unwrap();
+                for username in usernames.iter() {
+                    user_config.mute_username(username);
+                }
+            }
+            match store.save_user_config() {
+                Ok(()) => events.send(InternalEvent::UsersMuted).unwrap(),
+                Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
+            }
+        });
+        self.events
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    /// The selected tweet, or for a retweet the original, which is what the feed line shows.
+    fn get_selected_original_tweet(&self) -> Option<api::Tweet> {
+        let tweets = self.store.tweets.lock().unwrap();
+        let tweet = tweets.get(&self.tweet_selected_id)?;
+        let retweeted = tweet.retweeted_id().and_then(|id| tweets.get(id));
+        Some(retweeted.unwrap_or(tweet).clone())
+    }
+
+    /// Selects the tweet that the selected tweet is replying to, fetching it if it isn't cached.
+    pub fn do_jump_to_parent(&mut self) {
+        let parent_id = self
+            .get_selected_original_tweet()
+            .and_then(|tweet| tweet.replied_to_id().map(String::from));
+        match parent_id {
+            Some(parent_id) => {
+                self.select_tweet(&parent_id);
+                self.tweet_pane_stack.component.do_load_tweet();
+            }
+            None => self
+                .events
+                .send(InternalEvent::Notify(
+                    NotifyLevel::Info,
+                    "Not a reply".to_string(),
+                ))
+                .unwrap(),
+        }
+    }
+
+    /// Fetches [tweet_id] if need be, and opens it in the tweet pane with its thread.
+    fn do_open_tweet(&mut self, tweet_id: &String) {
+        self.select_tweet(tweet_id);
+        self.tweet_pane_stack.component.do_load_conversation();
+        self.set_focus(Focus::TweetPaneStack);
+    }
+
+    /// Shows the selected tweet's whole conversation in the tweet pane, and focuses it.
+    pub fn do_open_conversation(&mut self) {
+        let Some(tweet) = self.get_selected_original_tweet() else {
+            return;
+        };
+        self.tweet_pane_stack.component.set_tweet_id(&tweet.id);
+        self.tweet_pane_stack.component.do_load_conversation();
+        self.set_focus(Focus::TweetPaneStack);
+    }
+
+    fn do_close_compose_pane(&mut self, post: bool) {
+        let cross_post_targets = self.compose_pane.component.get_cross_post_targets();
+        let draft = self.compose_pane.component.close_draft();
+        self.pop_focus();
+        self.tweet_pane_stack.component.invalidate();
+
+        if let (Some(draft), true) = (draft, post) {
+            self.do_post_draft(draft, cross_post_targets);
+        }
+    }
+
+    /// Opens $EDITOR on [draft].  The editor writes straight to the draft file, so every save is
+    /// persisted.  Empty drafts are discarded; returns the draft if it's still around.
+    fn do_edit_draft(&mut self, mut draft: Draft) -> Option<Draft> {
+        let editor = env::var("EDITOR").unwrap_or("vi".to_string());
+
+        // CR: also handle the errors here
+        let mut subshell = process::Command::new(editor)
+            .arg(&draft.path)
+            .spawn()
+            .unwrap();
+        subshell.wait().unwrap();
+        self.invalidate();
+
+        let result = draft.reload().and_then(|()| {
+            if compose::split_thread(&draft.text).is_empty() {
+                draft.delete()?;
+                Ok(None)
+            } else {
+                Ok(Some(draft))
+            }
+        });
+        self.drafts_pane.component.reload();
+
+        match result {
+            Ok(draft) => draft,
+            Err(err) => {
+                self.events.send(InternalEvent::LogError(err)).unwrap();
+                None
+            }
+        }
+    }
+
+    /// Posts [draft] as a thread, splitting on [compose::THREAD_DELIMITER] lines.  The draft is
+    /// deleted only once the whole thread is posted.  Then it's cross-posted to each of
+    /// [cross_post_targets] in a task of its own, so one network failing doesn't hold up the others.
+    fn do_post_draft(&self, draft: Draft, cross_post_targets: Vec<cross_post::Target>) {
+        let tweets = compose::split_thread(&draft.text);
+        if tweets.is_empty() {
+            return;
+        }
+
+        let events = self.events.clone();
+        let store = self.store.clone();
+
+        let task = tokio::spawn(async move {
+            let on_progress = |progress: compose::ThreadProgress| {
+                let level = match progress {
+                    compose::ThreadProgress::Posted { .. } => NotifyLevel::Info,
+                    compose::ThreadProgress::RolledBack { .. } => NotifyLevel::Warning,
+                    _ => NotifyLevel::Error,
+                };
+                events
+                    .send(InternalEvent::Notify(level, progress.describe()))
+                    .unwrap()
+            };
+            let result = compose::post_thread(
+                &store.twitter_client,
+                &tweets,
+                draft.in_reply_to_tweet_id.clone(),
+                on_progress,
+            )
+            .await
+            .and_then(|tweet_ids| {
+                draft.delete()?;
+                Ok(tweet_ids)
+            });
+            let tweet_ids = match result {
+                Ok(tweet_ids) => tweet_ids,
+                Err(error) => {
+                    events.send(InternalEvent::LogError(error)).unwrap();
+                    return;
+                }
+            };
+            spawn_plugins(
+                &events,
+                &store,
+                PluginEvent::TweetPosted {
+                    tweet_ids,
+                    texts: tweets.clone(),
+                },
+            );
+
+            let cross_post = store.user_config.lock().unwrap().cross_post.clone();
+            for target in cross_post_targets {
+                let task_events = events.clone();
+                let cross_post = cross_post.clone();
+                let tweets = tweets.clone();
+                let task = tokio::spawn(async move {
+                    let notification = match cross_post.post_thread(target, &tweets).await {
+                        Ok(()) => (
+                            NotifyLevel::Info,
+                            format!("Cross-posted to {}", target.name()),
+                        ),
+                        Err(error) => (
+                            NotifyLevel::Error,
+                            format!("Failed to cross-post to {}: {error:#}", target.name()),
+                        ),
+                    };
+                    task_events
+                        .send(InternalEvent::Notify(notification.0, notification.1))
+                        .unwrap();
+                });
+                events
+                    .send(InternalEvent::RegisterTask(None, task))
+                    .unwrap();
+            }
+        });
+
+        self.events
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    pub fn log_selected_tweet(&self) {
+        self.events
+            .send(InternalEvent::LogTweet(self.tweet_selected_id.clone()))
+            .unwrap();
+    }
+
+    pub fn do_open_selected_tweet(&self) {
+        // NB: lol... https://developer.twitter.com/en/blog/community/2020/getting-to-the-canonical-url-for-a-tweet
+        open_in_browser(&format!(
+            "https://twitter.com/t/status/{}",
+            self.tweet_selected_id
+        ));
+    }
+
+    /// Copies the selected tweet as a snippet, see [embed].
+    pub fn do_copy_selected_tweet(&self, format: EmbedFormat) {
+        let snippet = {
+            let tweets = self.store.tweets.lock().unwrap();
+            match tweets.get(&self.tweet_selected_id) {
+                Some(tweet) => embed::embed(tweet, format),
+                None => return,
+            }
+        };
+        let event = match copy_to_clipboard(&snippet) {
+            Ok(()) => InternalEvent::Notify(
+                NotifyLevel::Info,
+                format!("Copied as {}", format.describe()),
+            ),
+            Err(err) => InternalEvent::LogError(err),
+        };
+        self.events.send(event).unwrap();
+    }
+
+    /// Opens the selected tweet's URL if it has exactly one, otherwise offers a picker.
+    pub fn do_open_selected_tweet_urls(&mut self) {
+        let urls = {
+            let tweets = self.store.tweets.lock().unwrap();
+            match tweets.get(&self.tweet_selected_id) {
+                Some(tweet) => tweet.urls(),
+                None => return,
+            }
+        };
+
+        match urls.as_slice() {
+            [] => self
+                .events
+                .send(InternalEvent::LogError(anyhow!("No URLs in tweet")))
+                .unwrap(),
+            [url] => open_in_browser(url),
+            _ => {
+                let items = urls
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, url)| (format!("{}. {url}", i + 1), url))
+                    .collect();
+                self.url_picker.component.set_items(items);
+                self.push_focus(Focus::UrlPicker);
+            }
+        }
+    }
+
+    fn do_close_url_picker(&mut self, url: Option<String>) {
+        self.pop_focus();
+        self.tweet_pane_stack.component.invalidate();
+
+        if let Some(url) = url {
+            open_in_browser(&url);
+        }
+    }
+}
+
+/// Runs the plugins on [event] in the background, passing on the commands they print.
+///
+/// NB: [PluginEvent::TweetSelected] waits out [TWEET_SELECTED_DEBOUNCE] first, and supersedes the
+/// previous one, plugins and all, so only where the cursor comes to rest is sent.
+fn spawn_plugins(events: &UnboundedSender<InternalEvent>, store: &Store, event: PluginEvent) {
+    let plugins = store.user_config.lock().unwrap().plugins.clone();
+    if plugins.is_empty() {
+        return;
+    }
+    let task_key = match event {
+        PluginEvent::TweetSelected { .. } => Some(TaskKey::TweetSelected),
+        _ => None,
+    };
+    let task_events = events.clone();
+    let task = tokio::spawn(async move {
+        if let PluginEvent::TweetSelected { .. } = event {
+            tokio::time::sleep(TWEET_SELECTED_DEBOUNCE).await;
+        }
+        let (commands, errors) = plugins::run_plugins(&plugins, &event).await;
+        for command in commands {
+            task_events
+                .send(InternalEvent::PluginCommand(command))
+                .unwrap();
+        }
+        for error in errors {
+            task_events.send(InternalEvent::LogError(error)).unwrap();
+        }
+    });
+    events
+        .send(InternalEvent::RegisterTask(task_key, task))
+        .unwrap();
+}
+
+pub fn open_in_browser(url: &str) {
+    process::Command::new("open")
+        .arg(url)
+        .output()
+        .unwrap_or_else(|_| panic!("Failed to open url in browser: {url}"));
+}
+
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut child = process::Command::new("pbcopy")
+        .stdin(process::Stdio::piped())
+        .spawn()
+        .map_err(|err| anyhow!("Failed to copy to the clipboard: {err}"))?;
+    child.stdin.take().unwrap().write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("Failed to copy to the clipboard: pbcopy {status}"));
+    }
+    Ok(())
+}
+
+/// Whether [tweet]'s author or text contains [filter], which is lowercase.
+fn tweet_matches(tweet: &api::Tweet, filter: &str) -> bool {
+    [
+        tweet.author_username.as_deref(),
+        tweet.author_name.as_deref(),
+        Some(tweet.text.as_str()),
+    ]
+    .into_iter()
+    .flatten()
+    .any(|field| field.to_lowercase().contains(filter))
+}
+
+/// The tweet id in a tweet's URL, e.g. https://twitter.com/rustlang/status/123?s=20, or a bare id.
+/// NB: a bare id has to be snowflake-sized, so that searches for e.g. "1984" still search
+fn parse_tweet_permalink(search_term: &str) -> Option<String> {
+    let re = Regex::new(
+        r"^(?:(?:https?://)?(?:www\.|mobile\.)?(?:twitter|x)\.com/\w+/status(?:es)?/(\d+)(?:[/?#].*)?|(\d{10,}))$",
+    )
+    .unwrap();
+    let captures = re.captures(search_term.trim())?;
+    let tweet_id = captures.get(1).or(captures.get(2))?;
+    Some(tweet_id.as_str().to_string())
+}
+
+/// A line of the feed grouped by conversation: when it last grew, who's in it and how it started.
+fn conversation_line(
+    conversation: &Conversation,
+    tweets: &HashMap<String, api::Tweet>,
+    theme: &user_config::Theme,
+    width: usize,
+    is_collapsed: &dyn Fn(&api::Tweet) -> bool,
+) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    if let Some(newest) = store::shown_tweet(tweets, &conversation.tweet_ids[0]) {
+        let tweet_time = newest.local_created_at().format("%m-%d %H:%M:%S");
+        segments.push(TextSegment::color(
+            &format!("{tweet_time}  >  "),
+            Colors::new(theme.timestamp.0.into(), Color::Reset),
+        ));
+    }
+    segments.push(TextSegment::color(
+        &format!("💬 {} ", conversation.tweet_ids.len()),
+        Colors::new(Color::DarkGrey, Color::Reset),
+    ));
+    segments.push(TextSegment::color(
+        &format!("{}: ", conversation.describe()),
+        Colors::new(theme.author.0.into(), Color::Reset),
+    ));
+
+    // NB: the tweet that started it, or else the earliest one loaded
+    let first = tweets.get(&conversation.conversation_id).or_else(|| {
+        let tweet_id = conversation.tweet_ids.last()?;
+        store::shown_tweet(tweets, tweet_id)
+    });
+    let used_length = segments.iter().map(TextSegment::width).sum::<usize>();
+    let remaining_length = width.saturating_sub(used_length);
+    match first {
+        Some(first) if is_collapsed(first) => {
+            segments.push(tweet_text::sensitive_placeholder(remaining_length))
+        }
+        Some(first) => segments.append(&mut tweet_text::one_line(first, remaining_length)),
+        None => {}
+    }
+    segments
+}
+
+/// A line of the feed when it isn't grouped.
+enum FeedLine<'a> {
+    Tweet(&'a String),
+    /// Likely spam, in feed order, behind one line
+    Hidden(Vec<String>),
+}
+
+/// e.g. "02-01 00:05:00  >  show 3 filtered tweets  (likely spam: link only; x to show)"
+fn hidden_run_line(
+    run: &[String],
+    signals: &[Signal],
+    tweets: &HashMap<String, api::Tweet>,
+    theme: &user_config::Theme,
+) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    if let Some(first) = tweets.get(&run[0]) {
+        let tweet_time = first.local_created_at().format("%m-%d %H:%M:%S");
+        segments.push(TextSegment::color(
+            &format!("{tweet_time}  >  "),
+            Colors::new(theme.timestamp.0.into(), Color::Reset),
+        ));
+    }
+    let signals: Vec<&str> = signals.iter().map(Signal::describe).collect();
+    segments.push(TextSegment::color(
+        &format!(
+            "show {} filtered tweet{}  (likely spam: {}; x to show)",
+            run.len(),
+            if run.len() == 1 { "" } else { "s" },
+            signals.join(", ")
+        ),
+        Colors::new(Color::DarkGrey, Color::Reset),
+    ));
+    segments
+}
+
+/// The line for [author] when grouped by author, e.g. "02-01 00:05:00  >  ▸ 12 (30%) @alice: …"
+/// with [newest], their newest tweet shown; [num_tweets] is how many are in the feed.
+fn author_line(
+    author: &AuthorGroup,
+    newest: Option<&api::Tweet>,
+    num_tweets: usize,
+    is_expanded: bool,
+    theme: &user_config::Theme,
+    width: usize,
+    is_collapsed: &dyn Fn(&api::Tweet) -> bool,
+) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    if let Some(newest) = newest {
+        let tweet_time = newest.local_created_at().format("%m-%d %H:%M:%S");
+        segments.push(TextSegment::color(
+            &format!("{tweet_time}  >  "),
+            Colors::new(theme.timestamp.0.into(), Color::Reset),
+        ));
+    }
+    let share = author.tweet_ids.len() * 100 / num_tweets.max(1);
+    segments.push(TextSegment::color(
+        &format!(
+            "{} {} ({share}%) ",
+            if is_expanded { "▾" } else { "▸" },
+            author.tweet_ids.len()
+        ),
+        Colors::new(Color::DarkGrey, Color::Reset),
+    ));
+    segments.push(TextSegment::color(
+        &format!("@{}: ", author.username),
+        Colors::new(theme.author.0.into(), Color::Reset),
+    ));
+
+    let used_length = segments.iter().map(TextSegment::width).sum::<usize>();
+    let remaining_length = width.saturating_sub(used_length);
+    match newest {
+        Some(newest) if is_collapsed(newest) => {
+            segments.push(tweet_text::sensitive_placeholder(remaining_length))
+        }
+        Some(newest) => segments.append(&mut tweet_text::one_line(newest, remaining_length)),
+        None => {}
+    }
+    segments
+}
+
+/// e.g. "♻ 12  ♥ 1.2k  💬 6"
+fn format_public_metrics(metrics: &api::PublicMetrics) -> String {
+    format!(
+        "♻ {}  ♥ {}  💬 {}",
+        humanize_count(metrics.retweet_count),
+        humanize_count(metrics.like_count),
+        humanize_count(metrics.reply_count)
+    )
+}
+
+/// Abbreviates large counts to at most four columns, e.g. 1234 as "1.2k" and 345678 as "345k".
+/// Rounds down, so a count never looks bigger than it is.
+fn humanize_count(count: i32) -> String {
+    let count = count.max(0);
+    let abbreviate = |unit: i32, suffix: &str| {
+        if count < 10 * unit {
+            let tenths = count / (unit / 10);
+            if tenths % 10 == 0 {
+                format!("{}{suffix}", tenths / 10)
+            } else {
+                format!("{}.{}{suffix}", tenths / 10, tenths % 10)
+            }
+        } else {
+            format!("{}{suffix}", count / unit)
+        }
+    };
+
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => abbreviate(1_000, "k"),
+        _ => abbreviate(1_000_000, "M"),
+    }
+}
+
+impl FeedPane {
+    /// How [width] is shared between the feed and the tweet pane (or whatever's in its place).
+    fn layout(&self, width: u16) -> PaneLayout {
+        let tweet_pane_has_focus = matches!(
+            self.get_focus(),
+            Focus::TweetPaneStack | Focus::DraftsPane | Focus::ComposePane | Focus::UrlPicker
+        );
+        if width < MIN_SPLIT_WIDTH && tweet_pane_has_focus {
+            PaneLayout::TweetPaneOnly
+        } else if width < MIN_SPLIT_WIDTH || (self.is_column && !tweet_pane_has_focus) {
+            PaneLayout::FeedOnly
+        } else {
+            PaneLayout::Split
+        }
+    }
+
+    /// The feed, with its scrollbar in the column just right of [bounding_box].
+    fn render_feed(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        self.scroll_buffer.render(stdout, bounding_box)?;
+        scroll_buffer::render_scrollbar(
+            stdout,
+            self.scroll_buffer.viewport(),
+            bounding_box.left + bounding_box.width,
+            bounding_box.top,
+            bounding_box.height,
+        )
+    }
+}
+
+impl Render for FeedPane {
+    fn should_render(&self) -> bool {
+        self.should_update_scroll_buffer.load(Ordering::SeqCst)
+            || self.scroll_buffer.should_render()
+            || self.tweet_pane_stack.component.should_render()
+            || self.search_bar.component.should_render()
+            || self.filter_bar.component.should_render()
+            || self.drafts_pane.component.should_render()
+            || self.compose_pane.component.should_render()
+            || self.tweet_finder.component.should_render()
+            || self.url_picker.component.should_render()
+            || self.should_render
+    }
+
+    fn invalidate(&mut self) {
+        self.scroll_buffer.invalidate();
+        self.tweet_pane_stack.component.invalidate();
+        self.search_bar.component.invalidate();
+        self.filter_bar.component.invalidate();
+        self.drafts_pane.component.invalidate();
+        self.compose_pane.component.invalidate();
+        self.tweet_finder.component.invalidate();
+        self.url_picker.component.invalidate();
+        self.should_render = true;
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        // CR-someday: does using SeqCst have a performance impact?  Frankly, we already use Mutex
+        // in the render loop, so I'm not sure it matters.
+        let BoundingBox { left, width, .. } = bounding_box;
+        let layout = self.layout(width);
+        // NB: whatever comes back into view was drawn over
+        if self.last_layout != Some(layout) {
+            self.last_layout = Some(layout);
+            self.invalidate();
+        }
+        let feed_width = match layout {
+            PaneLayout::Split => ((width as usize) / 2).saturating_sub(1),
+            PaneLayout::FeedOnly => (width as usize).saturating_sub(1),
+            PaneLayout::TweetPaneOnly => self.display_width,
+        };
+
+        if self.should_update_scroll_buffer.load(Ordering::SeqCst)
+            || self.display_width != feed_width
+        {
+            self.display_width = feed_width;
+            self.update_scroll_buffer();
+            if self.pending_position.is_some() {
+                self.apply_pending_position();
+            }
+            let goto = self.goto.lock().unwrap().clone();
+            if let Goto::Found(tweet_id) = goto {
+                *self.goto.lock().unwrap() = Goto::Idle;
+                self.select_tweet(&tweet_id);
+            }
+        }
+
+        if layout == PaneLayout::TweetPaneOnly {
+            // NB: nothing of the feed is shown
+        } else if self.get_focus() == Focus::TweetFinder {
+            self.tweet_finder.bounding_box = BoundingBox {
+                width: feed_width as u16,
+                ..bounding_box
+            };
+            self.tweet_finder.render_if_necessary(stdout)?;
+        } else if self.get_focus() == Focus::SearchBar {
+            // CR: this bounding_box concept is superfluous
+            self.search_bar.bounding_box = BoundingBox {
+                width: feed_width as u16,
+                height: 1,
+                ..bounding_box
+            };
+            self.search_bar.render_if_necessary(stdout)?;
+
+            // CR: need a generic [clear] method
+            let str_clear = " ".repeat(feed_width);
+            queue!(stdout, cursor::MoveTo(left, bounding_box.top + 1))?;
+            queue!(stdout, style::Print(str_clear))?;
+
+            self.render_feed(
                 stdout,
                 BoundingBox {
-                    width: half_width as u16,
+                    width: feed_width as u16,
                     top: bounding_box.top + 2,
                     height: bounding_box.height.saturating_sub(2),
                     ..bounding_box
                 },
             )?;
         } else {
-            self.scroll_buffer.render(
+            self.render_feed(
                 stdout,
                 BoundingBox {
-                    width: half_width as u16,
+                    width: feed_width as u16,
                     ..bounding_box
                 },
             )?;
+            // NB: over the feed's header, so after it, whether or not the bar changed
+            if self.get_focus() == Focus::FilterBar {
+                self.filter_bar.bounding_box = BoundingBox {
+                    width: feed_width as u16,
+                    height: 1,
+                    ..bounding_box
+                };
+                self.filter_bar
+                    .component
+                    .render(stdout, self.filter_bar.bounding_box)?;
+            }
         }
 
-        self.tweet_pane.bounding_box = BoundingBox {
-            left: left + (half_width as u16) + 1,
-            width: half_width.saturating_sub(2) as u16,
-            ..bounding_box
+        let right_bounding_box = match layout {
+            PaneLayout::Split => BoundingBox {
+                left: left + (feed_width as u16) + 1,
+                width: feed_width.saturating_sub(2) as u16,
+                ..bounding_box
+            },
+            _ => BoundingBox {
+                width: width.saturating_sub(1),
+                ..bounding_box
+            },
         };
-        self.tweet_pane.render_if_necessary(stdout)?;
+        match self.get_focus() {
+            _ if layout == PaneLayout::FeedOnly => {}
+            Focus::DraftsPane => {
+                self.drafts_pane.bounding_box = right_bounding_box;
+                self.drafts_pane.render_if_necessary(stdout)?;
+            }
+            Focus::ComposePane => {
+                self.compose_pane.bounding_box = right_bounding_box;
+                self.compose_pane.render_if_necessary(stdout)?;
+            }
+            Focus::UrlPicker => {
+                self.url_picker.bounding_box = right_bounding_box;
+                self.url_picker.render_if_necessary(stdout)?;
+            }
+            _ => {
+                self.tweet_pane_stack.bounding_box = right_bounding_box;
+                self.tweet_pane_stack.render_if_necessary(stdout)?;
+            }
+        }
 
         stdout.flush()?;
         Ok(())
     }
 
+    fn is_animating(&self) -> bool {
+        *self.store.load_state.lock().unwrap() == LoadState::Loading
+    }
+
+    /// Advances the loading spinner.
+    fn tick(&mut self) {
+        if let (true, Some(status_line)) = (self.is_animating(), self.status_line()) {
+            self.scroll_buffer
+                .set_line(self.visible_feed.len(), status_line);
+        }
+    }
+
     fn get_cursor(&self) -> (u16, u16) {
-        match self.focus {
+        match self.get_focus() {
             Focus::FeedPane => self.scroll_buffer.get_cursor(),
-            Focus::TweetPaneStack => self.tweet_pane.get_cursor(),
+            Focus::TweetPaneStack => self.tweet_pane_stack.get_cursor(),
             Focus::SearchBar => self.search_bar.get_cursor(),
+            Focus::FilterBar => self.filter_bar.get_cursor(),
+            Focus::DraftsPane => self.drafts_pane.get_cursor(),
+            Focus::ComposePane => self.compose_pane.get_cursor(),
+            Focus::TweetFinder => self.tweet_finder.get_cursor(),
+            Focus::UrlPicker => self.url_picker.get_cursor(),
+        }
+    }
+}
+
+impl FocusContainer for FeedPane {
+    type Focus = Focus;
+
+    fn focus_ring(&self) -> &FocusRing<Focus> {
+        &self.focus
+    }
+
+    fn focus_ring_mut(&mut self) -> &mut FocusRing<Focus> {
+        &mut self.focus
+    }
+
+    fn child(&mut self, focus: Focus) -> &mut dyn Input {
+        match focus {
+            Focus::FeedPane => &mut self.scroll_buffer,
+            Focus::TweetPaneStack => &mut self.tweet_pane_stack.component,
+            Focus::SearchBar => &mut self.search_bar.component,
+            Focus::FilterBar => &mut self.filter_bar.component,
+            Focus::DraftsPane => &mut self.drafts_pane.component,
+            Focus::ComposePane => &mut self.compose_pane.component,
+            Focus::TweetFinder => &mut self.tweet_finder.component,
+            Focus::UrlPicker => &mut self.url_picker.component,
         }
     }
 }
 
 impl Input for FeedPane {
     fn handle_focus(&mut self) {
-        match self.focus {
-            Focus::FeedPane => self.scroll_buffer.handle_focus(),
-            Focus::TweetPaneStack => self.tweet_pane.component.handle_focus(),
-            Focus::SearchBar => self.search_bar.component.handle_focus(),
-        }
+        self.focus_child();
     }
 
     fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
         match event.code {
-            KeyCode::Tab => {
-                let next_focus = match self.focus {
-                    Focus::FeedPane => Focus::TweetPaneStack,
-                    Focus::TweetPaneStack => Focus::FeedPane,
-                    Focus::SearchBar => Focus::SearchBar,
-                };
-                self.focus = next_focus;
-                self.handle_focus();
-            }
-            _ => match self.focus {
-                Focus::FeedPane => match event.code {
-                    KeyCode::Char('i') => self.log_selected_tweet(),
-                    KeyCode::Char('o') => self.do_open_selected_tweet(),
-                    KeyCode::Char('n') => self.do_load_page_of_tweets(false),
-                    KeyCode::Char('r') => self.do_load_page_of_tweets(true),
-                    KeyCode::Char('S') => self.do_toggle_selected_tweet_starred(),
-                    KeyCode::Char('s') => self.do_search_starred_accounts(true),
-                    KeyCode::Char('/') => {
-                        self.focus = Focus::SearchBar;
-                        self.handle_focus();
-                        self.should_render = true;
-                    }
-                    KeyCode::Char('*') => {
-                        {
-                            let user_config = self.store.user_config.lock().unwrap();
-                            let starred_accounts = user_config.starred_accounts.values();
-                            let out = starred_accounts
-                                .map(|user| format!("@{} [{}]", user.username, user.name))
-                                .collect::<Vec<String>>()
-                                .join("\n");
-                            // CR: okay, maybe handle the error here
-                            fs::write("/tmp/starred_accounts", out).unwrap();
-                        }
-
-                        // CR: also handle the errors here
-                        let mut subshell = process::Command::new("less")
-                            .args(["/tmp/starred_accounts"])
-                            .spawn()
-                            .unwrap();
-                        subshell.wait().unwrap();
-                    }
-                    _ => {
+            KeyCode::Tab | KeyCode::BackTab => {
+                // NB: without room for both, the tweet pane comes and goes with focus
+                self.should_render = true;
+                return self.route_key_event(event);
+            }
+            _ => match self.get_focus() {
+                Focus::FeedPane => match self.key_map.get(event) {
+                    Some(action) => return self.dispatch(action),
+                    None => {
                         let handled = self.scroll_buffer.handle_key_event(event);
 
                         if let Some(tweet_id) = self.get_selected_tweet_id() {
-                            self.tweet_selected_id = tweet_id.clone();
-                            self.tweet_pane.component.set_tweet_id(&tweet_id);
+                            self.set_selected_tweet_id(&tweet_id);
                         }
 
                         return handled;
                     }
                 },
-                Focus::TweetPaneStack => return self.tweet_pane.component.handle_key_event(event),
-                Focus::SearchBar => match event.code {
-                    KeyCode::Esc => {
-                        self.focus = Focus::FeedPane;
-                        self.handle_focus();
+                Focus::TweetPaneStack => return self.route_key_event(event),
+                Focus::UrlPicker => match event.code {
+                    KeyCode::Esc => self.do_close_url_picker(None),
+                    KeyCode::Enter => {
+                        let url = self.url_picker.component.get_selected();
+                        self.do_close_url_picker(url);
+                    }
+                    // NB: digits pick by number, unless the user is already filtering
+                    KeyCode::Char(ch)
+                        if ch.is_ascii_digit()
+                            && self.url_picker.component.get_query().is_empty() =>
+                    {
+                        let index = (ch.to_digit(10).unwrap() as usize).checked_sub(1);
+                        if let Some(url) = index.and_then(|i| self.url_picker.component.get_item(i))
+                        {
+                            self.do_close_url_picker(Some(url));
+                        }
+                    }
+                    _ => return self.route_key_event(event),
+                },
+                Focus::TweetFinder => match event.code {
+                    KeyCode::Esc => self.do_close_tweet_finder(None),
+                    KeyCode::Enter => {
+                        let tweet_id = self.tweet_finder.component.get_selected();
+                        self.do_close_tweet_finder(tweet_id);
+                    }
+                    _ => return self.route_key_event(event),
+                },
+                Focus::ComposePane => {
+                    let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+                    match event.code {
+                        KeyCode::Esc => self.do_close_compose_pane(false),
+                        KeyCode::Char('s') if ctrl => self.do_close_compose_pane(false),
+                        KeyCode::Char('o') if ctrl => self.do_close_compose_pane(true),
+                        _ => return self.route_key_event(event),
                     }
+                }
+                Focus::DraftsPane => {
+                    let selected_draft = self.drafts_pane.component.get_selected_draft().cloned();
+                    match (event.code, selected_draft) {
+                        (KeyCode::Esc | KeyCode::Char('D'), _) => {
+                            self.pop_focus();
+                            self.tweet_pane_stack.component.invalidate();
+                        }
+                        (KeyCode::Enter, Some(draft)) => self.do_resume_draft(draft),
+                        (KeyCode::Char('e'), Some(draft)) => {
+                            self.do_edit_draft(draft);
+                        }
+                        (KeyCode::Char('p'), Some(draft)) => self.do_post_draft(draft, Vec::new()),
+                        (KeyCode::Char('x') | KeyCode::Delete, Some(draft)) => {
+                            self.do_delete_draft(draft)
+                        }
+                        _ => return self.route_key_event(event),
+                    }
+                }
+                Focus::SearchBar => match event.code {
+                    KeyCode::Esc => self.pop_focus(),
                     KeyCode::Enter => {
                         self.do_search();
                         self.search_bar.component.clear();
-                        self.focus = Focus::FeedPane;
-                        self.handle_focus();
+                        self.pop_focus();
                     }
-                    _ => return self.search_bar.component.handle_key_event(event),
+                    _ => return self.route_key_event(event),
                 },
+                Focus::FilterBar => {
+                    match event.code {
+                        KeyCode::Esc => {
+                            self.filter_bar.component.clear();
+                            self.pop_focus();
+                        }
+                        KeyCode::Enter => self.pop_focus(),
+                        _ => {
+                            if !self.route_key_event(event) {
+                                return false;
+                            }
+                        }
+                    }
+                    self.redraw_feed();
+                }
             },
         };
         true
@@ -419,6 +2380,9 @@ impl Input for FeedPane {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::fixtures;
+    use crate::ui_framework::testing;
+    use tokio::sync::mpsc;
 
     #[test]
     fn test_regex() {
@@ -428,4 +2392,442 @@ mod tests {
         let expected = "Detected new closed trade⏎ Trader: @Burgerinnn⏎ Symbol: $ETH⏎ Position: short ↘\u{fe0f}⏎ Entry: 1 500.6⏎ Exit: 1 498.2⏎ Profit: 3 994⏎ Leverage: 10x⏎ Entry, take profit, stats, leaderboard can be found at https://t.co/EFjrCz4DgD";
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_humanize_count() {
+        assert_eq!(humanize_count(0), "0");
+        assert_eq!(humanize_count(999), "999");
+        assert_eq!(humanize_count(1_000), "1k");
+        assert_eq!(humanize_count(1_250), "1.2k");
+        assert_eq!(humanize_count(9_999), "9.9k");
+        assert_eq!(humanize_count(12_345), "12k");
+        assert_eq!(humanize_count(999_999), "999k");
+        assert_eq!(humanize_count(1_050_000), "1M");
+        assert_eq!(humanize_count(23_456_789), "23M");
+    }
+
+    #[tokio::test]
+    async fn test_render_feed() {
+        let store = fixtures::store(vec![
+            fixtures::tweet(
+                "2",
+                "bob",
+                "a reply that is far too long to fit on one line",
+                2,
+            ),
+            fixtures::tweet("1", "alice", "hello\nworld", 1),
+        ]);
+        store
+            .user_config
+            .lock()
+            .unwrap()
+            .star_account(&fixtures::user("id_alice", "alice"));
+        *store.load_state.lock().unwrap() = LoadState::Failed("boom".to_string());
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        testing::render_to_screen(&mut feed_pane, 100, 6);
+        feed_pane.handle_key_event(&KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        feed_pane.invalidate();
+
+        let screen = testing::render_to_screen(&mut feed_pane, 100, 6);
+        assert_eq!(
+            screen.to_text(),
+            [
+                "02-01 00:02:00  >  @bob a reply that is far too…  @alice [1]",
+                "02-01 00:01:00  >  @alice hello⏎ world            2023-02-01 00:01:00",
+                "! load failed: boom (R to retry)                  @alice [ALICE]",
+                "",
+                "                                                  hello",
+                "                                                  world",
+            ]
+            .join("\n")
+        );
+        // NB: starred authors are yellow, others cyan
+        assert_eq!(screen.style_at(20, 0), "0;38;5;6");
+        assert_eq!(screen.style_at(20, 1), "0;38;5;11");
+        assert_eq!(screen.style_at(0, 2), "0;38;5;9");
+    }
+
+    #[tokio::test]
+    async fn test_group_by_conversation() {
+        let reply = |id: &str, username: &str, minute: u32| api::Tweet {
+            conversation_id: Some("1".to_string()),
+            ..fixtures::tweet(id, username, "a reply", minute)
+        };
+        let store = fixtures::store(vec![
+            reply("3", "carol", 3),
+            fixtures::tweet("2", "dave", "unrelated", 2),
+            reply("1", "alice", 1),
+        ]);
+        store.tweets.lock().unwrap().get_mut("1").unwrap().text = "the start".to_string();
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        feed_pane.toggle_group_by_conversation();
+
+        let screen = testing::render_to_screen(&mut feed_pane, 200, 6);
+        let text = screen.to_text();
+        // NB: just the feed, not the tweet pane beside it
+        let lines: Vec<String> = text
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .take(80)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        assert!(lines[0].starts_with("by conversation"));
+        assert_eq!(
+            lines[1],
+            "02-01 00:03:00  >  💬 2 @carol and @alice are discussing: the start"
+        );
+        assert_eq!(
+            lines[2],
+            "02-01 00:02:00  >  💬 1 @dave is discussing: unrelated"
+        );
+        assert_eq!(feed_pane.visible_feed, vec!["3", "2"]);
+    }
+
+    #[tokio::test]
+    async fn test_group_by_author() {
+        let store = fixtures::store(vec![
+            fixtures::tweet("4", "alice", "newest", 4),
+            fixtures::tweet("3", "bob", "hello", 3),
+            fixtures::tweet("2", "alice", "older", 2),
+            fixtures::tweet("1", "alice", "oldest", 1),
+        ]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        feed_pane.toggle_group_by_author();
+        let lines = |feed_pane: &mut FeedPane| -> Vec<String> {
+            let screen = testing::render_to_screen(feed_pane, 200, 7);
+            // NB: just the feed, not the tweet pane beside it
+            screen
+                .to_text()
+                .lines()
+                .map(|line| {
+                    line.chars()
+                        .take(80)
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        let screen_lines = lines(&mut feed_pane);
+        assert!(screen_lines[0].starts_with("by author"));
+        assert_eq!(
+            screen_lines[1],
+            "02-01 00:04:00  >  ▸ 3 (75%) @alice: newest"
+        );
+        assert_eq!(screen_lines[2], "02-01 00:03:00  >  ▸ 1 (25%) @bob: hello");
+        assert_eq!(feed_pane.visible_feed, vec!["4", "3"]);
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        feed_pane.handle_key_event(&key(KeyCode::Up));
+        feed_pane.handle_key_event(&key(KeyCode::Char('t')));
+        let screen_lines = lines(&mut feed_pane);
+        assert_eq!(
+            screen_lines[1],
+            "02-01 00:04:00  >  ▾ 3 (75%) @alice: newest"
+        );
+        assert_eq!(screen_lines[2], "    02-01 00:02:00  >  older");
+        assert_eq!(screen_lines[3], "    02-01 00:01:00  >  oldest");
+        assert_eq!(feed_pane.visible_feed, vec!["4", "2", "1", "3"]);
+
+        // NB: and back to a line per tweet
+        feed_pane.toggle_group_by_author();
+        lines(&mut feed_pane);
+        assert_eq!(feed_pane.visible_feed, vec!["4", "3", "2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn test_sensitive_tweets() {
+        let store = fixtures::store(vec![api::Tweet {
+            possibly_sensitive: Some(true),
+            ..fixtures::tweet("1", "alice", "spoiler", 1)
+        }]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        let screen = testing::render_to_screen(&mut feed_pane, 200, 6);
+        assert!(screen
+            .to_text()
+            .starts_with("02-01 00:01:00  >  @alice [sensitive content — press x to reveal]"));
+        assert!(!screen.to_text().contains("spoiler"));
+
+        // NB: moving the cursor selects the tweet
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        feed_pane.handle_key_event(&key(KeyCode::Up));
+        feed_pane.handle_key_event(&key(KeyCode::Char('x')));
+        let screen = testing::render_to_screen(&mut feed_pane, 200, 6);
+        assert!(screen
+            .to_text()
+            .starts_with("02-01 00:01:00  >  @alice spoiler"));
+
+        store.user_config.lock().unwrap().filters.sensitive = user_config::SensitiveContent::Show;
+        feed_pane.dispatch(Action::RevealSensitive);
+        assert!(!store.is_collapsed(&store.get_tweet("1").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_hides_likely_spam() {
+        let bot = |id: &str, minute: u32| api::Tweet {
+            author_created_at: Some(Utc::now()),
+            ..fixtures::tweet(id, "bot", "https://t.co/abc", minute)
+        };
+        let store = fixtures::store(vec![
+            fixtures::tweet("4", "alice", "hello", 4),
+            bot("3", 3),
+            bot("2", 2),
+            fixtures::tweet("1", "alice", "https://t.co/abc", 1),
+        ]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        let lines = |feed_pane: &mut FeedPane| -> Vec<String> {
+            let screen = testing::render_to_screen(feed_pane, 200, 6);
+            // NB: just the feed, not the tweet pane beside it
+            screen
+                .to_text()
+                .lines()
+                .map(|line| {
+                    line.chars()
+                        .take(100)
+                        .collect::<String>()
+                        .trim_end()
+                        .to_string()
+                })
+                .collect()
+        };
+
+        let screen_lines = lines(&mut feed_pane);
+        assert_eq!(
+            screen_lines[1],
+            "02-01 00:03:00  >  show 2 filtered tweets  (likely spam: new account, link only; x to show)"
+        );
+        assert_eq!(feed_pane.visible_feed, vec!["4", "3", "1"]);
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        feed_pane.handle_key_event(&key(KeyCode::Up));
+        feed_pane.handle_key_event(&key(KeyCode::Down));
+        feed_pane.handle_key_event(&key(KeyCode::Char('x')));
+        lines(&mut feed_pane);
+        assert_eq!(feed_pane.visible_feed, vec!["4", "3", "2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn test_column() {
+        let text = "a tweet long enough to be cut short when the feed is only half the width";
+        let store = fixtures::store(vec![fixtures::tweet("1", "alice", text, 1)]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        feed_pane.set_column(true);
+        let screen = testing::render_to_screen(&mut feed_pane, 120, 6);
+        assert!(screen
+            .to_text()
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with("half the width"));
+
+        // NB: the tweet pane takes the right half back while it has focus
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        feed_pane.handle_key_event(&key(KeyCode::Tab));
+        let screen = testing::render_to_screen(&mut feed_pane, 120, 6);
+        let first_line: String = screen
+            .to_text()
+            .lines()
+            .next()
+            .unwrap()
+            .chars()
+            .take(59)
+            .collect();
+        assert_eq!(
+            first_line.trim_end(),
+            "02-01 00:01:00  >  @alice a tweet long enough to be cut…"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_narrow() {
+        let store = fixtures::store(vec![fixtures::tweet("1", "alice", "hello", 1)]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        let screen = testing::render_to_screen(&mut feed_pane, 60, 6);
+        assert_eq!(
+            screen.to_text().lines().next().unwrap(),
+            "02-01 00:01:00  >  @alice hello"
+        );
+
+        // NB: the tweet pane takes the whole width while it has focus
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        feed_pane.handle_key_event(&key(KeyCode::Tab));
+        let screen = testing::render_to_screen(&mut feed_pane, 60, 6);
+        assert!(!screen.to_text().contains("02-01 00:01:00  >"));
+        feed_pane.handle_key_event(&key(KeyCode::Tab));
+        let screen = testing::render_to_screen(&mut feed_pane, 60, 6);
+        assert!(screen
+            .to_text()
+            .starts_with("02-01 00:01:00  >  @alice hello"));
+    }
+
+    #[tokio::test]
+    async fn test_wants_next_page() {
+        let store = fixtures::store(
+            (0..5)
+                .map(|i| fixtures::tweet(&i.to_string(), "alice", "hello", i))
+                .collect(),
+        );
+        store.user_config.lock().unwrap().prefetch_distance = 2;
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        feed_pane.set_home_timeline(true);
+        testing::render_to_screen(&mut feed_pane, 100, 10);
+
+        feed_pane.scroll_buffer.scroll_to(0, 3);
+        // NB: no next page yet
+        assert!(!feed_pane.wants_next_page());
+        *store.tweets_feed_page_token.lock().await = Some("page_2".to_string());
+        assert!(feed_pane.wants_next_page());
+        feed_pane.scroll_buffer.scroll_to(0, 2);
+        assert!(!feed_pane.wants_next_page());
+
+        feed_pane.scroll_buffer.scroll_to(0, 4);
+        *store.load_state.lock().unwrap() = LoadState::Loading;
+        assert!(!feed_pane.wants_next_page());
+        *store.load_state.lock().unwrap() = LoadState::Idle;
+        let page_token = store.tweets_feed_page_token.lock().await;
+        assert!(!feed_pane.wants_next_page());
+        drop(page_token);
+        feed_pane.set_home_timeline(false);
+        assert!(!feed_pane.wants_next_page());
+    }
+
+    #[tokio::test]
+    async fn test_filter_feed() {
+        let store = fixtures::store(vec![
+            fixtures::tweet("3", "carol", "nothing to see", 3),
+            fixtures::tweet("2", "bob", "hello world", 2),
+            fixtures::tweet("1", "alice", "Bob says hi", 1),
+        ]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        let key = |ch| KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE);
+        testing::render_to_screen(&mut feed_pane, 100, 6);
+
+        for ch in "fbob".chars() {
+            feed_pane.handle_key_event(&key(ch));
+        }
+        let screen = testing::render_to_text(&mut feed_pane, 100, 4);
+        let lines: Vec<&str> = screen
+            .lines()
+            .map(|line| &line[..36.min(line.len())])
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "filter: bob",
+                "02-01 00:02:00  >  @bob hello world",
+                "02-01 00:01:00  >  @alice Bob says h",
+            ]
+        );
+
+        feed_pane.handle_key_event(&KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        feed_pane.handle_key_event(&KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        assert_eq!(feed_pane.get_selected_tweet_id().as_deref(), Some("1"));
+        feed_pane.invalidate();
+        assert!(testing::render_to_text(&mut feed_pane, 100, 4)
+            .starts_with("filter: bob  (2 of 3, f to edit)"));
+
+        feed_pane.handle_key_event(&key('f'));
+        feed_pane.handle_key_event(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE));
+        testing::render_to_screen(&mut feed_pane, 100, 4);
+        assert_eq!(feed_pane.visible_feed, vec!["3", "2", "1"]);
+    }
+
+    #[tokio::test]
+    async fn test_mark_tweets() {
+        let store = fixtures::store(vec![
+            fixtures::tweet("4", "alice", "four", 4),
+            fixtures::tweet("3", "bob", "three", 3),
+            fixtures::tweet("2", "alice", "two", 2),
+            fixtures::tweet("1", "carol", "one", 1),
+        ]);
+        let (events, mut events_rx) = mpsc::unbounded_channel();
+        let mut feed_pane = FeedPane::new(&events, &store, &Arc::new(KeyMap::default()));
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        testing::render_to_screen(&mut feed_pane, 100, 6);
+
+        feed_pane.handle_key_event(&key(KeyCode::Char(' ')));
+        feed_pane.handle_key_event(&key(KeyCode::Down));
+        feed_pane.handle_key_event(&key(KeyCode::Down));
+        feed_pane.handle_key_event(&key(KeyCode::Char('V')));
+        let screen = testing::render_to_text(&mut feed_pane, 100, 4);
+        let lines: Vec<String> = screen
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .take(30)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "02-01 00:04:00  >  ● @alice fo",
+                "02-01 00:03:00  >  ● @bob thre",
+                "02-01 00:02:00  >  ● @alice tw",
+                "02-01 00:01:00  >  @carol one",
+            ]
+        );
+        assert!(matches!(
+            events_rx.try_recv(),
+            Ok(InternalEvent::Notify(NotifyLevel::Info, message)) if message == "3 tweets marked"
+        ));
+
+        // NB: each author once, and the marks are used up
+        feed_pane.handle_key_event(&key(KeyCode::Char('O')));
+        let mut opened = Vec::new();
+        while let Ok(InternalEvent::OpenTab(FeedSource::User(username))) = events_rx.try_recv() {
+            opened.push(username);
+        }
+        assert_eq!(opened, vec!["alice", "bob"]);
+        assert!(feed_pane.take_marked_tweet_ids().is_empty());
+    }
+
+    #[test]
+    fn test_tweet_matches() {
+        let tweet = fixtures::tweet("1", "alice", "Hello World", 1);
+        assert!(tweet_matches(&tweet, "world"));
+        assert!(tweet_matches(&tweet, "ali"));
+        assert!(!tweet_matches(&tweet, "bob"));
+    }
+
+    #[test]
+    fn test_parse_tweet_permalink() {
+        let tweet_id = |search_term| parse_tweet_permalink(search_term);
+        assert_eq!(
+            tweet_id("https://twitter.com/rustlang/status/1620000000000000000"),
+            Some("1620000000000000000".to_string())
+        );
+        assert_eq!(
+            tweet_id("x.com/rustlang/status/123?s=20&t=abc"),
+            Some("123".to_string())
+        );
+        assert_eq!(
+            tweet_id("https://mobile.twitter.com/a_b/statuses/123/photo/1"),
+            Some("123".to_string())
+        );
+        assert_eq!(
+            tweet_id(" 1620000000000000000 "),
+            Some("1620000000000000000".to_string())
+        );
+        assert_eq!(tweet_id("1984"), None);
+        assert_eq!(tweet_id("https://twitter.com/rustlang"), None);
+        assert_eq!(tweet_id("https://example.com/a/status/123"), None);
+        assert_eq!(tweet_id("@rustlang"), None);
+    }
 }
@@ -1,32 +1,80 @@
+pub mod action;
 mod bottom_bar;
+mod command_bar;
+mod compose_pane;
+mod drafts_pane;
+mod embed;
 mod feed_pane;
+mod raw_view;
 mod search_bar;
+pub mod session;
+mod tab_bar;
+mod task_manager;
+mod toasts;
+mod tweet_image;
 mod tweet_pane;
 mod tweet_pane_stack;
+mod tweet_text;
 
+use crate::analytics::Analytics;
+use crate::feed_source::FeedSource;
+use crate::followers;
+use crate::keybindings::Action;
+use crate::media_cache::{self, MediaCache};
+use crate::plugins::PluginCommand;
+use crate::store::LoadState;
 use crate::store::Store;
-use crate::twitter_client::{api, TwitterClient};
+use crate::twitter_client::quota::{self, QuotaUsage};
+use crate::twitter_client::{self, api, TwitterApi, TwitterClient};
+use crate::ui::action::KeyMap;
 use crate::ui::bottom_bar::BottomBar;
+use crate::ui::command_bar::{Command, CommandBar};
 use crate::ui::feed_pane::FeedPane;
-use crate::ui::tweet_pane::TweetPane;
+use crate::ui::raw_view::RawView;
+use crate::ui::session::{Session, TabSession};
+use crate::ui::tab_bar::TabBar;
+use crate::ui::task_manager::{TaskKey, TaskManager};
+use crate::ui::toasts::{NotifyLevel, Toasts};
+use crate::ui::tweet_image::TweetImages;
 use crate::ui_framework::bounding_box::BoundingBox;
-use crate::ui_framework::{Component, Input, Render};
-use crate::user_config::UserConfig;
+use crate::ui_framework::confirm_prompt::ConfirmPrompt;
+use crate::ui_framework::form::Form;
+use crate::ui_framework::overlay::OverlayLayer;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Component, Input, Render, FRAME_INTERVAL, TICK_INTERVAL};
+use crate::user_cache::{self, UserCache};
+use crate::user_config::{StatusSegment, UserConfig};
 use anyhow::{anyhow, Context, Error, Result};
+use chrono::{Local, Utc};
 use crossterm::cursor;
 use crossterm::event::{Event, EventStream, KeyCode, KeyEvent};
+use crossterm::style;
 use crossterm::terminal;
 use crossterm::{
     execute, queue,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
-use futures_util::stream::FuturesUnordered;
 use futures_util::{FutureExt, StreamExt};
-use std::fs;
-use std::io::{stdout, Stdout, Write};
+use std::io::{stdout, Stdout};
+use std::ops::Range;
+use std::path::Path;
 use std::process;
 use std::sync::Arc;
-use tokio::sync::mpsc::{self, UnboundedReceiver};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+use tokio::time::MissedTickBehavior;
+
+/// The most tabs shown side by side, see [Command::Columns]
+const MAX_COLUMNS: usize = 4;
+
+/// The smallest terminal laid out; anything smaller just says so until it's resized
+const MIN_COLS: u16 = 40;
+const MIN_ROWS: u16 = 8;
+
+/// How often the bottom bar's clock is checked, if it's shown
+const CLOCK_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 #[repr(u8)]
@@ -36,6 +84,52 @@ pub enum Mode {
     Interactive,
 }
 
+impl FeedSource {
+    fn load(&self, feed_pane: &mut FeedPane) {
+        feed_pane.set_activity(*self == FeedSource::Activity);
+        feed_pane.set_home_timeline(*self == FeedSource::Home);
+        feed_pane.set_search_query(match self {
+            FeedSource::Search(query) => Some(query),
+            _ => None,
+        });
+        match self {
+            FeedSource::Home => feed_pane.do_load_page_of_tweets(true),
+            FeedSource::Mentions => feed_pane.do_load_mentions(),
+            FeedSource::Activity => feed_pane.do_load_activity(false),
+            FeedSource::Search(query) => feed_pane.do_search_tweets(query, true),
+            FeedSource::User(username) => feed_pane.do_load_user_tweets(username),
+            FeedSource::List(name) if name.eq_ignore_ascii_case("starred") => {
+                feed_pane.do_search_starred_accounts(None, true)
+            }
+            FeedSource::Tag(tag) => feed_pane.do_search_starred_accounts(Some(tag), true),
+            FeedSource::Collection(name) => feed_pane.do_load_collection(name),
+            FeedSource::CatchUp(username) => feed_pane.do_load_catch_up(username),
+            FeedSource::List(name) => feed_pane.do_load_list_tweets(name),
+        }
+    }
+}
+
+/// Each tab has its own feed (sharing the tweets themselves) and its own scroll and selection.
+struct Tab {
+    feed_source: FeedSource,
+    store: Arc<Store>,
+    feed_pane: Component<FeedPane>,
+}
+
+impl Tab {
+    /// The feed's title, with the number of unread items for activity.
+    fn title(&self) -> String {
+        let title = self.feed_source.title();
+        match self.feed_source {
+            FeedSource::Activity => match self.store.num_unread_activity() {
+                0 => title,
+                num_unread => format!("{title} ({num_unread})"),
+            },
+            _ => title,
+        }
+    }
+}
+
 /// NB: not totally comfortable with this event bus architecture; the loose coupling is convenient
 /// but it introduces non-deterministic delay, and feels overly general (over time I guess there
 /// will end up being too many enum variants.
@@ -44,19 +138,61 @@ pub enum Mode {
 /// consider directly coupling those pieces together.
 #[derive(Debug)]
 pub enum InternalEvent {
-    RegisterTask(tokio::task::JoinHandle<()>),
+    /// Track a spawned task, cancelling the one in flight with the same key, if any
+    RegisterTask(Option<TaskKey>, tokio::task::JoinHandle<()>),
     LogTweet(String),
+    /// Show preformatted text full screen, with this title
+    View(String, String),
     LogError(Error),
+    /// Show a transient, non-fatal notification
+    Notify(NotifyLevel, String),
+    /// Show a yes/no prompt with the given message, sending the answer to the responder
+    Confirm(String, oneshot::Sender<bool>),
+    /// Show a form, which sends its values (or None, if cancelled) to its responder
+    Form(Form),
+    /// Show this feed in the current tab, e.g. a search for a hashtag picked out of a tweet
+    LoadFeed(FeedSource),
+    /// Show this feed in a new tab
+    OpenTab(FeedSource),
+    /// Users were muted, whose tweets every tab drops
+    UsersMuted,
+    /// Carry out a command printed by a plugin
+    PluginCommand(PluginCommand),
+    /// A note or mute changed the user config, which all tabs share, so redraw them all
+    UserConfigChanged,
+    /// Who I follow and who follows me were refreshed, so redraw the badges in all tabs
+    RelationshipsChanged,
 }
 
 pub struct UI {
     stdout: Stdout,
+    /// Everything is drawn here first, and only the changed cells are written to [stdout]
+    screen: ScreenBuffer,
     mode: Mode,
     events: UnboundedReceiver<InternalEvent>,
-    tasks: FuturesUnordered<tokio::task::JoinHandle<()>>,
+    events_tx: UnboundedSender<InternalEvent>,
+    tasks: TaskManager,
     store: Arc<Store>,
-    feed_pane: Component<FeedPane>,
+    key_map: Arc<KeyMap>,
+    tabs: Vec<Tab>,
+    active_tab: usize,
+    tab_bar: Component<TabBar>,
+    /// Area shared by the feed panes, overlays and toasts
+    feed_bounding_box: BoundingBox,
+    /// How many tabs are shown side by side, from [UI::first_column]; 1 for just the current tab
+    num_columns: usize,
+    first_column: usize,
+    /// Whether the terminal is smaller than [MIN_COLS] x [MIN_ROWS]
+    is_too_small: bool,
+    /// Whether the last key was a 'g', as in vim's gt/gT
+    pending_g: bool,
     bottom_bar: Component<BottomBar>,
+    command_bar: Component<CommandBar>,
+    command_bar_active: bool,
+    overlays: Component<OverlayLayer>,
+    toasts: Component<Toasts>,
+    /// Avatars and media, e.g. for exporting tweets as images
+    media_cache: Arc<MediaCache>,
 }
 
 impl UI {
@@ -64,34 +200,281 @@ impl UI {
         twitter_client: TwitterClient,
         twitter_user: &api::User,
         user_config: &UserConfig,
+        user_config_path: &Path,
     ) -> Self {
         let (cols, rows) = terminal::size().unwrap();
         let (events_tx, events_rx) = mpsc::unbounded_channel();
 
-        let store = Arc::new(Store::new(twitter_client, twitter_user, user_config));
+        let store = Arc::new(
+            Store::new(twitter_client, twitter_user, user_config)
+                .with_user_config_path(user_config_path)
+                // NB: a cache that won't load is just rebuilt as users are seen again
+                .with_user_cache(
+                    UserCache::load(Path::new(user_cache::USER_CACHE_PATH)).unwrap_or_default(),
+                ),
+        );
 
-        let feed_pane = FeedPane::new(&events_tx, &store);
         let bottom_bar = BottomBar::new(&store);
 
         let mut this = Self {
             stdout: stdout(),
+            screen: ScreenBuffer::new(cols, rows),
             mode: Mode::Log,
             events: events_rx,
-            tasks: FuturesUnordered::new(),
+            events_tx,
+            tasks: TaskManager::new(),
             store,
-            feed_pane: Component::new(feed_pane),
+            key_map: Arc::new(KeyMap::default().with_bindings(&user_config.keybindings)),
+            tabs: Vec::new(),
+            active_tab: 0,
+            tab_bar: Component::new(TabBar::new()),
+            feed_bounding_box: BoundingBox::default(),
+            num_columns: 1,
+            first_column: 0,
+            is_too_small: false,
+            pending_g: false,
             bottom_bar: Component::new(bottom_bar),
+            command_bar: Component::new(CommandBar::new()),
+            command_bar_active: false,
+            overlays: Component::new(OverlayLayer::new()),
+            toasts: Component::new(Toasts::new()),
+            media_cache: Arc::new(MediaCache::open(
+                Path::new(media_cache::MEDIA_CACHE_DIR),
+                user_config.media_cache_mb * 1_000_000,
+            )),
         };
 
         this.resize(cols, rows);
         this
     }
 
-    pub fn initialize(&mut self) {
-        self.feed_pane.component.do_load_page_of_tweets(true);
+    /// Opens a tab for each of [startup_feeds], showing the first.
+    pub fn initialize(&mut self, startup_feeds: Vec<FeedSource>) {
+        for feed_source in startup_feeds {
+            self.open_tab(feed_source);
+        }
+        self.switch_tab(0);
         self.set_mode(Mode::Interactive).unwrap();
     }
 
+    /// Fetches the authenticated user in the background, updating the cache the UI started with.
+    /// NB: the stores keep the cached user, so a different account needs a restart
+    pub fn refresh_twitter_user(&self) {
+        let store = self.store.clone();
+        let events = self.events_tx.clone();
+        let task = tokio::spawn(async move {
+            let result = store.twitter_client.me().await.and_then(|me| {
+                twitter_client::save_cached_me(&me)?;
+                Ok(me)
+            });
+            match result {
+                Ok(me) if me.id != store.twitter_user.id => {
+                    let message = format!(
+                        "Signed in as @{}, not @{}; restart to switch accounts",
+                        me.username, store.twitter_user.username
+                    );
+                    events
+                        .send(InternalEvent::Notify(NotifyLevel::Warning, message))
+                        .unwrap();
+                }
+                Ok(_) => (),
+                Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
+            }
+        });
+        self.events_tx
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    /// Reopens the tabs of [session], each at its saved position.
+    pub fn restore_session(&mut self, session: Session) {
+        for tab_session in session.tabs {
+            self.open_tab(tab_session.feed_source);
+            self.feed_pane()
+                .component
+                .restore_position(tab_session.position);
+        }
+        if self.tabs.is_empty() {
+            self.open_tab(FeedSource::Home);
+        }
+        self.switch_tab(session.active_tab.min(self.tabs.len() - 1));
+        self.set_mode(Mode::Interactive).unwrap();
+    }
+
+    fn session(&self) -> Session {
+        let is_saved = |tab: &&Tab| !matches!(tab.feed_source, FeedSource::CatchUp(_));
+        Session {
+            tabs: self
+                .tabs
+                .iter()
+                .filter(is_saved)
+                .map(|tab| TabSession {
+                    feed_source: tab.feed_source.clone(),
+                    position: tab.feed_pane.component.get_position(),
+                })
+                .collect(),
+            active_tab: self.tabs[..self.active_tab].iter().filter(is_saved).count(),
+        }
+    }
+
+    /// Saves the session and the user cache for the next launch, then exits.
+    fn quit(&self) -> ! {
+        let result = self.session().save(Path::new(session::SESSION_PATH));
+        let users_result =
+            (self.store.users.lock().unwrap()).save(Path::new(user_cache::USER_CACHE_PATH));
+        reset();
+        if let Err(err) = result {
+            eprintln!("Failed to save the session: {err:#}");
+        }
+        if let Err(err) = users_result {
+            eprintln!("Failed to save the user cache: {err:#}");
+        }
+        process::exit(0);
+    }
+
+    fn feed_pane(&mut self) -> &mut Component<FeedPane> {
+        &mut self.tabs[self.active_tab].feed_pane
+    }
+
+    fn open_tab(&mut self, feed_source: FeedSource) {
+        let store = Arc::new(self.store.new_feed());
+        let mut feed_pane = Component::new(FeedPane::new(&self.events_tx, &store, &self.key_map));
+        feed_pane.bounding_box = self.feed_bounding_box;
+        feed_source.load(&mut feed_pane.component);
+
+        self.tabs.push(Tab {
+            feed_source,
+            store,
+            feed_pane,
+        });
+        self.switch_tab(self.tabs.len() - 1);
+    }
+
+    /// Switches to the tab of the search for [query], or opens one, so that each search keeps its
+    /// own feed and paging rather than replacing the current feed.
+    fn open_search_tab(&mut self, query: String) {
+        let feed_source = FeedSource::Search(query);
+        match self
+            .tabs
+            .iter()
+            .position(|tab| tab.feed_source == feed_source)
+        {
+            Some(index) => self.switch_tab(index),
+            None => self.open_tab(feed_source),
+        }
+    }
+
+    fn close_tab(&mut self) {
+        if self.tabs.len() > 1 {
+            self.tabs.remove(self.active_tab);
+            self.switch_tab(self.active_tab.min(self.tabs.len() - 1));
+        }
+    }
+
+    fn switch_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() {
+            return;
+        }
+        // NB: activity stays marked unread while the user is looking at it, and is only marked
+        // seen on the way out
+        if index != self.active_tab {
+            if let Some(tab) = self.tabs.get(self.active_tab) {
+                if tab.feed_source == FeedSource::Activity {
+                    if let Err(err) = tab.store.mark_activity_seen() {
+                        self.bottom_bar.component.push_error(&err);
+                    }
+                }
+            }
+        }
+        self.active_tab = index;
+        self.layout_columns();
+        let tab = &mut self.tabs[index];
+        self.bottom_bar.component.set_store(&tab.store);
+        tab.feed_pane.component.invalidate();
+        tab.feed_pane.component.handle_focus();
+        self.update_tab_bar();
+    }
+
+    /// The tabs on screen, side by side if there's more than one.
+    fn column_tabs(&self) -> Range<usize> {
+        self.first_column..(self.first_column + self.num_columns).min(self.tabs.len())
+    }
+
+    /// Shows [num_columns] tabs side by side, from the current one.
+    fn set_columns(&mut self, num_columns: usize) {
+        if num_columns > 1 && self.tabs.len() < 2 {
+            self.notify(
+                NotifyLevel::Warning,
+                "Open another tab to show side by side, e.g. :tab mentions",
+            );
+            return;
+        }
+        self.num_columns = num_columns.clamp(1, MAX_COLUMNS);
+        self.first_column = self.active_tab;
+        self.layout_columns();
+        // NB: whatever was drawn across the column boundaries has to go
+        self.screen.invalidate();
+    }
+
+    /// Divides the feed area between the columns, moving them along to keep the current tab on
+    /// screen.  NB: tabs off screen keep the whole area, for when they're shown on their own
+    fn layout_columns(&mut self) {
+        let num_columns = self.num_columns.min(self.tabs.len());
+        if self.active_tab < self.first_column {
+            self.first_column = self.active_tab;
+        } else if self.active_tab >= self.first_column + num_columns {
+            self.first_column = self.active_tab + 1 - num_columns;
+        }
+        self.first_column = self.first_column.min(self.tabs.len() - num_columns);
+
+        let columns = self.feed_bounding_box.split_columns(num_columns as u16);
+        for (index, tab) in self.tabs.iter_mut().enumerate() {
+            let column = index
+                .checked_sub(self.first_column)
+                .and_then(|column| columns.get(column));
+            let feed_pane = &mut tab.feed_pane;
+            feed_pane.component.set_column(num_columns > 1);
+            match column {
+                Some(&column) => {
+                    if feed_pane.bounding_box != column {
+                        feed_pane.bounding_box = column;
+                        feed_pane.component.invalidate();
+                    }
+                }
+                None => feed_pane.bounding_box = self.feed_bounding_box,
+            }
+        }
+    }
+
+    /// Switches by [delta] tabs, wrapping around at either end.
+    fn cycle_tab(&mut self, delta: isize) {
+        let num_tabs = self.tabs.len() as isize;
+        let index = (self.active_tab as isize + delta).rem_euclid(num_tabs);
+        self.switch_tab(index as usize);
+    }
+
+    fn update_tab_bar(&mut self) {
+        let titles = self.tabs.iter().map(Tab::title).collect();
+        self.tab_bar.component.set_tabs(titles, self.active_tab);
+        let bottom_bar = &mut self.bottom_bar.component;
+        bottom_bar.set_feed_name(&self.tabs[self.active_tab].feed_source.title());
+        let num_unread = self
+            .tabs
+            .iter()
+            .filter(|tab| matches!(tab.feed_source, FeedSource::Activity))
+            .map(|tab| tab.store.num_unread_activity())
+            .sum();
+        bottom_bar.set_num_unread(num_unread);
+    }
+
+    /// Loads [feed_source] into the current tab, rather than opening a new one.
+    fn load_feed(&mut self, feed_source: FeedSource) {
+        let tab = &mut self.tabs[self.active_tab];
+        feed_source.load(&mut tab.feed_pane.component);
+        tab.feed_source = feed_source;
+        self.update_tab_bar();
+    }
+
     // CR: just return unit and panic
     fn set_mode(&mut self, mode: Mode) -> Result<()> {
         let prev_mode = self.mode;
@@ -100,6 +483,7 @@ impl UI {
         if prev_mode == Mode::Log && mode == Mode::Interactive {
             execute!(self.stdout, EnterAlternateScreen)?;
             terminal::enable_raw_mode()?;
+            self.screen.invalidate();
         } else if prev_mode == Mode::Interactive && mode == Mode::Log {
             execute!(self.stdout, LeaveAlternateScreen)?;
             terminal::enable_raw_mode()?;
@@ -111,74 +495,573 @@ impl UI {
     }
 
     pub fn resize(&mut self, cols: u16, rows: u16) {
-        self.feed_pane.bounding_box = BoundingBox::new(0, 0, cols, rows - 2);
-        self.bottom_bar.bounding_box = BoundingBox::new(0, rows - 1, cols, 1);
+        self.is_too_small = cols < MIN_COLS || rows < MIN_ROWS;
+        // NB: saturating, so that a terminal too small to lay out doesn't underflow either
+        self.feed_bounding_box = BoundingBox::new(0, 1, cols, rows.saturating_sub(3));
+        self.tab_bar.bounding_box = BoundingBox::new(0, 0, cols, 1);
+        self.layout_columns();
+        self.overlays.bounding_box = self.feed_bounding_box;
+        self.toasts.bounding_box = self.feed_bounding_box;
+        self.command_bar.bounding_box = BoundingBox::new(0, rows.saturating_sub(2), cols, 1);
+        self.bottom_bar.bounding_box = BoundingBox::new(0, rows.saturating_sub(1), cols, 1);
+        self.screen.resize(cols, rows);
+        self.invalidate_all();
+    }
+
+    /// Redraws everything on the next render, e.g. into a new screen buffer.
+    fn invalidate_all(&mut self) {
+        self.tab_bar.component.invalidate();
+        let column_tabs = self.column_tabs();
+        for tab in self.tabs[column_tabs].iter_mut() {
+            tab.feed_pane.component.invalidate();
+        }
+        self.bottom_bar.component.invalidate();
+        self.command_bar.component.invalidate();
+        self.overlays.component.invalidate();
+        self.toasts.component.invalidate();
+    }
+
+    /// Blanks the screen but for a note to make the terminal bigger.
+    fn render_too_small(&mut self) -> Result<()> {
+        let (cols, rows) = self.screen.size();
+        let blank = " ".repeat(cols as usize);
+        for row in 0..rows {
+            queue!(self.screen, cursor::MoveTo(0, row), style::Print(&blank))?;
+        }
+        let text = format!("Terminal too small, need {MIN_COLS}x{MIN_ROWS}");
+        let text = text_width::truncate(&text, cols as usize);
+        let text_box =
+            BoundingBox::new(0, 0, cols, rows).centered(text_width::width(text) as u16, 1);
+        queue!(
+            self.screen,
+            cursor::MoveTo(text_box.left, text_box.top),
+            style::Print(text)
+        )?;
+        self.screen.present(&mut self.stdout)?;
+        Ok(())
     }
 
     pub async fn render(&mut self) -> Result<()> {
-        self.feed_pane.render_if_necessary(&mut self.stdout)?;
-        self.bottom_bar.render_if_necessary(&mut self.stdout)?;
+        if self.is_too_small {
+            return self.render_too_small();
+        }
+        let position = self.tabs[self.active_tab]
+            .feed_pane
+            .component
+            .get_line_position();
+        self.bottom_bar.component.set_position(position);
+        let column_tabs = self.column_tabs();
+        let columns = &mut self.tabs[column_tabs];
+        if self.overlays.component.take_dismissed() {
+            for tab in columns.iter_mut() {
+                tab.feed_pane.component.invalidate();
+            }
+            self.bottom_bar.component.invalidate();
+        }
+        let feed_should_render = columns
+            .iter()
+            .any(|tab| tab.feed_pane.component.should_render());
+        // NB: the background would otherwise draw over the overlays
+        if feed_should_render || self.bottom_bar.component.should_render() {
+            self.overlays.component.invalidate();
+        }
+        if feed_should_render || self.overlays.component.should_render() {
+            self.toasts.component.invalidate();
+        }
+
+        self.tab_bar.render_if_necessary(&mut self.screen)?;
+        for tab in columns.iter_mut() {
+            tab.feed_pane.render_if_necessary(&mut self.screen)?;
+        }
+        self.bottom_bar.render_if_necessary(&mut self.screen)?;
+        self.overlays.render_if_necessary(&mut self.screen)?;
+        self.toasts.render_if_necessary(&mut self.screen)?;
 
-        let focus = self.feed_pane.get_cursor();
-        queue!(&self.stdout, cursor::MoveTo(focus.0, focus.1))?;
+        let focus = if self.overlays.component.is_active() {
+            self.overlays.get_cursor()
+        } else if self.command_bar_active {
+            self.command_bar.render_if_necessary(&mut self.screen)?;
+            self.command_bar.get_cursor()
+        } else {
+            self.feed_pane().get_cursor()
+        };
+        queue!(self.screen, cursor::MoveTo(focus.0, focus.1))?;
 
-        self.stdout.flush()?;
+        self.screen.present(&mut self.stdout)?;
         Ok(())
     }
 
-    pub fn log_message(&mut self, message: &str) -> Result<()> {
-        self.set_mode(Mode::Log)?;
-        println!("{message}\r");
-        Ok(())
+    /// Aborts every cancellable task in flight, marking the loads into feeds as cancelled.
+    fn abort_tasks(&mut self) {
+        let aborted_keys = self.tasks.abort_all();
+        self.bottom_bar
+            .component
+            .set_num_tasks_in_flight(self.tasks.len());
+
+        for tab in self.tabs.iter() {
+            if aborted_keys.contains(&TaskKey::Feed(tab.store.feed_id)) {
+                *tab.store.load_state.lock().unwrap() = LoadState::Failed("Cancelled".to_string());
+                tab.feed_pane.component.redraw_feed();
+            }
+        }
+        self.notify(
+            NotifyLevel::Info,
+            &format!("Cancelled {} tasks", aborted_keys.len()),
+        );
+    }
+
+    /// Reloads the current tab's feed from scratch, if the last load into it failed.
+    fn retry_failed_load(&mut self) {
+        let tab = &mut self.tabs[self.active_tab];
+        if matches!(*tab.store.load_state.lock().unwrap(), LoadState::Failed(_)) {
+            tab.feed_source.load(&mut tab.feed_pane.component);
+        }
+    }
+
+    /// Refreshes every activity tab.
+    fn poll_activity(&mut self) {
+        // NB: only background refreshes run hooks, so opening a tab doesn't replay old activity
+        for tab in self.tabs.iter_mut() {
+            if tab.feed_source == FeedSource::Activity {
+                tab.feed_pane.component.do_load_activity(true);
+            }
+        }
+    }
+
+    /// Snapshots my followers in the background and diffs against the snapshot from when they
+    /// were last shown.  When [show], shows the changes and marks them seen; otherwise (a periodic
+    /// check) only notifies of any, and skips the check if the latest snapshot is recent enough.
+    fn check_followers(&self, show: bool) {
+        let store = self.store.clone();
+        let events = self.events_tx.clone();
+        let task = tokio::spawn(async move {
+            let result = async {
+                let dir = Path::new(followers::FOLLOWERS_DIR);
+                let (since, interval_secs) = {
+                    let user_config = store.user_config.lock().unwrap();
+                    let interval_secs = user_config.followers_interval_secs.get();
+                    (user_config.followers_checked_at, interval_secs as i64)
+                };
+                if !show {
+                    let latest = followers::Snapshot::load_at(dir, None)?;
+                    let age = latest.map(|snapshot| Utc::now() - snapshot.taken_at);
+                    if age.is_some_and(|age| age.num_seconds() < interval_secs) {
+                        return Ok(());
+                    }
+                }
+
+                let diff = followers::check_followers(
+                    &store.twitter_client,
+                    &store.twitter_user.id,
+                    dir,
+                    since,
+                )
+                .await?;
+                if show {
+                    let text = match &diff {
+                        Some(diff) => diff.describe(),
+                        None => "First snapshot of your followers taken; changes show from the \
+                                 next one"
+                            .to_string(),
+                    };
+                    events.send(InternalEvent::View("Followers".to_string(), text))?;
+                    store.user_config.lock().unwrap().followers_checked_at = Some(Utc::now());
+                    store.save_user_config()?;
+                } else if let Some(diff) = diff.filter(|diff| !diff.is_empty()) {
+                    let message = format!("{}; :followers for who", diff.summary());
+                    events.send(InternalEvent::Notify(NotifyLevel::Info, message))?;
+                }
+                Ok::<_, Error>(())
+            };
+            if let Err(err) = result.await {
+                events.send(InternalEvent::LogError(err)).unwrap();
+            }
+        });
+        self.events_tx
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    /// Refetches who I follow and who follows me in the background, for the badges after authors.
+    fn refresh_relationships(&self) {
+        let store = self.store.clone();
+        let events = self.events_tx.clone();
+        let task = tokio::spawn(async move {
+            match store.refresh_relationships().await {
+                Ok(()) => events.send(InternalEvent::RelationshipsChanged).unwrap(),
+                Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
+            }
+        });
+        self.events_tx
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    fn show_analytics(&self) {
+        let store = self.store.clone();
+        let events = self.events_tx.clone();
+        let task = tokio::spawn(async move {
+            match store.load_my_tweets().await {
+                Ok(tweets) => {
+                    let analytics = Analytics::new(&tweets, Local::now().date_naive());
+                    let title = format!("Analytics for @{}", store.twitter_user.username);
+                    events
+                        .send(InternalEvent::View(title, analytics.describe()))
+                        .unwrap();
+                }
+                Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
+            }
+        });
+        self.events_tx
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    /// Loads [tweet]'s avatar and media in the background (unless cached), then draws it to
+    /// [path].
+    fn export_tweet_image(&self, tweet: api::Tweet, path: String) {
+        let store = self.store.clone();
+        let media_cache = self.media_cache.clone();
+        let events = self.events_tx.clone();
+        let task = tokio::spawn(async move {
+            let (images, errors) =
+                TweetImages::load(&tweet, &store.twitter_client, &media_cache).await;
+            for error in errors {
+                events.send(InternalEvent::LogError(error)).unwrap();
+            }
+            let image_theme = store.user_config.lock().unwrap().image_theme.clone();
+            let event = match tweet_image::export(&tweet, &image_theme, &images, Path::new(&path)) {
+                Ok(()) => {
+                    InternalEvent::Notify(NotifyLevel::Info, format!("Exported tweet to {path}"))
+                }
+                Err(err) => InternalEvent::LogError(err),
+            };
+            events.send(event).unwrap();
+        });
+        self.events_tx
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    fn show_following_overlap(&self, first_username: String, second_username: String) {
+        let store = self.store.clone();
+        let events = self.events_tx.clone();
+        let task = tokio::spawn(async move {
+            match store
+                .compare_following(&first_username, &second_username)
+                .await
+            {
+                Ok(overlap) => {
+                    let title = format!(
+                        "Following: @{} vs @{}",
+                        overlap.first.username, overlap.second.username
+                    );
+                    events
+                        .send(InternalEvent::View(title, overlap.describe()))
+                        .unwrap();
+                }
+                Err(err) => events.send(InternalEvent::LogError(err)).unwrap(),
+            }
+        });
+        self.events_tx
+            .send(InternalEvent::RegisterTask(None, task))
+            .unwrap();
+    }
+
+    fn show_error_history(&mut self) {
+        let bottom_bar = &mut self.bottom_bar.component;
+        let error_history = bottom_bar.get_error_history();
+        bottom_bar.mark_errors_seen();
+
+        let raw_view = if error_history.is_empty() {
+            RawView::new("Errors", "<no errors>")
+        } else {
+            RawView::new("Errors, newest first", &error_history)
+        };
+        self.overlays.component.push(Box::new(raw_view));
+    }
+
+    fn show_alerts(&mut self) {
+        let raw_view = if self.store.user_config.lock().unwrap().alerts.is_empty() {
+            RawView::new("Alerts", "<no alert rules; add [[alerts]] to the config>")
+        } else {
+            let hits: Vec<String> = self
+                .store
+                .alert_hits()
+                .iter()
+                .map(|hit| {
+                    let time = hit.tweet.local_created_at().format("%Y-%m-%d %H:%M");
+                    format!("{time} {}", hit.describe())
+                })
+                .collect();
+            if hits.is_empty() {
+                RawView::new("Alerts", "<no alerts yet>")
+            } else {
+                RawView::new("Alerts, newest first", &hits.join("\n"))
+            }
+        };
+        self.overlays.component.push(Box::new(raw_view));
+    }
+
+    fn notify(&mut self, level: NotifyLevel, message: &str) {
+        self.toasts.component.push(level, message);
     }
 
     async fn handle_internal_event(&mut self, event: InternalEvent) {
         match event {
-            InternalEvent::RegisterTask(task) => {
-                self.tasks.push(task);
+            InternalEvent::RegisterTask(task_key, task) => {
+                self.tasks.register(task_key, task);
                 self.bottom_bar
                     .component
                     .set_num_tasks_in_flight(self.tasks.len());
             }
             InternalEvent::LogTweet(tweet_id) => {
-                {
-                    let tweets = self.store.tweets.lock().unwrap();
-                    let tweet = &tweets[&tweet_id];
-                    // CR: okay, maybe handle the error here
-                    fs::write("/tmp/tweet", format!("{:#?}", tweet)).unwrap();
+                // NB: the selected tweet may not have loaded, e.g. one opened by a bad id
+                let Some(tweet) = self.store.get_tweet(&tweet_id) else {
+                    let err = anyhow!("Tweet {tweet_id} isn't loaded");
+                    self.bottom_bar.component.push_error(&err);
+                    return;
+                };
+                let text = serde_json::to_string_pretty(&tweet).unwrap_or(format!("{:#?}", tweet));
+                let raw_view = RawView::new(&format!("Tweet {tweet_id}"), &text);
+                self.overlays.component.push(Box::new(raw_view));
+            }
+            InternalEvent::View(title, text) => {
+                let raw_view = RawView::new(&title, &text);
+                self.overlays.component.push(Box::new(raw_view));
+            }
+            InternalEvent::LogError(err) => {
+                self.bottom_bar.component.push_error(&err);
+            }
+            InternalEvent::Notify(level, message) => {
+                self.toasts.component.push(level, &message);
+            }
+            InternalEvent::Confirm(message, responder) => {
+                let prompt = ConfirmPrompt::new(&message, responder);
+                self.overlays.component.push(Box::new(prompt));
+            }
+            InternalEvent::Form(form) => {
+                self.overlays.component.push(Box::new(form));
+            }
+            InternalEvent::LoadFeed(feed_source) => self.load_feed(feed_source),
+            InternalEvent::OpenTab(feed_source) => self.open_tab(feed_source),
+            InternalEvent::PluginCommand(command) => {
+                let is_mute = matches!(command, PluginCommand::MuteUser { .. });
+                if let Err(err) = self.feed_pane().component.do_plugin_command(command) {
+                    self.bottom_bar.component.push_error(&err);
+                }
+                if is_mute {
+                    for tab in self.tabs.iter() {
+                        tab.store.hide_muted();
+                    }
                 }
+                self.redraw_tabs();
+            }
+            InternalEvent::UsersMuted => {
+                for tab in self.tabs.iter() {
+                    tab.store.hide_muted();
+                }
+                self.redraw_tabs();
+            }
+            InternalEvent::UserConfigChanged | InternalEvent::RelationshipsChanged => {
+                self.redraw_tabs()
+            }
+        }
+    }
 
-                // CR: also handle the errors here
-                let mut subshell = process::Command::new("less")
-                    .args(["/tmp/tweet"])
-                    .spawn()
-                    .unwrap();
-                subshell.wait().unwrap();
+    fn redraw_tabs(&self) {
+        for tab in self.tabs.iter() {
+            tab.feed_pane.component.redraw_feed();
+            tab.feed_pane.component.redraw_tweet_panes();
+        }
+    }
+
+    fn set_command_bar_active(&mut self, active: bool) {
+        self.command_bar_active = active;
+        self.command_bar.component.clear();
+        if active {
+            self.command_bar.component.handle_focus();
+        } else {
+            // NB: the command bar row is otherwise blank
+            let BoundingBox { top, width, .. } = self.command_bar.bounding_box;
+            queue!(self.screen, cursor::MoveTo(0, top)).unwrap();
+            queue!(self.screen, style::Print(" ".repeat(width as usize))).unwrap();
+        }
+    }
+
+    fn execute_command(&mut self, command: Command) -> Result<()> {
+        match command {
+            Command::Search(query) => self.open_search_tab(query),
+            Command::Append(query) => self.feed_pane().component.do_append_search_tweets(&query),
+            Command::User(username) => self.load_feed(FeedSource::User(username)),
+            Command::List(name) => self.load_feed(FeedSource::List(name)),
+            Command::Collection(name) => self.load_feed(FeedSource::Collection(name)),
+            Command::Tab(feed_source) => self.open_tab(feed_source),
+            Command::TabClose => self.close_tab(),
+            Command::Columns(num_columns) => {
+                let num_columns = match (num_columns, self.num_columns) {
+                    (Some(num_columns), _) => num_columns,
+                    (None, 1) => self.tabs.len(),
+                    (None, _) => 1,
+                };
+                self.set_columns(num_columns);
             }
-            InternalEvent::LogError(err) => {
-                self.log_message(err.to_string().as_str()).unwrap();
+            Command::Export(Some(path)) if path.ends_with(".png") => {
+                let tab = &self.tabs[self.active_tab];
+                let tweet_id = tab
+                    .feed_pane
+                    .component
+                    .get_selected_tweet_id()
+                    .ok_or(anyhow!("No tweet selected"))?;
+                let tweet = tab
+                    .store
+                    .tweets
+                    .lock()
+                    .unwrap()
+                    .get(&tweet_id)
+                    .cloned()
+                    .ok_or(anyhow!("Tweet {tweet_id} isn't loaded"))?;
+                self.export_tweet_image(tweet, path);
+            }
+            Command::Export(path) => {
+                let path = path.unwrap_or(format!(
+                    "./var/export-{}.json",
+                    chrono::Local::now().format("%Y%m%d-%H%M%S")
+                ));
+                // NB: just the marked tweets, if any are
+                let tab = &mut self.tabs[self.active_tab];
+                let marked_ids = tab.feed_pane.component.take_marked_tweet_ids();
+                let num_tweets = if marked_ids.is_empty() {
+                    tab.store.export_feed(&path)?
+                } else {
+                    tab.store.export_tweets(&marked_ids, &path)?
+                };
+                self.notify(
+                    NotifyLevel::Info,
+                    &format!("Exported {num_tweets} tweets to {path}"),
+                );
+            }
+            Command::Sort(feed_sort) => {
+                let tab = &mut self.tabs[self.active_tab];
+                *tab.store.feed_sort.lock().unwrap() = feed_sort;
+                tab.feed_pane.component.redraw_feed();
+            }
+            Command::Conversations => {
+                let tab = &mut self.tabs[self.active_tab];
+                tab.feed_pane.component.toggle_group_by_conversation();
+            }
+            Command::Authors => {
+                let tab = &mut self.tabs[self.active_tab];
+                tab.feed_pane.component.toggle_group_by_author();
+            }
+            Command::Goto(date) => {
+                let tab = &mut self.tabs[self.active_tab];
+                let feed_pane = &mut tab.feed_pane.component;
+                match &tab.feed_source {
+                    FeedSource::Home => feed_pane.do_goto_date(date, None),
+                    FeedSource::User(username) => feed_pane.do_goto_date(date, Some(username)),
+                    _ => return Err(anyhow!(":goto only works in the home and user feeds")),
+                }
+            }
+            Command::Metrics => {
+                {
+                    let mut user_config = self.store.user_config.lock().unwrap();
+                    user_config.show_public_metrics = !user_config.show_public_metrics;
+                }
+                self.store.save_user_config()?;
+                for tab in self.tabs.iter() {
+                    tab.feed_pane.component.redraw_feed();
+                }
+            }
+            Command::Analytics => self.show_analytics(),
+            Command::Compare(first, second) => self.show_following_overlap(first, second),
+            Command::Followers => self.check_followers(true),
+            Command::Alerts => self.show_alerts(),
+            Command::CacheClear => {
+                let num_files = self.media_cache.clear()?;
+                self.notify(
+                    NotifyLevel::Info,
+                    &format!("Cleared {num_files} cached images"),
+                );
+            }
+            Command::Debug => {
+                let (num_tweets, num_evicted) = self.store.tweet_cache_stats();
+                let max_tweets = self.store.user_config.lock().unwrap().max_cached_tweets;
+                let text = format!(
+                    "Tweets\n\nCached      {num_tweets} of {max_tweets}\nEvictions   {num_evicted}\n\n\
+                     Media cache\n\n{}",
+                    self.media_cache.stats().describe()
+                );
+                let raw_view = RawView::new("Debug", &text);
+                self.overlays.component.push(Box::new(raw_view));
+            }
+            Command::Budget => {
+                let usage = QuotaUsage::load(Path::new(quota::QUOTA_PATH))?;
+                let budget = self.store.user_config.lock().unwrap().monthly_call_budget;
+                let raw_view = RawView::new("API quota", &usage.describe(Local::now(), budget));
+                self.overlays.component.push(Box::new(raw_view));
+            }
+            Command::Quit => self.quit(),
+            Command::Help => {
+                let help = format!("{}\n\nKeys\n\n{}", Command::help(), self.key_map.help());
+                let raw_view = RawView::new("Commands", &help);
+                self.overlays.component.push(Box::new(raw_view));
+            }
+        }
+        Ok(())
+    }
+
+    /// Carries out the app-wide [action]s; the feed's are handled by the feed pane.
+    fn dispatch(&mut self, action: Action) {
+        match action {
+            Action::Redraw => {
+                self.set_mode(Mode::Interactive).unwrap();
+                self.feed_pane().component.invalidate();
+                self.tab_bar.component.invalidate();
+                self.bottom_bar.component.invalidate();
             }
+            Action::OpenCommandBar => self.set_command_bar_active(true),
+            Action::ShowErrors => self.show_error_history(),
+            Action::RetryLoad => self.retry_failed_load(),
+            Action::AbortTasks => self.abort_tasks(),
+            Action::TabPrefix => self.pending_g = true,
+            Action::SwitchTab(index) => self.switch_tab(index),
+            Action::Quit => self.quit(),
+            _ => (),
         }
     }
 
     async fn handle_terminal_event(&mut self, event: &Event) {
         match event {
-            Event::Key(key_event) => {
-                let handled = self.feed_pane.component.handle_key_event(key_event);
-                if !handled {
-                    match key_event.code {
-                        KeyCode::Esc => {
-                            self.set_mode(Mode::Interactive).unwrap();
-                            self.feed_pane.component.invalidate();
-                            self.bottom_bar.component.invalidate();
-                        }
-                        KeyCode::Char('q') => {
-                            reset();
-                            process::exit(0);
-                        }
-                        _ => (),
+            Event::Key(key_event) if self.overlays.component.is_active() => {
+                self.overlays.component.handle_key_event(key_event);
+            }
+            Event::Key(key_event) if self.command_bar_active => match key_event.code {
+                KeyCode::Esc => self.set_command_bar_active(false),
+                KeyCode::Enter => {
+                    let line = self.command_bar.component.submit();
+                    self.set_command_bar_active(false);
+                    if let Err(err) = Command::parse(&line).and_then(|c| self.execute_command(c)) {
+                        self.notify(NotifyLevel::Error, &err.to_string());
                     }
                 }
+                _ => {
+                    self.command_bar.component.handle_key_event(key_event);
+                }
+            },
+            Event::Key(key_event) if self.pending_g => {
+                self.pending_g = false;
+                match key_event.code {
+                    KeyCode::Char('t') => self.cycle_tab(1),
+                    KeyCode::Char('T') => self.cycle_tab(-1),
+                    _ => (),
+                }
+            }
+            Event::Key(key_event) => {
+                let handled = self.feed_pane().component.handle_key_event(key_event);
+                if let (false, Some(action)) = (handled, self.key_map.get(key_event)) {
+                    self.dispatch(action);
+                }
             }
             Event::Resize(cols, rows) => self.resize(*cols, *rows),
             _ => (),
@@ -187,12 +1070,49 @@ impl UI {
 
     pub async fn event_loop(&mut self) -> Result<()> {
         let mut terminal_event_stream = EventStream::new();
+        let activity_poll_interval = {
+            let user_config = self.store.user_config.lock().unwrap();
+            Duration::from_secs(user_config.refresh_interval_secs.get())
+        };
+        let mut activity_poll = tokio::time::interval_at(
+            tokio::time::Instant::now() + activity_poll_interval,
+            activity_poll_interval,
+        );
+        activity_poll.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let followers_poll_interval = {
+            let user_config = self.store.user_config.lock().unwrap();
+            Duration::from_secs(user_config.followers_interval_secs.get())
+        };
+        // NB: the first tick is immediate, to catch up if the app wasn't running when one was due
+        let mut followers_poll = tokio::time::interval(followers_poll_interval);
+        followers_poll.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut tick = tokio::time::interval(TICK_INTERVAL);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut clock = tokio::time::interval(CLOCK_INTERVAL);
+        clock.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // NB: rendering after every event is wasteful during bursts, e.g. key repeat while
+        // scrolling, so events only mark the screen dirty and frames are rendered at most every
+        // [FRAME_INTERVAL]
+        let mut is_dirty = true;
+        let mut last_frame = Instant::now();
 
         loop {
             let terminal_event = terminal_event_stream.next().fuse();
+            let column_tabs = self.column_tabs();
+            let is_animating = self.tabs[column_tabs]
+                .iter()
+                .any(|tab| tab.feed_pane.component.is_animating());
             let internal_event = self.events.recv();
             let there_are_tasks = !self.tasks.is_empty();
+            let shows_clock = {
+                let user_config = self.store.user_config.lock().unwrap();
+                user_config.status_bar.contains(&StatusSegment::Clock)
+            };
+            let next_frame = tokio::time::sleep_until((last_frame + FRAME_INTERVAL).into());
             let task_event = self.tasks.next().fuse();
+            let next_toast_expiry = self.toasts.component.next_expiry();
+            let toast_expiry =
+                tokio::time::sleep_until(next_toast_expiry.unwrap_or_else(Instant::now).into());
 
             tokio::select! {
                 event = terminal_event => {
@@ -209,10 +1129,45 @@ impl UI {
                 // match arm handler is empty, why?
                 _ = task_event, if there_are_tasks => {
                     self.bottom_bar.component.set_num_tasks_in_flight(self.tasks.len());
+                    // NB: a load may have brought in unread activity
+                    self.update_tab_bar();
+                }
+                _ = activity_poll.tick() => self.poll_activity(),
+                _ = followers_poll.tick() => {
+                    self.check_followers(false);
+                    self.refresh_relationships();
+                }
+                _ = tick.tick(), if is_animating => {
+                    let column_tabs = self.column_tabs();
+                    for tab in self.tabs[column_tabs].iter_mut() {
+                        tab.feed_pane.component.tick();
+                    }
+                }
+                _ = clock.tick(), if shows_clock => {
+                    self.bottom_bar.component.tick_clock();
+                    if !self.bottom_bar.component.should_render() {
+                        continue;
+                    }
+                }
+                _ = toast_expiry, if next_toast_expiry.is_some() => {
+                    if self.toasts.component.expire(Instant::now()) {
+                        // NB: redraw whatever the expired toasts were covering
+                        let column_tabs = self.column_tabs();
+                        for tab in self.tabs[column_tabs].iter_mut() {
+                            tab.feed_pane.component.invalidate();
+                        }
+                        self.overlays.component.invalidate();
+                    }
+                }
+                _ = next_frame, if is_dirty => {
+                    self.render().await?;
+                    is_dirty = false;
+                    last_frame = Instant::now();
+                    continue;
                 }
             }
 
-            self.render().await?
+            is_dirty = true;
         }
     }
 }
@@ -0,0 +1,137 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::overlay::Overlay;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::scroll_buffer::{self, ScrollBuffer, TextSegment};
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::style::{Color, Colors};
+use crossterm::{cursor, queue, style};
+use std::io::Write;
+
+/// The title and a blank line
+const HEADER_HEIGHT: u16 = 2;
+
+/// Full screen, scrollable view of some preformatted text (e.g. a tweet's raw JSON); stands in
+/// for shelling out to `less`.
+pub struct RawView {
+    text: String,
+    scroll_buffer: ScrollBuffer,
+    should_update_scroll_buffer: bool,
+    display_width: usize,
+    dismissed: bool,
+}
+
+impl RawView {
+    pub fn new(title: &str, text: &str) -> Self {
+        let mut scroll_buffer = ScrollBuffer::new();
+        scroll_buffer.set_header(vec![
+            vec![TextSegment::color(
+                &format!("{title} (q or Esc to close)"),
+                Colors::new(Color::DarkGrey, Color::Reset),
+            )],
+            vec![],
+        ]);
+        Self {
+            text: text.to_string(),
+            scroll_buffer,
+            should_update_scroll_buffer: true,
+            display_width: 0,
+            dismissed: false,
+        }
+    }
+
+    fn update_scroll_buffer(&mut self) {
+        self.scroll_buffer.clear();
+        for line in self.text.lines() {
+            for wrapped_line in text_width::wrap(line, self.display_width) {
+                self.scroll_buffer
+                    .push(vec![TextSegment::plain(wrapped_line)]);
+            }
+        }
+        let y = self.scroll_buffer.get_cursor_line();
+        self.scroll_buffer.move_cursor_to(0, y);
+        self.should_update_scroll_buffer = false;
+    }
+}
+
+impl Render for RawView {
+    fn should_render(&self) -> bool {
+        self.should_update_scroll_buffer || self.scroll_buffer.should_render()
+    }
+
+    fn invalidate(&mut self) {
+        self.scroll_buffer.invalidate();
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left,
+            top,
+            width,
+            height,
+        } = bounding_box;
+
+        // NB: the last column is for the scrollbar
+        let text_width = width.saturating_sub(1);
+        if self.should_update_scroll_buffer || self.display_width != text_width as usize {
+            self.display_width = text_width as usize;
+            self.update_scroll_buffer();
+            self.scroll_buffer.invalidate();
+        }
+
+        if self.scroll_buffer.should_render() {
+            let str_clear = " ".repeat(width as usize);
+            for y_offset in 0..height {
+                queue!(stdout, cursor::MoveTo(left, top + y_offset))?;
+                queue!(stdout, style::Print(&str_clear))?;
+            }
+
+            self.scroll_buffer.render(
+                stdout,
+                BoundingBox {
+                    width: text_width,
+                    ..bounding_box
+                },
+            )?;
+            // NB: alongside the text, not the title
+            scroll_buffer::render_scrollbar(
+                stdout,
+                self.scroll_buffer.viewport(),
+                left + text_width,
+                top + HEADER_HEIGHT,
+                height.saturating_sub(HEADER_HEIGHT),
+            )?;
+        }
+
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        self.scroll_buffer.get_cursor()
+    }
+}
+
+impl Input for RawView {
+    fn handle_focus(&mut self) {
+        self.invalidate();
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.dismissed = true;
+                true
+            }
+            _ => self.scroll_buffer.handle_key_event(event),
+        }
+    }
+}
+
+impl Overlay for RawView {
+    fn is_dismissed(&self) -> bool {
+        self.dismissed
+    }
+}
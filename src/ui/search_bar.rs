@@ -1,23 +1,36 @@
 use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
 use crate::ui_framework::{Input, Render};
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
 use crossterm::queue;
 use crossterm::{cursor, style};
-use std::io::{Stdout, Write};
+use std::io::Write;
 
 #[derive(Debug)]
 pub struct SearchBar {
+    pub prompt: String,
     pub text_input: String,
+    /// NB: in chars, not bytes
     pub caret_position: usize,
+    /// Number of chars scrolled off the left edge when the text is wider than the viewport
+    pub scroll_offset: usize,
+    pub display_width: usize,
     pub should_render: bool,
 }
 
 impl SearchBar {
     pub fn new() -> Self {
+        Self::with_prompt("/ ")
+    }
+
+    pub fn with_prompt(prompt: &str) -> Self {
         Self {
+            prompt: prompt.to_string(),
             text_input: "".to_string(),
             caret_position: 0,
+            scroll_offset: 0,
+            display_width: 0,
             should_render: true,
         }
     }
@@ -27,21 +40,72 @@ impl SearchBar {
         self.text_input.clone()
     }
 
+    pub fn set_text(&mut self, text: &str) {
+        self.text_input = text.to_string();
+        self.caret_position = self.text_length();
+        self.update_scroll_offset();
+        self.should_render = true;
+    }
+
     pub fn clear(&mut self) {
         self.text_input = "".to_string();
         self.caret_position = 0;
+        self.scroll_offset = 0;
         self.should_render = true;
     }
 
+    fn text_length(&self) -> usize {
+        self.text_input.chars().count()
+    }
+
+    fn caret_byte_offset(&self) -> usize {
+        self.text_input
+            .char_indices()
+            .nth(self.caret_position)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text_input.len())
+    }
+
+    /// Width available to the text, leaving one column at the end for the caret.
+    fn viewport_width(&self) -> usize {
+        self.display_width
+            .saturating_sub(self.prompt.len() + 1)
+            .max(1)
+    }
+
+    /// Scrolls just enough to keep the caret inside the viewport.
+    fn update_scroll_offset(&mut self) {
+        let viewport_width = self.viewport_width();
+        let old_scroll_offset = self.scroll_offset;
+
+        if self.caret_position < self.scroll_offset {
+            self.scroll_offset = self.caret_position;
+        } else if self.caret_position > self.scroll_offset + viewport_width {
+            self.scroll_offset = self.caret_position - viewport_width;
+        }
+        // NB: don't leave blank space on the right if the text could fill it
+        self.scroll_offset = self
+            .scroll_offset
+            .min(self.text_length().saturating_sub(viewport_width));
+
+        if self.scroll_offset != old_scroll_offset {
+            self.should_render = true;
+        }
+    }
+
     fn insert_char_at_caret(&mut self, ch: char) {
-        self.text_input.insert(self.caret_position, ch);
+        let byte_offset = self.caret_byte_offset();
+        self.text_input.insert(byte_offset, ch);
         self.caret_position += 1;
+        self.update_scroll_offset();
         self.should_render = true;
     }
 
     fn delete_char_at_caret(&mut self) {
-        if self.caret_position < self.text_input.len() {
-            self.text_input.remove(self.caret_position);
+        if self.caret_position < self.text_length() {
+            let byte_offset = self.caret_byte_offset();
+            self.text_input.remove(byte_offset);
+            self.update_scroll_offset();
             self.should_render = true;
         }
     }
@@ -55,8 +119,9 @@ impl SearchBar {
 
     fn move_caret(&mut self, delta: isize) {
         let new_position = self.caret_position as isize + delta;
-        if new_position >= 0 && new_position <= self.text_input.len() as isize {
+        if new_position >= 0 && new_position <= self.text_length() as isize {
             self.caret_position = new_position as usize;
+            self.update_scroll_offset();
             self.should_render = true;
         }
     }
@@ -71,30 +136,46 @@ impl Render for SearchBar {
         self.should_render = true;
     }
 
-    fn render(&mut self, stdout: &mut Stdout, bounding_box: BoundingBox) -> Result<()> {
-        let BoundingBox { left, top, .. } = bounding_box;
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left, top, width, ..
+        } = bounding_box;
+
+        if self.display_width != width as usize {
+            self.display_width = width as usize;
+            self.update_scroll_offset();
+        }
 
         queue!(stdout, cursor::MoveTo(left, top))?;
-        queue!(stdout, style::Print("/ "))?;
+        queue!(stdout, style::Print(&self.prompt))?;
 
-        // CR-soon: search bar horizontal scrolling
-        let str_clear = " ".repeat(bounding_box.width.saturating_sub(2) as usize);
+        let prompt_width = self.prompt.len() as u16;
+        let str_clear = " ".repeat(width.saturating_sub(prompt_width) as usize);
         queue!(stdout, style::Print(str_clear))?;
-        queue!(stdout, cursor::MoveTo(left + 2, top))?;
-        queue!(stdout, style::Print(&self.text_input))?;
+        queue!(stdout, cursor::MoveTo(left + prompt_width, top))?;
+
+        let visible_text: String = self
+            .text_input
+            .chars()
+            .skip(self.scroll_offset)
+            .take(self.viewport_width())
+            .collect();
+        queue!(stdout, style::Print(visible_text))?;
 
         stdout.flush()?;
+        self.should_render = false;
         Ok(())
     }
 
     fn get_cursor(&self) -> (u16, u16) {
-        (self.caret_position as u16 + 2, 0)
+        let caret_offset = self.caret_position.saturating_sub(self.scroll_offset);
+        ((self.prompt.len() + caret_offset) as u16, 0)
     }
 }
 
 impl Input for SearchBar {
     fn handle_focus(&mut self) {
-        ()
+        self.should_render = true;
     }
 
     fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
@@ -109,3 +190,43 @@ impl Input for SearchBar {
         true
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_horizontal_scrolling() {
+        let mut search_bar = SearchBar::with_prompt("/ ");
+        search_bar.display_width = 12; // NB: viewport of 9 chars plus caret column
+        search_bar.set_text("0123456789abcdef");
+        assert_eq!(search_bar.scroll_offset, 7);
+        assert_eq!(search_bar.get_cursor(), (11, 0));
+
+        search_bar.move_caret(-9);
+        assert_eq!(search_bar.scroll_offset, 7);
+        assert_eq!(search_bar.get_cursor(), (2, 0));
+
+        search_bar.move_caret(-1);
+        assert_eq!(search_bar.scroll_offset, 6);
+        assert_eq!(search_bar.get_cursor(), (2, 0));
+
+        search_bar.delete_char_at_caret();
+        search_bar.delete_char_at_caret();
+        assert_eq!(search_bar.get_text(), "01234589abcdef");
+        assert_eq!(search_bar.scroll_offset, 5);
+    }
+
+    #[test]
+    fn test_multibyte_chars() {
+        let mut search_bar = SearchBar::new();
+        search_bar.display_width = 80;
+        search_bar.insert_char_at_caret('é');
+        search_bar.insert_char_at_caret('日');
+        search_bar.move_caret(-1);
+        search_bar.insert_char_at_caret('x');
+        assert_eq!(search_bar.get_text(), "éx日");
+        search_bar.delete_char_before_caret();
+        assert_eq!(search_bar.get_text(), "é日");
+    }
+}
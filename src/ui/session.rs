@@ -0,0 +1,126 @@
+//! What the UI was showing when it quit (tabs, scroll positions, open threads), so the next launch
+//! picks up where it left off.
+
+use crate::feed_source::FeedSource;
+use crate::fs_util;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const SESSION_PATH: &str = "./var/session.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub tabs: Vec<TabSession>,
+    pub active_tab: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TabSession {
+    pub feed_source: FeedSource,
+    #[serde(flatten)]
+    pub position: FeedPosition,
+}
+
+/// Where a feed pane was scrolled to and what it had open.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedPosition {
+    pub selected_tweet_id: Option<String>,
+    pub cursor_line: usize,
+    pub display_offset: usize,
+    /// The tweet pane stack, bottom first
+    #[serde(default)]
+    pub tweet_pane_ids: Vec<String>,
+}
+
+impl FeedPosition {
+    /// (cursor line, display offset) in [feed], following the selected tweet if it moved, e.g.
+    /// because newer tweets came in above it.  None until the feed has loaded.
+    pub fn locate(&self, feed: &[String]) -> Option<(usize, usize)> {
+        if feed.is_empty() {
+            return None;
+        }
+        let selected_line = self
+            .selected_tweet_id
+            .as_ref()
+            .and_then(|tweet_id| feed.iter().position(|id| id == tweet_id));
+        match selected_line {
+            Some(line) => {
+                let shift = line as isize - self.cursor_line as isize;
+                let display_offset = (self.display_offset as isize + shift).max(0) as usize;
+                Some((line, display_offset))
+            }
+            None => {
+                let line = self.cursor_line.min(feed.len() - 1);
+                Some((line, self.display_offset.min(line)))
+            }
+        }
+    }
+}
+
+impl Session {
+    /// None if there's no saved session.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs_util::write_atomic(path, &serde_json::to_vec_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn test_locate() {
+        let feed: Vec<String> = (0..10).map(|id| id.to_string()).collect();
+        let position = FeedPosition {
+            selected_tweet_id: Some("5".to_string()),
+            cursor_line: 3,
+            display_offset: 1,
+            tweet_pane_ids: Vec::new(),
+        };
+        assert_eq!(position.locate(&[]), None);
+        assert_eq!(position.locate(&feed), Some((5, 3)));
+
+        let position = FeedPosition {
+            selected_tweet_id: Some("gone".to_string()),
+            cursor_line: 30,
+            display_offset: 25,
+            ..position
+        };
+        assert_eq!(position.locate(&feed), Some((9, 9)));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = env::temp_dir().join(format!("twitter-tool-session-{}.json", process::id()));
+        assert_eq!(Session::load(&path).unwrap(), None);
+
+        let session = Session {
+            tabs: vec![TabSession {
+                feed_source: FeedSource::Search("rust lang".to_string()),
+                position: FeedPosition {
+                    selected_tweet_id: Some("2".to_string()),
+                    cursor_line: 1,
+                    display_offset: 0,
+                    tweet_pane_ids: vec!["2".to_string(), "1".to_string()],
+                },
+            }],
+            active_tab: 0,
+        };
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path).unwrap(), Some(session));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use crossterm::style::{Attribute, Color};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, queue, style};
+use std::io::Write;
+
+/// One row of numbered tab titles, with the active tab highlighted.
+pub struct TabBar {
+    titles: Vec<String>,
+    active: usize,
+    should_render: bool,
+}
+
+impl TabBar {
+    pub fn new() -> Self {
+        Self {
+            titles: Vec::new(),
+            active: 0,
+            should_render: true,
+        }
+    }
+
+    pub fn set_tabs(&mut self, titles: Vec<String>, active: usize) {
+        self.titles = titles;
+        self.active = active;
+        self.should_render = true;
+    }
+}
+
+impl Default for TabBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Render for TabBar {
+    fn should_render(&self) -> bool {
+        self.should_render
+    }
+
+    fn invalidate(&mut self) {
+        self.should_render = true;
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left, top, width, ..
+        } = bounding_box;
+
+        queue!(stdout, cursor::MoveTo(left, top))?;
+        let mut remaining_width = width as usize;
+        for (i, title) in self.titles.iter().enumerate() {
+            let tab = format!(" {} {title} ", i + 1);
+            let tab = text_width::truncate(&tab, remaining_width);
+            remaining_width -= text_width::width(tab);
+
+            if i == self.active {
+                queue!(stdout, style::SetAttribute(Attribute::Reverse))?;
+            } else {
+                queue!(stdout, style::SetForegroundColor(Color::DarkGrey))?;
+            }
+            queue!(stdout, style::Print(tab))?;
+            queue!(stdout, style::SetAttribute(Attribute::Reset))?;
+            queue!(stdout, style::ResetColor)?;
+        }
+        queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
+
+        stdout.flush()?;
+        self.should_render = false;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        (0, 0)
+    }
+}
+
+impl Input for TabBar {
+    fn handle_focus(&mut self) {}
+
+    fn handle_key_event(&mut self, _event: &KeyEvent) -> bool {
+        false
+    }
+}
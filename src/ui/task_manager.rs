@@ -0,0 +1,145 @@
+use futures_util::future;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+use tokio::task::JoinHandle;
+
+/// What a task is for.  Only keyed tasks can be cancelled: a new task with the same key supersedes
+/// the one in flight, whose result would just be overwritten.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TaskKey {
+    /// Loading into the feed of the store with this [Store::feed_id]
+    ///
+    /// [Store::feed_id]: crate::store::Store::feed_id
+    Feed(usize),
+    /// Fetching this tweet for a tweet pane
+    Tweet(String),
+    /// Loading this tweet's conversation for a tweet pane
+    Conversation(String),
+    /// Translating this tweet for a tweet pane
+    Translation(String),
+    /// Fetching this tweet's other versions for a tweet pane
+    EditHistory(String),
+    /// Running the plugins on the newly selected tweet
+    TweetSelected,
+}
+
+/// Every spawned task in flight, so the UI can show how many there are and cancel them.
+///
+/// NB: unkeyed tasks (e.g. posting a thread, or waiting on a confirmation) are never aborted,
+/// since stopping them halfway could leave things in a worse state than either outcome.
+pub struct TaskManager {
+    next_task_id: u64,
+    tasks: HashMap<u64, (Option<TaskKey>, JoinHandle<()>)>,
+}
+
+impl TaskManager {
+    pub fn new() -> Self {
+        Self {
+            next_task_id: 0,
+            tasks: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Tracks [task], aborting the task in flight with the same [key] if there is one.
+    pub fn register(&mut self, key: Option<TaskKey>, task: JoinHandle<()>) {
+        if let Some(key) = &key {
+            self.tasks.retain(|_, (task_key, task)| {
+                let is_superseded = task_key.as_ref() == Some(key);
+                if is_superseded {
+                    task.abort();
+                }
+                !is_superseded
+            });
+        }
+        self.tasks.insert(self.next_task_id, (key, task));
+        self.next_task_id += 1;
+    }
+
+    /// Aborts every keyed task; returns their keys.
+    pub fn abort_all(&mut self) -> Vec<TaskKey> {
+        let mut aborted_keys = Vec::new();
+        self.tasks.retain(|_, (task_key, task)| match task_key {
+            Some(key) => {
+                task.abort();
+                aborted_keys.push(key.clone());
+                false
+            }
+            None => true,
+        });
+        aborted_keys
+    }
+
+    /// Resolves once any task finishes, and stops tracking it.  Pending forever if there are no
+    /// tasks.
+    pub async fn next(&mut self) {
+        future::poll_fn(|cx| {
+            let mut finished_task_id = None;
+            for (&task_id, (_, task)) in self.tasks.iter_mut() {
+                if Pin::new(task).poll(cx).is_ready() {
+                    finished_task_id = Some(task_id);
+                    break;
+                }
+            }
+            match finished_task_id {
+                Some(task_id) => {
+                    self.tasks.remove(&task_id);
+                    Poll::Ready(())
+                }
+                None => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+impl Default for TaskManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn sleeper() -> JoinHandle<()> {
+        tokio::spawn(tokio::time::sleep(Duration::from_secs(60)))
+    }
+
+    #[tokio::test]
+    async fn test_supersede_and_abort_all() {
+        let mut task_manager = TaskManager::new();
+        task_manager.register(Some(TaskKey::Feed(0)), sleeper());
+        task_manager.register(Some(TaskKey::Feed(1)), sleeper());
+        task_manager.register(None, sleeper());
+        assert_eq!(task_manager.len(), 3);
+
+        task_manager.register(Some(TaskKey::Feed(0)), sleeper());
+        assert_eq!(task_manager.len(), 3);
+
+        let mut aborted_keys = task_manager.abort_all();
+        aborted_keys.sort_by_key(|key| format!("{key:?}"));
+        assert_eq!(aborted_keys, vec![TaskKey::Feed(0), TaskKey::Feed(1)]);
+        assert_eq!(task_manager.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_next() {
+        let mut task_manager = TaskManager::new();
+        task_manager.register(None, sleeper());
+        task_manager.register(None, tokio::spawn(async {}));
+        task_manager.next().await;
+        assert_eq!(task_manager.len(), 1);
+    }
+}
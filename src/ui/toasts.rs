@@ -0,0 +1,169 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use crossterm::style::{Color, Colors};
+use crossterm::{cursor, queue, style};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+const MAX_TOASTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl NotifyLevel {
+    fn colors(&self) -> Colors {
+        match self {
+            NotifyLevel::Info => Colors::new(Color::Black, Color::Green),
+            NotifyLevel::Warning => Colors::new(Color::Black, Color::Yellow),
+            NotifyLevel::Error => Colors::new(Color::White, Color::Red),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Toast {
+    level: NotifyLevel,
+    message: String,
+    expires_at: Instant,
+}
+
+/// Transient notifications stacked in the top right corner; each disappears after a few seconds.
+/// Never takes focus.
+///
+/// NB: the owner is responsible for calling [expire] on time (see [next_expiry]) and redrawing
+/// whatever the expired toasts covered.
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    should_render: bool,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self {
+            toasts: Vec::new(),
+            should_render: false,
+        }
+    }
+
+    pub fn push(&mut self, level: NotifyLevel, message: &str) {
+        // NB: multi-line messages would break the layout
+        let message = message.lines().collect::<Vec<&str>>().join(" ");
+        self.toasts.push(Toast {
+            level,
+            message,
+            expires_at: Instant::now() + TOAST_DURATION,
+        });
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+        self.should_render = true;
+    }
+
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.toasts.iter().map(|toast| toast.expires_at).min()
+    }
+
+    /// Drops toasts that have expired by [now]; returns true if there were any.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let num_toasts = self.toasts.len();
+        self.toasts.retain(|toast| toast.expires_at > now);
+        let expired = self.toasts.len() != num_toasts;
+        if expired {
+            self.should_render = true;
+        }
+        expired
+    }
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Render for Toasts {
+    fn should_render(&self) -> bool {
+        self.should_render
+    }
+
+    fn invalidate(&mut self) {
+        self.should_render = true;
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left,
+            top,
+            width,
+            height,
+        } = bounding_box;
+        let max_width = (width as usize / 2).max(1);
+
+        for (i, toast) in self.toasts.iter().rev().take(height as usize).enumerate() {
+            let text = format!(" {} ", toast.message);
+            let text = text_width::truncate(&text, max_width);
+            let text_width = text_width::width(text) as u16;
+
+            queue!(
+                stdout,
+                cursor::MoveTo(left + width - text_width, top + i as u16)
+            )?;
+            queue!(stdout, style::SetColors(toast.level.colors()))?;
+            queue!(stdout, style::Print(text))?;
+            queue!(stdout, style::ResetColor)?;
+        }
+
+        stdout.flush()?;
+        self.should_render = false;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        (0, 0)
+    }
+}
+
+impl Input for Toasts {
+    fn handle_focus(&mut self) {}
+
+    fn handle_key_event(&mut self, _event: &KeyEvent) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expire() {
+        let mut toasts = Toasts::new();
+        assert_eq!(toasts.next_expiry(), None);
+
+        toasts.push(NotifyLevel::Info, "Tweet liked");
+        toasts.push(NotifyLevel::Error, "Rate limited");
+        let expiry = toasts.next_expiry().unwrap();
+        assert!(!toasts.expire(Instant::now()));
+        assert!(toasts.expire(expiry + Duration::from_millis(1)));
+        assert_eq!(toasts.next_expiry(), None);
+    }
+
+    #[test]
+    fn test_keeps_most_recent() {
+        let mut toasts = Toasts::new();
+        for i in 0..(MAX_TOASTS + 2) {
+            toasts.push(NotifyLevel::Info, &i.to_string());
+        }
+        assert_eq!(toasts.toasts.len(), MAX_TOASTS);
+        assert_eq!(toasts.toasts[0].message, "2");
+    }
+}
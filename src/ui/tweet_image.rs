@@ -0,0 +1,570 @@
+//! The selected tweet drawn to a PNG for sharing outside Twitter, see `:export <path>.png`.  The
+//! author's avatar and the first photo (or video preview) are drawn too, if they've been
+//! downloaded, see [crate::media_cache].
+//!
+//! NB: text is drawn with embedded-graphics' Latin-1 bitmap fonts, so anything outside Latin-1
+//! (emoji, CJK, ...) comes out as '?'
+
+use crate::media_cache::MediaCache;
+use crate::twitter_client::{api, TwitterClient};
+use crate::ui::tweet_text;
+use crate::ui_framework::text_width;
+use crate::user_config::{ImageTheme, TerminalColor, ThemeColor};
+use anyhow::{anyhow, Error, Result};
+use embedded_graphics::mono_font::iso_8859_1::{FONT_10X20, FONT_9X18, FONT_9X18_BOLD};
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{Circle, PrimitiveStyle};
+use embedded_graphics::text::{Alignment, Baseline, Text, TextStyleBuilder};
+use std::convert::Infallible;
+use std::fs;
+use std::path::Path;
+
+const PADDING: u32 = 20;
+const AVATAR_DIAMETER: u32 = 48;
+const LINE_SPACING: u32 = 4;
+/// Wide enough for the avatar and a few characters of name
+const MIN_WIDTH: u32 = 200;
+/// So a tall photo doesn't dwarf the text
+const MAX_MEDIA_HEIGHT: u32 = 400;
+
+/// An RGB image to draw on.
+pub struct Canvas {
+    size: Size,
+    pixels: Vec<Rgb888>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, background: Rgb888) -> Self {
+        Self {
+            size: Size::new(width, height),
+            pixels: vec![background; (width * height) as usize],
+        }
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.size.width, self.size.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        let data: Vec<u8> = self
+            .pixels
+            .iter()
+            .flat_map(|pixel| [pixel.r(), pixel.g(), pixel.b()])
+            .collect();
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(png)
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        self.size
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> std::result::Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            // NB: anything drawn past the edges is clipped
+            if let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y)) {
+                if x < self.size.width && y < self.size.height {
+                    self.pixels[(y * self.size.width + x) as usize] = color;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A decoded image, e.g. an avatar, to draw into a [render].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Vec<Rgb888>,
+}
+
+impl Bitmap {
+    /// From a PNG or JPEG, which is what Twitter serves avatars and photos as.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.starts_with(b"\x89PNG") {
+            Self::decode_png(bytes)
+        } else if bytes.starts_with(&[0xff, 0xd8]) {
+            Self::decode_jpeg(bytes)
+        } else {
+            Err(anyhow!("Expected a PNG or JPEG image"))
+        }
+    }
+
+    fn decode_png(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info()?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data)?;
+        // NB: alpha is dropped, as if over black
+        let pixels = match info.color_type {
+            png::ColorType::Rgb => data
+                .chunks_exact(3)
+                .map(|rgb| Rgb888::new(rgb[0], rgb[1], rgb[2]))
+                .collect(),
+            png::ColorType::Rgba => data
+                .chunks_exact(4)
+                .map(|rgba| Rgb888::new(rgba[0], rgba[1], rgba[2]))
+                .collect(),
+            png::ColorType::Grayscale => data.iter().map(|&l| Rgb888::new(l, l, l)).collect(),
+            png::ColorType::GrayscaleAlpha => data
+                .chunks_exact(2)
+                .map(|la| Rgb888::new(la[0], la[0], la[0]))
+                .collect(),
+            png::ColorType::Indexed => return Err(anyhow!("Expected an expanded palette")),
+        };
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    fn decode_jpeg(bytes: &[u8]) -> Result<Self> {
+        let mut decoder = jpeg_decoder::Decoder::new(bytes);
+        let data = decoder.decode()?;
+        let info = decoder.info().ok_or(anyhow!("Expected JPEG metadata"))?;
+        let pixels = match info.pixel_format {
+            jpeg_decoder::PixelFormat::RGB24 => data
+                .chunks_exact(3)
+                .map(|rgb| Rgb888::new(rgb[0], rgb[1], rgb[2]))
+                .collect(),
+            jpeg_decoder::PixelFormat::L8 => data.iter().map(|&l| Rgb888::new(l, l, l)).collect(),
+            format => return Err(anyhow!("Unsupported JPEG pixel format {format:?}")),
+        };
+        Ok(Self {
+            width: info.width as u32,
+            height: info.height as u32,
+            pixels,
+        })
+    }
+
+    /// [size] scaled to fit within [max_width] x [max_height], keeping the aspect ratio.
+    fn fit(&self, max_width: u32, max_height: u32) -> Size {
+        let scale = f64::min(
+            max_width as f64 / self.width.max(1) as f64,
+            max_height as f64 / self.height.max(1) as f64,
+        );
+        Size::new(
+            ((self.width as f64 * scale) as u32).max(1),
+            ((self.height as f64 * scale) as u32).max(1),
+        )
+    }
+
+    /// Scaled (nearest neighbor) to [size] at [top_left]; if [is_round], only the circle inscribed
+    /// in it.
+    fn draw(&self, canvas: &mut Canvas, top_left: Point, size: Size, is_round: bool) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let circle = Circle::new(top_left, size.width.min(size.height));
+        let pixels = (0..size.height).flat_map(|y| (0..size.width).map(move |x| (x, y)));
+        let pixels = pixels.filter_map(|(x, y)| {
+            let point = top_left + Point::new(x as i32, y as i32);
+            if is_round && !circle.contains(point) {
+                return None;
+            }
+            let source_x = (x * self.width / size.width).min(self.width - 1);
+            let source_y = (y * self.height / size.height).min(self.height - 1);
+            let color = self.pixels[(source_y * self.width + source_x) as usize];
+            Some(Pixel(point, color))
+        });
+        canvas.draw_iter(pixels).unwrap();
+    }
+}
+
+/// The images to draw into a [render], where they've been downloaded.
+#[derive(Debug, Clone, Default)]
+pub struct TweetImages {
+    pub avatar: Option<Bitmap>,
+    pub media: Option<Bitmap>,
+}
+
+impl TweetImages {
+    pub fn avatar_url(tweet: &api::Tweet) -> Option<String> {
+        tweet.author_profile_image_url.clone()
+    }
+
+    /// The first photo, or the preview of the first video or GIF.
+    pub fn media_url(tweet: &api::Tweet) -> Option<String> {
+        let media = tweet.media.as_ref()?.first()?;
+        media.url.clone().or(media.preview_image_url.clone())
+    }
+
+    /// Downloads [tweet]'s avatar and media, unless they're cached.  An image that can't be had is
+    /// left out, and its error returned, so the render falls back to a placeholder or no media.
+    pub async fn load(
+        tweet: &api::Tweet,
+        twitter_client: &TwitterClient,
+        media_cache: &MediaCache,
+    ) -> (Self, Vec<Error>) {
+        let mut images = Self::default();
+        let mut errors = Vec::new();
+        if let Some(url) = Self::avatar_url(tweet) {
+            match load_bitmap(&url, twitter_client, media_cache).await {
+                Ok(avatar) => images.avatar = Some(avatar),
+                Err(err) => errors.push(err.context(format!("Loading the avatar at {url}"))),
+            }
+        }
+        if let Some(url) = Self::media_url(tweet) {
+            match load_bitmap(&url, twitter_client, media_cache).await {
+                Ok(media) => images.media = Some(media),
+                Err(err) => errors.push(err.context(format!("Loading the media at {url}"))),
+            }
+        }
+        (images, errors)
+    }
+}
+
+async fn load_bitmap(
+    url: &str,
+    twitter_client: &TwitterClient,
+    media_cache: &MediaCache,
+) -> Result<Bitmap> {
+    let bytes = media_cache.fetch(url, twitter_client.download(url)).await?;
+    Bitmap::decode(&bytes)
+}
+
+/// The closest RGB to a terminal color, with xterm's palette; [TerminalColor::Reset] is [reset].
+fn rgb(color: ThemeColor, reset: Rgb888) -> Rgb888 {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let ansi = |value: u8| match value {
+        0..=15 => ANSI[value as usize],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let value = value - 16;
+            (level(value / 36), level(value / 6 % 6), level(value % 6))
+        }
+        _ => {
+            let grey = 8 + (value - 232) * 10;
+            (grey, grey, grey)
+        }
+    };
+    let (r, g, b) = match color.0 {
+        TerminalColor::Reset => return reset,
+        TerminalColor::Black => ansi(0),
+        TerminalColor::DarkRed => ansi(1),
+        TerminalColor::DarkGreen => ansi(2),
+        TerminalColor::DarkYellow => ansi(3),
+        TerminalColor::DarkBlue => ansi(4),
+        TerminalColor::DarkMagenta => ansi(5),
+        TerminalColor::DarkCyan => ansi(6),
+        TerminalColor::Grey => ansi(7),
+        TerminalColor::DarkGrey => ansi(8),
+        TerminalColor::Red => ansi(9),
+        TerminalColor::Green => ansi(10),
+        TerminalColor::Yellow => ansi(11),
+        TerminalColor::Blue => ansi(12),
+        TerminalColor::Magenta => ansi(13),
+        TerminalColor::Cyan => ansi(14),
+        TerminalColor::White => ansi(15),
+        TerminalColor::Rgb { r, g, b } => (r, g, b),
+        TerminalColor::AnsiValue(value) => ansi(value),
+    };
+    Rgb888::new(r, g, b)
+}
+
+fn draw_text(canvas: &mut Canvas, text: &str, x: u32, y: u32, font: &MonoFont, color: Rgb888) {
+    let style = MonoTextStyle::new(font, color);
+    Text::with_baseline(text, Point::new(x as i32, y as i32), style, Baseline::Top)
+        .draw(canvas)
+        .unwrap();
+}
+
+/// The author, with their avatar (or a placeholder), then the text, media, date and metrics, as on
+/// twitter.com.  The height follows from the text's length and the media's size.
+pub fn render(tweet: &api::Tweet, theme: &ImageTheme, images: &TweetImages) -> Canvas {
+    let background = rgb(theme.background, Rgb888::WHITE);
+    let text_color = rgb(theme.text, Rgb888::BLACK);
+    let secondary = rgb(theme.secondary, Rgb888::BLACK);
+    let accent = rgb(theme.accent, Rgb888::BLACK);
+
+    let width = theme.width.max(MIN_WIDTH);
+    let columns = |font: &MonoFont, left: u32| {
+        ((width - left - PADDING) / font.character_size.width) as usize
+    };
+    let line_height = |font: &MonoFont| font.character_size.height + LINE_SPACING;
+
+    let text: String = tweet_text::spans(tweet)
+        .iter()
+        .map(|span| span.text.as_str())
+        .collect();
+    let lines = text_width::wrap(&text, columns(&FONT_10X20, PADDING));
+    let mut footer = vec![tweet
+        .local_created_at()
+        .format("%-I:%M %p · %b %-d, %Y")
+        .to_string()];
+    if let Some(metrics) = &tweet.public_metrics {
+        footer.push(format!(
+            "{} Retweets  {} Quotes  {} Likes  {} Replies",
+            metrics.retweet_count, metrics.quote_count, metrics.like_count, metrics.reply_count
+        ));
+    }
+
+    let body_top = PADDING + AVATAR_DIAMETER + PADDING;
+    let media_top = body_top + lines.len() as u32 * line_height(&FONT_10X20) + PADDING;
+    let media_size = images
+        .media
+        .as_ref()
+        .map(|media| media.fit(width - 2 * PADDING, MAX_MEDIA_HEIGHT));
+    let footer_top = match media_size {
+        Some(size) => media_top + size.height + PADDING,
+        None => media_top,
+    };
+    let height = footer_top + footer.len() as u32 * line_height(&FONT_9X18) + PADDING;
+    let mut canvas = Canvas::new(width, height, background);
+
+    let author = tweet.author("[unknown]");
+    let avatar_top_left = Point::new(PADDING as i32, PADDING as i32);
+    match &images.avatar {
+        Some(avatar) => {
+            let size = Size::new(AVATAR_DIAMETER, AVATAR_DIAMETER);
+            avatar.draw(&mut canvas, avatar_top_left, size, true);
+        }
+        None => draw_avatar_placeholder(&mut canvas, &author, avatar_top_left, accent, background),
+    }
+
+    let left = PADDING + AVATAR_DIAMETER + PADDING / 2;
+    let top = PADDING + LINE_SPACING;
+    let name = text_width::truncate(&author.name, columns(&FONT_9X18_BOLD, left));
+    draw_text(&mut canvas, name, left, top, &FONT_9X18_BOLD, text_color);
+    let username = format!("@{}", author.username);
+    let username = text_width::truncate(&username, columns(&FONT_9X18, left));
+    let top = top + line_height(&FONT_9X18_BOLD);
+    draw_text(&mut canvas, username, left, top, &FONT_9X18, secondary);
+
+    for (i, line) in lines.iter().enumerate() {
+        let top = body_top + i as u32 * line_height(&FONT_10X20);
+        draw_text(&mut canvas, line, PADDING, top, &FONT_10X20, text_color);
+    }
+    if let (Some(media), Some(size)) = (&images.media, media_size) {
+        let top_left = Point::new(PADDING as i32, media_top as i32);
+        media.draw(&mut canvas, top_left, size, false);
+    }
+    for (i, line) in footer.iter().enumerate() {
+        let top = footer_top + i as u32 * line_height(&FONT_9X18);
+        let line = text_width::truncate(line, columns(&FONT_9X18, PADDING));
+        draw_text(&mut canvas, line, PADDING, top, &FONT_9X18, secondary);
+    }
+    canvas
+}
+
+/// A circle in [fill] with the author's initial.
+fn draw_avatar_placeholder(
+    canvas: &mut Canvas,
+    author: &api::User,
+    top_left: Point,
+    fill: Rgb888,
+    text_color: Rgb888,
+) {
+    Circle::new(top_left, AVATAR_DIAMETER)
+        .into_styled(PrimitiveStyle::with_fill(fill))
+        .draw(canvas)
+        .unwrap();
+    let initial: String = author
+        .name
+        .chars()
+        .take(1)
+        .flat_map(char::to_uppercase)
+        .collect();
+    let center = top_left + Point::new_equal((AVATAR_DIAMETER / 2) as i32);
+    Text::with_text_style(
+        &initial,
+        center,
+        MonoTextStyle::new(&FONT_10X20, text_color),
+        TextStyleBuilder::new()
+            .alignment(Alignment::Center)
+            .baseline(Baseline::Middle)
+            .build(),
+    )
+    .draw(canvas)
+    .unwrap();
+}
+
+pub fn export(
+    tweet: &api::Tweet,
+    theme: &ImageTheme,
+    images: &TweetImages,
+    path: &Path,
+) -> Result<()> {
+    fs::write(path, render(tweet, theme, images).to_png()?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(canvas: &Canvas, x: u32, y: u32) -> Rgb888 {
+        canvas.pixels[(y * canvas.size.width + x) as usize]
+    }
+
+    fn tweet(text: &str) -> api::Tweet {
+        let mut tweet: api::Tweet = serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "text": text,
+            "created_at": "2023-02-01T12:00:00Z",
+            "author_id": "2",
+            "public_metrics": {"retweet_count": 1, "reply_count": 2, "like_count": 3, "quote_count": 4}
+        }))
+        .unwrap();
+        tweet.author_username = Some("alice".to_string());
+        tweet.author_name = Some("Alice".to_string());
+        tweet
+    }
+
+    #[test]
+    fn test_render() {
+        let theme = ImageTheme::default();
+        let canvas = render(&tweet("hello"), &theme, &TweetImages::default());
+        let height = canvas.size().height;
+        assert_eq!(canvas.size().width, 600);
+
+        assert_eq!(pixel(&canvas, 0, 0), rgb(theme.background, Rgb888::WHITE));
+        // NB: the avatar's left edge, clear of the initial
+        let avatar_left = (PADDING + 2, PADDING + AVATAR_DIAMETER / 2);
+        assert_eq!(
+            pixel(&canvas, avatar_left.0, avatar_left.1),
+            rgb(theme.accent, Rgb888::BLACK)
+        );
+        let body_top = PADDING + AVATAR_DIAMETER + PADDING;
+        let text_color = rgb(theme.text, Rgb888::BLACK);
+        assert!((body_top..body_top + 20)
+            .any(|y| (PADDING..PADDING + 50).any(|x| pixel(&canvas, x, y) == text_color)));
+
+        // NB: each wrapped line adds to the height
+        let long = render(
+            &tweet(&"word ".repeat(100)),
+            &theme,
+            &TweetImages::default(),
+        );
+        assert!(long.size().height > height);
+    }
+
+    #[test]
+    fn test_render_images() {
+        let theme = ImageTheme::default();
+        let red = Rgb888::new(255, 0, 0);
+        let blue = Rgb888::new(0, 0, 255);
+        let solid = |color: Rgb888, width: u32, height: u32| Bitmap {
+            width,
+            height,
+            pixels: vec![color; (width * height) as usize],
+        };
+        let images = TweetImages {
+            avatar: Some(solid(red, 4, 4)),
+            media: Some(solid(blue, 200, 100)),
+        };
+        let plain = render(&tweet("hello"), &theme, &TweetImages::default());
+        let canvas = render(&tweet("hello"), &theme, &images);
+
+        let center = PADDING + AVATAR_DIAMETER / 2;
+        assert_eq!(pixel(&canvas, center, center), red);
+        // NB: the avatar is round, so its bounding box's corner is left as the background
+        assert_eq!(pixel(&canvas, PADDING, PADDING), pixel(&plain, 0, 0));
+
+        // NB: 560 wide at 2:1, below the text
+        let media_top = PADDING + AVATAR_DIAMETER + PADDING + 24 + PADDING;
+        assert_eq!(pixel(&canvas, PADDING, media_top), blue);
+        assert_eq!(pixel(&canvas, 600 - PADDING - 1, media_top + 279), blue);
+        assert_eq!(canvas.size().height, plain.size().height + 280 + PADDING);
+    }
+
+    #[test]
+    fn test_decode() {
+        let theme = ImageTheme {
+            width: MIN_WIDTH,
+            ..ImageTheme::default()
+        };
+        let canvas = render(&tweet("hello"), &theme, &TweetImages::default());
+        let bitmap = Bitmap::decode(&canvas.to_png().unwrap()).unwrap();
+        assert_eq!(bitmap.width, MIN_WIDTH);
+        assert_eq!(bitmap.pixels, canvas.pixels);
+        assert!(Bitmap::decode(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn test_media_url() {
+        let mut tweet = tweet("hello");
+        assert_eq!(TweetImages::media_url(&tweet), None);
+        tweet.media = Some(vec![serde_json::from_value(serde_json::json!({
+            "media_key": "3_1",
+            "type": "video",
+            "preview_image_url": "https://pbs.twimg.com/preview.jpg"
+        }))
+        .unwrap()]);
+        assert_eq!(
+            TweetImages::media_url(&tweet).as_deref(),
+            Some("https://pbs.twimg.com/preview.jpg")
+        );
+    }
+
+    #[test]
+    fn test_to_png() {
+        let theme = ImageTheme {
+            width: 10,
+            ..ImageTheme::default()
+        };
+        let canvas = render(&tweet("hello"), &theme, &TweetImages::default());
+        let png = canvas.to_png().unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().unwrap();
+        assert_eq!(reader.info().width, MIN_WIDTH);
+        assert_eq!(reader.info().height, canvas.size().height);
+    }
+
+    #[test]
+    fn test_rgb() {
+        let black = Rgb888::BLACK;
+        assert_eq!(rgb(ThemeColor(TerminalColor::Reset), black), black);
+        assert_eq!(
+            rgb(ThemeColor(TerminalColor::Rgb { r: 1, g: 2, b: 3 }), black),
+            Rgb888::new(1, 2, 3)
+        );
+        assert_eq!(
+            rgb(ThemeColor(TerminalColor::AnsiValue(208)), black),
+            Rgb888::new(255, 135, 0)
+        );
+        assert_eq!(
+            rgb(ThemeColor(TerminalColor::AnsiValue(232)), black),
+            Rgb888::new(8, 8, 8)
+        );
+        assert_eq!(
+            rgb(ThemeColor(TerminalColor::AnsiValue(9)), black),
+            rgb(ThemeColor(TerminalColor::Red), black)
+        );
+    }
+}
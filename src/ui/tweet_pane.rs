@@ -1,17 +1,21 @@
+use crate::followers::Relationship;
 use crate::store::Store;
 use crate::twitter_client::api;
+use crate::ui::task_manager::TaskKey;
+use crate::ui::tweet_text;
 use crate::ui::InternalEvent;
 use crate::ui_framework::bounding_box::BoundingBox;
-use crate::ui_framework::scroll_buffer::{ScrollBuffer, TextSegment};
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::scroll_buffer::{Payload, ScrollBuffer, TextSegment};
+use crate::ui_framework::text_width;
 use crate::ui_framework::{Input, Render};
 use anyhow::Result;
 use crossterm::cursor;
 use crossterm::event::{KeyCode, KeyEvent};
 use crossterm::queue;
 use crossterm::style::{self, Color, Colors};
-use regex::Regex;
 use std::collections::HashMap;
-use std::io::{Stdout, Write};
+use std::io::Write;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc::UnboundedSender;
@@ -24,6 +28,8 @@ enum Focus {
     Tweet,
     Reply(usize),
     Quote,
+    /// A link, mention, hashtag or cashtag in the tweet, or attached media, by order of appearance
+    Link(usize),
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -63,6 +69,8 @@ pub struct TweetPane {
     display_width: usize,
     focus: Focus,
     focus_map: HashMap<Focus, (usize, usize)>,
+    /// How many [Focus::Link]s the tweet has
+    num_links: usize,
 }
 
 impl TweetPane {
@@ -75,21 +83,134 @@ impl TweetPane {
             events: events.clone(),
             store: store.clone(),
             tweet_details: Arc::new(Mutex::new(TweetDetails::new(tweet_id))),
-            scroll_buffer: ScrollBuffer::new(),
+            scroll_buffer: ScrollBuffer::new().with_soft_wrap(),
             should_update_scroll_buffer: Arc::new(AtomicBool::new(true)),
             display_width: 0,
             focus: Focus::Tweet,
             focus_map: HashMap::new(),
+            num_links: 0,
         }
     }
 
     pub fn set_tweet_id(&mut self, tweet_id: &String) {
         let mut tweet_details = self.tweet_details.lock().unwrap();
-        tweet_details.tweet_id = tweet_id.clone();
+        if tweet_details.tweet_id != *tweet_id {
+            *tweet_details = TweetDetails::new(tweet_id);
+        }
         self.should_update_scroll_buffer
             .store(true, Ordering::Relaxed);
     }
 
+    /// Rebuilds the pane on the next render, e.g. after a note was added.
+    pub fn redraw(&self) {
+        self.should_update_scroll_buffer
+            .store(true, Ordering::SeqCst);
+    }
+
+    pub fn get_tweet_id(&self) -> String {
+        self.tweet_details.lock().unwrap().tweet_id.clone()
+    }
+
+    /// What's under the cursor: another tweet (a parent, the quoted tweet or a reply), or a link,
+    /// mention or tag in the tweet.
+    pub fn get_focused_payload(&self) -> Option<Payload> {
+        let payload = self.scroll_buffer.payload_at_cursor()?;
+        match payload {
+            Payload::TweetId(tweet_id) if *tweet_id == self.get_tweet_id() => None,
+            _ => Some(payload.clone()),
+        }
+    }
+
+    /// Fetches the tweet if it isn't cached, e.g. when it's not from any loaded feed.
+    pub fn do_load_tweet(&self) {
+        let events = self.events.clone();
+        let store = self.store.clone();
+        let tweet_id = self.tweet_details.lock().unwrap().tweet_id.clone();
+        let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
+
+        let task = tokio::spawn(async move {
+            match store.load_tweets(&[tweet_id]).await {
+                Ok(()) => should_update_scroll_buffer.store(true, Ordering::SeqCst),
+                Err(error) => events.send(InternalEvent::LogError(error)).unwrap(),
+            }
+        });
+
+        let task_key = TaskKey::Tweet(self.get_tweet_id());
+        self.events
+            .send(InternalEvent::RegisterTask(Some(task_key), task))
+            .unwrap();
+    }
+
+    /// Translates the tweet, showing the translation beneath it.
+    pub fn do_translate(&self) {
+        let events = self.events.clone();
+        let store = self.store.clone();
+        let tweet_id = self.get_tweet_id();
+        let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
+
+        let task = tokio::spawn(async move {
+            match store.translate_tweet(&tweet_id).await {
+                Ok(()) => should_update_scroll_buffer.store(true, Ordering::SeqCst),
+                Err(error) => events.send(InternalEvent::LogError(error)).unwrap(),
+            }
+        });
+
+        let task_key = TaskKey::Translation(self.get_tweet_id());
+        self.events
+            .send(InternalEvent::RegisterTask(Some(task_key), task))
+            .unwrap();
+    }
+
+    /// Fetches the earlier (or later) versions of an edited tweet, showing them beneath it.
+    pub fn do_load_edit_history(&self) {
+        let events = self.events.clone();
+        let store = self.store.clone();
+        let tweet_id = self.get_tweet_id();
+        let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
+
+        let task = tokio::spawn(async move {
+            match store.load_edit_history(&tweet_id).await {
+                Ok(()) => should_update_scroll_buffer.store(true, Ordering::SeqCst),
+                Err(error) => events.send(InternalEvent::LogError(error)).unwrap(),
+            }
+        });
+
+        let task_key = TaskKey::EditHistory(self.get_tweet_id());
+        self.events
+            .send(InternalEvent::RegisterTask(Some(task_key), task))
+            .unwrap();
+    }
+
+    /// Loads the tweet's conversation, then shows the tweets it replies to above it and the
+    /// replies to it below.
+    pub fn do_load_conversation(&self) {
+        let events = self.events.clone();
+        let store = self.store.clone();
+        let tweet_details = self.tweet_details.clone();
+        let tweet_id = tweet_details.lock().unwrap().tweet_id.clone();
+        let should_update_scroll_buffer = self.should_update_scroll_buffer.clone();
+
+        let task = tokio::spawn(async move {
+            match store.load_conversation(&tweet_id).await {
+                Ok(()) => {
+                    let mut tweet_details = tweet_details.lock().unwrap();
+                    // NB: another tweet may have been selected in the meantime
+                    if tweet_details.tweet_id == tweet_id {
+                        tweet_details.in_reply_to_ids = Some(store.thread_ancestor_ids(&tweet_id));
+                        tweet_details.reply_ids = Some(store.reply_ids(&tweet_id));
+                    }
+                    should_update_scroll_buffer.store(true, Ordering::SeqCst);
+                }
+                Err(error) => events.send(InternalEvent::LogError(error)).unwrap(),
+            }
+        });
+
+        let task_key = TaskKey::Conversation(self.get_tweet_id());
+        self.events
+            .send(InternalEvent::RegisterTask(Some(task_key), task))
+            .unwrap();
+    }
+
     fn set_focus(&mut self, focus: &Focus) {
         let desired = self.focus_map.get(&focus).map(|cur| (focus, cur));
         let default = self
@@ -127,6 +248,9 @@ impl TweetPane {
                 focus_order.push(Focus::InReplyTo(i));
             }
             focus_order.push(Focus::Tweet);
+            for i in 0..self.num_links {
+                focus_order.push(Focus::Link(i));
+            }
             if has_quote {
                 focus_order.push(Focus::Quote);
             }
@@ -145,9 +269,21 @@ impl TweetPane {
     }
 
     fn update_scroll_buffer_and_focus_map(&mut self) {
+        let store = self.store.clone();
+        let show_badges = store.user_config.lock().unwrap().show_badges;
         {
             let tweets = self.store.tweets.lock().unwrap();
-            let tweet_details = self.tweet_details.lock().unwrap();
+            let mut tweet_details = self.tweet_details.lock().unwrap();
+
+            if tweet_details.quote_id.is_none() {
+                if let Some(tweet) = tweets.get(&tweet_details.tweet_id) {
+                    let retweeted = tweet.retweeted_id().map(|id| (QuoteType::Retweet, id));
+                    let quoted = tweet.quoted_id().map(|id| (QuoteType::QuoteTweet, id));
+                    tweet_details.quote_id = retweeted
+                        .or(quoted)
+                        .map(|(quote_type, id)| (quote_type, id.to_string()));
+                }
+            }
 
             let TweetDetails {
                 in_reply_to_ids,
@@ -158,6 +294,7 @@ impl TweetPane {
 
             self.scroll_buffer.clear();
             self.focus_map.clear();
+            self.num_links = 0;
 
             if let Some(in_reply_to_ids) = in_reply_to_ids {
                 for (i, in_reply_to_id) in in_reply_to_ids.iter().enumerate() {
@@ -165,11 +302,19 @@ impl TweetPane {
                         .insert(Focus::InReplyTo(i), (0, self.scroll_buffer.height()));
 
                     if let Some(tweet) = tweets.get(in_reply_to_id) {
-                        self.scroll_buffer
-                            .append(&mut draw_tweet(self.display_width, tweet));
+                        self.scroll_buffer.append(&mut draw_tweet(
+                            self.display_width,
+                            tweet,
+                            store.is_collapsed(tweet),
+                            store.relationship(&tweet.author_id),
+                            show_badges,
+                        ));
                     } else {
-                        self.scroll_buffer
-                            .push(draw_tweet_id(self.display_width, in_reply_to_id));
+                        self.scroll_buffer.push(draw_tweet_id(
+                            self.display_width,
+                            in_reply_to_id,
+                            store.unavailable_reason(in_reply_to_id),
+                        ));
                     }
                     self.scroll_buffer
                         .push(vec![TextSegment::plain("↖ in reply to")]);
@@ -185,30 +330,109 @@ impl TweetPane {
                 .insert(Focus::Tweet, (0, self.scroll_buffer.height()));
 
             if let Some(tweet) = tweets.get(tweet_id) {
-                self.scroll_buffer
-                    .append(&mut draw_tweet(self.display_width, tweet));
+                let top = self.scroll_buffer.height();
+                self.scroll_buffer.append(&mut draw_tweet(
+                    self.display_width,
+                    tweet,
+                    store.is_collapsed(tweet),
+                    store.relationship(&tweet.author_id),
+                    show_badges,
+                ));
+                let payloads = self
+                    .scroll_buffer
+                    .payloads(top..self.scroll_buffer.height());
+                for (cursor, payload) in payloads {
+                    if !matches!(payload, Payload::TweetId(_)) {
+                        self.focus_map.insert(Focus::Link(self.num_links), cursor);
+                        self.num_links += 1;
+                    }
+                }
             } else {
+                self.scroll_buffer.push(draw_tweet_id(
+                    self.display_width,
+                    tweet_id,
+                    store.unavailable_reason(tweet_id),
+                ));
+            }
+            if let Some(tweet) = tweets.get(tweet_id) {
                 self.scroll_buffer
-                    .push(draw_tweet_id(self.display_width, tweet_id));
+                    .append(&mut draw_edit_history(tweet, &tweets));
+            }
+            if let Some(translation) = self.store.translations.lock().unwrap().get(tweet_id) {
+                self.scroll_buffer.push_newline();
+                self.scroll_buffer.push(vec![TextSegment::color(
+                    &format!("⇄ {translation}"),
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                )]);
+            }
+            if let Some(note) = self.store.user_config.lock().unwrap().notes.get(tweet_id) {
+                self.scroll_buffer.push_newline();
+                self.scroll_buffer.push(vec![TextSegment::color(
+                    &format!("✎ {note}"),
+                    Colors::new(Color::DarkGrey, Color::Reset),
+                )]);
             }
             self.scroll_buffer.push_newline();
 
+            if let Some((quote_type, quote_id)) = quote_id {
+                let header = match quote_type {
+                    QuoteType::Retweet => "🔁 retweet of",
+                    QuoteType::QuoteTweet => "❝ quoting",
+                };
+                self.scroll_buffer.push(vec![TextSegment::plain(header)]);
+
+                let str_indent = "    ";
+                self.focus_map.insert(
+                    Focus::Quote,
+                    (text_width::width(str_indent), self.scroll_buffer.height()),
+                );
+                let rem_width = self
+                    .display_width
+                    .saturating_sub(text_width::width(str_indent));
+                let mut line = vec![TextSegment::plain(str_indent)];
+                if let Some(tweet) = tweets.get(quote_id) {
+                    line.append(&mut draw_tweet_one_line(
+                        rem_width,
+                        tweet,
+                        store.is_collapsed(tweet),
+                    ));
+                } else {
+                    line.append(&mut draw_tweet_id(
+                        rem_width,
+                        quote_id,
+                        store.unavailable_reason(quote_id),
+                    ));
+                }
+                self.scroll_buffer.push(line);
+                self.scroll_buffer.push_newline();
+            }
+
             if let Some(reply_ids) = reply_ids {
                 for (i, reply_id) in reply_ids.iter().enumerate() {
                     let str_indent = "    ↪ ";
 
                     self.focus_map.insert(
                         Focus::Reply(i),
-                        (str_indent.len(), self.scroll_buffer.height()),
+                        (text_width::width(str_indent), self.scroll_buffer.height()),
                     );
 
-                    let rem_width = self.display_width.saturating_sub(str_indent.len());
+                    let rem_width = self
+                        .display_width
+                        .saturating_sub(text_width::width(str_indent));
                     let mut line = vec![TextSegment::plain(str_indent)];
 
                     if let Some(tweet) = tweets.get(reply_id) {
-                        line.append(&mut draw_tweet_one_line(rem_width, tweet));
+                        line.append(&mut draw_tweet_one_line(
+                            rem_width,
+                            tweet,
+                            store.is_collapsed(tweet),
+                        ));
                     } else {
-                        line.append(&mut draw_tweet_id(rem_width, reply_id));
+                        line.append(&mut draw_tweet_id(
+                            rem_width,
+                            reply_id,
+                            store.unavailable_reason(reply_id),
+                        ));
                     }
 
                     self.scroll_buffer.push(line);
@@ -218,8 +442,6 @@ impl TweetPane {
                     .push(vec![TextSegment::plain("<reply_ids?>")]);
                 self.scroll_buffer.push_newline();
             }
-
-            // TODO: QT / RT
         }
 
         let current_focus = self.focus.clone();
@@ -229,55 +451,157 @@ impl TweetPane {
     }
 }
 
-fn draw_tweet_id(_width: usize, tweet_id: &str) -> Vec<TextSegment> {
-    vec![TextSegment::plain(&format!("<tweet id: {tweet_id}>"))]
+/// A tweet that isn't cached: a placeholder if it's [unavailable], otherwise its id until it loads.
+fn draw_tweet_id(
+    _width: usize,
+    tweet_id: &str,
+    unavailable: Option<api::Unavailable>,
+) -> Vec<TextSegment> {
+    let payload = Payload::TweetId(tweet_id.to_string());
+    match unavailable {
+        Some(unavailable) => vec![
+            TextSegment::plain("[tweet unavailable]").with_payload(payload),
+            TextSegment::color(
+                &format!("  {}", unavailable.describe()),
+                Colors::new(Color::DarkGrey, Color::Reset),
+            ),
+        ],
+        None => vec![TextSegment::plain(&format!("<tweet id: {tweet_id}>")).with_payload(payload)],
+    }
+}
+
+/// Whether [tweet] was edited, and its other versions that are cached, oldest first.
+fn draw_edit_history(
+    tweet: &api::Tweet,
+    tweets: &HashMap<String, api::Tweet>,
+) -> Vec<Vec<TextSegment>> {
+    let colors = Colors::new(Color::DarkGrey, Color::Reset);
+    let versions: Vec<&api::Tweet> = tweet
+        .other_version_ids()
+        .into_iter()
+        .filter_map(|id| tweets.get(id))
+        .collect();
+    let mut header = if tweet.is_superseded() {
+        "✎ an earlier version; it's since been edited".to_string()
+    } else if tweet.is_edited() {
+        "✎ edited".to_string()
+    } else {
+        return Vec::new();
+    };
+    if versions.is_empty() {
+        header.push_str("  (e for the other versions)");
+    }
+
+    let mut buffer = vec![vec![], vec![TextSegment::color(&header, colors)]];
+    for version in versions {
+        let time = version.local_created_at().format("%Y-%m-%d %H:%M:%S");
+        buffer.push(vec![TextSegment::color(
+            &format!("  {time}  {}", version.text),
+            colors,
+        )
+        .with_payload(Payload::TweetId(version.id.clone()))]);
+    }
+    buffer
 }
 
-fn draw_tweet(width: usize, tweet: &api::Tweet) -> Vec<Vec<TextSegment>> {
+/// [is_collapsed] hides the text and media of a sensitive tweet behind a placeholder.
+fn draw_tweet(
+    _width: usize,
+    tweet: &api::Tweet,
+    is_collapsed: bool,
+    relationship: Option<Relationship>,
+    show_badges: bool,
+) -> Vec<Vec<TextSegment>> {
     let mut buffer = Vec::new();
     let str_unknown = String::from("[unknown]");
-    let tweet_time = tweet.created_at.format("%Y-%m-%d %H:%M:%S");
+    let tweet_time = tweet.local_created_at().format("%Y-%m-%d %H:%M:%S");
     let tweet_author_username = tweet.author_username.as_ref().unwrap_or(&str_unknown);
     let tweet_author_name = tweet.author_name.as_ref().unwrap_or(&str_unknown);
-    let tweet_lines = textwrap::wrap(&tweet.text, width.saturating_sub(1) as usize);
 
     // CR-someday: DSL quote macro, if worthwhile
-    buffer.push(vec![TextSegment::plain(&format!("{tweet_time}"))]);
-    buffer.push(vec![TextSegment::plain(&format!(
-        "@{tweet_author_username} [{tweet_author_name}]"
-    ))]);
+    let payload = Payload::TweetId(tweet.id.clone());
+    let mut header =
+        vec![TextSegment::plain(&format!("{tweet_time}")).with_payload(payload.clone())];
+    if let Some(lang) = tweet.lang.as_deref().filter(|&lang| lang != "und") {
+        header.push(TextSegment::color(
+            &format!("  {lang}"),
+            Colors::new(Color::DarkGrey, Color::Reset),
+        ));
+    }
+    buffer.push(header);
+    let mut author =
+        vec![
+            TextSegment::plain(&format!("@{tweet_author_username} [{tweet_author_name}]"))
+                .with_payload(payload),
+        ];
+    if show_badges {
+        author.append(&mut tweet_text::author_badges(tweet, true));
+    }
+    if tweet.author_protected == Some(true) {
+        author.push(TextSegment::color(
+            "  🔒 protected",
+            Colors::new(Color::DarkGrey, Color::Reset),
+        ));
+    }
+    if let Some(relationship) = relationship {
+        author.push(TextSegment::color(
+            &format!("  {} {}", relationship.badge(), relationship.describe()),
+            Colors::new(Color::DarkGrey, Color::Reset),
+        ));
+    }
+    buffer.push(author);
     buffer.push(vec![]);
 
-    for line in tweet_lines {
-        buffer.push(vec![TextSegment::plain(&line)]);
+    if is_collapsed {
+        buffer.push(vec![tweet_text::sensitive_placeholder(usize::MAX)]);
+        return buffer;
+    }
+    buffer.push(tweet_text::segments(&tweet_text::spans(tweet)));
+
+    let media = tweet.media.as_deref().unwrap_or_default();
+    if !media.is_empty() {
+        buffer.push(vec![]);
+    }
+    for media in media {
+        let kind = media.r#type.replace('_', " ");
+        let segment = match &media.alt_text {
+            Some(alt_text) if media.is_described() => TextSegment::color(
+                &format!("🖼 {kind}: {alt_text}"),
+                Colors::new(Color::DarkGrey, Color::Reset),
+            ),
+            _ => TextSegment::color(
+                &format!("🖼 {kind}, no alt text"),
+                Colors::new(Color::Yellow, Color::Reset),
+            ),
+        };
+        let url = media.url.as_ref().or(media.preview_image_url.as_ref());
+        buffer.push(vec![match url {
+            Some(url) => segment.with_payload(Payload::Url(url.clone())),
+            None => segment,
+        }]);
     }
 
     buffer
 }
 
-fn draw_tweet_one_line(width: usize, tweet: &api::Tweet) -> Vec<TextSegment> {
+fn draw_tweet_one_line(width: usize, tweet: &api::Tweet, is_collapsed: bool) -> Vec<TextSegment> {
     // CR: factor str_unknown to 'static
     let str_unknown = String::from("[unknown]");
     let tweet_author = tweet.author_username.as_ref().unwrap_or(&str_unknown);
     let tweet_author = format!("@{tweet_author} ");
 
-    let mut line = vec![
-        TextSegment::plain("    ↪ "),
-        TextSegment::color(&tweet_author, Colors::new(Color::DarkCyan, Color::Black)),
-    ];
-
-    // TODO: this should be factored, same as feed_pane
-    let re_newlines = Regex::new(r"[\r\n]+").unwrap();
-    let formatted = re_newlines.replace_all(&tweet.text, "⏎ ");
-    let remaining_length = width.saturating_sub(tweet_author.len() + 6) as usize;
-    let lines = textwrap::wrap(&formatted, remaining_length);
-    if lines.len() == 1 {
-        line.push(TextSegment::plain(&lines[0]));
-    } else if lines.len() > 1 {
-        // Rewrap lines to accommodate ellipsis (…), which may knock out a word
-        let remaining_length = remaining_length.saturating_sub(1);
-        let lines = textwrap::wrap(&formatted, remaining_length);
-        line.push(TextSegment::plain(&format!("{}…", &lines[0])));
+    let mut line =
+        vec![
+            TextSegment::color(&tweet_author, Colors::new(Color::DarkCyan, Color::Black))
+                .with_payload(Payload::TweetId(tweet.id.clone())),
+        ];
+
+    // NB: callers indent the line themselves
+    let remaining_length = width.saturating_sub(text_width::width(&tweet_author));
+    if is_collapsed {
+        line.push(tweet_text::sensitive_placeholder(remaining_length));
+    } else {
+        line.append(&mut tweet_text::one_line(tweet, remaining_length));
     }
 
     line
@@ -294,7 +618,7 @@ impl Render for TweetPane {
         self.scroll_buffer.invalidate();
     }
 
-    fn render(&mut self, stdout: &mut Stdout, bounding_box: BoundingBox) -> Result<()> {
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
         let BoundingBox {
             left,
             top,
@@ -335,8 +659,8 @@ impl Input for TweetPane {
 
     fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
         match event.code {
-            KeyCode::Up => (),
-            KeyCode::Down => (),
+            KeyCode::Up => self.update_focus(-1),
+            KeyCode::Down => self.update_focus(1),
             _ => return self.scroll_buffer.handle_key_event(event),
         };
         true
@@ -346,6 +670,9 @@ impl Input for TweetPane {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::fixtures;
+    use crate::ui_framework::testing;
+    use tokio::sync::mpsc;
 
     #[test]
     fn test_segmentation() {
@@ -372,4 +699,155 @@ mod tests {
         let r = Focus::InReplyTo(3);
         assert_eq!(l, r);
     }
+
+    #[test]
+    fn test_unavailable_reply() {
+        let store = fixtures::store(vec![fixtures::tweet("1", "alice", "hello", 1)]);
+        store
+            .unavailable_tweets
+            .lock()
+            .unwrap()
+            .insert("2".to_string(), api::Unavailable::NotAuthorized);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut tweet_pane = TweetPane::new(&events, &store, "1");
+        let mut tweet_details = tweet_pane.tweet_details.lock().unwrap();
+        tweet_details.in_reply_to_ids = Some(vec![]);
+        tweet_details.reply_ids = Some(vec!["2".to_string(), "3".to_string()]);
+        drop(tweet_details);
+        let text = testing::render_to_text(&mut tweet_pane, 50, 12);
+        assert!(text
+            .contains("\n    ↪ [tweet unavailable]  protected or withheld\n    ↪ <tweet id: 3>\n"));
+    }
+
+    #[test]
+    fn test_edit_history() {
+        let edited = |id: &str, minute: u32| api::Tweet {
+            edit_history_tweet_ids: Some(vec!["1".to_string(), "2".to_string()]),
+            ..fixtures::tweet(id, "alice", &format!("version {id}"), minute)
+        };
+        let store = fixtures::store(vec![edited("2", 2)]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut tweet_pane = TweetPane::new(&events, &store, "2");
+        let text = testing::render_to_text(&mut tweet_pane, 50, 12);
+        assert!(text.contains("\n✎ edited  (e for the other versions)\n"));
+
+        store
+            .tweets
+            .lock()
+            .unwrap()
+            .insert("1".to_string(), edited("1", 1));
+        tweet_pane.redraw();
+        let text = testing::render_to_text(&mut tweet_pane, 50, 12);
+        assert!(text.contains("\n✎ edited\n  2023-02-01 00:01:00  version 1\n"));
+
+        tweet_pane.set_tweet_id(&"1".to_string());
+        let text = testing::render_to_text(&mut tweet_pane, 50, 12);
+        assert!(text.contains("\n✎ an earlier version; it's since been edited\n"));
+    }
+
+    #[test]
+    fn test_render() {
+        let store = fixtures::store(vec![
+            fixtures::tweet(
+                "1",
+                "alice",
+                "Why did the chicken cross the road? To get to the other side.",
+                1,
+            ),
+            fixtures::tweet("2", "bob", "to be fair, it was a very long road", 2),
+        ]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut tweet_pane = TweetPane::new(&events, &store, "1");
+        assert_eq!(
+            testing::render_to_text(&mut tweet_pane, 24, 12),
+            [
+                "2023-02-01 00:01:00",
+                "@alice [ALICE]",
+                "",
+                "Why did the chicken",
+                "cross the road? To get",
+                "to the other side.",
+                "",
+                "<reply_ids?>",
+                "",
+                "",
+                "",
+                "",
+            ]
+            .join("\n")
+        );
+
+        tweet_pane.tweet_details.lock().unwrap().in_reply_to_ids = Some(vec![]);
+        tweet_pane.tweet_details.lock().unwrap().reply_ids =
+            Some(vec!["2".to_string(), "3".to_string()]);
+        tweet_pane.set_tweet_id(&"1".to_string());
+        assert_eq!(
+            testing::render_to_text(&mut tweet_pane, 30, 12),
+            [
+                "2023-02-01 00:01:00",
+                "@alice [ALICE]",
+                "",
+                "Why did the chicken cross the",
+                "road? To get to the other",
+                "side.",
+                "",
+                "    ↪ @bob to be fair, it was…",
+                "    ↪ <tweet id: 3>",
+                "",
+                "",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_focus_links() {
+        let tweet = api::Tweet {
+            entities: Some(api::Entities {
+                mentions: Some(vec![api::MentionEntity {
+                    start: 0,
+                    end: 4,
+                    username: "bob".to_string(),
+                    id: None,
+                }]),
+                hashtags: Some(vec![api::TagEntity {
+                    start: 20,
+                    end: 29,
+                    tag: "rustlang".to_string(),
+                }]),
+                cashtags: Some(vec![api::TagEntity {
+                    start: 34,
+                    end: 39,
+                    tag: "TSLA".to_string(),
+                }]),
+                ..Default::default()
+            }),
+            ..fixtures::tweet("1", "alice", "@bob what a week in #rustlang and $TSLA", 1)
+        };
+        let store = fixtures::store(vec![tweet]);
+        let (events, _events_rx) = mpsc::unbounded_channel();
+        let mut tweet_pane = TweetPane::new(&events, &store, "1");
+        testing::render_to_text(&mut tweet_pane, 24, 12);
+        assert_eq!(tweet_pane.get_focused_payload(), None);
+
+        tweet_pane.update_focus(1);
+        assert_eq!(
+            tweet_pane.get_focused_payload(),
+            Some(Payload::User("bob".to_string()))
+        );
+        assert_eq!(tweet_pane.get_cursor(), (0, 3));
+        tweet_pane.update_focus(1);
+        assert_eq!(
+            tweet_pane.get_focused_payload(),
+            Some(Payload::Hashtag("#rustlang".to_string()))
+        );
+        assert_eq!(tweet_pane.get_cursor(), (0, 4));
+        tweet_pane.update_focus(1);
+        assert_eq!(
+            tweet_pane.get_focused_payload(),
+            Some(Payload::Hashtag("$TSLA".to_string()))
+        );
+        assert_eq!(tweet_pane.get_cursor(), (14, 4));
+    }
 }
@@ -1,83 +1,169 @@
-use crate::twitter_client::{api, TwitterClient};
+use crate::feed_source::FeedSource;
+use crate::store::Store;
+use crate::ui::feed_pane::open_in_browser;
+use crate::ui::tweet_pane::TweetPane;
 use crate::ui::InternalEvent;
 use crate::ui_framework::bounding_box::BoundingBox;
-use crate::ui_framework::scroll_buffer::{ScrollBuffer, TextSegment};
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::scroll_buffer::Payload;
+use crate::ui_framework::text_width;
 use crate::ui_framework::{Input, Render};
 use anyhow::Result;
-use crossterm::event::KeyEvent;
-use crossterm::style::{Color, Colors};
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::style::{Attribute, Color};
 use crossterm::terminal::{self, ClearType};
 use crossterm::{cursor, queue, style};
-use regex::Regex;
-use std::collections::HashMap;
-use std::io::Stdout;
-use std::sync::{Arc, Mutex};
+use std::io::Write;
+use std::sync::Arc;
 use tokio::sync::mpsc::UnboundedSender;
 
-// TODO:
-// on focus, attempt to "resolve" a tweet pane
-// that is, load all thread tweets until in_reply_to_ids[0].id == tweet.conversation_id
-//     and, load quoted tweet
-//     and, load one page of replies
-
-// TODO: at this point, let's research a bit into tui-rs and see
-// if some of these problems we're acc-ing haven't simply been solved
+const BREADCRUMB_SEPARATOR: &str = " › ";
 
+/// Drill down into threads: Enter on a parent, quoted tweet or reply opens it in a new pane on top
+/// of the stack, Backspace goes back.  Enter on a link in the tweet opens it in the browser instead,
+/// on a mention the user's timeline in the feed, and on a hashtag or cashtag a search for it.  The header row shows the path
+/// taken.
+///
+/// The bottom pane is whatever is selected in the feed; selecting another tweet there resets the
+/// stack.
 pub struct TweetPaneStack {
     events: UnboundedSender<InternalEvent>,
-    tweets: Arc<Mutex<HashMap<String, api::Tweet>>>,
-    // TODO: consider shifting up and making Arc<Mutex>, not a huge fan of relying on set_selected_tweet_id
-    // unlike move_selected_index in feed_pane, which is legitimate, this is a non-local datum
-    //selected_tweet_id: Option<String>,
-    //stack: Option<TweetPane>,
-    // stack: Vec<TweetPane>,
-    // focus: Focus,
-    // scroll_offset: u16,
-    cursor_position: (u16, u16),
+    store: Arc<Store>,
+    /// Never empty
+    stack: Vec<TweetPane>,
+    should_render_breadcrumbs: bool,
 }
 
 impl TweetPaneStack {
     pub fn new(
         events: &UnboundedSender<InternalEvent>,
-        tweets: &Arc<Mutex<HashMap<String, api::Tweet>>>,
+        store: &Arc<Store>,
+        tweet_id: &str,
     ) -> Self {
         Self {
             events: events.clone(),
-            tweets: tweets.clone(),
-            //selected_tweet_id: None,
-            //stack: None,
-            cursor_position: (0, 0),
+            store: store.clone(),
+            stack: vec![TweetPane::new(events, store, tweet_id)],
+            should_render_breadcrumbs: true,
         }
     }
 
-    // TODO: see above TODO about where selected_tweet_id should live. But now this function also resets the stack
-    // maybe rename it to something more appropriate like [select_tweet]
-    // TODO: deprecate
-    // pub fn set_selected_tweet_id(&mut self, tweet_id: Option<String>) {
-    //     self.selected_tweet_id = tweet_id;
-    // }
-
-    // pub fn open_tweet_pane(&mut self, tweet_primer: &TweetPrimer) {
-    //     let mut tweet_pane = TweetPane::new(&self.tweets, &tweet_primer.tweet_id);
-    //     tweet_pane.set_tweet_primer(tweet_primer);
-    //     self.stack = Some(tweet_pane);
-    // }
-    //
-    // pub fn push_tweet_pane(&mut self, tweet_primer: &TweetPrimer) {
-    //     todo!();
-    // }
+    fn top(&self) -> &TweetPane {
+        self.stack.last().unwrap()
+    }
+
+    fn top_mut(&mut self) -> &mut TweetPane {
+        self.stack.last_mut().unwrap()
+    }
+
+    /// Shows [tweet_id] in the bottom pane, closing any panes above it.
+    pub fn set_tweet_id(&mut self, tweet_id: &String) {
+        if self.stack.len() > 1 {
+            self.stack.truncate(1);
+            self.invalidate();
+        }
+        self.top_mut().set_tweet_id(tweet_id);
+        self.should_render_breadcrumbs = true;
+    }
+
+    pub fn do_load_tweet(&self) {
+        self.top().do_load_tweet();
+    }
+
+    pub fn do_load_conversation(&self) {
+        self.top().do_load_conversation();
+    }
+
+    pub fn do_translate(&self) {
+        self.top().do_translate();
+    }
+
+    pub fn do_load_edit_history(&self) {
+        self.top().do_load_edit_history();
+    }
+
+    /// Rebuilds every pane on the next render.
+    pub fn redraw(&self) {
+        for tweet_pane in self.stack.iter() {
+            tweet_pane.redraw();
+        }
+    }
+
+    /// Opens [tweet_id] in a new pane on top, with its conversation.
+    pub fn push(&mut self, tweet_id: &str) {
+        let mut tweet_pane = TweetPane::new(&self.events, &self.store, tweet_id);
+        tweet_pane.do_load_conversation();
+        tweet_pane.handle_focus();
+        self.stack.push(tweet_pane);
+        self.should_render_breadcrumbs = true;
+    }
+
+    /// The tweets shown, bottom pane first.
+    pub fn tweet_ids(&self) -> Vec<String> {
+        self.stack.iter().map(TweetPane::get_tweet_id).collect()
+    }
+
+    /// Closes the top pane; returns false if it's the only one.
+    pub fn pop(&mut self) -> bool {
+        if self.stack.len() == 1 {
+            return false;
+        }
+        self.stack.pop();
+        self.invalidate();
+        self.top_mut().handle_focus();
+        true
+    }
+
+    fn load_feed(&self, feed_source: FeedSource) {
+        self.events
+            .send(InternalEvent::LoadFeed(feed_source))
+            .unwrap();
+    }
+
+    /// e.g. "@alice › @bob › @carol [3]", eliding from the left to fit [width].
+    fn breadcrumbs(&self, width: usize) -> (String, String) {
+        let tweets = self.store.tweets.lock().unwrap();
+        let labels: Vec<String> = self
+            .stack
+            .iter()
+            .map(|tweet_pane| {
+                let tweet_id = tweet_pane.get_tweet_id();
+                match tweets.get(&tweet_id) {
+                    Some(tweet) => format!(
+                        "@{}",
+                        tweet.author_username.as_deref().unwrap_or("[unknown]")
+                    ),
+                    None => "…".to_string(),
+                }
+            })
+            .collect();
+
+        let (last, rest) = labels.split_last().unwrap();
+        let depth = format!(" [{}]", self.stack.len());
+        let max_rest_width = width.saturating_sub(text_width::width(last) + depth.len());
+        let mut rest: String = rest
+            .iter()
+            .map(|label| format!("{label}{BREADCRUMB_SEPARATOR}"))
+            .collect();
+        if text_width::width(&rest) > max_rest_width {
+            let elided = text_width::truncate_start(&rest, max_rest_width.saturating_sub(1));
+            rest = format!("…{elided}");
+        }
+        (rest, format!("{last}{depth}"))
+    }
 }
 
 impl Render for TweetPaneStack {
     fn should_render(&self) -> bool {
-        todo!()
+        self.should_render_breadcrumbs || self.top().should_render()
     }
 
     fn invalidate(&mut self) {
-        todo!()
+        self.should_render_breadcrumbs = true;
+        self.top_mut().invalidate();
     }
 
-    fn render(&mut self, stdout: &mut Stdout, bounding_box: BoundingBox) -> Result<()> {
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
         let BoundingBox {
             left,
             top,
@@ -85,31 +171,57 @@ impl Render for TweetPaneStack {
             height,
         } = bounding_box;
 
-        self.cursor_position = (left, top);
+        // NB: the author may only be known once the tweet is loaded, so always redraw along with
+        // the pane
+        if self.should_render_breadcrumbs || self.top().should_render() {
+            let (rest, last) = self.breadcrumbs(width as usize);
+            queue!(stdout, cursor::MoveTo(left, top))?;
+            queue!(stdout, style::SetForegroundColor(Color::DarkGrey))?;
+            queue!(stdout, style::Print(rest))?;
+            queue!(stdout, style::ResetColor)?;
+            queue!(stdout, style::SetAttribute(Attribute::Bold))?;
+            queue!(stdout, style::Print(last))?;
+            queue!(stdout, style::SetAttribute(Attribute::Reset))?;
+            queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
+            self.should_render_breadcrumbs = false;
+        }
 
-        // if let Some(tweet_pane) = &mut self.stack {
-        //     tweet_pane.render(stdout, bounding_box)?;
-        // }
+        self.top_mut().render(
+            stdout,
+            BoundingBox {
+                top: top + 1,
+                height: height.saturating_sub(1),
+                ..bounding_box
+            },
+        )?;
 
+        stdout.flush()?;
         Ok(())
     }
 
     fn get_cursor(&self) -> (u16, u16) {
-        return self.cursor_position;
+        let (x, y) = self.top().get_cursor();
+        (x, y + 1)
     }
 }
 
 impl Input for TweetPaneStack {
     fn handle_focus(&mut self) {
-        // if let Some(tweet_pane) = &self.stack {
-        //     let tweet_primer = &tweet_pane.tweet_primer;
-        //     self.events
-        //         .send(InternalEvent::HydrateSelectedTweet(tweet_primer.clone()))
-        //         .unwrap()
-        // }
+        self.top_mut().handle_focus();
     }
 
-    fn handle_key_event(&mut self, _event: &KeyEvent) -> bool {
-        todo!()
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Enter => match self.top().get_focused_payload() {
+                Some(Payload::TweetId(tweet_id)) => self.push(&tweet_id),
+                Some(Payload::Url(url)) => open_in_browser(&url),
+                Some(Payload::User(username)) => self.load_feed(FeedSource::User(username)),
+                Some(Payload::Hashtag(tag)) => self.load_feed(FeedSource::Search(tag)),
+                None => return false,
+            },
+            KeyCode::Backspace => return self.pop(),
+            _ => return self.top_mut().handle_key_event(event),
+        }
+        true
     }
 }
@@ -0,0 +1,324 @@
+use crate::twitter_client::api;
+use crate::ui_framework::scroll_buffer::{Payload, TextSegment};
+use crate::ui_framework::text_width;
+use crossterm::style::{Attribute, Attributes, Color, Colors};
+use regex::Regex;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Plain,
+    Url,
+    Mention,
+    Hashtag,
+}
+
+/// A run of tweet text, styled according to the entity (if any) that it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub kind: SpanKind,
+    pub payload: Option<Payload>,
+}
+
+impl Span {
+    fn to_segment(&self, text: &str) -> TextSegment {
+        let segment = match self.kind {
+            SpanKind::Plain => TextSegment::plain(text),
+            SpanKind::Url => TextSegment::new(
+                text,
+                Colors::new(Color::Blue, Color::Reset),
+                Attributes::from(Attribute::Underlined),
+            ),
+            SpanKind::Mention => TextSegment::color(text, Colors::new(Color::Cyan, Color::Reset)),
+            SpanKind::Hashtag => {
+                TextSegment::color(text, Colors::new(Color::Magenta, Color::Reset))
+            }
+        };
+        match &self.payload {
+            Some(payload) => segment.with_payload(payload.clone()),
+            None => segment,
+        }
+    }
+}
+
+/// Shown instead of a tweet's text while it's collapsed as sensitive, see [Filters::collapses].
+///
+/// [Filters::collapses]: crate::user_config::Filters::collapses
+pub fn sensitive_placeholder(width: usize) -> TextSegment {
+    TextSegment::color(
+        text_width::truncate("[sensitive content — press x to reveal]", width),
+        Colors::new(Color::DarkGrey, Color::Reset),
+    )
+}
+
+/// The author's verified badge, colored by [api::User::verified_type], then their affiliation's;
+/// [verbose] spells out what each one means.
+pub fn author_badges(tweet: &api::Tweet, verbose: bool) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    let verified_color = match tweet.author_verified_type.as_deref() {
+        Some("blue") => Some(Color::Blue),
+        Some("business") => Some(Color::Yellow),
+        Some("government") => Some(Color::Grey),
+        _ => None,
+    };
+    if let (Some(verified_type), Some(color)) = (&tweet.author_verified_type, verified_color) {
+        let text = if verbose {
+            format!("  ✓ verified ({verified_type})")
+        } else {
+            "✓ ".to_string()
+        };
+        segments.push(TextSegment::color(&text, Colors::new(color, Color::Reset)));
+    }
+    if let Some(affiliation) = &tweet.author_affiliation {
+        let text = if verbose {
+            format!("  ◆ {affiliation}")
+        } else {
+            "◆ ".to_string()
+        };
+        segments.push(TextSegment::color(
+            &text,
+            Colors::new(Color::DarkYellow, Color::Reset),
+        ));
+    }
+    segments
+}
+
+/// Start, end, kind, replacement text and payload.
+type Entity = (usize, usize, SpanKind, Option<String>, Option<Payload>);
+
+/// Splits the tweet text into spans at its entities, substituting display URLs for t.co links.
+/// Entities with indices that don't fit the text are ignored.
+pub fn spans(tweet: &api::Tweet) -> Vec<Span> {
+    let chars: Vec<char> = tweet.text.chars().collect();
+    let mut entities: Vec<Entity> = Vec::new();
+
+    if let Some(tweet_entities) = &tweet.entities {
+        for url in tweet_entities.urls.iter().flatten() {
+            let display_url = url.display_url.clone().or(url.expanded_url.clone());
+            let payload = Payload::Url(url.expanded_url.clone().unwrap_or(url.url.clone()));
+            entities.push((
+                url.start,
+                url.end,
+                SpanKind::Url,
+                display_url,
+                Some(payload),
+            ));
+        }
+        for mention in tweet_entities.mentions.iter().flatten() {
+            let payload = Payload::User(mention.username.clone());
+            entities.push((
+                mention.start,
+                mention.end,
+                SpanKind::Mention,
+                None,
+                Some(payload),
+            ));
+        }
+        for tag in tweet_entities
+            .hashtags
+            .iter()
+            .chain(tweet_entities.cashtags.iter())
+            .flatten()
+        {
+            entities.push((tag.start, tag.end, SpanKind::Hashtag, None, None));
+        }
+    }
+    entities.sort_by_key(|&(start, ..)| start);
+
+    let mut spans = Vec::new();
+    let mut last_end = 0;
+    let mut push = |text: String, kind: SpanKind, payload: Option<Payload>| {
+        if !text.is_empty() {
+            spans.push(Span {
+                text,
+                kind,
+                payload,
+            });
+        }
+    };
+
+    for (start, end, kind, replacement, payload) in entities {
+        // NB: skip overlapping or out of range entities rather than trusting the API blindly
+        if start < last_end || start >= end || end > chars.len() {
+            continue;
+        }
+        push(
+            chars[last_end..start].iter().collect(),
+            SpanKind::Plain,
+            None,
+        );
+        let text = replacement.unwrap_or_else(|| chars[start..end].iter().collect());
+        // NB: a tag's payload is its text as written, with the '#' or '$'
+        let payload = match kind {
+            SpanKind::Hashtag => Some(Payload::Hashtag(text.clone())),
+            _ => payload,
+        };
+        push(text, kind, payload);
+        last_end = end;
+    }
+    push(chars[last_end..].iter().collect(), SpanKind::Plain, None);
+
+    spans
+}
+
+/// [spans] as a single line, for a [ScrollBuffer] that wraps it itself.
+///
+/// [ScrollBuffer]: crate::ui_framework::scroll_buffer::ScrollBuffer
+pub fn segments(spans: &[Span]) -> Vec<TextSegment> {
+    spans
+        .iter()
+        .map(|span| span.to_segment(&span.text))
+        .collect()
+}
+
+/// Word wraps [spans] to [width], keeping each span's style across line breaks.
+pub fn wrap(spans: &[Span], width: usize) -> Vec<Vec<TextSegment>> {
+    let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+    let mut span_ranges = Vec::new();
+    let mut offset = 0;
+    for span in spans {
+        span_ranges.push((offset, offset + span.text.len(), span));
+        offset += span.text.len();
+    }
+
+    let mut lines = Vec::new();
+    for Range {
+        start: line_start,
+        end: line_end,
+    } in text_width::wrap_ranges(&text, width)
+    {
+        let segments = span_ranges
+            .iter()
+            .filter(|&&(start, end, _)| start < line_end && end > line_start)
+            .map(|&(start, end, span)| {
+                span.to_segment(&text[start.max(line_start)..end.min(line_end)])
+            })
+            .collect();
+        lines.push(segments);
+    }
+    lines
+}
+
+/// The tweet on a single line of [width], with newlines marked and an ellipsis if truncated.
+pub fn one_line(tweet: &api::Tweet, width: usize) -> Vec<TextSegment> {
+    let re_newlines = Regex::new(r"[\r\n]+").unwrap();
+    let spans: Vec<Span> = spans(tweet)
+        .into_iter()
+        .map(|span| Span {
+            text: re_newlines.replace_all(&span.text, "⏎ ").to_string(),
+            ..span
+        })
+        .collect();
+
+    let lines = wrap(&spans, width);
+    if lines.len() > 1 {
+        // Rewrap lines to accommodate ellipsis (…), which may knock out a word
+        let mut lines = wrap(&spans, width.saturating_sub(1));
+        let mut line = lines.swap_remove(0);
+        line.push(TextSegment::plain("…"));
+        line
+    } else {
+        lines.into_iter().next().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tweet() -> api::Tweet {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "text": "hey @ferris, see https://t.co/abc #rustlang $TSLA",
+            "created_at": "2023-02-01T00:00:00Z",
+            "author_id": "2",
+            "entities": {
+                "urls": [{
+                    "start": 17,
+                    "end": 33,
+                    "url": "https://t.co/abc",
+                    "expanded_url": "https://blog.example.com/post",
+                    "display_url": "blog.example.com/post"
+                }],
+                "mentions": [{"start": 4, "end": 11, "username": "ferris", "id": "3"}],
+                "hashtags": [{"start": 34, "end": 43, "tag": "rustlang"}],
+                "cashtags": [{"start": 44, "end": 49, "tag": "TSLA"}]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_spans() {
+        let spans = spans(&tweet());
+        let summary: Vec<(&str, SpanKind)> = spans
+            .iter()
+            .map(|span| (span.text.as_str(), span.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("hey ", SpanKind::Plain),
+                ("@ferris", SpanKind::Mention),
+                (", see ", SpanKind::Plain),
+                ("blog.example.com/post", SpanKind::Url),
+                (" ", SpanKind::Plain),
+                ("#rustlang", SpanKind::Hashtag),
+                (" ", SpanKind::Plain),
+                ("$TSLA", SpanKind::Hashtag),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrap_splits_spans_at_line_breaks() {
+        let spans = spans(&tweet());
+        let lines = wrap(&spans, 12);
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(lines.len(), text_width::wrap(&text, 12).len());
+        // NB: the first line, "hey @ferris,", is a mention between plain text
+        assert_eq!(lines[0].len(), 3);
+    }
+
+    #[test]
+    fn test_author_badges() {
+        let mut tweet = tweet();
+        assert!(author_badges(&tweet, false).is_empty());
+
+        tweet.author_verified_type = Some("business".to_string());
+        tweet.author_affiliation = Some("Acme".to_string());
+        assert_eq!(
+            author_badges(&tweet, false),
+            vec![
+                TextSegment::color("✓ ", Colors::new(Color::Yellow, Color::Reset)),
+                TextSegment::color("◆ ", Colors::new(Color::DarkYellow, Color::Reset)),
+            ]
+        );
+        assert_eq!(
+            author_badges(&tweet, true)[1],
+            TextSegment::color("  ◆ Acme", Colors::new(Color::DarkYellow, Color::Reset))
+        );
+
+        // NB: "none" is what the API says for unverified accounts
+        tweet.author_verified_type = Some("none".to_string());
+        assert_eq!(author_badges(&tweet, false).len(), 1);
+    }
+
+    #[test]
+    fn test_payloads() {
+        let payloads: Vec<Payload> = spans(&tweet())
+            .into_iter()
+            .filter_map(|span| span.payload)
+            .collect();
+        assert_eq!(
+            payloads,
+            vec![
+                Payload::User("ferris".to_string()),
+                Payload::Url("https://blog.example.com/post".to_string()),
+                Payload::Hashtag("#rustlang".to_string()),
+                Payload::Hashtag("$TSLA".to_string()),
+            ]
+        );
+    }
+}
@@ -15,4 +15,54 @@ impl BoundingBox {
             height,
         }
     }
+
+    /// A box of at most [width] x [height], centered within this one.
+    pub fn centered(&self, width: u16, height: u16) -> Self {
+        let width = width.min(self.width);
+        let height = height.min(self.height);
+        Self {
+            left: self.left + (self.width - width) / 2,
+            top: self.top + (self.height - height) / 2,
+            width,
+            height,
+        }
+    }
+
+    /// [n] boxes side by side filling this one, the leftmost ones a cell wider if it doesn't divide
+    /// evenly.
+    pub fn split_columns(&self, n: u16) -> Vec<Self> {
+        let n = n.max(1);
+        let (width, remainder) = (self.width / n, self.width % n);
+        let mut left = self.left;
+        (0..n)
+            .map(|i| {
+                let width = width + u16::from(i < remainder);
+                let column = Self {
+                    left,
+                    width,
+                    ..*self
+                };
+                left += width;
+                column
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_columns() {
+        let columns = BoundingBox::new(2, 1, 11, 5).split_columns(3);
+        let lefts_and_widths: Vec<(u16, u16)> = columns
+            .iter()
+            .map(|column| (column.left, column.width))
+            .collect();
+        assert_eq!(lefts_and_widths, vec![(2, 4), (6, 4), (10, 3)]);
+        assert!(columns
+            .iter()
+            .all(|column| column.top == 1 && column.height == 5));
+    }
 }
@@ -0,0 +1,124 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::overlay::Overlay;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::style::{Color, Colors};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, queue, style};
+use std::io::Write;
+use tokio::sync::oneshot;
+
+/// One line yes/no prompt (e.g. "Delete draft? y/n") drawn over the bottom bar, for confirming
+/// destructive actions.  Captures keys until answered; Esc counts as no.
+pub struct ConfirmPrompt {
+    message: String,
+    responder: Option<oneshot::Sender<bool>>,
+    should_render: bool,
+}
+
+impl ConfirmPrompt {
+    /// The answer is sent to [responder]; if the prompt is dropped unanswered, the receiver sees
+    /// an error, which callers should treat as no.
+    pub fn new(message: &str, responder: oneshot::Sender<bool>) -> Self {
+        Self {
+            message: message.to_string(),
+            responder: Some(responder),
+            should_render: true,
+        }
+    }
+
+    fn respond(&mut self, answer: bool) {
+        if let Some(responder) = self.responder.take() {
+            // NB: the receiver may have given up waiting, which is fine
+            let _ = responder.send(answer);
+        }
+    }
+}
+
+impl Render for ConfirmPrompt {
+    fn should_render(&self) -> bool {
+        self.should_render
+    }
+
+    fn invalidate(&mut self) {
+        self.should_render = true;
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        queue!(stdout, cursor::MoveTo(bounding_box.left, bounding_box.top))?;
+        queue!(
+            stdout,
+            style::SetColors(Colors::new(Color::Black, Color::Yellow))
+        )?;
+        queue!(stdout, style::Print(format!("{} y/n ", self.message)))?;
+        queue!(stdout, terminal::Clear(ClearType::UntilNewLine))?;
+        queue!(stdout, style::ResetColor)?;
+
+        stdout.flush()?;
+        self.should_render = false;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        ((text_width::width(&self.message) + 5) as u16, 0)
+    }
+}
+
+impl Input for ConfirmPrompt {
+    fn handle_focus(&mut self) {
+        self.should_render = true;
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Char('y' | 'Y') => self.respond(true),
+            KeyCode::Char('n' | 'N') | KeyCode::Esc => self.respond(false),
+            _ => (),
+        }
+        true
+    }
+}
+
+impl Overlay for ConfirmPrompt {
+    /// The row just below the overlay area, i.e. the bottom bar.
+    fn placement(&self, area: BoundingBox) -> BoundingBox {
+        BoundingBox::new(area.left, area.top + area.height + 1, area.width, 1)
+    }
+
+    fn is_dismissed(&self) -> bool {
+        self.responder.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn key(ch: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(ch), KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_resolves_on_answer() {
+        let (tx, mut rx) = oneshot::channel();
+        let mut prompt = ConfirmPrompt::new("Delete draft?", tx);
+        prompt.handle_key_event(&key('x'));
+        assert!(!prompt.is_dismissed());
+        assert!(rx.try_recv().is_err());
+
+        prompt.handle_key_event(&key('y'));
+        assert!(prompt.is_dismissed());
+        assert_eq!(rx.try_recv(), Ok(true));
+    }
+
+    #[test]
+    fn test_dropped_prompt_is_an_error() {
+        let (tx, mut rx) = oneshot::channel::<bool>();
+        drop(ConfirmPrompt::new("Delete draft?", tx));
+        assert!(rx.try_recv().is_err());
+    }
+}
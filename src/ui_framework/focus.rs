@@ -0,0 +1,155 @@
+use crate::ui_framework::Input;
+use crossterm::event::{KeyCode, KeyEvent};
+
+/// Which child of a container has focus.  Tab and Shift-Tab cycle through the children in the
+/// ring; other children (e.g. a picker) are modal, focused with [push] until they [pop] back to
+/// whatever had focus before.
+#[derive(Debug, Clone)]
+pub struct FocusRing<F> {
+    ring: Vec<F>,
+    /// Never empty; the last is focused
+    stack: Vec<F>,
+}
+
+impl<F: Copy + Eq> FocusRing<F> {
+    /// Focuses the first of [ring], which must not be empty.
+    pub fn new(ring: Vec<F>) -> Self {
+        let stack = vec![ring[0]];
+        Self { ring, stack }
+    }
+
+    pub fn get(&self) -> F {
+        *self.stack.last().unwrap()
+    }
+
+    /// Focuses [focus] in place of the current focus; returns the previous focus.
+    pub fn set(&mut self, focus: F) -> F {
+        std::mem::replace(self.stack.last_mut().unwrap(), focus)
+    }
+
+    /// Focuses [focus] on top of the current focus; returns the previous focus.
+    pub fn push(&mut self, focus: F) -> F {
+        let prev_focus = self.get();
+        self.stack.push(focus);
+        prev_focus
+    }
+
+    /// Returns focus to whatever had it before the last [push]; returns the previous focus.  Does
+    /// nothing if there was no push.
+    pub fn pop(&mut self) -> F {
+        let prev_focus = self.get();
+        if self.stack.len() > 1 {
+            self.stack.pop();
+        }
+        prev_focus
+    }
+
+    /// Moves focus by [delta] around the ring, wrapping at either end, unless a modal child has
+    /// focus.  Returns the previous focus.
+    pub fn cycle(&mut self, delta: isize) -> F {
+        let prev_focus = self.get();
+        if let Some(index) = self.ring.iter().position(|&focus| focus == prev_focus) {
+            let num_focuses = self.ring.len() as isize;
+            let index = (index as isize + delta).rem_euclid(num_focuses);
+            self.set(self.ring[index as usize]);
+        }
+        prev_focus
+    }
+}
+
+/// A component whose children take turns having focus.  Implementers map each focus to its child;
+/// moving focus through these methods tells the children when they gain and lose it.
+pub trait FocusContainer {
+    type Focus: Copy + Eq;
+
+    fn focus_ring(&self) -> &FocusRing<Self::Focus>;
+
+    fn focus_ring_mut(&mut self) -> &mut FocusRing<Self::Focus>;
+
+    fn child(&mut self, focus: Self::Focus) -> &mut dyn Input;
+
+    fn get_focus(&self) -> Self::Focus {
+        self.focus_ring().get()
+    }
+
+    fn set_focus(&mut self, focus: Self::Focus) {
+        let prev_focus = self.focus_ring_mut().set(focus);
+        self.notify_focus_change(prev_focus);
+    }
+
+    fn push_focus(&mut self, focus: Self::Focus) {
+        let prev_focus = self.focus_ring_mut().push(focus);
+        self.notify_focus_change(prev_focus);
+    }
+
+    fn pop_focus(&mut self) {
+        let prev_focus = self.focus_ring_mut().pop();
+        self.notify_focus_change(prev_focus);
+    }
+
+    /// Gives focus to the focused child, e.g. when the container itself gains focus.
+    fn focus_child(&mut self) {
+        let focus = self.get_focus();
+        self.child(focus).handle_focus();
+    }
+
+    fn notify_focus_change(&mut self, prev_focus: Self::Focus) {
+        let focus = self.get_focus();
+        if focus != prev_focus {
+            self.child(prev_focus).handle_blur();
+        }
+        // NB: always refocus, since e.g. the terminal cursor may have moved in the meantime
+        self.child(focus).handle_focus();
+    }
+
+    /// Tab and Shift-Tab move focus around the ring; everything else goes to the focused child.
+    fn route_key_event(&mut self, event: &KeyEvent) -> bool {
+        let delta = match event.code {
+            KeyCode::Tab => 1,
+            KeyCode::BackTab => -1,
+            _ => {
+                let focus = self.get_focus();
+                return self.child(focus).handle_key_event(event);
+            }
+        };
+        let prev_focus = self.focus_ring_mut().cycle(delta);
+        self.notify_focus_change(prev_focus);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Focus {
+        A,
+        B,
+        C,
+        Modal,
+    }
+
+    #[test]
+    fn test_focus_ring() {
+        let mut focus_ring = FocusRing::new(vec![Focus::A, Focus::B, Focus::C]);
+        assert_eq!(focus_ring.get(), Focus::A);
+
+        focus_ring.cycle(1);
+        assert_eq!(focus_ring.get(), Focus::B);
+        focus_ring.cycle(-2);
+        assert_eq!(focus_ring.get(), Focus::C);
+
+        assert_eq!(focus_ring.push(Focus::Modal), Focus::C);
+        // NB: a modal child keeps focus until it's popped
+        focus_ring.cycle(1);
+        assert_eq!(focus_ring.get(), Focus::Modal);
+        assert_eq!(focus_ring.pop(), Focus::Modal);
+        assert_eq!(focus_ring.get(), Focus::C);
+
+        focus_ring.pop();
+        assert_eq!(focus_ring.get(), Focus::C);
+        assert_eq!(focus_ring.set(Focus::A), Focus::C);
+        assert_eq!(focus_ring.get(), Focus::A);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::overlay::Overlay;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_area::TextArea;
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{Color, Colors};
+use crossterm::{cursor, queue, style};
+use std::io::Write;
+use tokio::sync::oneshot;
+
+const WIDTH: u16 = 64;
+const HINT: &str = "Tab next field, ^S save, Esc cancel";
+
+#[derive(Debug)]
+struct Field {
+    label: String,
+    text_area: TextArea,
+    height: u16,
+}
+
+/// Small dialog of labelled text fields, centered over the overlay area.  Tab/Shift-Tab move
+/// between fields, Ctrl-S submits and Esc cancels.
+#[derive(Debug)]
+pub struct Form {
+    title: String,
+    fields: Vec<Field>,
+    focus: usize,
+    responder: Option<oneshot::Sender<Option<Vec<String>>>>,
+    should_render: bool,
+}
+
+impl Form {
+    /// On submit, the field values (in the order they were added) are sent to [responder]; on
+    /// cancel, None is.
+    pub fn new(title: &str, responder: oneshot::Sender<Option<Vec<String>>>) -> Self {
+        Self {
+            title: title.to_string(),
+            fields: Vec::new(),
+            focus: 0,
+            responder: Some(responder),
+            should_render: true,
+        }
+    }
+
+    /// Adds a field [height] rows tall, prefilled with [value].
+    pub fn with_field(mut self, label: &str, value: &str, height: u16) -> Self {
+        let mut text_area = TextArea::new();
+        text_area.set_text(value);
+        self.fields.push(Field {
+            label: label.to_string(),
+            text_area,
+            height: height.max(1),
+        });
+        self
+    }
+
+    fn respond(&mut self, answer: Option<Vec<String>>) {
+        if let Some(responder) = self.responder.take() {
+            // NB: the receiver may have given up waiting, which is fine
+            let _ = responder.send(answer);
+        }
+    }
+
+    fn move_focus(&mut self, delta: isize) {
+        if self.fields.is_empty() {
+            return;
+        }
+        let len = self.fields.len() as isize;
+        self.focus = (self.focus as isize + delta).rem_euclid(len) as usize;
+        self.fields[self.focus].text_area.handle_focus();
+        self.should_render = true;
+    }
+
+    /// Row offset of each field's text area from the top of the form.
+    fn field_offsets(&self) -> Vec<u16> {
+        let mut offset = 1;
+        self.fields
+            .iter()
+            .map(|field| {
+                // NB: one row for the label
+                offset += 1;
+                let top = offset;
+                offset += field.height;
+                top
+            })
+            .collect()
+    }
+
+    fn height(&self) -> u16 {
+        // NB: title, then each label and field, then the hint
+        2 + self
+            .fields
+            .iter()
+            .map(|field| 1 + field.height)
+            .sum::<u16>()
+    }
+}
+
+impl Render for Form {
+    fn should_render(&self) -> bool {
+        self.should_render
+            || self
+                .fields
+                .iter()
+                .any(|field| field.text_area.should_render())
+    }
+
+    fn invalidate(&mut self) {
+        self.should_render = true;
+        for field in self.fields.iter_mut() {
+            field.text_area.invalidate();
+        }
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left, top, width, ..
+        } = bounding_box;
+        let str_clear = " ".repeat(width as usize);
+
+        if self.should_render {
+            for y_offset in 0..bounding_box.height {
+                queue!(stdout, cursor::MoveTo(left, top + y_offset))?;
+                queue!(stdout, style::Print(&str_clear))?;
+            }
+            queue!(stdout, cursor::MoveTo(left, top))?;
+            queue!(stdout, style::SetAttribute(style::Attribute::Bold))?;
+            queue!(
+                stdout,
+                style::Print(text_width::truncate(&self.title, width as usize))
+            )?;
+            queue!(stdout, style::SetAttribute(style::Attribute::Reset))?;
+
+            for (i, (field, field_top)) in self.fields.iter().zip(self.field_offsets()).enumerate()
+            {
+                let color = if i == self.focus {
+                    Color::Yellow
+                } else {
+                    Color::DarkGrey
+                };
+                queue!(stdout, cursor::MoveTo(left, top + field_top - 1))?;
+                queue!(stdout, style::SetColors(Colors::new(color, Color::Reset)))?;
+                queue!(stdout, style::Print(&field.label))?;
+                queue!(stdout, style::ResetColor)?;
+            }
+
+            queue!(stdout, cursor::MoveTo(left, top + self.height() - 1))?;
+            queue!(
+                stdout,
+                style::SetColors(Colors::new(Color::DarkGrey, Color::Reset))
+            )?;
+            queue!(stdout, style::Print(HINT))?;
+            queue!(stdout, style::ResetColor)?;
+
+            for field in self.fields.iter_mut() {
+                field.text_area.invalidate();
+            }
+        }
+
+        let offsets = self.field_offsets();
+        for (field, field_top) in self.fields.iter_mut().zip(offsets) {
+            if field.text_area.should_render() {
+                field.text_area.render(
+                    stdout,
+                    BoundingBox::new(left, top + field_top, width, field.height),
+                )?;
+            }
+        }
+
+        stdout.flush()?;
+        self.should_render = false;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        match self.fields.get(self.focus) {
+            Some(field) => {
+                let (x, y) = field.text_area.get_cursor();
+                (x, y + self.field_offsets()[self.focus])
+            }
+            None => (0, 0),
+        }
+    }
+}
+
+impl Input for Form {
+    fn handle_focus(&mut self) {
+        self.should_render = true;
+        if let Some(field) = self.fields.get_mut(self.focus) {
+            field.text_area.handle_focus();
+        }
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        match (event.code, event.modifiers) {
+            (KeyCode::Esc, _) => self.respond(None),
+            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+                let values = self
+                    .fields
+                    .iter()
+                    .map(|field| field.text_area.get_text())
+                    .collect();
+                self.respond(Some(values));
+            }
+            (KeyCode::Tab, _) => self.move_focus(1),
+            (KeyCode::BackTab, _) => self.move_focus(-1),
+            _ => {
+                if let Some(field) = self.fields.get_mut(self.focus) {
+                    field.text_area.handle_key_event(event);
+                }
+            }
+        }
+        true
+    }
+}
+
+impl Overlay for Form {
+    fn placement(&self, area: BoundingBox) -> BoundingBox {
+        area.centered(WIDTH, self.height())
+    }
+
+    fn is_dismissed(&self) -> bool {
+        self.responder.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_submit_and_cancel() {
+        let (tx, mut rx) = oneshot::channel();
+        let mut form = Form::new("@alice", tx)
+            .with_field("Tags", "rust", 1)
+            .with_field("Notes", "", 3);
+        form.handle_focus();
+        form.handle_key_event(&key(KeyCode::Char(' ')));
+        form.handle_key_event(&key(KeyCode::Char('m')));
+        form.handle_key_event(&key(KeyCode::Tab));
+        form.handle_key_event(&key(KeyCode::Char('x')));
+        assert_eq!(form.get_cursor(), (1, 4));
+        assert!(!form.is_dismissed());
+
+        form.handle_key_event(&KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+        assert!(form.is_dismissed());
+        assert_eq!(
+            rx.try_recv(),
+            Ok(Some(vec!["rust m".to_string(), "x".to_string()]))
+        );
+
+        let (tx, mut rx) = oneshot::channel();
+        let mut form = Form::new("@alice", tx).with_field("Tags", "rust", 1);
+        form.handle_key_event(&key(KeyCode::Esc));
+        assert!(form.is_dismissed());
+        assert_eq!(rx.try_recv(), Ok(None));
+    }
+}
@@ -1,10 +1,27 @@
 use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
 use anyhow::Result;
 use crossterm::event::KeyEvent;
-use std::io::Stdout;
+use std::time::Duration;
 
 pub mod bounding_box;
+pub mod confirm_prompt;
+pub mod focus;
+pub mod form;
+pub mod overlay;
+pub mod picker;
+pub mod screen_buffer;
 pub mod scroll_buffer;
+#[cfg(test)]
+pub mod testing;
+pub mod text_area;
+pub mod text_width;
+
+/// The shortest time between renders; events arriving in the meantime are batched into one frame.
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How often [Render::tick] is called on animating components.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(100);
 
 pub trait Render {
     // CR-soon: it's actually pretty tricky for implementers to get invalidation logic correct by
@@ -17,14 +34,25 @@ pub trait Render {
 
     /// NB: [render] takes [&mut self] since there isn't a separate notification to component that
     /// their bbox changed.
-    fn render(&mut self, stdout: &mut Stdout, bounding_box: BoundingBox) -> Result<()>;
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()>;
 
     fn get_cursor(&self) -> (u16, u16);
+
+    /// Whether the component wants [tick] called every [TICK_INTERVAL], e.g. for a spinner.
+    fn is_animating(&self) -> bool {
+        false
+    }
+
+    /// Advances animations by a frame, invalidating whatever changed.
+    fn tick(&mut self) {}
 }
 
 pub trait Input {
     fn handle_focus(&mut self);
 
+    /// Called when focus moves elsewhere, see [focus::FocusContainer].
+    fn handle_blur(&mut self) {}
+
     /// Returns true if the event was handled; that is, the caller won't consider the event and run
     /// it's own handler.
     fn handle_key_event(&mut self, event: &KeyEvent) -> bool;
@@ -43,7 +71,7 @@ impl<T: Render + Input> Component<T> {
         }
     }
 
-    pub fn render_if_necessary(&mut self, stdout: &mut Stdout) -> Result<()> {
+    pub fn render_if_necessary(&mut self, stdout: &mut ScreenBuffer) -> Result<()> {
         if self.component.should_render() {
             self.component.render(stdout, self.bounding_box)?;
         }
@@ -0,0 +1,210 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+
+/// A modal component drawn on top of everything else, e.g. a dialog, picker or prompt.
+pub trait Overlay: Render + Input {
+    /// Where to draw, given the area the overlay layer covers; defaults to all of it.
+    fn placement(&self, area: BoundingBox) -> BoundingBox {
+        area
+    }
+
+    /// Checked after every key event; once true, the overlay is popped off the layer.
+    fn is_dismissed(&self) -> bool;
+}
+
+/// Z-ordered stack of overlays.  While any overlay is open, the topmost one captures all key
+/// events.
+///
+/// Whatever is underneath the layer needs redrawing when an overlay goes away; rather than have
+/// each overlay clean up after itself, the owner of the layer checks [take_dismissed] and
+/// invalidates the background.
+#[derive(Default)]
+pub struct OverlayLayer {
+    overlays: Vec<Box<dyn Overlay>>,
+    area: BoundingBox,
+    dismissed: bool,
+}
+
+impl OverlayLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.overlays.is_empty()
+    }
+
+    pub fn push(&mut self, mut overlay: Box<dyn Overlay>) {
+        overlay.handle_focus();
+        self.overlays.push(overlay);
+    }
+
+    /// Dismisses the topmost overlay, if any.
+    pub fn pop(&mut self) {
+        if self.overlays.pop().is_some() {
+            self.dismissed = true;
+            // NB: the remaining overlays may have been partly drawn over
+            self.invalidate();
+            if let Some(overlay) = self.overlays.last_mut() {
+                overlay.handle_focus();
+            }
+        }
+    }
+
+    /// True if an overlay was dismissed since the last call, meaning the background should be
+    /// invalidated.
+    pub fn take_dismissed(&mut self) -> bool {
+        std::mem::take(&mut self.dismissed)
+    }
+}
+
+impl Render for OverlayLayer {
+    fn should_render(&self) -> bool {
+        self.overlays.iter().any(|overlay| overlay.should_render())
+    }
+
+    fn invalidate(&mut self) {
+        for overlay in self.overlays.iter_mut() {
+            overlay.invalidate();
+        }
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        self.area = bounding_box;
+
+        // NB: once an overlay redraws, every overlay above it has to redraw too
+        let mut must_render = false;
+        for overlay in self.overlays.iter_mut() {
+            if must_render || overlay.should_render() {
+                overlay.invalidate();
+                overlay.render(stdout, overlay.placement(bounding_box))?;
+                must_render = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        match self.overlays.last() {
+            Some(overlay) => {
+                let placement = overlay.placement(self.area);
+                let (x, y) = overlay.get_cursor();
+                (
+                    placement.left - self.area.left + x,
+                    placement.top - self.area.top + y,
+                )
+            }
+            None => (0, 0),
+        }
+    }
+}
+
+impl Input for OverlayLayer {
+    fn handle_focus(&mut self) {
+        if let Some(overlay) = self.overlays.last_mut() {
+            overlay.handle_focus();
+        }
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        let Some(overlay) = self.overlays.last_mut() else {
+            return false;
+        };
+        overlay.handle_key_event(event);
+        if overlay.is_dismissed() {
+            self.pop();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[derive(Default)]
+    struct Dialog {
+        keys: usize,
+        dismissed: bool,
+        should_render: bool,
+    }
+
+    impl Render for Dialog {
+        fn should_render(&self) -> bool {
+            self.should_render
+        }
+
+        fn invalidate(&mut self) {
+            self.should_render = true;
+        }
+
+        fn render(&mut self, _stdout: &mut ScreenBuffer, _bounding_box: BoundingBox) -> Result<()> {
+            self.should_render = false;
+            Ok(())
+        }
+
+        fn get_cursor(&self) -> (u16, u16) {
+            (1, 1)
+        }
+    }
+
+    impl Input for Dialog {
+        fn handle_focus(&mut self) {
+            self.should_render = true;
+        }
+
+        fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+            self.keys += 1;
+            self.dismissed = event.code == KeyCode::Esc;
+            true
+        }
+    }
+
+    impl Overlay for Dialog {
+        fn placement(&self, area: BoundingBox) -> BoundingBox {
+            area.centered(10, 4)
+        }
+
+        fn is_dismissed(&self) -> bool {
+            self.dismissed
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_captures_keys_until_dismissed() {
+        let mut layer = OverlayLayer::new();
+        assert!(!layer.handle_key_event(&key(KeyCode::Char('x'))));
+
+        layer.push(Box::<Dialog>::default());
+        layer.push(Box::<Dialog>::default());
+        assert!(layer.should_render());
+        assert!(layer.handle_key_event(&key(KeyCode::Char('x'))));
+        assert!(!layer.take_dismissed());
+
+        layer.handle_key_event(&key(KeyCode::Esc));
+        assert!(layer.is_active());
+        assert!(layer.take_dismissed());
+        assert!(!layer.take_dismissed());
+
+        layer.handle_key_event(&key(KeyCode::Esc));
+        assert!(!layer.is_active());
+        assert!(layer.take_dismissed());
+    }
+
+    #[test]
+    fn test_cursor_is_relative_to_layer() {
+        let mut layer = OverlayLayer::new();
+        layer.area = BoundingBox::new(0, 1, 20, 10);
+        layer.push(Box::<Dialog>::default());
+        // NB: centered at (5, 4) absolute, (5, 3) relative to the layer, plus the dialog's (1, 1)
+        assert_eq!(layer.get_cursor(), (6, 4));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::overlay::Overlay;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::scroll_buffer::{ScrollBuffer, TextSegment};
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::style::{Color, Colors};
+use crossterm::{cursor, queue, style};
+use std::cmp::Reverse;
+use std::io::Write;
+
+const PROMPT: &str = "> ";
+
+/// Fuzzy filterable list: type to narrow it down, Up/Down to select, Enter to pick.
+///
+/// Embed it in a pane (intercepting Enter and Esc and calling [get_selected]), or push it onto an
+/// overlay layer with [with_on_pick], in which case Enter picks and Esc dismisses.
+pub struct Picker<T> {
+    title: String,
+    items: Vec<(String, T)>,
+    query: String,
+    /// Indices into [items], best match first
+    matches: Vec<usize>,
+    scroll_buffer: ScrollBuffer,
+    should_update_matches: bool,
+    should_render_query: bool,
+    display_width: usize,
+    on_pick: Option<Box<dyn FnMut(T)>>,
+    dismissed: bool,
+}
+
+impl<T: Clone> Picker<T> {
+    /// [items] are (label, value); the label is what's shown and matched against.
+    pub fn new(title: &str, items: Vec<(String, T)>) -> Self {
+        Self {
+            title: title.to_string(),
+            items,
+            query: String::new(),
+            matches: Vec::new(),
+            scroll_buffer: ScrollBuffer::new(),
+            should_update_matches: true,
+            should_render_query: true,
+            display_width: 0,
+            on_pick: None,
+            dismissed: false,
+        }
+    }
+
+    pub fn with_on_pick(mut self, on_pick: impl FnMut(T) + 'static) -> Self {
+        self.on_pick = Some(Box::new(on_pick));
+        self
+    }
+
+    pub fn set_items(&mut self, items: Vec<(String, T)>) {
+        self.items = items;
+        self.query.clear();
+        self.scroll_buffer.move_cursor_to(0, 0);
+        self.should_update_matches = true;
+    }
+
+    pub fn get_query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn get_selected(&self) -> Option<T> {
+        let index = self.matches.get(self.scroll_buffer.get_cursor_line())?;
+        Some(self.items[*index].1.clone())
+    }
+
+    /// The [index]th item, ignoring the filter.
+    pub fn get_item(&self, index: usize) -> Option<T> {
+        self.items.get(index).map(|(_, value)| value.clone())
+    }
+
+    fn set_query(&mut self, query: String) {
+        self.query = query;
+        self.scroll_buffer.move_cursor_to(0, 0);
+        self.should_update_matches = true;
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (label, _))| fuzzy_score(&self.query, label).map(|score| (score, i)))
+            .collect();
+        // NB: stable, so equally good matches stay in the order they were given
+        scored.sort_by_key(|&(score, _)| Reverse(score));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+
+        self.scroll_buffer.clear();
+        for &i in &self.matches {
+            let label = text_width::truncate(&self.items[i].0, self.display_width);
+            self.scroll_buffer.push(vec![TextSegment::plain(label)]);
+        }
+        let y = self.scroll_buffer.get_cursor_line();
+        self.scroll_buffer.move_cursor_to(0, y);
+        self.should_update_matches = false;
+    }
+}
+
+/// Scores [needle] as a case-insensitive subsequence of [haystack]; None if it doesn't match.
+/// Consecutive matches and matches at the start of a word score higher.
+pub fn fuzzy_score(needle: &str, haystack: &str) -> Option<i64> {
+    let needle: Vec<char> = needle
+        .to_lowercase()
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect();
+    let haystack: Vec<char> = haystack.to_lowercase().chars().collect();
+
+    let mut score = 0;
+    let mut needle_index = 0;
+    let mut prev_match: Option<usize> = None;
+
+    for (i, ch) in haystack.iter().enumerate() {
+        if needle_index == needle.len() {
+            break;
+        }
+        if *ch != needle[needle_index] {
+            continue;
+        }
+
+        score += 1;
+        if prev_match.is_some_and(|prev| prev + 1 == i) {
+            score += 5;
+        }
+        if i == 0 || !haystack[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        prev_match = Some(i);
+        needle_index += 1;
+    }
+
+    if needle_index == needle.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+impl<T: Clone> Render for Picker<T> {
+    fn should_render(&self) -> bool {
+        self.should_update_matches || self.should_render_query || self.scroll_buffer.should_render()
+    }
+
+    fn invalidate(&mut self) {
+        self.should_render_query = true;
+        self.scroll_buffer.invalidate();
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        let BoundingBox {
+            left,
+            top,
+            width,
+            height,
+        } = bounding_box;
+
+        let str_clear = " ".repeat(width as usize);
+        if self.should_update_matches || self.display_width != width as usize {
+            self.display_width = width as usize;
+            self.update_matches();
+            // NB: rows past the end of the matches would otherwise keep stale content
+            for y_offset in 2..height {
+                queue!(stdout, cursor::MoveTo(left, top + y_offset))?;
+                queue!(stdout, style::Print(&str_clear))?;
+            }
+        }
+
+        if self.should_render_query {
+            queue!(stdout, cursor::MoveTo(left, top))?;
+            queue!(stdout, style::Print(&str_clear))?;
+            queue!(stdout, cursor::MoveTo(left, top))?;
+            queue!(
+                stdout,
+                style::SetColors(Colors::new(Color::DarkGrey, Color::Reset))
+            )?;
+            queue!(stdout, style::Print(&self.title))?;
+            queue!(stdout, style::ResetColor)?;
+
+            let query = text_width::truncate_start(
+                &self.query,
+                (width as usize).saturating_sub(PROMPT.len() + 1),
+            );
+            queue!(stdout, cursor::MoveTo(left, top + 1))?;
+            queue!(stdout, style::Print(&str_clear))?;
+            queue!(stdout, cursor::MoveTo(left, top + 1))?;
+            queue!(stdout, style::Print(format!("{PROMPT}{query}")))?;
+            self.should_render_query = false;
+        }
+
+        self.scroll_buffer.render(
+            stdout,
+            BoundingBox {
+                top: top + 2,
+                height: height.saturating_sub(2),
+                ..bounding_box
+            },
+        )?;
+
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        let query_length = text_width::width(&self.query);
+        let max_x = self.display_width.saturating_sub(1);
+        ((PROMPT.len() + query_length).min(max_x) as u16, 1)
+    }
+}
+
+impl<T: Clone> Input for Picker<T> {
+    fn handle_focus(&mut self) {
+        self.invalidate();
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        match event.code {
+            KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+            | KeyCode::Home
+            | KeyCode::End => return self.scroll_buffer.handle_key_event(event),
+            KeyCode::Enter => {
+                if let Some(value) = self.get_selected() {
+                    if let Some(on_pick) = self.on_pick.as_mut() {
+                        on_pick(value);
+                    }
+                    self.dismissed = true;
+                }
+            }
+            KeyCode::Esc => self.dismissed = true,
+            KeyCode::Char(ch) => {
+                let mut query = std::mem::take(&mut self.query);
+                query.push(ch);
+                self.set_query(query);
+            }
+            KeyCode::Backspace => {
+                let mut query = std::mem::take(&mut self.query);
+                query.pop();
+                self.set_query(query);
+            }
+            _ => return false,
+        }
+        self.should_render_query = true;
+        true
+    }
+}
+
+impl<T: Clone> Overlay for Picker<T> {
+    fn is_dismissed(&self) -> bool {
+        self.dismissed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn type_keys(picker: &mut Picker<u32>, keys: &[KeyCode]) {
+        for key in keys {
+            picker.handle_key_event(&KeyEvent::new(*key, KeyModifiers::NONE));
+        }
+        picker.update_matches();
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("btc", "@trader Long BTC here").is_some());
+        assert!(fuzzy_score("xyz", "@trader Long BTC here").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+
+        let contiguous = fuzzy_score("rust", "@someone rust lang").unwrap();
+        let scattered = fuzzy_score("rust", "@someone ride unicycles to town").unwrap();
+        assert!(contiguous > scattered);
+    }
+
+    #[test]
+    fn test_filter_and_pick() {
+        let picked = Rc::new(RefCell::new(None));
+        let picked_clone = picked.clone();
+        let items = ["apple", "banana", "cherry", "grape"]
+            .iter()
+            .enumerate()
+            .map(|(i, label)| (label.to_string(), i as u32))
+            .collect();
+        let mut picker = Picker::new("Fruit", items)
+            .with_on_pick(move |value| *picked_clone.borrow_mut() = Some(value));
+
+        picker.update_matches();
+        assert_eq!(picker.get_selected(), Some(0));
+
+        type_keys(&mut picker, &[KeyCode::Char('a'), KeyCode::Char('p')]);
+        assert_eq!(picker.matches, vec![0, 3]);
+
+        type_keys(&mut picker, &[KeyCode::Down, KeyCode::Enter]);
+        assert_eq!(*picked.borrow(), Some(3));
+        assert!(picker.is_dismissed());
+    }
+}
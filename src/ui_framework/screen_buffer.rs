@@ -0,0 +1,434 @@
+//! Double buffered screen, so a frame only sends the terminal the cells that actually changed.
+//!
+//! Components keep drawing with crossterm commands (`queue!(stdout, cursor::MoveTo(..))` etc.), but
+//! into a [ScreenBuffer] instead of stdout: it interprets the ANSI sequences those commands write
+//! (cursor moves, SGR styling, clear to end of line, and text) into a back buffer of styled cells.
+//! [ScreenBuffer::present] then diffs the back buffer against what's on the terminal and writes
+//! just the difference, which avoids the flicker of clearing and reprinting whole regions.
+//!
+//! Each cell holds one grapheme; a wide grapheme (CJK, most emoji) takes its own cell plus an empty
+//! continuation cell after it, matching how the terminal advances the cursor.
+
+use crate::ui_framework::text_width;
+use crossterm::{cursor, queue, terminal};
+use std::io::{self, Write};
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Style {
+    /// SGR parameters of the foreground color, e.g. "38;5;8"; None is the terminal default
+    foreground: Option<String>,
+    background: Option<String>,
+    /// Bit n set means SGR attribute n (bold = 1, ..., crossed out = 9) is on
+    attributes: u16,
+}
+
+impl Style {
+    fn apply_sgr(&mut self, params: &str) {
+        // NB: crossterm writes some underline styles as e.g. "4:3"; treat them as plain underline
+        let mut tokens = params
+            .split(';')
+            .map(|token| token.split(':').next().unwrap_or("").parse::<u16>());
+
+        while let Some(token) = tokens.next() {
+            let Ok(code) = token else { continue };
+            match code {
+                0 => *self = Style::default(),
+                1..=9 => self.attributes |= 1 << code,
+                22 => self.attributes &= !(1 << 1 | 1 << 2),
+                23 | 24 | 27 | 28 | 29 => self.attributes &= !(1 << (code - 20)),
+                25 => self.attributes &= !(1 << 5 | 1 << 6),
+                30..=37 | 90..=97 => self.foreground = Some(code.to_string()),
+                40..=47 | 100..=107 => self.background = Some(code.to_string()),
+                39 => self.foreground = None,
+                49 => self.background = None,
+                38 | 48 => {
+                    let mut color = vec![code];
+                    let num_args = match tokens.next() {
+                        Some(Ok(5)) => {
+                            color.push(5);
+                            1
+                        }
+                        Some(Ok(2)) => {
+                            color.push(2);
+                            3
+                        }
+                        _ => continue,
+                    };
+                    for _ in 0..num_args {
+                        color.push(tokens.next().and_then(|t| t.ok()).unwrap_or(0));
+                    }
+                    let color = Some(
+                        color
+                            .iter()
+                            .map(u16::to_string)
+                            .collect::<Vec<_>>()
+                            .join(";"),
+                    );
+                    if code == 38 {
+                        self.foreground = color;
+                    } else {
+                        self.background = color;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn to_sgr(&self) -> String {
+        let mut params = vec!["0".to_string()];
+        for code in 1..=9 {
+            if self.attributes & (1 << code) != 0 {
+                params.push(code.to_string());
+            }
+        }
+        params.extend(self.foreground.iter().cloned());
+        params.extend(self.background.iter().cloned());
+        format!("\x1b[{}m", params.join(";"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cell {
+    /// A grapheme, or empty if this is the right half of a wide grapheme
+    symbol: String,
+    style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            symbol: " ".to_string(),
+            style: Style::default(),
+        }
+    }
+}
+
+pub struct ScreenBuffer {
+    width: u16,
+    height: u16,
+    back: Vec<Cell>,
+    /// What the terminal currently shows
+    front: Vec<Cell>,
+    cursor: (u16, u16),
+    style: Style,
+    /// Bytes of an incomplete escape sequence or UTF-8 char, waiting for the next write
+    pending: Vec<u8>,
+    should_redraw_all: bool,
+}
+
+impl ScreenBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        let num_cells = width as usize * height as usize;
+        Self {
+            width,
+            height,
+            back: vec![Cell::default(); num_cells],
+            front: vec![Cell::default(); num_cells],
+            cursor: (0, 0),
+            style: Style::default(),
+            pending: Vec::new(),
+            should_redraw_all: true,
+        }
+    }
+
+    pub fn resize(&mut self, width: u16, height: u16) {
+        *self = Self::new(width, height);
+    }
+
+    /// (width, height)
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// The back buffer as text, one line per row with trailing blanks trimmed.
+    #[cfg(test)]
+    pub fn to_text(&self) -> String {
+        (0..self.height as usize)
+            .map(|y| {
+                let from = y * self.width as usize;
+                let row: String = self.back[from..from + self.width as usize]
+                    .iter()
+                    .map(|cell| cell.symbol.as_str())
+                    .collect();
+                row.trim_end().to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// SGR parameters of the style of the cell at ([x], [y]), e.g. "0;1;38;5;8" for bold dark grey.
+    #[cfg(test)]
+    pub fn style_at(&self, x: u16, y: u16) -> String {
+        let sgr = self.back[self.index(x, y)].style.to_sgr();
+        sgr.trim_start_matches("\x1b[")
+            .trim_end_matches('m')
+            .to_string()
+    }
+
+    /// Makes the next [present] rewrite every cell, e.g. after the terminal was cleared behind our
+    /// back.
+    pub fn invalidate(&mut self) {
+        self.should_redraw_all = true;
+    }
+
+    /// Writes the cells that changed since the last call to [out], then moves the terminal cursor
+    /// to wherever it was last moved to in the buffer.
+    pub fn present(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self.should_redraw_all {
+            queue!(out, terminal::Clear(terminal::ClearType::All))?;
+        }
+
+        let mut last_position: Option<(u16, u16)> = None;
+        let mut last_style: Option<&Style> = None;
+        for (i, cell) in self.back.iter().enumerate() {
+            if !self.should_redraw_all && *cell == self.front[i] {
+                continue;
+            }
+            // NB: drawn along with the wide grapheme to its left
+            if cell.symbol.is_empty() {
+                continue;
+            }
+
+            let position = (
+                (i % self.width as usize) as u16,
+                (i / self.width as usize) as u16,
+            );
+            if last_position != Some(position) {
+                queue!(out, cursor::MoveTo(position.0, position.1))?;
+            }
+            if last_style != Some(&cell.style) {
+                write!(out, "{}", cell.style.to_sgr())?;
+                last_style = Some(&cell.style);
+            }
+            write!(out, "{}", cell.symbol)?;
+            let cell_width = text_width::grapheme_width(&cell.symbol).max(1) as u16;
+            last_position = Some((position.0 + cell_width, position.1));
+        }
+
+        if last_style.is_some() {
+            write!(out, "{}", Style::default().to_sgr())?;
+        }
+        queue!(out, cursor::MoveTo(self.cursor.0, self.cursor.1))?;
+        out.flush()?;
+
+        self.front.clone_from(&self.back);
+        self.should_redraw_all = false;
+        Ok(())
+    }
+
+    fn index(&self, x: u16, y: u16) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    fn put_grapheme(&mut self, grapheme: &str) {
+        let (x, y) = self.cursor;
+        match grapheme {
+            "\r" => self.cursor.0 = 0,
+            "\n" => self.cursor.1 = y.saturating_add(1),
+            "\r\n" => self.cursor = (0, y.saturating_add(1)),
+            _ => {
+                let grapheme_width = text_width::grapheme_width(grapheme) as u16;
+                if grapheme_width == 0 {
+                    // NB: e.g. a combining mark written separately from its base char
+                    if x > 0 && x <= self.width && y < self.height {
+                        let i = self.index(x - 1, y);
+                        self.back[i].symbol.push_str(grapheme);
+                    }
+                    return;
+                }
+
+                // NB: anything past the right edge is clipped rather than wrapped
+                if x + grapheme_width <= self.width && y < self.height {
+                    let i = self.index(x, y);
+                    // NB: don't leave half of a wide grapheme behind
+                    if self.back[i].symbol.is_empty() && x > 0 {
+                        self.back[i - 1].symbol = " ".to_string();
+                    }
+                    let after = i + grapheme_width as usize;
+                    if x + grapheme_width < self.width && self.back[after].symbol.is_empty() {
+                        self.back[after].symbol = " ".to_string();
+                    }
+
+                    self.back[i] = Cell {
+                        symbol: grapheme.to_string(),
+                        style: self.style.clone(),
+                    };
+                    for j in 1..grapheme_width as usize {
+                        self.back[i + j] = Cell {
+                            symbol: String::new(),
+                            style: self.style.clone(),
+                        };
+                    }
+                }
+                self.cursor.0 = x.saturating_add(grapheme_width);
+            }
+        }
+    }
+
+    fn apply_csi(&mut self, params: &str, command: u8) {
+        // NB: private sequences like "?25l" (hide cursor) don't affect the cells
+        if params.starts_with('?') {
+            return;
+        }
+        match command {
+            b'H' => {
+                let mut coordinates = params.split(';').map(|n| n.parse::<u16>().unwrap_or(1));
+                let row = coordinates.next().unwrap_or(1);
+                let column = coordinates.next().unwrap_or(1);
+                self.cursor = (column.saturating_sub(1), row.saturating_sub(1));
+            }
+            b'm' => self.style.apply_sgr(params),
+            b'K' => {
+                let cursor = self.cursor;
+                while self.cursor.0 < self.width {
+                    self.put_grapheme(" ");
+                }
+                self.cursor = cursor;
+            }
+            _ => (),
+        }
+    }
+
+    /// Interprets as much of [pending] as is complete.
+    fn process_pending(&mut self) {
+        let pending = std::mem::take(&mut self.pending);
+        let mut i = 0;
+
+        while i < pending.len() {
+            if pending[i] == 0x1b {
+                if i + 1 >= pending.len() {
+                    break;
+                }
+                if pending[i + 1] != b'[' {
+                    // NB: not a CSI sequence; skip the escape and its one following byte
+                    i += 2;
+                    continue;
+                }
+                let Some(length) = pending[i + 2..]
+                    .iter()
+                    .position(|byte| (0x40..=0x7e).contains(byte))
+                else {
+                    break;
+                };
+                let params = String::from_utf8_lossy(&pending[i + 2..i + 2 + length]).to_string();
+                self.apply_csi(&params, pending[i + 2 + length]);
+                i += 2 + length + 1;
+            } else {
+                let end = pending[i..]
+                    .iter()
+                    .position(|&byte| byte == 0x1b)
+                    .map_or(pending.len(), |j| i + j);
+                let (text, valid_length) = match std::str::from_utf8(&pending[i..end]) {
+                    Ok(text) => (text, end - i),
+                    Err(err) => {
+                        let valid_length = err.valid_up_to();
+                        // NB: an invalid (rather than incomplete) byte is dropped
+                        let skip = err.error_len().map_or(0, |n| n);
+                        let text = std::str::from_utf8(&pending[i..i + valid_length]).unwrap();
+                        if skip == 0 && valid_length == 0 {
+                            break;
+                        }
+                        (text, valid_length + skip)
+                    }
+                };
+                for grapheme in text.graphemes(true) {
+                    self.put_grapheme(grapheme);
+                }
+                i += valid_length;
+            }
+        }
+
+        self.pending = pending[i..].to_vec();
+    }
+}
+
+impl Write for ScreenBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.process_pending();
+        Ok(buf.len())
+    }
+
+    /// NB: a no-op; nothing reaches the terminal until [present].
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::{self, Color};
+
+    fn row(screen: &ScreenBuffer, y: u16) -> String {
+        let from = y as usize * screen.width as usize;
+        screen.back[from..from + screen.width as usize]
+            .iter()
+            .map(|cell| cell.symbol.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_interprets_crossterm_commands() {
+        let mut screen = ScreenBuffer::new(10, 2);
+        queue!(screen, cursor::MoveTo(2, 1), style::Print("héllo, world")).unwrap();
+        queue!(screen, cursor::MoveTo(0, 0)).unwrap();
+        queue!(screen, style::SetForegroundColor(Color::DarkGrey)).unwrap();
+        queue!(screen, style::Print("ab"), style::ResetColor).unwrap();
+        queue!(screen, style::Print("c")).unwrap();
+
+        assert_eq!(row(&screen, 0), "abc       ");
+        assert_eq!(row(&screen, 1), "  héllo, w");
+        assert_eq!(screen.back[0].style.foreground.as_deref(), Some("38;5;8"));
+        assert_eq!(screen.back[2].style, Style::default());
+
+        queue!(screen, cursor::MoveTo(1, 1)).unwrap();
+        queue!(screen, terminal::Clear(terminal::ClearType::UntilNewLine)).unwrap();
+        assert_eq!(row(&screen, 1), "          ");
+    }
+
+    #[test]
+    fn test_split_writes() {
+        let mut screen = ScreenBuffer::new(4, 1);
+        let bytes = "\x1b[1;2Hé".as_bytes();
+        for byte in bytes {
+            screen.write_all(&[*byte]).unwrap();
+        }
+        assert_eq!(row(&screen, 0), " é  ");
+    }
+
+    #[test]
+    fn test_wide_graphemes() {
+        let mut screen = ScreenBuffer::new(8, 1);
+        queue!(screen, style::Print("a日👨\u{200D}👩b")).unwrap();
+        assert_eq!(row(&screen, 0), "a日👨\u{200D}👩b  ");
+        assert_eq!(screen.cursor, (6, 0));
+
+        // NB: overwriting half of a wide grapheme blanks the other half
+        queue!(screen, cursor::MoveTo(2, 0), style::Print("x")).unwrap();
+        assert_eq!(row(&screen, 0), "a x👨\u{200D}👩b  ");
+
+        let mut out = Vec::new();
+        screen.present(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("a x👨\u{200D}👩b"));
+    }
+
+    #[test]
+    fn test_present_only_writes_changes() {
+        let mut screen = ScreenBuffer::new(10, 3);
+        queue!(screen, cursor::MoveTo(0, 0), style::Print("same")).unwrap();
+        screen.present(&mut Vec::new()).unwrap();
+
+        queue!(screen, cursor::MoveTo(0, 0), style::Print("same")).unwrap();
+        queue!(screen, cursor::MoveTo(3, 2), style::Print("new")).unwrap();
+        let mut out = Vec::new();
+        screen.present(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("new"));
+        assert!(!out.contains("same"));
+    }
+}
@@ -1,4 +1,6 @@
 use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_width;
 use crate::ui_framework::{Input, Render};
 use anyhow::Result;
 use crossterm::cursor;
@@ -6,12 +8,23 @@ use crossterm::event::{KeyCode, KeyEvent};
 use crossterm::queue;
 use crossterm::style::{self, Attributes, Color, Colors};
 use std::cmp::{max, min};
-use std::io::{Stdout, Write};
+use std::io::Write;
+use std::ops::Range;
 
+/// Lines of styled text, scrolled to keep a cursor in view.
+///
+/// With [ScrollBuffer::with_soft_wrap], a line wider than the view is word wrapped over as many
+/// display rows as it needs; otherwise it's cut off.  Either way the cursor and
+/// [ScrollBuffer::move_cursor_to] work in lines and columns of the line, not display rows.
+///
+/// Header lines, see [ScrollBuffer::set_header], stay put at the top while the lines scroll below.
 #[derive(Debug, Clone)]
 pub struct ScrollBuffer {
+    header: Vec<Vec<TextSegment>>,
     lines: Vec<Vec<TextSegment>>,
+    soft_wrap: bool,
     display_height: usize,
+    /// The first line shown, which is always shown from its first row
     display_offset: usize,
     cursor_position: (usize, usize),
     should_render: bool,
@@ -22,7 +35,9 @@ pub struct ScrollBuffer {
 impl ScrollBuffer {
     pub fn new() -> Self {
         Self {
+            header: Vec::new(),
             lines: Vec::new(),
+            soft_wrap: false,
             display_height: 0,
             display_offset: 0,
             cursor_position: (0, 0),
@@ -31,12 +46,34 @@ impl ScrollBuffer {
         }
     }
 
+    pub fn with_soft_wrap(self) -> Self {
+        Self {
+            soft_wrap: true,
+            ..self
+        }
+    }
+
+    /// Pins [header] above the lines, e.g. a title or column headings; [ScrollBuffer::clear]
+    /// leaves it be.
+    pub fn set_header(&mut self, header: Vec<Vec<TextSegment>>) {
+        self.header = header;
+        self.should_render = true;
+    }
+
     pub fn push(&mut self, line: Vec<TextSegment>) {
         self.lines.push(line);
         // CR: not optimal
         self.should_render = true;
     }
 
+    /// Replaces line [line_no], if there is one.
+    pub fn set_line(&mut self, line_no: usize, line: Vec<TextSegment>) {
+        if let Some(existing) = self.lines.get_mut(line_no) {
+            *existing = line;
+            self.should_render = true;
+        }
+    }
+
     pub fn push_newline(&mut self) {
         self.push(vec![]);
     }
@@ -68,17 +105,151 @@ impl ScrollBuffer {
         if new_offset < self.display_offset {
             self.display_offset = new_offset;
             self.should_render = true;
-        } else if new_offset >= self.display_offset + self.display_height {
-            self.display_offset = new_offset - self.display_height + 1;
-            self.should_render = true;
+        } else {
+            let first_line = self.first_line_of_page_ending_at(new_offset);
+            if first_line > self.display_offset {
+                self.display_offset = first_line;
+                self.should_render = true;
+            }
         }
 
         self.cursor_position = (x_offset, new_offset);
     }
 
+    /// The earliest line from which the view still reaches down to [line_no] (inclusive).
+    fn first_line_of_page_ending_at(&self, line_no: usize) -> usize {
+        let mut first_line = line_no;
+        let mut num_rows = self.num_rows(line_no);
+        while first_line > 0 {
+            let prev_rows = self.num_rows(first_line - 1);
+            if num_rows + prev_rows > self.display_height {
+                break;
+            }
+            first_line -= 1;
+            num_rows += prev_rows;
+        }
+        first_line
+    }
+
+    /// The width lines are wrapped to, if they are.  NB: unknown until the first render
+    fn wrap_width(&self) -> Option<usize> {
+        let width = self.last_bounding_box.width as usize;
+        (self.soft_wrap && width > 0).then_some(width)
+    }
+
+    /// Line [line_no] as display rows, each with the column in the line it starts at.
+    fn rows(&self, line_no: usize) -> Vec<(usize, Vec<TextSegment>)> {
+        let line = self
+            .lines
+            .get(line_no)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        match self.wrap_width() {
+            Some(width) => wrap_line(line, width),
+            None => vec![(0, line.to_vec())],
+        }
+    }
+
+    fn num_rows(&self, line_no: usize) -> usize {
+        match self.wrap_width() {
+            Some(_) => self.rows(line_no).len(),
+            None => 1,
+        }
+    }
+
+    /// Scrolls the view by [delta] pages, moving the cursor by the same amount so that it stays at
+    /// the same place on screen where possible.
+    pub fn move_page(&mut self, delta: isize) {
+        let page = max(self.display_height, 1) as isize;
+        let max_offset =
+            self.first_line_of_page_ending_at(self.lines.len().saturating_sub(1)) as isize;
+        let new_offset = (self.display_offset as isize + delta * page).clamp(0, max_offset);
+        if new_offset as usize != self.display_offset {
+            self.display_offset = new_offset as usize;
+            self.should_render = true;
+        }
+
+        let line_no = max(0, self.cursor_position.1 as isize + delta * page) as usize;
+        self.move_cursor_to(self.cursor_position.0, line_no);
+    }
+
+    pub fn move_cursor_to_start(&mut self) {
+        self.move_cursor_to(self.cursor_position.0, 0);
+    }
+
+    pub fn move_cursor_to_end(&mut self) {
+        self.move_cursor_to(self.cursor_position.0, self.lines.len().saturating_sub(1));
+    }
+
     pub fn get_cursor_line(&self) -> usize {
         self.cursor_position.1
     }
+
+    pub fn get_display_offset(&self) -> usize {
+        self.display_offset
+    }
+
+    /// The payloads on [lines], in order, each with where (column, line) it starts.  Consecutive
+    /// segments with the same payload, e.g. a word wrapped across lines, count once.
+    pub fn payloads(&self, lines: Range<usize>) -> Vec<((usize, usize), Payload)> {
+        let mut payloads = Vec::new();
+        let mut last_payload = None;
+        for line_no in lines {
+            let mut x_offset = 0;
+            for segment in self.lines.get(line_no).into_iter().flatten() {
+                if let Some(payload) = &segment.payload {
+                    if last_payload != Some(payload) {
+                        payloads.push(((x_offset, line_no), payload.clone()));
+                    }
+                }
+                last_payload = segment.payload.as_ref();
+                x_offset += segment.width();
+            }
+        }
+        payloads
+    }
+
+    /// The payload of the segment at [x_offset] on line [line_no], if it has one.
+    pub fn payload_at(&self, x_offset: usize, line_no: usize) -> Option<&Payload> {
+        let mut segment_start = 0;
+        for segment in self.lines.get(line_no)? {
+            let segment_end = segment_start + segment.width();
+            if (segment_start..segment_end).contains(&x_offset) {
+                return segment.payload.as_ref();
+            }
+            segment_start = segment_end;
+        }
+        None
+    }
+
+    pub fn payload_at_cursor(&self) -> Option<&Payload> {
+        self.payload_at(self.cursor_position.0, self.cursor_position.1)
+    }
+
+    /// Which part of the buffer is in view, as of the last render.
+    pub fn viewport(&self) -> Viewport {
+        let mut num_rows = 0;
+        let num_visible = (self.display_offset..self.lines.len())
+            .take_while(|&line_no| {
+                num_rows += self.num_rows(line_no);
+                num_rows <= self.display_height
+            })
+            .count();
+        Viewport {
+            offset: self.display_offset,
+            num_visible,
+            num_lines: self.lines.len(),
+            cursor_line: self.cursor_position.1,
+        }
+    }
+
+    /// Scrolls so the view starts at [display_offset], then moves the cursor to [line_no], e.g. to
+    /// restore a saved position.
+    pub fn scroll_to(&mut self, display_offset: usize, line_no: usize) {
+        self.display_offset = min(display_offset, self.lines.len().saturating_sub(1));
+        self.should_render = true;
+        self.move_cursor_to(self.cursor_position.0, line_no);
+    }
 }
 
 impl Render for ScrollBuffer {
@@ -90,7 +261,7 @@ impl Render for ScrollBuffer {
         self.should_render = true;
     }
 
-    fn render(&mut self, stdout: &mut Stdout, bounding_box: BoundingBox) -> Result<()> {
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
         if bounding_box != self.last_bounding_box {
             self.last_bounding_box = bounding_box;
             self.should_render = true;
@@ -104,17 +275,21 @@ impl Render for ScrollBuffer {
                 height,
             } = bounding_box;
 
-            if self.display_height != height as usize {
-                self.display_height = height as usize;
+            let header_height = min(self.header.len(), height as usize);
+            let lines_height = height as usize - header_height;
+            if self.display_height != lines_height {
+                self.display_height = lines_height;
                 self.move_cursor(0); // NB: recalculate scroll
             }
 
             let str_clear = " ".repeat(width as usize);
-            let from_line = min(self.display_offset, self.lines.len());
-            let to_line = min(self.display_offset + self.display_height, self.lines.len());
+            let header_rows = self.header.iter().map(|line| (0, line.clone()));
+            let rows = (self.display_offset..self.lines.len())
+                .flat_map(|line_no| self.rows(line_no))
+                .take(self.display_height);
 
-            for line_no in from_line..to_line {
-                let delta = (line_no - from_line) as u16;
+            for (delta, (_, row)) in header_rows.take(header_height).chain(rows).enumerate() {
+                let delta = delta as u16;
 
                 queue!(stdout, cursor::MoveTo(left, top + delta))?;
                 queue!(stdout, style::ResetColor)?;
@@ -126,7 +301,8 @@ impl Render for ScrollBuffer {
                     colors,
                     attributes,
                     text,
-                } in &self.lines[line_no]
+                    ..
+                } in &row
                 {
                     queue!(stdout, style::SetColors(*colors))?;
                     queue!(stdout, style::SetAttributes(*attributes))?;
@@ -142,9 +318,26 @@ impl Render for ScrollBuffer {
     }
 
     fn get_cursor(&self) -> (u16, u16) {
+        let (x_offset, line_no) = self.cursor_position;
+        let header_height = self.header.len();
+        if self.wrap_width().is_none() {
+            return (
+                x_offset as u16,
+                (header_height + line_no.saturating_sub(self.display_offset)) as u16,
+            );
+        }
+
+        let rows_above: usize = (self.display_offset..line_no)
+            .map(|line_no| self.num_rows(line_no))
+            .sum();
+        let row_starts: Vec<usize> = self.rows(line_no).into_iter().map(|(x, _)| x).collect();
+        let row = row_starts
+            .iter()
+            .rposition(|&row_start| row_start <= x_offset)
+            .unwrap_or(0);
         (
-            self.cursor_position.0 as u16,
-            self.cursor_position.1.saturating_sub(self.display_offset) as u16,
+            x_offset.saturating_sub(row_starts.get(row).copied().unwrap_or(0)) as u16,
+            (header_height + rows_above + row) as u16,
         )
     }
 }
@@ -158,17 +351,117 @@ impl Input for ScrollBuffer {
         match event.code {
             KeyCode::Up => self.move_cursor(-1),
             KeyCode::Down => self.move_cursor(1),
+            KeyCode::PageUp => self.move_page(-1),
+            KeyCode::PageDown => self.move_page(1),
+            KeyCode::Home => self.move_cursor_to_start(),
+            KeyCode::End => self.move_cursor_to_end(),
             _ => return false,
         }
         true
     }
 }
 
-#[derive(Debug, Clone)]
+/// Where a [ScrollBuffer] is scrolled to, for its owner to show a scrollbar or position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// The first line in view
+    pub offset: usize,
+    /// How many lines fit in view (whole lines only, if wrapped)
+    pub num_visible: usize,
+    pub num_lines: usize,
+    pub cursor_line: usize,
+}
+
+impl Viewport {
+    /// The rows of a scrollbar [height] rows tall that the thumb covers, or None if everything is
+    /// in view.
+    pub fn thumb(&self, height: usize) -> Option<Range<usize>> {
+        if self.offset == 0 && self.num_visible >= self.num_lines || height == 0 {
+            return None;
+        }
+        let size = (height * self.num_visible / self.num_lines).clamp(1, height);
+        // NB: pin the thumb to the bottom at the end, which rounding down would miss
+        let start = if self.offset + self.num_visible >= self.num_lines {
+            height - size
+        } else {
+            min(height * self.offset / self.num_lines, height - size)
+        };
+        Some(start..start + size)
+    }
+}
+
+/// Draws the scrollbar for [viewport] down the column at [left], from [top] for [height] rows.
+pub fn render_scrollbar(
+    stdout: &mut ScreenBuffer,
+    viewport: Viewport,
+    left: u16,
+    top: u16,
+    height: u16,
+) -> Result<()> {
+    let thumb = viewport.thumb(height as usize).unwrap_or_default();
+    queue!(stdout, style::SetForegroundColor(Color::DarkGrey))?;
+    for y_offset in 0..height {
+        let str_row = if thumb.contains(&(y_offset as usize)) {
+            "┃"
+        } else {
+            " "
+        };
+        queue!(stdout, cursor::MoveTo(left, top + y_offset))?;
+        queue!(stdout, style::Print(str_row))?;
+    }
+    queue!(stdout, style::ResetColor)?;
+    Ok(())
+}
+
+/// Word wraps [line] to [width], keeping each segment's style across row breaks, and giving the
+/// column in the line that each row starts at.
+fn wrap_line(line: &[TextSegment], width: usize) -> Vec<(usize, Vec<TextSegment>)> {
+    let text: String = line.iter().map(|segment| segment.text.as_str()).collect();
+    let mut segment_ranges = Vec::new();
+    let mut offset = 0;
+    for segment in line {
+        segment_ranges.push((offset, offset + segment.text.len(), segment));
+        offset += segment.text.len();
+    }
+
+    let mut rows = Vec::new();
+    for Range {
+        start: row_start,
+        end: row_end,
+    } in text_width::wrap_ranges(&text, width)
+    {
+        let row = segment_ranges
+            .iter()
+            .filter(|&&(start, end, _)| start < row_end && end > row_start)
+            .map(|&(start, end, segment)| TextSegment {
+                text: text[start.max(row_start)..end.min(row_end)].to_string(),
+                ..segment.clone()
+            })
+            .collect();
+        rows.push((text_width::width(&text[..row_start]), row));
+    }
+    rows
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TextSegment {
     colors: Colors,
     attributes: Attributes,
     text: String,
+    /// What the text stands for, see [ScrollBuffer::payload_at_cursor]
+    payload: Option<Payload>,
+}
+
+/// The thing a [TextSegment] shows, so that a pane can act on whatever is under the cursor
+/// without working it out again from the line number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    TweetId(String),
+    Url(String),
+    /// Username, without the leading '@'
+    User(String),
+    /// A hashtag or cashtag, with its leading '#' or '$'
+    Hashtag(String),
 }
 
 impl TextSegment {
@@ -177,6 +470,14 @@ impl TextSegment {
             colors,
             attributes,
             text: text.to_string(),
+            payload: None,
+        }
+    }
+
+    pub fn with_payload(self, payload: Payload) -> Self {
+        Self {
+            payload: Some(payload),
+            ..self
         }
     }
 
@@ -191,4 +492,254 @@ impl TextSegment {
             Attributes::default(),
         )
     }
+
+    /// Columns the text takes up on screen.
+    pub fn width(&self) -> usize {
+        text_width::width(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui_framework::testing;
+
+    fn scroll_buffer(num_lines: usize, display_height: usize) -> ScrollBuffer {
+        let mut scroll_buffer = ScrollBuffer::new();
+        for i in 0..num_lines {
+            scroll_buffer.push(vec![TextSegment::plain(&i.to_string())]);
+        }
+        scroll_buffer.display_height = display_height;
+        scroll_buffer
+    }
+
+    #[test]
+    fn test_payloads() {
+        let user = Payload::User("a".to_string());
+        let hashtag = Payload::Hashtag("#verylong".to_string());
+        let mut scroll_buffer = ScrollBuffer::new();
+        scroll_buffer.push(vec![
+            TextSegment::plain("see "),
+            TextSegment::plain("@a").with_payload(user.clone()),
+            TextSegment::plain(" and "),
+            TextSegment::plain("#very").with_payload(hashtag.clone()),
+        ]);
+        scroll_buffer.push(vec![
+            TextSegment::plain("long").with_payload(hashtag.clone()),
+            TextSegment::plain("@a").with_payload(user.clone()),
+        ]);
+        assert_eq!(
+            scroll_buffer.payloads(0..3),
+            vec![
+                ((4, 0), user.clone()),
+                ((11, 0), hashtag.clone()),
+                ((4, 1), user.clone()),
+            ]
+        );
+        assert_eq!(scroll_buffer.payloads(1..2).len(), 2);
+
+        assert_eq!(scroll_buffer.payload_at(3, 0), None);
+        assert_eq!(scroll_buffer.payload_at(5, 0), Some(&user));
+        assert_eq!(scroll_buffer.payload_at(15, 0), Some(&hashtag));
+        assert_eq!(scroll_buffer.payload_at(16, 0), None);
+        scroll_buffer.move_cursor_to(4, 1);
+        assert_eq!(scroll_buffer.payload_at_cursor(), Some(&user));
+    }
+
+    #[test]
+    fn test_soft_wrap() {
+        let mut scroll_buffer = ScrollBuffer::new().with_soft_wrap();
+        scroll_buffer.push(vec![TextSegment::plain("one")]);
+        scroll_buffer.push(vec![
+            TextSegment::plain("the quick "),
+            TextSegment::plain("brown").with_payload(Payload::User("fox".to_string())),
+            TextSegment::plain(" fox jumps"),
+        ]);
+        scroll_buffer.push(vec![TextSegment::plain("two")]);
+        scroll_buffer.display_height = 3;
+        scroll_buffer.last_bounding_box = BoundingBox::new(0, 0, 10, 3);
+
+        let rows: Vec<usize> = scroll_buffer.rows(1).iter().map(|(x, _)| *x).collect();
+        assert_eq!(rows, vec![0, 10, 20]);
+        assert_eq!(scroll_buffer.rows(1)[1].1[0].text, "brown");
+
+        // NB: the wrapped line only fits in the view without the line before it
+        scroll_buffer.move_cursor_to(12, 1);
+        assert_eq!(scroll_buffer.display_offset, 1);
+        assert_eq!(scroll_buffer.get_cursor(), (2, 1));
+        assert_eq!(
+            scroll_buffer.payload_at_cursor(),
+            Some(&Payload::User("fox".to_string()))
+        );
+
+        scroll_buffer.move_cursor_to(0, 2);
+        assert_eq!(scroll_buffer.display_offset, 2);
+        assert_eq!(scroll_buffer.get_cursor(), (0, 0));
+        scroll_buffer.move_cursor_to(0, 0);
+        assert_eq!(scroll_buffer.display_offset, 0);
+    }
+
+    #[test]
+    fn test_header() {
+        let mut scroll_buffer = scroll_buffer(20, 0);
+        scroll_buffer.set_header(vec![vec![TextSegment::plain("Title")], vec![]]);
+        testing::render_to_text(&mut scroll_buffer, 10, 6);
+        assert_eq!(scroll_buffer.display_height, 4);
+
+        scroll_buffer.move_cursor_to(0, 9);
+        assert_eq!(scroll_buffer.get_display_offset(), 6);
+        assert_eq!(scroll_buffer.get_cursor(), (0, 5));
+        assert_eq!(
+            testing::render_to_text(&mut scroll_buffer, 10, 6),
+            "Title\n\n6\n7\n8\n9"
+        );
+    }
+
+    #[test]
+    fn test_viewport() {
+        assert_eq!(scroll_buffer(5, 10).viewport().thumb(10), None);
+
+        let mut scroll_buffer = scroll_buffer(40, 10);
+        assert_eq!(scroll_buffer.viewport().thumb(10), Some(0..2));
+
+        scroll_buffer.move_cursor_to(0, 24);
+        let viewport = scroll_buffer.viewport();
+        assert_eq!(
+            viewport,
+            Viewport {
+                offset: 15,
+                num_visible: 10,
+                num_lines: 40,
+                cursor_line: 24
+            }
+        );
+        assert_eq!(viewport.thumb(10), Some(3..5));
+
+        scroll_buffer.move_cursor_to_end();
+        assert_eq!(scroll_buffer.viewport().thumb(10), Some(8..10));
+    }
+
+    #[test]
+    fn test_page_down_and_up() {
+        let mut scroll_buffer = scroll_buffer(25, 10);
+        scroll_buffer.move_cursor_to(0, 3);
+
+        scroll_buffer.move_page(1);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (10, 13)
+        );
+        assert_eq!(scroll_buffer.get_cursor(), (0, 3));
+
+        // NB: the view can't scroll past the last page, so the cursor moves within it instead
+        scroll_buffer.move_page(1);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (15, 23)
+        );
+
+        scroll_buffer.move_page(1);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (15, 24)
+        );
+
+        scroll_buffer.move_page(-1);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (5, 14)
+        );
+
+        scroll_buffer.move_page(-5);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_home_and_end() {
+        let mut scroll_buffer = scroll_buffer(25, 10);
+
+        scroll_buffer.move_cursor_to_end();
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (15, 24)
+        );
+
+        scroll_buffer.move_cursor_to_start();
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn test_buffer_shorter_than_viewport() {
+        let mut scroll_buffer = scroll_buffer(3, 10);
+
+        scroll_buffer.move_page(1);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (0, 2)
+        );
+
+        scroll_buffer.move_page(-1);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (0, 0)
+        );
+
+        scroll_buffer.move_cursor_to_end();
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (0, 2)
+        );
+    }
+
+    #[test]
+    fn test_empty_buffer() {
+        let mut scroll_buffer = scroll_buffer(0, 10);
+
+        scroll_buffer.move_page(1);
+        scroll_buffer.move_cursor_to_end();
+        scroll_buffer.move_cursor(-1);
+        assert_eq!(
+            (
+                scroll_buffer.display_offset,
+                scroll_buffer.get_cursor_line()
+            ),
+            (0, 0)
+        );
+    }
 }
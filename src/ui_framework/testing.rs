@@ -0,0 +1,20 @@
+//! Headless rendering for tests: components draw into an in-memory [ScreenBuffer] that's never
+//! presented, and tests compare its cells against snapshots.
+
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::Render;
+
+/// Renders [component] into a blank [width] x [height] screen.
+pub fn render_to_screen(component: &mut impl Render, width: u16, height: u16) -> ScreenBuffer {
+    let mut screen = ScreenBuffer::new(width, height);
+    component
+        .render(&mut screen, BoundingBox::new(0, 0, width, height))
+        .unwrap();
+    screen
+}
+
+/// Renders [component] and returns the screen as text, see [ScreenBuffer::to_text].
+pub fn render_to_text(component: &mut impl Render, width: u16, height: u16) -> String {
+    render_to_screen(component, width, height).to_text()
+}
@@ -0,0 +1,498 @@
+use crate::ui_framework::bounding_box::BoundingBox;
+use crate::ui_framework::screen_buffer::ScreenBuffer;
+use crate::ui_framework::text_width;
+use crate::ui_framework::{Input, Render};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::style::{self, Attribute, Color};
+use crossterm::{cursor, queue};
+use std::cmp::{max, min};
+use std::io::Write;
+
+/// Multi-line text input with word-wrap, emacs-ish editing and an optional character counter.
+///
+/// The buffer is kept as chars so that caret arithmetic never lands inside a code point.  The
+/// counter occupies the last row of the bounding box when [char_limit] is set.
+#[derive(Debug, Clone)]
+pub struct TextArea {
+    text: Vec<char>,
+    caret: usize,
+    selection_anchor: Option<usize>,
+    kill_buffer: String,
+    char_limit: Option<usize>,
+    count_chars: fn(&str) -> usize,
+    segment_delimiter: Option<String>,
+    display_width: usize,
+    display_height: usize,
+    display_offset: usize,
+    should_render: bool,
+    last_bounding_box: BoundingBox,
+}
+
+impl TextArea {
+    pub fn new() -> Self {
+        Self {
+            text: Vec::new(),
+            caret: 0,
+            selection_anchor: None,
+            kill_buffer: String::new(),
+            char_limit: None,
+            count_chars: |text| text.chars().count(),
+            segment_delimiter: None,
+            display_width: 0,
+            display_height: 0,
+            display_offset: 0,
+            should_render: true,
+            last_bounding_box: BoundingBox::default(),
+        }
+    }
+
+    /// Shows a live "count/limit" counter; [count_chars] decides how text is measured.
+    pub fn with_char_limit(mut self, char_limit: usize, count_chars: fn(&str) -> usize) -> Self {
+        self.char_limit = Some(char_limit);
+        self.count_chars = count_chars;
+        self
+    }
+
+    /// Counts only the segment under the caret, where segments are separated by lines consisting
+    /// solely of [delimiter] (e.g. the tweets of a thread).
+    pub fn with_segment_delimiter(mut self, delimiter: &str) -> Self {
+        self.segment_delimiter = Some(delimiter.to_string());
+        self
+    }
+
+    pub fn get_text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.chars().collect();
+        self.caret = self.text.len();
+        self.selection_anchor = None;
+        self.should_render = true;
+    }
+
+    pub fn clear(&mut self) {
+        self.set_text("");
+    }
+
+    /// Returns (count, limit, segment index, number of segments) for the counter.
+    pub fn get_counter(&self) -> Option<(usize, usize, usize, usize)> {
+        let char_limit = self.char_limit?;
+        let text = self.get_text();
+
+        let Some(delimiter) = &self.segment_delimiter else {
+            return Some(((self.count_chars)(&text), char_limit, 0, 1));
+        };
+
+        let caret_line = self.text[..self.caret]
+            .iter()
+            .filter(|&&ch| ch == '\n')
+            .count();
+        let mut segments: Vec<Vec<&str>> = vec![vec![]];
+        let mut caret_segment = 0;
+        for (line_no, line) in text.split('\n').enumerate() {
+            if line.trim() == delimiter {
+                segments.push(vec![]);
+            } else {
+                segments.last_mut().unwrap().push(line);
+            }
+            if line_no == caret_line {
+                caret_segment = segments.len() - 1;
+            }
+        }
+
+        let segment = segments[caret_segment].join("\n");
+        let count = (self.count_chars)(segment.trim());
+        Some((count, char_limit, caret_segment, segments.len()))
+    }
+
+    fn selection(&self) -> Option<(usize, usize)> {
+        self.selection_anchor
+            .filter(|&anchor| anchor != self.caret)
+            .map(|anchor| (min(anchor, self.caret), max(anchor, self.caret)))
+    }
+
+    fn delete_range(&mut self, from: usize, to: usize) -> String {
+        let removed: String = self.text.drain(from..to).collect();
+        self.caret = from;
+        self.selection_anchor = None;
+        self.should_render = true;
+        removed
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some((from, to)) => {
+                self.delete_range(from, to);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert_str(&mut self, str: &str) {
+        self.delete_selection();
+        for ch in str.chars() {
+            self.text.insert(self.caret, ch);
+            self.caret += 1;
+        }
+        self.selection_anchor = None;
+        self.should_render = true;
+    }
+
+    fn move_caret_to(&mut self, position: usize, extend_selection: bool) {
+        if extend_selection {
+            self.selection_anchor.get_or_insert(self.caret);
+        } else {
+            self.selection_anchor = None;
+        }
+        self.caret = min(position, self.text.len());
+        self.should_render = true;
+    }
+
+    fn line_start(&self, position: usize) -> usize {
+        self.text[..position]
+            .iter()
+            .rposition(|&ch| ch == '\n')
+            .map(|i| i + 1)
+            .unwrap_or(0)
+    }
+
+    fn line_end(&self, position: usize) -> usize {
+        self.text[position..]
+            .iter()
+            .position(|&ch| ch == '\n')
+            .map(|i| position + i)
+            .unwrap_or(self.text.len())
+    }
+
+    fn word_left(&self, position: usize) -> usize {
+        let mut i = position;
+        while i > 0 && !self.text[i - 1].is_alphanumeric() {
+            i -= 1;
+        }
+        while i > 0 && self.text[i - 1].is_alphanumeric() {
+            i -= 1;
+        }
+        i
+    }
+
+    fn word_right(&self, position: usize) -> usize {
+        let mut i = position;
+        while i < self.text.len() && !self.text[i].is_alphanumeric() {
+            i += 1;
+        }
+        while i < self.text.len() && self.text[i].is_alphanumeric() {
+            i += 1;
+        }
+        i
+    }
+
+    /// Kills (cuts) the selection if there is one, otherwise [from..to], into the kill buffer.
+    fn kill(&mut self, from: usize, to: usize) {
+        let (from, to) = self.selection().unwrap_or((min(from, to), max(from, to)));
+        if from < to {
+            self.kill_buffer = self.delete_range(from, to);
+        }
+    }
+
+    fn yank(&mut self) {
+        let kill_buffer = self.kill_buffer.clone();
+        self.insert_str(&kill_buffer);
+    }
+
+    /// Columns taken by the chars in [from..to].
+    fn columns(&self, from: usize, to: usize) -> usize {
+        self.text[from..to]
+            .iter()
+            .map(|&ch| text_width::char_width(ch))
+            .sum()
+    }
+
+    /// Visual rows as [start, end) char ranges, soft-wrapped at word boundaries to [width] columns.
+    fn layout(&self, width: usize) -> Vec<(usize, usize)> {
+        let width = max(width, 1);
+        let mut rows = Vec::new();
+        let mut line_start = 0;
+
+        loop {
+            let line_end = self.line_end(line_start);
+            let mut row_start = line_start;
+
+            if row_start == line_end {
+                rows.push((row_start, row_start));
+            }
+            while row_start < line_end {
+                if self.columns(row_start, line_end) <= width {
+                    rows.push((row_start, line_end));
+                    break;
+                }
+                // NB: always at least one char per row, however wide
+                let mut limit = row_start + 1;
+                let mut used_width = text_width::char_width(self.text[row_start]);
+                while limit < line_end {
+                    used_width += text_width::char_width(self.text[limit]);
+                    if used_width > width {
+                        break;
+                    }
+                    limit += 1;
+                }
+                let row_end = (row_start + 1..=limit)
+                    .rev()
+                    .find(|&i| self.text[i - 1].is_whitespace())
+                    .unwrap_or(limit);
+                rows.push((row_start, row_end));
+                row_start = row_end;
+            }
+
+            if line_end >= self.text.len() {
+                break;
+            }
+            line_start = line_end + 1;
+        }
+
+        rows
+    }
+
+    /// Returns the (column, row) of the caret in [layout].
+    fn caret_row_col(&self, layout: &[(usize, usize)]) -> (usize, usize) {
+        let row = layout
+            .iter()
+            .rposition(|&(start, _)| start <= self.caret)
+            .unwrap_or(0);
+        (self.columns(layout[row].0, self.caret), row)
+    }
+
+    fn move_caret_vertically(&mut self, delta: isize, extend_selection: bool) {
+        let layout = self.layout(self.display_width);
+        let (col, row) = self.caret_row_col(&layout);
+        let new_row = (row as isize + delta).clamp(0, layout.len() as isize - 1) as usize;
+        let (start, end) = layout[new_row];
+        let new_caret = (start..end)
+            .find(|&i| self.columns(start, i + 1) > col)
+            .unwrap_or(end);
+        self.move_caret_to(new_caret, extend_selection);
+    }
+
+    fn text_height(&self) -> usize {
+        if self.char_limit.is_some() {
+            self.display_height.saturating_sub(1)
+        } else {
+            self.display_height
+        }
+    }
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Render for TextArea {
+    fn should_render(&self) -> bool {
+        self.should_render
+    }
+
+    fn invalidate(&mut self) {
+        self.should_render = true;
+    }
+
+    fn render(&mut self, stdout: &mut ScreenBuffer, bounding_box: BoundingBox) -> Result<()> {
+        if bounding_box != self.last_bounding_box {
+            self.last_bounding_box = bounding_box;
+            self.should_render = true;
+        }
+
+        if !self.should_render {
+            return Ok(());
+        }
+
+        let BoundingBox {
+            left,
+            top,
+            width,
+            height,
+        } = bounding_box;
+        self.display_width = width as usize;
+        self.display_height = height as usize;
+
+        let layout = self.layout(self.display_width);
+        let (_, caret_row) = self.caret_row_col(&layout);
+        let text_height = self.text_height();
+        if caret_row < self.display_offset {
+            self.display_offset = caret_row;
+        } else if caret_row >= self.display_offset + text_height {
+            self.display_offset = caret_row + 1 - max(text_height, 1);
+        }
+
+        let selection = self.selection();
+        let str_clear = " ".repeat(width as usize);
+
+        for y_offset in 0..text_height {
+            queue!(stdout, cursor::MoveTo(left, top + y_offset as u16))?;
+            queue!(stdout, style::ResetColor)?;
+            queue!(stdout, style::Print(&str_clear))?;
+            queue!(stdout, cursor::MoveTo(left, top + y_offset as u16))?;
+
+            if let Some(&(start, end)) = layout.get(self.display_offset + y_offset) {
+                for i in start..end {
+                    let ch = self.text[i];
+                    let ch = if ch.is_whitespace() { ' ' } else { ch };
+                    let selected = selection.is_some_and(|(from, to)| from <= i && i < to);
+                    if selected {
+                        queue!(stdout, style::SetAttribute(Attribute::Reverse))?;
+                        queue!(stdout, style::Print(ch))?;
+                        queue!(stdout, style::SetAttribute(Attribute::NoReverse))?;
+                    } else {
+                        queue!(stdout, style::Print(ch))?;
+                    }
+                }
+            }
+        }
+
+        if let Some((count, limit, segment, num_segments)) = self.get_counter() {
+            let counter = if num_segments > 1 {
+                format!("[{}/{num_segments}] {count}/{limit}", segment + 1)
+            } else {
+                format!("{count}/{limit}")
+            };
+            let counter_left = left + (width as usize).saturating_sub(counter.len()) as u16;
+            let color = if count > limit {
+                Color::Red
+            } else {
+                Color::DarkGrey
+            };
+
+            queue!(stdout, cursor::MoveTo(left, top + text_height as u16))?;
+            queue!(stdout, style::Print(&str_clear))?;
+            queue!(
+                stdout,
+                cursor::MoveTo(counter_left, top + text_height as u16)
+            )?;
+            queue!(stdout, style::SetForegroundColor(color))?;
+            queue!(stdout, style::Print(counter))?;
+            queue!(stdout, style::ResetColor)?;
+        }
+
+        stdout.flush()?;
+        self.should_render = false;
+        Ok(())
+    }
+
+    fn get_cursor(&self) -> (u16, u16) {
+        let layout = self.layout(self.display_width);
+        let (col, row) = self.caret_row_col(&layout);
+        (col as u16, row.saturating_sub(self.display_offset) as u16)
+    }
+}
+
+impl Input for TextArea {
+    fn handle_focus(&mut self) {
+        self.should_render = true;
+    }
+
+    fn handle_key_event(&mut self, event: &KeyEvent) -> bool {
+        let shift = event.modifiers.contains(KeyModifiers::SHIFT);
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = event.modifiers.contains(KeyModifiers::ALT);
+        let caret = self.caret;
+
+        match event.code {
+            KeyCode::Char('a') if ctrl => self.move_caret_to(self.line_start(caret), shift),
+            KeyCode::Char('e') if ctrl => self.move_caret_to(self.line_end(caret), shift),
+            KeyCode::Char('b') if alt => self.move_caret_to(self.word_left(caret), shift),
+            KeyCode::Char('f') if alt => self.move_caret_to(self.word_right(caret), shift),
+            KeyCode::Char('k') if ctrl => {
+                let line_end = self.line_end(caret);
+                // NB: like emacs, killing at the end of a line joins it with the next
+                self.kill(caret, max(line_end, min(caret + 1, self.text.len())));
+            }
+            KeyCode::Char('u') if ctrl => self.kill(self.line_start(caret), caret),
+            KeyCode::Char('w') if ctrl => self.kill(self.word_left(caret), caret),
+            KeyCode::Char('d') if alt => self.kill(caret, self.word_right(caret)),
+            KeyCode::Char('y') if ctrl => self.yank(),
+            KeyCode::Char(_) if ctrl || alt => return false,
+            KeyCode::Char(ch) => self.insert_str(&ch.to_string()),
+            KeyCode::Enter => self.insert_str("\n"),
+            KeyCode::Backspace => {
+                if !self.delete_selection() && caret > 0 {
+                    self.delete_range(caret - 1, caret);
+                }
+            }
+            KeyCode::Delete => {
+                if !self.delete_selection() && caret < self.text.len() {
+                    self.delete_range(caret, caret + 1);
+                }
+            }
+            KeyCode::Left if ctrl || alt => self.move_caret_to(self.word_left(caret), shift),
+            KeyCode::Right if ctrl || alt => self.move_caret_to(self.word_right(caret), shift),
+            KeyCode::Left => self.move_caret_to(caret.saturating_sub(1), shift),
+            KeyCode::Right => self.move_caret_to(caret + 1, shift),
+            KeyCode::Up => self.move_caret_vertically(-1, shift),
+            KeyCode::Down => self.move_caret_vertically(1, shift),
+            KeyCode::Home if ctrl => self.move_caret_to(0, shift),
+            KeyCode::End if ctrl => self.move_caret_to(self.text.len(), shift),
+            KeyCode::Home => self.move_caret_to(self.line_start(caret), shift),
+            KeyCode::End => self.move_caret_to(self.line_end(caret), shift),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_layout_wraps_at_words() {
+        let mut text_area = TextArea::new();
+        text_area.set_text("the quick brown fox\n\njumps");
+        let rows: Vec<String> = text_area
+            .layout(10)
+            .iter()
+            .map(|&(start, end)| text_area.text[start..end].iter().collect())
+            .collect();
+        assert_eq!(rows, vec!["the quick ", "brown fox", "", "jumps"]);
+    }
+
+    #[test]
+    fn test_layout_wide_chars() {
+        let mut text_area = TextArea::new();
+        text_area.set_text("日本語のテキスト");
+        let layout = text_area.layout(7);
+        let rows: Vec<String> = layout
+            .iter()
+            .map(|&(start, end)| text_area.text[start..end].iter().collect())
+            .collect();
+        assert_eq!(rows, vec!["日本語", "のテキ", "スト"]);
+        // NB: the caret is at the end of the last row, two wide chars in
+        assert_eq!(text_area.caret_row_col(&layout), (4, 2));
+    }
+
+    #[test]
+    fn test_kill_and_yank() {
+        let mut text_area = TextArea::new();
+        text_area.set_text("hello world");
+        text_area.handle_key_event(&key(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(text_area.get_text(), "hello ");
+        text_area.handle_key_event(&key(KeyCode::Home, KeyModifiers::NONE));
+        text_area.handle_key_event(&key(KeyCode::Char('y'), KeyModifiers::CONTROL));
+        assert_eq!(text_area.get_text(), "worldhello ");
+    }
+
+    #[test]
+    fn test_segment_counter() {
+        let mut text_area = TextArea::new()
+            .with_char_limit(280, |text| text.chars().count())
+            .with_segment_delimiter("---");
+        text_area.set_text("first\n---\nsecond one");
+        assert_eq!(text_area.get_counter(), Some((10, 280, 1, 2)));
+    }
+}
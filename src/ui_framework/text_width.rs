@@ -0,0 +1,182 @@
+//! How many terminal columns text takes up, for layout.
+//!
+//! Neither [str::len] (bytes) nor chars().count() is right: CJK characters and most emoji take two
+//! columns, combining marks take none, and an emoji sequence (ZWJ families, flags, skin tones) is
+//! several chars drawn as one two column glyph.  So widths here are per grapheme cluster, and
+//! truncation and wrapping never split one.
+//!
+//! NB: terminals disagree about some emoji sequences (older ones draw each part of a ZWJ sequence
+//! separately); we go with what modern terminals do.
+
+use std::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns taken by a single grapheme cluster.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    // NB: variation selector 16 asks for the emoji presentation, e.g. ❤️ rather than ❤
+    if grapheme.contains('\u{FE0F}') {
+        return 2;
+    }
+    grapheme.width().min(2)
+}
+
+/// Columns taken by a single char, for callers that store text as chars.
+pub fn char_width(ch: char) -> usize {
+    ch.width().unwrap_or(0)
+}
+
+pub fn width(text: &str) -> usize {
+    text.graphemes(true).map(grapheme_width).sum()
+}
+
+/// The longest prefix of [text] that fits in [max_width] columns.
+pub fn truncate(text: &str, max_width: usize) -> &str {
+    let mut used_width = 0;
+    for (i, grapheme) in text.grapheme_indices(true) {
+        used_width += grapheme_width(grapheme);
+        if used_width > max_width {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// The longest suffix of [text] that fits in [max_width] columns.
+pub fn truncate_start(text: &str, max_width: usize) -> &str {
+    let mut used_width = 0;
+    for (i, grapheme) in text.grapheme_indices(true).rev() {
+        used_width += grapheme_width(grapheme);
+        if used_width > max_width {
+            return &text[i + grapheme.len()..];
+        }
+    }
+    text
+}
+
+/// Word wraps [text] to [max_width] columns, returning the byte range of each line.
+///
+/// Newlines always break.  Whitespace at a soft break is dropped, leading whitespace of a
+/// paragraph is kept, and words wider than a line are split between graphemes.
+pub fn wrap_ranges(text: &str, max_width: usize) -> Vec<Range<usize>> {
+    let max_width = max_width.max(1);
+    let mut lines = Vec::new();
+    let mut paragraph_start = 0;
+
+    for paragraph in text.split('\n') {
+        let mut line = paragraph_start..paragraph_start;
+        let mut line_width = 0;
+        let mut offset = paragraph_start;
+        let mut rest = paragraph;
+
+        loop {
+            let word_start = rest
+                .find(|ch: char| !ch.is_whitespace())
+                .unwrap_or(rest.len());
+            if word_start == rest.len() {
+                break;
+            }
+            let word_end = rest[word_start..]
+                .find(char::is_whitespace)
+                .map_or(rest.len(), |i| word_start + i);
+            let space_width = width(&rest[..word_start]);
+            let word = &rest[word_start..word_end];
+            let word_width = width(word);
+
+            if line_width + space_width + word_width <= max_width {
+                line.end = offset + word_end;
+                line_width += space_width + word_width;
+            } else {
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+                let word_offset = offset + word_start;
+                line = word_offset..word_offset;
+                line_width = 0;
+                for (i, grapheme) in word.grapheme_indices(true) {
+                    let grapheme_width = grapheme_width(grapheme);
+                    if line_width > 0 && line_width + grapheme_width > max_width {
+                        lines.push(line);
+                        line = word_offset + i..word_offset + i;
+                        line_width = 0;
+                    }
+                    line.end = word_offset + i + grapheme.len();
+                    line_width += grapheme_width;
+                }
+            }
+
+            offset += word_end;
+            rest = &rest[word_end..];
+        }
+
+        lines.push(line);
+        paragraph_start += paragraph.len() + 1;
+    }
+
+    lines
+}
+
+pub fn wrap(text: &str, max_width: usize) -> Vec<&str> {
+    wrap_ranges(text, max_width)
+        .into_iter()
+        .map(|range| &text[range])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAMILY: &str = "👨\u{200D}👩\u{200D}👧\u{200D}👦";
+
+    #[test]
+    fn test_width() {
+        assert_eq!(width("hello"), 5);
+        assert_eq!(width("日本語"), 6);
+        assert_eq!(width("cafe\u{301}"), 4);
+        assert_eq!(width("😀"), 2);
+        assert_eq!(width("👍🏽"), 2);
+        assert_eq!(width(FAMILY), 2);
+        assert_eq!(width("🇯🇵"), 2);
+        assert_eq!(width("❤️"), 2);
+        assert_eq!(width("hi 😀!"), 6);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 3), "hel");
+        assert_eq!(truncate("hello", 10), "hello");
+        // NB: a wide char that would straddle the limit is left out entirely
+        assert_eq!(truncate("日本語", 3), "日");
+        assert_eq!(truncate(&format!("a{FAMILY}b"), 2), "a");
+        assert_eq!(truncate(&format!("a{FAMILY}b"), 3), format!("a{FAMILY}"));
+        assert_eq!(truncate("cafe\u{301}s", 4), "cafe\u{301}");
+        assert_eq!(truncate_start("日本語", 5), "本語");
+        assert_eq!(truncate_start(&format!("{FAMILY}ab"), 3), "ab");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("Why did the chicken cross the road?\n\nBecause", 20),
+            vec!["Why did the chicken", "cross the road?", "", "Because"]
+        );
+        assert_eq!(wrap("  indented text", 10), vec!["  indented", "text"]);
+        assert_eq!(wrap("", 10), vec![""]);
+    }
+
+    #[test]
+    fn test_wrap_wide() {
+        assert_eq!(
+            wrap("日本語のテキスト", 6),
+            vec!["日本語", "のテキ", "スト"]
+        );
+        assert_eq!(wrap("ab 😀😀 cd", 5), vec!["ab", "😀😀", "cd"]);
+        let family_text = format!("{FAMILY}{FAMILY}{FAMILY}");
+        let lines = wrap(&family_text, 3);
+        assert_eq!(lines, vec![FAMILY; 3]);
+        for line in wrap("👍🏽 ❤️ 🇯🇵 spicy 🌶️ tacos", 7) {
+            assert!(width(line) <= 7);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Users seen in API responses, kept across launches under [USER_CACHE_PATH], so that resolving a
+//! handle to an id (or back) doesn't need a lookup each time.
+
+use crate::fs_util;
+use crate::twitter_client::api;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const USER_CACHE_PATH: &str = "./var/users.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "Vec<api::User>", into = "Vec<api::User>")]
+pub struct UserCache {
+    /// By id
+    users: HashMap<String, api::User>,
+    /// Lowercase username to id.  NB: a username can move to another account, so this follows
+    /// whichever user was seen with it last
+    ids_by_username: HashMap<String, String>,
+}
+
+impl From<Vec<api::User>> for UserCache {
+    fn from(users: Vec<api::User>) -> Self {
+        let mut user_cache = Self::default();
+        for user in users {
+            user_cache.insert(user);
+        }
+        user_cache
+    }
+}
+
+impl From<UserCache> for Vec<api::User> {
+    fn from(user_cache: UserCache) -> Self {
+        let mut users: Vec<api::User> = user_cache.users.into_values().collect();
+        users.sort_by(|a, b| a.id.cmp(&b.id));
+        users
+    }
+}
+
+impl UserCache {
+    /// Empty if there's no saved cache.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs_util::write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    /// Adds or updates [user], e.g. after they changed their handle or display name.
+    pub fn insert(&mut self, user: api::User) {
+        if let Some(prev) = self.users.get(&user.id) {
+            if *prev == user {
+                return;
+            }
+            let prev_username = prev.username.to_lowercase();
+            if self.ids_by_username.get(&prev_username) == Some(&user.id) {
+                self.ids_by_username.remove(&prev_username);
+            }
+        }
+        self.ids_by_username
+            .insert(user.username.to_lowercase(), user.id.clone());
+        self.users.insert(user.id.clone(), user);
+    }
+
+    pub fn by_id(&self, user_id: &str) -> Option<&api::User> {
+        self.users.get(user_id)
+    }
+
+    /// NB: handles are case-insensitive
+    pub fn by_username(&self, username: &str) -> Option<&api::User> {
+        let user_id = self.ids_by_username.get(&username.to_lowercase())?;
+        self.users.get(user_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::fixtures;
+    use std::{env, process};
+
+    #[test]
+    fn test_insert() {
+        let mut user_cache = UserCache::default();
+        user_cache.insert(fixtures::user("1", "Alice"));
+        assert_eq!(
+            user_cache.by_username("alice"),
+            Some(&fixtures::user("1", "Alice"))
+        );
+        assert_eq!(user_cache.by_id("1"), Some(&fixtures::user("1", "Alice")));
+
+        // NB: a changed handle frees the old one, which someone else can then take
+        user_cache.insert(fixtures::user("1", "alice_2"));
+        assert_eq!(user_cache.by_username("alice"), None);
+        assert_eq!(
+            user_cache.by_username("ALICE_2"),
+            Some(&fixtures::user("1", "alice_2"))
+        );
+        user_cache.insert(fixtures::user("2", "alice"));
+        assert_eq!(
+            user_cache.by_username("alice"),
+            Some(&fixtures::user("2", "alice"))
+        );
+        assert_eq!(user_cache.len(), 2);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = env::temp_dir().join(format!("twitter-tool-users-{}.json", process::id()));
+        assert!(UserCache::load(&path).unwrap().is_empty());
+
+        let mut user_cache = UserCache::default();
+        user_cache.insert(fixtures::user("1", "alice"));
+        user_cache.insert(fixtures::user("2", "bob"));
+        user_cache.save(&path).unwrap();
+        let loaded = UserCache::load(&path).unwrap();
+        assert_eq!(loaded, user_cache);
+        assert_eq!(loaded.by_username("bob"), Some(&fixtures::user("2", "bob")));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,23 +1,885 @@
-use crate::twitter_client::api;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+//! The user's config, in TOML: settings they write (theme, keybindings, startup feeds, refresh
+//! interval, filters, ...) along with what the app keeps there itself (starred accounts, seen
+//! activity, notes, mutes).  Kept at [DEFAULT_PATH] unless overridden with `--config`.
+//!
+//! Saves are atomic (a crash mid-save leaves the old config intact), and the last
+//! [NUM_BACKUPS] versions are kept alongside as config.toml.1 (newest), config.toml.2, ...
+//!
+//! Configs carry a schema [version](UserConfig::version); older ones are brought up to date on
+//! load by [MIGRATIONS].
+//!
+//! Malformed configs are rejected on load with the line and field at fault, e.g.
+//!
+//! ```text
+//! TOML parse error at line 2, column 10
+//!   |
+//! 2 | "ctrl-f" = "open_sesame"
+//!   |            ^^^^^^^^^^^^^
+//! Unknown action `open_sesame`
+//! ```
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+use crate::alerts::AlertRule;
+use crate::cross_post::CrossPostConfig;
+use crate::feed_source::FeedSource;
+use crate::fs_util;
+use crate::hooks::Hook;
+use crate::keybindings::{Action, Key};
+use crate::plugins::Plugin;
+use crate::spam::SpamThresholds;
+use crate::translate::TranslateConfig;
+use crate::twitter_client::{api, ClientType, HttpConfig};
+use anyhow::{anyhow, Context, Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PATH: &str = "./var/config.toml";
+
+/// Where the config was kept when it was JSON; read if there's no config at [DEFAULT_PATH] yet, and
+/// replaced by it on the next save
+const LEGACY_JSON_PATH: &str = "./var/.user_config";
+
+/// How many previous versions of the config are kept
+pub const NUM_BACKUPS: usize = 5;
+
+pub const CURRENT_VERSION: u32 = 1;
+
+/// The least [UserConfig::max_cached_tweets] can be: ten feed pages
+pub const MIN_CACHED_TWEETS: usize = 1_000;
+
+/// [MIGRATIONS][n - 1] brings a config from version n to n + 1; none yet.
+const MIGRATIONS: &[fn(&mut toml::Table) -> Result<()>] = &[];
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UserConfig {
-    pub starred_accounts: HashMap<String, api::User>,
+    /// Schema version; missing means 1, i.e. from before versioning (which includes the JSON
+    /// configs)
+    #[serde(default = "default_version")]
+    pub version: u32,
+    /// By user id
+    #[serde(default)]
+    pub starred_accounts: BTreeMap<String, StarredAccount>,
+    /// Whether feed lines end with retweet, like and reply counts
+    #[serde(default = "default_show_public_metrics")]
+    pub show_public_metrics: bool,
+    /// Whether verified and affiliation badges are shown next to handles
+    #[serde(default = "default_show_badges")]
+    pub show_badges: bool,
+    /// Newest activity (mention, reply or quote) the user has seen; anything newer is unread
+    #[serde(default)]
+    pub activity_seen_id: Option<String>,
+    /// When the user last looked at follower changes (`:followers`); later ones are new
+    #[serde(default)]
+    pub followers_checked_at: Option<DateTime<Utc>>,
+    /// How often a snapshot of my followers is taken, see [crate::followers]
+    #[serde(default = "default_followers_interval_secs")]
+    pub followers_interval_secs: NonZeroU64,
+    /// Run on new tweets from starred accounts, see [crate::hooks]
+    #[serde(default)]
+    pub hooks: Vec<Hook>,
+    /// Notified of in the background, see [crate::alerts]
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    /// Other networks that posts from the compose box also go to, see [crate::cross_post]
+    #[serde(default)]
+    pub cross_post: CrossPostConfig,
+    /// The service that translates tweets on request, see [crate::translate]
+    #[serde(default)]
+    pub translate: Option<TranslateConfig>,
+    /// Run on app events, see [crate::plugins]
+    #[serde(default)]
+    pub plugins: Vec<Plugin>,
+    /// Lowercase usernames whose tweets are left out of feeds
+    #[serde(default)]
+    pub muted_usernames: BTreeSet<String>,
+    /// Notes on tweets, by tweet id
+    #[serde(default)]
+    pub notes: BTreeMap<String, String>,
+    /// The newest tweet of each account's that the user has caught up on (`:tab catchup`), by
+    /// user id
+    #[serde(default)]
+    pub read_markers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub theme: Theme,
+    /// What the bar at the bottom shows, in order
+    #[serde(default = "default_status_bar")]
+    pub status_bar: Vec<StatusSegment>,
+    /// How `:export <path>.png` draws the selected tweet
+    #[serde(default)]
+    pub image_theme: ImageTheme,
+    /// Feed keys, on top of the defaults, e.g. `"ctrl-f" = "open_search_bar"`
+    #[serde(default)]
+    pub keybindings: BTreeMap<Key, Action>,
+    /// Tabs opened on startup, as for `:tab`, e.g. `["home", "activity", "search rust lang"]`
+    #[serde(default = "default_feeds")]
+    pub feeds: Vec<FeedSource>,
+    /// How often activity tabs are refreshed
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: NonZeroU64,
+    /// The next page of the home timeline starts loading once the selection is this many tweets
+    /// from the bottom; 0 to only load it on request
+    #[serde(default = "default_prefetch_distance")]
+    pub prefetch_distance: usize,
+    /// API calls a month to plan for, e.g. the tier's read limit; `:budget` shows when it'll run out
+    #[serde(default)]
+    pub monthly_call_budget: Option<u64>,
+    /// How much the downloaded avatars and media can take up on disk, in MB, see
+    /// [crate::media_cache]
+    #[serde(default = "default_media_cache_mb")]
+    pub media_cache_mb: u64,
+    /// How many tweets are kept in memory; past that, the least recently used ones not in an open
+    /// feed are dropped, and fetched again if needed.  At least [MIN_CACHED_TWEETS]
+    #[serde(
+        default = "default_max_cached_tweets",
+        deserialize_with = "deserialize_max_cached_tweets"
+    )]
+    pub max_cached_tweets: NonZeroUsize,
+    #[serde(default)]
+    pub filters: Filters,
+    /// When tweets are taken for spam and hidden behind a row in the feed, see [crate::spam]
+    #[serde(default)]
+    pub spam: SpamThresholds,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+}
+
+fn default_version() -> u32 {
+    1
+}
+
+fn default_show_public_metrics() -> bool {
+    true
+}
+
+fn default_show_badges() -> bool {
+    true
+}
+
+fn default_status_bar() -> Vec<StatusSegment> {
+    vec![
+        StatusSegment::Tasks,
+        StatusSegment::Tweets,
+        StatusSegment::Errors,
+    ]
+}
+
+fn default_media_cache_mb() -> u64 {
+    100
+}
+
+fn default_max_cached_tweets() -> NonZeroUsize {
+    NonZeroUsize::new(20_000).unwrap()
+}
+
+fn deserialize_max_cached_tweets<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<NonZeroUsize, D::Error> {
+    let max_cached_tweets = usize::deserialize(deserializer)?;
+    NonZeroUsize::new(max_cached_tweets)
+        .filter(|max_cached_tweets| max_cached_tweets.get() >= MIN_CACHED_TWEETS)
+        .ok_or_else(|| {
+            de::Error::custom(format!(
+                "Expected at least {MIN_CACHED_TWEETS} cached tweets, not {max_cached_tweets}"
+            ))
+        })
+}
+
+fn default_feeds() -> Vec<FeedSource> {
+    vec![FeedSource::Home]
+}
+
+fn default_refresh_interval_secs() -> NonZeroU64 {
+    NonZeroU64::new(60).unwrap()
+}
+
+fn default_followers_interval_secs() -> NonZeroU64 {
+    NonZeroU64::new(24 * 60 * 60).unwrap()
+}
+
+fn default_prefetch_distance() -> usize {
+    10
+}
+
+impl Default for UserConfig {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            starred_accounts: BTreeMap::new(),
+            show_public_metrics: default_show_public_metrics(),
+            show_badges: default_show_badges(),
+            activity_seen_id: None,
+            followers_checked_at: None,
+            followers_interval_secs: default_followers_interval_secs(),
+            hooks: Vec::new(),
+            alerts: Vec::new(),
+            cross_post: CrossPostConfig::default(),
+            translate: None,
+            plugins: Vec::new(),
+            muted_usernames: BTreeSet::new(),
+            notes: BTreeMap::new(),
+            read_markers: BTreeMap::new(),
+            theme: Theme::default(),
+            status_bar: default_status_bar(),
+            image_theme: ImageTheme::default(),
+            keybindings: BTreeMap::new(),
+            feeds: default_feeds(),
+            refresh_interval_secs: default_refresh_interval_secs(),
+            prefetch_distance: default_prefetch_distance(),
+            monthly_call_budget: None,
+            media_cache_mb: default_media_cache_mb(),
+            max_cached_tweets: default_max_cached_tweets(),
+            filters: Filters::default(),
+            spam: SpamThresholds::default(),
+            auth: AuthConfig::default(),
+            http: HttpConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// "public" for an app registered as a public client, in which case TWITTER_CLIENT_SECRET
+    /// isn't needed
+    pub client_type: ClientType,
+}
+
+/// A part of the bar at the bottom; each is left out while it has nothing to show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusSegment {
+    /// The current tab's title
+    FeedName,
+    /// Of the selected tweet in the feed, e.g. "12/340"
+    Position,
+    /// Unread activity, across tabs
+    Unread,
+    /// API calls and other background work in flight
+    Tasks,
+    /// How many tweets the feed has loaded
+    Tweets,
+    /// What's left of whichever endpoint's rate limit is closest to running out
+    RateLimit,
+    /// Local time, e.g. "14:05"
+    Clock,
+    /// The latest error not yet viewed with `E`
+    Errors,
+}
+
+/// Colors of the feed: a name ("dark_cyan"), an ANSI color number ("208") or RGB ("#ff8800").
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub timestamp: ThemeColor,
+    pub author: ThemeColor,
+    pub starred_author: ThemeColor,
+    pub retweeter: ThemeColor,
+    pub metrics: ThemeColor,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            timestamp: ThemeColor(TerminalColor::DarkGrey),
+            author: ThemeColor(TerminalColor::DarkCyan),
+            starred_author: ThemeColor(TerminalColor::Yellow),
+            retweeter: ThemeColor(TerminalColor::DarkGreen),
+            metrics: ThemeColor(TerminalColor::DarkGrey),
+        }
+    }
+}
+
+/// Colors and size of a tweet exported as an image; the height follows from the tweet's length.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImageTheme {
+    /// In pixels
+    pub width: u32,
+    pub background: ThemeColor,
+    pub text: ThemeColor,
+    /// The handle, date and metrics
+    pub secondary: ThemeColor,
+    /// The avatar placeholder
+    pub accent: ThemeColor,
+}
+
+impl Default for ImageTheme {
+    fn default() -> Self {
+        let rgb = |r, g, b| ThemeColor(TerminalColor::Rgb { r, g, b });
+        Self {
+            width: 600,
+            background: rgb(0xff, 0xff, 0xff),
+            text: rgb(0x0f, 0x14, 0x19),
+            secondary: rgb(0x53, 0x64, 0x71),
+            accent: rgb(0x1d, 0x9b, 0xf0),
+        }
+    }
+}
+
+/// crossterm's `Color`, so that the config doesn't need a terminal to load.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminalColor {
+    Reset,
+    Black,
+    DarkGrey,
+    Red,
+    DarkRed,
+    Green,
+    DarkGreen,
+    Yellow,
+    DarkYellow,
+    Blue,
+    DarkBlue,
+    Magenta,
+    DarkMagenta,
+    Cyan,
+    DarkCyan,
+    White,
+    Grey,
+    Rgb { r: u8, g: u8, b: u8 },
+    AnsiValue(u8),
+}
+
+#[cfg(feature = "tui")]
+impl From<TerminalColor> for crossterm::style::Color {
+    fn from(color: TerminalColor) -> Self {
+        use crossterm::style::Color;
+        match color {
+            TerminalColor::Reset => Color::Reset,
+            TerminalColor::Black => Color::Black,
+            TerminalColor::DarkGrey => Color::DarkGrey,
+            TerminalColor::Red => Color::Red,
+            TerminalColor::DarkRed => Color::DarkRed,
+            TerminalColor::Green => Color::Green,
+            TerminalColor::DarkGreen => Color::DarkGreen,
+            TerminalColor::Yellow => Color::Yellow,
+            TerminalColor::DarkYellow => Color::DarkYellow,
+            TerminalColor::Blue => Color::Blue,
+            TerminalColor::DarkBlue => Color::DarkBlue,
+            TerminalColor::Magenta => Color::Magenta,
+            TerminalColor::DarkMagenta => Color::DarkMagenta,
+            TerminalColor::Cyan => Color::Cyan,
+            TerminalColor::DarkCyan => Color::DarkCyan,
+            TerminalColor::White => Color::White,
+            TerminalColor::Grey => Color::Grey,
+            TerminalColor::Rgb { r, g, b } => Color::Rgb { r, g, b },
+            TerminalColor::AnsiValue(value) => Color::AnsiValue(value),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor(pub TerminalColor);
+
+const NAMED_COLORS: &[(&str, TerminalColor)] = &[
+    ("reset", TerminalColor::Reset),
+    ("black", TerminalColor::Black),
+    ("dark_grey", TerminalColor::DarkGrey),
+    ("red", TerminalColor::Red),
+    ("dark_red", TerminalColor::DarkRed),
+    ("green", TerminalColor::Green),
+    ("dark_green", TerminalColor::DarkGreen),
+    ("yellow", TerminalColor::Yellow),
+    ("dark_yellow", TerminalColor::DarkYellow),
+    ("blue", TerminalColor::Blue),
+    ("dark_blue", TerminalColor::DarkBlue),
+    ("magenta", TerminalColor::Magenta),
+    ("dark_magenta", TerminalColor::DarkMagenta),
+    ("cyan", TerminalColor::Cyan),
+    ("dark_cyan", TerminalColor::DarkCyan),
+    ("white", TerminalColor::White),
+    ("grey", TerminalColor::Grey),
+];
+
+impl TryFrom<String> for ThemeColor {
+    type Error = Error;
+
+    fn try_from(spec: String) -> Result<Self> {
+        let named = NAMED_COLORS
+            .iter()
+            .find(|(name, _)| spec.eq_ignore_ascii_case(name))
+            .map(|(_, color)| *color);
+        let ansi = || spec.parse().ok().map(TerminalColor::AnsiValue);
+        let rgb = || {
+            let hex = spec.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+            let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+            Some(TerminalColor::Rgb {
+                r: channel(0)?,
+                g: channel(2)?,
+                b: channel(4)?,
+            })
+        };
+        named
+            .or_else(ansi)
+            .or_else(rgb)
+            .map(ThemeColor)
+            .ok_or(anyhow!(
+                "Expected a color name, ANSI color number or #rrggbb, not `{spec}`"
+            ))
+    }
+}
+
+impl From<ThemeColor> for String {
+    fn from(color: ThemeColor) -> Self {
+        match color.0 {
+            TerminalColor::AnsiValue(value) => value.to_string(),
+            TerminalColor::Rgb { r, g, b } => format!("#{r:02x}{g:02x}{b:02x}"),
+            color => NAMED_COLORS
+                .iter()
+                .find(|(_, c)| *c == color)
+                .map_or("reset", |(name, _)| name)
+                .to_string(),
+        }
+    }
+}
+
+/// What's left out of feeds, besides muted users.  Activity is only filtered by muted words, since
+/// hiding replies there would leave nothing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Filters {
+    /// Case-insensitive
+    pub muted_words: Vec<String>,
+    pub hide_retweets: bool,
+    pub hide_replies: bool,
+    /// Only tweets in these languages are shown, e.g. `["en", "de"]`; all of them if empty.  Tweets
+    /// with no detectable language (links, emoji, ...) are always shown.
+    pub languages: Vec<String>,
+    /// What's done with tweets Twitter flags as possibly sensitive
+    pub sensitive: SensitiveContent,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitiveContent {
+    /// Shown as a placeholder until revealed
+    #[default]
+    Collapse,
+    Show,
+    /// Left out of feeds
+    Hide,
+}
+
+impl Filters {
+    /// Whether [tweet] is shown as a placeholder, unless the user has revealed it.
+    pub fn collapses(&self, tweet: &api::Tweet) -> bool {
+        self.sensitive == SensitiveContent::Collapse && tweet.is_possibly_sensitive()
+    }
+
+    /// Whether [tweet] is in one of [Filters::languages], or its language is unknown.
+    pub fn is_shown_language(&self, tweet: &api::Tweet) -> bool {
+        match tweet.lang.as_deref() {
+            _ if self.languages.is_empty() => true,
+            None | Some("und") => true,
+            Some(lang) => self
+                .languages
+                .iter()
+                .any(|language| language.eq_ignore_ascii_case(lang)),
+        }
+    }
 }
 
 impl UserConfig {
+    /// Loads the config at [path]; a missing config is the default one.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => {
+                Self::parse(&contents).with_context(|| format!("Invalid config {}", path.display()))
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                if path != Path::new(DEFAULT_PATH) {
+                    return Ok(Self::default());
+                }
+                match fs::read_to_string(LEGACY_JSON_PATH) {
+                    Ok(json) => serde_json::from_str(&json)
+                        .with_context(|| format!("Invalid config {LEGACY_JSON_PATH}")),
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+                    Err(err) => Err(err).context(format!("Reading {LEGACY_JSON_PATH}")),
+                }
+            }
+            Err(err) => Err(err).with_context(|| format!("Reading {}", path.display())),
+        }
+    }
+
+    /// Parses [toml], migrating it from an older version if need be.
+    pub fn parse(toml: &str) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(toml)?;
+        let version = match table.get("version") {
+            None => default_version(),
+            Some(toml::Value::Integer(version)) if *version >= 1 => u32::try_from(*version)?,
+            Some(version) => return Err(anyhow!("Expected a version number, not {version}")),
+        };
+        if version > CURRENT_VERSION {
+            return Err(anyhow!(
+                "Config version {version} is newer than this app supports ({CURRENT_VERSION})"
+            ));
+        }
+        if version == CURRENT_VERSION {
+            // NB: straight from the text, so errors point at the right line
+            return Ok(toml::from_str(toml)?);
+        }
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            migration(&mut table)?;
+        }
+        table.insert(
+            "version".to_string(),
+            toml::Value::Integer(CURRENT_VERSION.into()),
+        );
+        Ok(Self::deserialize(table)?)
+    }
+
+    /// Saves to [path] with [fs_util::write_atomic], keeping the config that was there as the newest backup.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut config = self.clone();
+        config.version = CURRENT_VERSION;
+        let contents = toml::to_string_pretty(&config)?;
+
+        if path.exists() {
+            rotate_backups(path)?;
+        }
+        fs_util::write_atomic(path, contents.as_bytes())
+    }
+
     pub fn is_starred(&self, user_id: &str) -> bool {
         self.starred_accounts.contains_key(user_id)
     }
 
     pub fn star_account(&mut self, user: &api::User) {
         self.starred_accounts
-            .insert(user.id.to_string(), user.clone());
+            .insert(user.id.to_string(), StarredAccount::new(user));
+    }
+
+    /// Sets [user]'s notes and tags, starring them if they aren't already.
+    pub fn annotate_account(&mut self, user: &api::User, notes: &str, tags: BTreeSet<String>) {
+        let account = self
+            .starred_accounts
+            .entry(user.id.to_string())
+            .or_insert_with(|| StarredAccount::new(user));
+        account.notes = notes.trim().to_string();
+        account.tags = tags;
+    }
+
+    /// Ids of the starred accounts tagged [tag].
+    pub fn tagged_account_ids(&self, tag: &str) -> Vec<String> {
+        let tag = normalize_tag(tag);
+        self.starred_accounts
+            .iter()
+            .filter(|(_, account)| account.tags.contains(&tag))
+            .map(|(user_id, _)| user_id.clone())
+            .collect()
     }
 
     pub fn unstar_account(&mut self, user: &api::User) {
         self.starred_accounts.remove(&user.id.to_string());
     }
+
+    /// Whether [tweet] is by a muted user or has a muted word.
+    pub fn is_muted(&self, tweet: &api::Tweet) -> bool {
+        let is_muted_user = match &tweet.author_username {
+            Some(username) => self.muted_usernames.contains(&username.to_lowercase()),
+            None => false,
+        };
+        let text = tweet.text.to_lowercase();
+        is_muted_user
+            || self
+                .filters
+                .muted_words
+                .iter()
+                .any(|word| text.contains(&word.to_lowercase()))
+    }
+
+    /// Whether [tweet] is left out of (non-activity) feeds.
+    pub fn is_filtered(&self, tweet: &api::Tweet) -> bool {
+        self.is_muted(tweet)
+            || (self.filters.hide_retweets && tweet.retweeted_id().is_some())
+            || (self.filters.hide_replies && tweet.replied_to_id().is_some())
+            || !self.filters.is_shown_language(tweet)
+            || (self.filters.sensitive == SensitiveContent::Hide && tweet.is_possibly_sensitive())
+    }
+
+    /// Sets the note on [tweet_id]; an empty [text] removes it.
+    pub fn set_note(&mut self, tweet_id: &str, text: &str) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.notes.remove(tweet_id);
+        } else {
+            self.notes.insert(tweet_id.to_string(), text.to_string());
+        }
+    }
+
+    pub fn mute_username(&mut self, username: &str) {
+        let username = username.trim_start_matches('@').to_lowercase();
+        self.muted_usernames.insert(username);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StarredAccount {
+    /// NB: flattened, so starred accounts saved before notes and tags still load
+    #[serde(flatten)]
+    pub user: api::User,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Normalized by [normalize_tag], e.g. "rust"
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+}
+
+impl StarredAccount {
+    pub fn new(user: &api::User) -> Self {
+        Self {
+            user: user.clone(),
+            notes: String::new(),
+            tags: BTreeSet::new(),
+        }
+    }
+}
+
+/// Lowercase, without a leading '#'.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Tags separated by commas and/or whitespace, e.g. "rust, #ML".
+pub fn parse_tags(tags: &str) -> BTreeSet<String> {
+    tags.split(|ch: char| ch == ',' || ch.is_whitespace())
+        .map(normalize_tag)
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// e.g. config.toml.1 for [path] config.toml and [suffix] "1"
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(format!(".{suffix}"));
+    PathBuf::from(path)
+}
+
+/// Shifts each backup of [path] back one, dropping the oldest, and copies [path] to the newest.
+fn rotate_backups(path: &Path) -> Result<()> {
+    for n in (1..NUM_BACKUPS).rev() {
+        let backup = with_suffix(path, &n.to_string());
+        if backup.exists() {
+            fs::rename(&backup, with_suffix(path, &(n + 1).to_string()))?;
+        }
+    }
+    fs::copy(path, with_suffix(path, "1"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r##"
+feeds = ["home", "activity", "search rust lang"]
+refresh_interval_secs = 30
+prefetch_distance = 5
+monthly_call_budget = 10000
+
+[theme]
+author = "#ff8800"
+starred_author = "208"
+
+[image_theme]
+width = 480
+background = "black"
+
+[keybindings]
+"ctrl-f" = "open_search_bar"
+x = "switch_tab_2"
+
+[filters]
+muted_words = ["Crypto"]
+hide_retweets = true
+
+[auth]
+client_type = "public"
+"##;
+
+    #[test]
+    fn test_parse() {
+        let config = UserConfig::parse(CONFIG).unwrap();
+        assert_eq!(
+            config.feeds,
+            vec![
+                FeedSource::Home,
+                FeedSource::Activity,
+                FeedSource::Search("rust lang".to_string())
+            ]
+        );
+        assert_eq!(config.refresh_interval_secs.get(), 30);
+        assert_eq!(config.prefetch_distance, 5);
+        assert_eq!(config.monthly_call_budget, Some(10000));
+        assert_eq!(
+            config.theme.author.0,
+            TerminalColor::Rgb {
+                r: 255,
+                g: 136,
+                b: 0
+            }
+        );
+        assert_eq!(config.theme.starred_author.0, TerminalColor::AnsiValue(208));
+        assert_eq!(config.theme.timestamp.0, TerminalColor::DarkGrey);
+        assert_eq!(config.image_theme.width, 480);
+        assert_eq!(config.image_theme.background.0, TerminalColor::Black);
+        assert_eq!(config.image_theme.accent, ImageTheme::default().accent);
+        assert_eq!(
+            config.keybindings.values().collect::<Vec<_>>(),
+            vec![&Action::OpenSearchBar, &Action::SwitchTab(1)]
+        );
+        assert!(config.show_public_metrics);
+        assert!(config.show_badges);
+        assert_eq!(config.auth.client_type, ClientType::Public);
+
+        // NB: everything the app writes back has to survive the round trip
+        let mut config = config;
+        config.star_account(&api::User {
+            id: "2".to_string(),
+            name: "Alice".to_string(),
+            username: "alice".to_string(),
+            protected: None,
+            verified_type: None,
+            affiliation: None,
+            profile_image_url: None,
+            created_at: None,
+            public_metrics: None,
+        });
+        config.activity_seen_id = Some("1005".to_string());
+        config.notes.insert("1005".to_string(), "hmm".to_string());
+        let saved = toml::to_string_pretty(&config).unwrap();
+        let reloaded = UserConfig::parse(&saved).unwrap();
+        assert_eq!(toml::to_string_pretty(&reloaded).unwrap(), saved);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |toml: &str| format!("{:#}", UserConfig::parse(toml).unwrap_err());
+        assert_eq!(
+            error("refresh_interval_secs = 60\n\n[keybindings]\n\"ctrl-f\" = \"open_sesame\"\n"),
+            "TOML parse error at line 4, column 12\n  |\n4 | \"ctrl-f\" = \"open_sesame\"\n  \
+             |            ^^^^^^^^^^^^^\nUnknown action `open_sesame`\n"
+        );
+        assert!(error("refresh_interval_secs = 0").contains("line 1"));
+        assert!(error("max_cached_tweets = 50").contains("at least 1000 cached tweets, not 50"));
+        assert!(error("[theme]\nauthor = \"chartreuse\"").contains(
+            "line 2, column 10\n  |\n2 | author = \"chartreuse\"\n  |          \
+             ^^^^^^^^^^^^\nExpected a color name"
+        ));
+        assert!(error("feeds = [\"home\", \"bookmarks\"]").contains("not `bookmarks`"));
+        assert!(error("[filters]\nhide_quotes = true").contains("unknown field `hide_quotes`"));
+    }
+
+    #[test]
+    fn test_versions() {
+        let config = UserConfig::parse("show_public_metrics = false").unwrap();
+        assert_eq!(config.version, default_version());
+        assert!(!config.show_public_metrics);
+        assert!(UserConfig::parse("version = 0").is_err());
+        assert_eq!(
+            UserConfig::parse("version = 99").unwrap_err().to_string(),
+            format!("Config version 99 is newer than this app supports ({CURRENT_VERSION})")
+        );
+    }
+
+    #[test]
+    fn test_save() {
+        let dir = std::env::temp_dir().join(format!("twitter-tool-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+
+        let mut config = UserConfig::default();
+        for id in 0..NUM_BACKUPS + 2 {
+            config.activity_seen_id = Some(id.to_string());
+            config.save(&path).unwrap();
+        }
+        let seen_id = |path: &Path| UserConfig::load(path).unwrap().activity_seen_id.unwrap();
+        assert_eq!(seen_id(&path), (NUM_BACKUPS + 1).to_string());
+        assert_eq!(seen_id(&with_suffix(&path, "1")), NUM_BACKUPS.to_string());
+        assert_eq!(seen_id(&with_suffix(&path, &NUM_BACKUPS.to_string())), "1");
+        assert!(!with_suffix(&path, &(NUM_BACKUPS + 1).to_string()).exists());
+        assert!(!with_suffix(&path, "tmp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut config = UserConfig::parse(CONFIG).unwrap();
+        let tweet =
+            |username: &str, text: &str| crate::store::fixtures::tweet("1", username, text, 0);
+        assert!(config.is_muted(&tweet("alice", "buy crypto now")));
+        assert!(!config.is_muted(&tweet("alice", "hello")));
+        config.mute_username("@Alice");
+        config.set_note("1", " read later ");
+        assert_eq!(config.notes["1"], "read later");
+        config.set_note("1", "");
+        assert!(config.notes.is_empty());
+        assert!(config.is_filtered(&tweet("alice", "hello")));
+        assert!(!config.is_filtered(&tweet("bob", "hello")));
+
+        config.filters.languages = vec!["en".to_string(), "DE".to_string()];
+        let in_lang = |lang: &str| api::Tweet {
+            lang: Some(lang.to_string()),
+            ..tweet("bob", "hello")
+        };
+        assert!(!config.is_filtered(&in_lang("de")));
+        assert!(config.is_filtered(&in_lang("ja")));
+        assert!(!config.is_filtered(&in_lang("und")));
+        assert!(!config.is_filtered(&tweet("bob", "hello")));
+
+        let sensitive = api::Tweet {
+            possibly_sensitive: Some(true),
+            ..tweet("bob", "hello")
+        };
+        assert!(config.filters.collapses(&sensitive));
+        assert!(!config.is_filtered(&sensitive));
+        config.filters.sensitive = SensitiveContent::Hide;
+        assert!(!config.filters.collapses(&sensitive));
+        assert!(config.is_filtered(&sensitive));
+        assert!(!config.is_filtered(&tweet("bob", "hello")));
+    }
+
+    #[test]
+    fn test_starred_account_tags() {
+        // NB: starred accounts from before notes and tags
+        let mut config = UserConfig::parse(
+            "[starred_accounts.2]\nid = \"2\"\nname = \"Alice\"\nusername = \"alice\"\n",
+        )
+        .unwrap();
+        let alice = config.starred_accounts["2"].user.clone();
+        assert!(config.starred_accounts["2"].tags.is_empty());
+
+        assert_eq!(
+            parse_tags("rust, #ML  rust"),
+            BTreeSet::from(["ml".to_string(), "rust".to_string()])
+        );
+        config.annotate_account(&alice, " compilers ", parse_tags("rust ml"));
+        let bob = api::User {
+            id: "3".to_string(),
+            name: "Bob".to_string(),
+            username: "bob".to_string(),
+            protected: None,
+            verified_type: None,
+            affiliation: None,
+            profile_image_url: None,
+            created_at: None,
+            public_metrics: None,
+        };
+        config.annotate_account(&bob, "", parse_tags("#Rust"));
+        assert_eq!(config.starred_accounts["2"].notes, "compilers");
+        assert_eq!(config.tagged_account_ids("#rust"), vec!["2", "3"]);
+        assert_eq!(config.tagged_account_ids("ml"), vec!["2"]);
+
+        let reloaded = UserConfig::parse(&toml::to_string_pretty(&config).unwrap()).unwrap();
+        assert_eq!(reloaded.starred_accounts, config.starred_accounts);
+    }
 }